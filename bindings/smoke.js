@@ -0,0 +1,34 @@
+// Smoke test of the WebAssembly build, loading the module with the wrapper and pricing a sale
+//
+//   node bindings/smoke.js target/wasm32-unknown-unknown/release/store_terminal.wasm
+
+import assert from "node:assert/strict";
+import { readFile } from "node:fs/promises";
+import { load } from "./store_terminal.js";
+
+const { Terminal } = await load(await readFile(process.argv[2]));
+const terminal = Terminal.create({
+  products: [
+    { code: "A", price: 2.0 },
+    { code: "B", price: 12.0 },
+  ],
+  promotions: [{ code: "PA", products: [{ product: { code: "A", price: 2.0 }, amount: 4.0 }], price: 7.0 }],
+});
+
+const report = terminal.scan("A A A A B X");
+assert.deepEqual(report.unknown, ["X"]);
+assert.equal(terminal.total(), 19.0);
+assert.deepEqual(
+  terminal.cart().items.map((item) => item.code).sort(),
+  ["B", "PA"]
+);
+
+const receipt = terminal.checkout();
+assert.equal(receipt.total, 19.0);
+assert.ok(receipt.timestamp > 0);
+assert.equal(terminal.total(), 0.0);
+
+assert.throws(() => terminal.loadCatalog("{"));
+terminal.free();
+
+console.log("store_terminal.wasm: ok");
@@ -0,0 +1,101 @@
+// JavaScript wrapper of the WebAssembly build of the store terminal.
+//
+// Build the module with
+//   cargo build --release --lib --no-default-features --features ffi --target wasm32-unknown-unknown
+// and load target/wasm32-unknown-unknown/release/store_terminal.wasm:
+//
+//   const { Terminal } = await load(fetch("store_terminal.wasm"));
+//   const terminal = Terminal.create({ products: [{ code: "A", price: 2.0 }], promotions: [] });
+//   terminal.scan("A A");
+//   terminal.total(); // 4
+//   terminal.free();
+//
+// The source is a response, or a promise of one, or the bytes of the module.
+
+export async function load(source) {
+  // The module reads the time of its sales from the host
+  const imports = { env: { store_terminal_now: () => Date.now() } };
+  const { instance } =
+    source instanceof ArrayBuffer || ArrayBuffer.isView(source)
+      ? await WebAssembly.instantiate(source, imports)
+      : await WebAssembly.instantiateStreaming(source, imports);
+  const exports = instance.exports;
+  const encoder = new TextEncoder();
+  const decoder = new TextDecoder();
+
+  // Run f with a NUL-terminated copy of text in the memory of the module
+  function withString(text, f) {
+    const bytes = encoder.encode(text);
+    const len = bytes.length + 1;
+    const ptr = exports.store_terminal_alloc(len);
+    new Uint8Array(exports.memory.buffer, ptr, len).set(bytes);
+    try {
+      return f(ptr);
+    } finally {
+      exports.store_terminal_dealloc(ptr, len);
+    }
+  }
+
+  // Parse and release a JSON string returned by the module, throwing its error if any
+  function readJson(ptr) {
+    const memory = new Uint8Array(exports.memory.buffer);
+    const end = memory.indexOf(0, ptr);
+    const document = JSON.parse(decoder.decode(memory.subarray(ptr, end)));
+    exports.store_terminal_string_free(ptr);
+    if (document.error !== undefined) {
+      throw new Error(document.error);
+    }
+    return document;
+  }
+
+  class Terminal {
+    constructor(ptr) {
+      this.ptr = ptr;
+    }
+
+    // Terminal with the catalog, an object or JSON string, or an empty one
+    static create(catalog) {
+      const ptr =
+        catalog === undefined
+          ? exports.store_terminal_create(0)
+          : withString(toJson(catalog), (c) => exports.store_terminal_create(c));
+      if (ptr === 0) {
+        throw new Error("invalid catalog");
+      }
+      return new Terminal(ptr);
+    }
+
+    loadCatalog(catalog) {
+      return withString(toJson(catalog), (c) =>
+        readJson(exports.store_terminal_load_catalog(this.ptr, c))
+      );
+    }
+
+    scan(codes) {
+      return withString(codes, (c) => readJson(exports.store_terminal_scan(this.ptr, c)));
+    }
+
+    cart() {
+      return readJson(exports.store_terminal_get_cart(this.ptr));
+    }
+
+    total() {
+      return readJson(exports.store_terminal_get_total(this.ptr)).total;
+    }
+
+    checkout() {
+      return readJson(exports.store_terminal_checkout(this.ptr));
+    }
+
+    free() {
+      exports.store_terminal_free(this.ptr);
+      this.ptr = 0;
+    }
+  }
+
+  return { Terminal };
+}
+
+function toJson(value) {
+  return typeof value === "string" ? value : JSON.stringify(value);
+}
@@ -0,0 +1,44 @@
+#ifndef STORE_TERMINAL_H
+#define STORE_TERMINAL_H
+
+/*
+ * C ABI of the store terminal pricing engine; build the crate with `--features ffi`.
+ *
+ * Returned strings are JSON documents, {"error": "<message>"} on failure, and must be
+ * released with store_terminal_string_free.
+ */
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct Terminal Terminal;
+
+/* Terminal with the catalog JSON document, or empty if catalog is NULL; NULL on failure */
+Terminal *store_terminal_create(const char *catalog);
+void store_terminal_free(Terminal *terminal);
+
+/* {"accepted": [...], "unknown": [...], "total": <scanned total>} */
+char *store_terminal_scan(const Terminal *terminal, const char *codes);
+/* Append the catalog JSON document; {"products": <count>, "promotions": <count>} */
+char *store_terminal_load_catalog(const Terminal *terminal, const char *catalog);
+/* {"items": [{"code", "description", "amount", "total"}, ...], "total": <total>} */
+char *store_terminal_get_cart(const Terminal *terminal);
+/* {"total": <total>} */
+char *store_terminal_get_total(const Terminal *terminal);
+/* {"items": [{"code", "description", "amount", "total"}, ...], "total": <total>} */
+char *store_terminal_checkout(const Terminal *terminal);
+
+void store_terminal_string_free(char *s);
+
+/* Buffer of len zeroed bytes in the memory of the library, and its release */
+unsigned char *store_terminal_alloc(size_t len);
+void store_terminal_dealloc(unsigned char *buffer, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
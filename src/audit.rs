@@ -0,0 +1,247 @@
+//! Append-only trail of the price overrides, line voids, cart resets and catalog changes of a
+//! terminal, for loss prevention
+//!
+//! Every [AuditEntry] records who performed the action, on which terminal and when, with the
+//! values before and after it. Logs opened on a file keep the trail across restarts, appending
+//! one JSON document per entry
+
+use crate::lock;
+use crate::prelude::ErrorVariant;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "json")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "json")]
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Action recorded in an [AuditLog]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "json",
+    derive(Serialize, Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+pub enum AuditAction {
+    /// Price of a product or promotion overridden, or restored by an undo
+    PriceOverride {
+        code: String,
+        previous: f64,
+        price: f64,
+    },
+    /// Amount of a product removed from the cart by an undo
+    LineVoid { code: String, amount: f64 },
+    /// Sale in progress voided, with the units and the price of its items
+    CartReset { units: f64, total: f64 },
+    /// Price of a product or promotion of the catalog changed; `None` when it is added or
+    /// removed
+    CatalogChange {
+        code: String,
+        previous: Option<f64>,
+        price: Option<f64>,
+    },
+    /// Database cleared
+    DatabaseReset,
+    /// Restricted products of the cart approved at a kiosk by the operator `approver`
+    HoldApproval {
+        approver: String,
+        codes: Vec<String>,
+    },
+}
+
+/// Action recorded by a terminal, with who performed it and when
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct AuditEntry {
+    sequence: u64,
+    timestamp: u64,
+    terminal: String,
+    operator: Option<String>,
+    action: AuditAction,
+}
+
+impl AuditEntry {
+    /// Position of the entry in its log, starting at 1
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Identifier of the terminal the action was performed on
+    pub fn get_terminal(&self) -> &str {
+        &self.terminal
+    }
+
+    /// Identifier of the operator logged in, if any
+    pub fn get_operator(&self) -> Option<&str> {
+        self.operator.as_deref()
+    }
+
+    pub fn get_action(&self) -> &AuditAction {
+        &self.action
+    }
+}
+
+#[derive(Debug, Default)]
+struct Trail {
+    entries: Vec<AuditEntry>,
+    #[cfg(feature = "json")]
+    file: Option<(String, File)>,
+}
+
+/// Audit trail of a terminal, queried with [Terminal::audit_log](crate::prelude::Terminal::audit_log)
+///
+/// Entries are only ever appended. An entry failing to be written is kept in memory and the
+/// error is returned
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::audit::AuditAction;
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let product = terminal.get_db().unwrap().fetch_product("C").unwrap();
+/// terminal.set_pricing(product, 1.0).unwrap();
+/// terminal.scan("AAB").unwrap();
+/// terminal.undo().unwrap();
+/// // The cart emptied by the undo has nothing to void
+/// terminal.reset_cart().unwrap();
+///
+/// let entries = terminal.audit_log().get_entries();
+/// let actions: Vec<_> = entries.iter().skip(6).map(|e| e.get_action().clone()).collect();
+/// assert_eq!(
+///     actions,
+///     vec![
+///         AuditAction::PriceOverride { code: "C".to_string(), previous: 1.25, price: 1.0 },
+///         AuditAction::LineVoid { code: "B".to_string(), amount: 1.0 },
+///         AuditAction::LineVoid { code: "A".to_string(), amount: 1.0 },
+///         AuditAction::LineVoid { code: "A".to_string(), amount: 1.0 },
+///     ]
+/// );
+/// assert_eq!(entries[6].get_sequence(), 7);
+/// assert_eq!(entries[6].get_terminal(), "terminal-1");
+/// assert_eq!(entries[6].get_operator(), None);
+///
+/// terminal.scan("B").unwrap();
+/// terminal.reset_cart().unwrap();
+/// assert_eq!(
+///     terminal.audit_log().get_entries().last().unwrap().get_action(),
+///     &AuditAction::CartReset { units: 1.0, total: 12.0 }
+/// );
+/// ```
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    trail: Mutex<Trail>,
+}
+
+impl AuditLog {
+    /// Log kept in memory only
+    pub fn new() -> Self {
+        AuditLog::default()
+    }
+
+    /// Log stored in the file at `path`, created if missing, restoring its entries
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::audit::{AuditAction, AuditLog};
+    /// use store_terminal::prelude::*;
+    ///
+    /// let path = std::env::temp_dir().join(format!("audit-{}.jsonl", std::process::id()));
+    /// let path = path.to_str().unwrap();
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_audit_log(AuditLog::open(path).unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// terminal.reset().unwrap();
+    ///
+    /// let restored = AuditLog::open(path).unwrap();
+    /// let entries = restored.get_entries();
+    /// assert_eq!(entries.len(), 1);
+    /// assert_eq!(entries[0].get_action(), &AuditAction::DatabaseReset);
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn open(path: &str) -> Result<Self, ErrorVariant> {
+        let io_error = |e: std::io::Error| ErrorVariant::IoError {
+            path: path.to_string(),
+            source: e.to_string(),
+        };
+
+        let mut trail = Trail::default();
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                    let entry = serde_json::from_str(line)
+                        .map_err(|e| ErrorVariant::from_json_error(e, "audit log"))?;
+                    trail.entries.push(entry);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => return Err(io_error(e)),
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+        trail.file = Some((path.to_string(), file));
+
+        Ok(AuditLog {
+            trail: Mutex::new(trail),
+        })
+    }
+
+    /// Entries, oldest first
+    pub fn get_entries(&self) -> Vec<AuditEntry> {
+        lock::lock(&self.trail).entries.clone()
+    }
+
+    /// Append the action performed on `terminal` by `operator` at `timestamp`
+    pub(crate) fn append(
+        &self,
+        terminal: &str,
+        operator: Option<String>,
+        timestamp: u64,
+        action: AuditAction,
+    ) -> Result<(), ErrorVariant> {
+        let mut trail = lock::lock(&self.trail);
+        let entry = AuditEntry {
+            sequence: trail.entries.len() as u64 + 1,
+            timestamp,
+            terminal: terminal.to_string(),
+            operator,
+            action,
+        };
+
+        #[cfg(feature = "json")]
+        let written = match &mut trail.file {
+            Some((path, file)) => serde_json::to_string(&entry)
+                .map_err(|e| e.to_string())
+                .and_then(|line| {
+                    writeln!(file, "{}", line)
+                        .and_then(|()| file.sync_data())
+                        .map_err(|e| e.to_string())
+                })
+                .map_err(|source| ErrorVariant::IoError {
+                    path: path.clone(),
+                    source,
+                }),
+            None => Ok(()),
+        };
+        #[cfg(not(feature = "json"))]
+        let written = Ok(());
+
+        trail.entries.push(entry);
+        written
+    }
+}
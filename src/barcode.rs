@@ -0,0 +1,244 @@
+//! Validation of EAN-8, UPC-A and EAN-13 barcodes
+//!
+//! Codes made only of digits are barcodes once they have 8 digits or more; shorter numeric
+//! codes, such as PLU numbers of weighed items, and codes with any other character are left
+//! as they are
+
+use crate::ErrorVariant;
+
+/// Shortest numeric code taken as a barcode
+const MIN_BARCODE_LEN: usize = 8;
+
+/// Check digit completing the digits of a barcode without its check digit
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::barcode;
+///
+/// assert_eq!(barcode::check_digit("789123456789"), Some(5));
+/// assert_eq!(barcode::check_digit("03600029145"), Some(2));
+/// assert_eq!(barcode::check_digit("78912345678A"), None);
+/// ```
+pub fn check_digit(payload: &str) -> Option<u32> {
+    let sum = payload
+        .chars()
+        .rev()
+        .enumerate()
+        .try_fold(0, |sum, (i, c)| {
+            // The digit next to the check digit weighs 3, then weights alternate with 1
+            let weight = if i % 2 == 0 { 3 } else { 1 };
+            c.to_digit(10).map(|digit| sum + digit * weight)
+        })?;
+    Some((10 - sum % 10) % 10)
+}
+
+/// Check if `code` is numeric and long enough to be validated as a barcode
+pub fn is_barcode(code: &str) -> bool {
+    code.len() >= MIN_BARCODE_LEN && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Validate a barcode, returning it with UPC-A codes extended to EAN-13
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::barcode;
+/// use store_terminal::prelude::*;
+///
+/// assert_eq!(barcode::normalize("036000291452").unwrap(), "0036000291452");
+/// assert_eq!(barcode::normalize("7891234567895").unwrap(), "7891234567895");
+/// assert_eq!(barcode::normalize("96385074").unwrap(), "96385074");
+/// assert_eq!(barcode::normalize("A").unwrap(), "A");
+/// assert_eq!(
+///     barcode::normalize("7891234567890").unwrap_err(),
+///     ErrorVariant::InvalidBarcode { code: "7891234567890".to_string() }
+/// );
+/// assert!(barcode::normalize("123456789").is_err());
+/// ```
+pub fn normalize(code: &str) -> Result<String, ErrorVariant> {
+    if !is_barcode(code) {
+        return Ok(code.to_string());
+    }
+
+    let invalid = || ErrorVariant::InvalidBarcode {
+        code: code.to_string(),
+    };
+    if ![8, 12, 13].contains(&code.len()) {
+        return Err(invalid());
+    }
+
+    let (payload, check) = code.split_at(code.len() - 1);
+    if check_digit(payload) != check.chars().next().and_then(|c| c.to_digit(10)) {
+        return Err(invalid());
+    }
+
+    match code.len() {
+        12 => Ok(format!("0{}", code)),
+        _ => Ok(code.to_string()),
+    }
+}
+
+/// Price or weight embedded in a barcode by [EmbeddedLayout]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmbeddedValue {
+    Price(f64),
+    /// Weight in kilograms
+    Weight(f64),
+}
+
+/// Item and value of a barcode printed by a deli scale
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedCode {
+    item: String,
+    value: EmbeddedValue,
+}
+
+impl EmbeddedCode {
+    /// Code of the item in the catalog: the prefix followed by the item number
+    pub fn get_item(&self) -> &str {
+        &self.item
+    }
+
+    pub fn get_value(&self) -> EmbeddedValue {
+        self.value
+    }
+}
+
+/// Layout of the EAN-13 codes of restricted circulation, prefixed `02` or `20` to `29`, embedding
+/// a price or a weight
+///
+/// The prefix is followed by a five digit item number, a check digit of the value, the value on
+/// four digits, in cents or grams, and the check digit of the barcode. Without the check digit
+/// of the value, the value takes five digits. UPC-A codes with the number system `2` are read
+/// with the `02` prefix
+///
+/// By default, prefixes `02` and `20` to `22` embed a price, `23` to `29` a weight, and values
+/// have a check digit
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::barcode::{EmbeddedLayout, EmbeddedValue};
+///
+/// let layout = EmbeddedLayout::default();
+///
+/// let code = layout.parse("2112345928753").unwrap().unwrap();
+/// assert_eq!(code.get_item(), "2112345");
+/// assert_eq!(code.get_value(), EmbeddedValue::Price(28.75));
+///
+/// let code = layout.parse("2501234315000").unwrap().unwrap();
+/// assert_eq!(code.get_item(), "2501234");
+/// assert_eq!(code.get_value(), EmbeddedValue::Weight(1.5));
+///
+/// // Price check digit not matching the price
+/// assert!(layout.parse("2112345128757").is_err());
+/// assert_eq!(layout.parse("7891234567895").unwrap(), None);
+///
+/// let layout = EmbeddedLayout::new(vec![], vec!["28".to_string()]).with_value_check_digit(false);
+/// let code = layout.parse("2800042125000").unwrap().unwrap();
+/// assert_eq!(code.get_value(), EmbeddedValue::Weight(12.5));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedLayout {
+    price_prefixes: Vec<String>,
+    weight_prefixes: Vec<String>,
+    value_check_digit: bool,
+}
+
+impl EmbeddedLayout {
+    /// Layout of codes embedding prices after `price_prefixes`, and weights after `weight_prefixes`
+    pub fn new(price_prefixes: Vec<String>, weight_prefixes: Vec<String>) -> Self {
+        EmbeddedLayout {
+            price_prefixes,
+            weight_prefixes,
+            value_check_digit: true,
+        }
+    }
+
+    /// Read a check digit of the value before it, as most scales print
+    pub fn with_value_check_digit(mut self, enabled: bool) -> Self {
+        self.value_check_digit = enabled;
+        self
+    }
+
+    pub fn has_value_check_digit(&self) -> bool {
+        self.value_check_digit
+    }
+
+    /// Item and value of `code`, or `None` for codes without an embedded value
+    pub fn parse(&self, code: &str) -> Result<Option<EmbeddedCode>, ErrorVariant> {
+        if !is_barcode(code) || ![12, 13].contains(&code.len()) {
+            return Ok(None);
+        }
+
+        let ean = normalize(code)?;
+        let (prefix, rest) = ean.split_at(2);
+        let price = self.price_prefixes.iter().any(|p| p == prefix);
+        if !price && !self.weight_prefixes.iter().any(|p| p == prefix) {
+            return Ok(None);
+        }
+
+        let (item, value) = (&rest[..5], &rest[5..10]);
+        let value = if self.value_check_digit {
+            let (check, value) = value.split_at(1);
+            if value_check_digit(value) != check.chars().next().and_then(|c| c.to_digit(10)) {
+                return Err(ErrorVariant::InvalidBarcode {
+                    code: code.to_string(),
+                });
+            }
+            value
+        } else {
+            value
+        };
+        let value: f64 = value.parse().map_err(|_| ErrorVariant::InvalidBarcode {
+            code: code.to_string(),
+        })?;
+
+        Ok(Some(EmbeddedCode {
+            item: format!("{}{}", prefix, item),
+            value: if price {
+                EmbeddedValue::Price(value / 100.0)
+            } else {
+                EmbeddedValue::Weight(value / 1000.0)
+            },
+        }))
+    }
+}
+
+impl Default for EmbeddedLayout {
+    fn default() -> Self {
+        let prefixes = |range: std::ops::RangeInclusive<u32>| range.map(|p| p.to_string());
+        EmbeddedLayout::new(
+            std::iter::once("02".to_string())
+                .chain(prefixes(20..=22))
+                .collect(),
+            prefixes(23..=29).collect(),
+        )
+    }
+}
+
+/// Check digit of a four digit price or weight, with the weights 2-, 2-, 3 and 5- of GS1
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::barcode;
+///
+/// assert_eq!(barcode::value_check_digit("2875"), Some(9));
+/// assert_eq!(barcode::value_check_digit("287"), None);
+/// ```
+pub fn value_check_digit(value: &str) -> Option<u32> {
+    const TWO_MINUS: [u32; 10] = [0, 2, 4, 6, 8, 9, 1, 3, 5, 7];
+    const THREE: [u32; 10] = [0, 3, 6, 9, 2, 5, 8, 1, 4, 7];
+    const FIVE_MINUS: [u32; 10] = [0, 5, 9, 4, 8, 3, 7, 2, 6, 1];
+
+    let digits: Vec<usize> = value
+        .chars()
+        .map(|c| c.to_digit(10).map(|d| d as usize))
+        .collect::<Option<_>>()?;
+    match digits.as_slice() {
+        [a, b, c, d] => Some((TWO_MINUS[*a] + TWO_MINUS[*b] + THREE[*c] + FIVE_MINUS[*d]) * 3 % 10),
+        _ => None,
+    }
+}
@@ -1,73 +1,50 @@
-use crate::prelude::{
-    Cart, CartItem, CartItemProduct, ErrorVariant, ProductAmount, ProductAmountGroupFuture,
-};
-use futures::prelude::*;
-use futures::try_ready;
+use crate::prelude::{Cart, CartItem, CartItemProduct, ErrorVariant, ProductAmount};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
+/// Future of [Cart::get_flat_quantities], for async contexts
 pub struct CartGroupFuture {
-    queue: Vec<Box<dyn CartItem>>,
     result: Vec<ProductAmount>,
 }
 
 impl CartGroupFuture {
-    /// Group all items of a cart into an optimal size vec of ProductAmount
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use store_terminal::prelude::*;
-    /// use futures::prelude::*;
-    ///
-    /// let mut database = Database::new();
-    ///
-    /// database.append(Product::new("Foo".to_string(), 1.0));
-    /// database.append(Product::new("Bar".to_string(), 2.0));
-    ///
-    /// let mut cart = Cart::new(database);
-    /// cart.push_product(&"Foo".to_string(), 15.0).unwrap();
-    /// cart.push_product(&"Bar".to_string(), 35.0).unwrap();
-    /// cart.push_product(&"Foo".to_string(), 4.0).unwrap();
-    /// cart.push_product(&"Foo".to_string(), 12.0).unwrap();
-    ///
-    /// let mut v_min = vec![];
-    /// v_min.push(ProductAmount::new(Product::new("Foo".to_string(), 1.0), 31.0));
-    /// v_min.push(ProductAmount::new(Product::new("Bar".to_string(), 1.0), 35.0));
-    ///
-    /// let result = CartGroupFuture::new(&cart).wait().unwrap();
-    ///
-    /// assert_eq!(result, v_min);
-    /// ```
     pub fn new(cart: &Cart) -> Self {
-        let result = vec![];
-        let queue = cart.get_items().clone();
-        CartGroupFuture { queue, result }
+        let result = group_items(cart.get_items());
+        CartGroupFuture { result }
     }
 }
 
 impl Future for CartGroupFuture {
-    type Item = Vec<ProductAmount>;
-    type Error = ErrorVariant;
+    type Output = Result<Vec<ProductAmount>, ErrorVariant>;
 
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        while !self.queue.is_empty() {
-            let amounts = self.queue[self.queue.len() - 1]
-                .get_products()
-                .iter()
-                .map(|&p| p.clone())
-                .collect();
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Self::Output> {
+        Poll::Ready(Ok(std::mem::take(&mut self.get_mut().result)))
+    }
+}
 
-            self.result.append(&mut try_ready!(
-                ProductAmountGroupFuture::new(amounts).poll()
-            ));
+/// Group the products of all the items, unwrapping the promotions, in the order they first
+/// appear; each product is cloned once, into the result
+pub(crate) fn group_items(items: &[Box<dyn CartItem>]) -> Vec<ProductAmount> {
+    let mut result: Vec<ProductAmount> = vec![];
+    let mut indexes: HashMap<&str, usize> = HashMap::new();
 
-            self.queue.pop();
+    for product in items
+        .iter()
+        .filter(|item| !item.is_custom())
+        .flat_map(|item| item.get_products())
+    {
+        match indexes.get(product.get_code().as_str()) {
+            Some(&i) => result[i].inc_amount(*product.get_amount()),
+            None => {
+                indexes.insert(product.get_code(), result.len());
+                result.push(product.clone());
+            }
         }
-
-        Ok(Async::Ready(try_ready!(ProductAmountGroupFuture::new(
-            (*self.result).to_vec()
-        )
-        .poll())))
     }
+
+    result
 }
 
 pub struct CartOptimizeFuture {
@@ -91,10 +68,9 @@ impl CartOptimizeFuture {
 }
 
 impl Future for CartOptimizeFuture {
-    type Item = Vec<Box<dyn CartItem>>;
-    type Error = ErrorVariant;
+    type Output = Result<Vec<Box<dyn CartItem>>, ErrorVariant>;
 
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        Ok(Async::Ready((*self.result).to_vec()))
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Self::Output> {
+        Poll::Ready(Ok(std::mem::take(&mut self.get_mut().result)))
     }
 }
@@ -1,6 +1,73 @@
-use crate::prelude::{CartItemProduct, CartItemPromotion, ProductAmount};
+#[cfg(feature = "json")]
+use crate::prelude::ErrorVariant;
+use crate::prelude::{CartItemProduct, CartItemPromotion, Code, ProductAmount};
+#[cfg(feature = "json")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "json")]
+use std::collections::HashMap;
 use std::fmt;
-use uuid::Uuid;
+#[cfg(feature = "json")]
+use std::sync::Arc;
+
+/// Identifier of an item added to a cart
+///
+/// Random version 4 UUIDs with the `uuid` feature, otherwise a counter unique within the process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ItemId(u128);
+
+impl ItemId {
+    #[cfg(feature = "uuid")]
+    pub fn new() -> Self {
+        ItemId(u128::from_be_bytes(*uuid::Uuid::new_v4().as_bytes()))
+    }
+
+    #[cfg(not(feature = "uuid"))]
+    pub fn new() -> Self {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        ItemId(u128::from(NEXT.fetch_add(1, Ordering::Relaxed)))
+    }
+
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+}
+
+impl From<u128> for ItemId {
+    fn from(id: u128) -> Self {
+        ItemId(id)
+    }
+}
+
+impl Default for ItemId {
+    fn default() -> Self {
+        ItemId::new()
+    }
+}
+
+/// Hyphenated hexadecimal format of UUIDs
+impl fmt::Display for ItemId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let id = self.0;
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            id >> 96,
+            (id >> 80) & 0xffff,
+            (id >> 64) & 0xffff,
+            (id >> 48) & 0xffff,
+            id & 0xffff_ffff_ffff
+        )
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<ItemId> for uuid::Uuid {
+    fn from(id: ItemId) -> Self {
+        uuid::Uuid::from_bytes(id.0.to_be_bytes())
+    }
+}
 
 pub trait CloneIntoDynBox {
     fn clone_into_dyn_box<'a>(&self) -> Box<dyn 'a + CartItem>
@@ -26,32 +93,259 @@ impl Clone for Box<dyn '_ + CartItem> {
 pub enum CartItemVariant<'a> {
     Product(&'a CartItemProduct),
     Promotion(&'a CartItemPromotion),
+    Custom(&'a dyn CustomCartItem),
 }
 
-pub trait CartItem: CloneIntoDynBox + fmt::Display {
-    fn get_id(&self) -> &Uuid;
+/// Item of a cart, either a product or the application of a promotion, `get_amount` times
+///
+/// The products of a product item are in the amount of the item, while the products of a
+/// promotion item are the ones of a single application
+///
+/// Items are `Send + Sync`, as the cart of a [Terminal](crate::prelude::Terminal) is shared
+/// between threads behind its lock; custom items must satisfy the same bound
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// fn assert_shared<T: Send + Sync + ?Sized>() {}
+///
+/// assert_shared::<dyn CartItem>();
+/// assert_shared::<Cart>();
+/// assert_shared::<Terminal>();
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AAAAB").unwrap();
+///
+/// let cart = terminal.get_cart().unwrap();
+/// let items = cart.get_items();
+/// let promotion = items.iter().find(|item| !item.is_product()).unwrap();
+/// assert_eq!(promotion.get_code(), "PA");
+/// assert_eq!(promotion.get_amount(), 1.0);
+/// assert_eq!(promotion.get_unit_price(), 7.0);
+/// assert_eq!(promotion.get_regular_unit_price(), 8.0);
+/// assert_eq!(promotion.get_line_total(), 7.0);
+/// assert_eq!(promotion.get_discount(), 1.0);
+///
+/// let product = items.iter().find(|item| item.is_product()).unwrap();
+/// assert_eq!(product.get_code(), "B");
+/// assert_eq!((product.get_unit_price(), product.get_line_total()), (12.0, 12.0));
+/// assert_eq!(product.get_discount(), 0.0);
+/// assert_eq!(cart.get_total_price(), 19.0);
+/// ```
+pub trait CartItem: CloneIntoDynBox + fmt::Display + Send + Sync {
+    fn get_id(&self) -> &ItemId;
+    fn get_code(&self) -> &Code;
+    fn get_description(&self) -> String;
     fn get_products(&self) -> Vec<&ProductAmount>;
     fn get_amount(&self) -> f64;
-    fn get_variant<'a>(&self) -> CartItemVariant;
+    fn get_variant(&self) -> CartItemVariant<'_>;
 
     fn is_product(&self) -> bool {
-        match self.get_variant() {
-            CartItemVariant::Product(_) => true,
-            _ => false,
-        }
+        matches!(self.get_variant(), CartItemVariant::Product(_))
+    }
+
+    fn is_promotion(&self) -> bool {
+        matches!(self.get_variant(), CartItemVariant::Promotion(_))
+    }
+
+    fn is_custom(&self) -> bool {
+        matches!(self.get_variant(), CartItemVariant::Custom(_))
+    }
+
+    /// Price of one unit of the item: the price of the product, or of one application of the
+    /// promotion
+    fn get_unit_price(&self) -> f64;
+
+    /// Price of one unit of the item at the regular prices of its products, before any
+    /// promotion
+    fn get_regular_unit_price(&self) -> f64;
+
+    /// Price of all the units of the item
+    fn get_line_total(&self) -> f64 {
+        self.get_unit_price() * self.get_amount()
+    }
+
+    /// Amount saved on all the units of the item, compared to their regular price
+    fn get_discount(&self) -> f64 {
+        (self.get_regular_unit_price() - self.get_unit_price()) * self.get_amount()
+    }
+}
+
+/// Item of a kind defined outside the crate, such as a fee, a warranty or a service, added with
+/// [Cart::push_custom_item](crate::prelude::Cart::push_custom_item)
+///
+/// Custom items count in the totals, are printed and rendered on receipts as any other item,
+/// and are sold under their code. The optimizer and [Cart::undo](crate::prelude::Cart::undo)
+/// leave them as they are, and their products, if any, are not available to promotions
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "json")]
+/// # fn main() {
+/// use serde::{Deserialize, Serialize};
+/// use std::fmt;
+/// use store_terminal::prelude::*;
+///
+/// #[derive(Clone, Serialize, Deserialize)]
+/// struct Warranty {
+///     #[serde(skip)]
+///     id: ItemId,
+///     code: Code,
+///     years: u32,
+///     price: f64,
+/// }
+///
+/// impl fmt::Display for Warranty {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         write!(f, "{}: {} years = {:.2}", self.code, self.years, self.price)
+///     }
+/// }
+///
+/// impl CartItem for Warranty {
+///     fn get_id(&self) -> &ItemId {
+///         &self.id
+///     }
+///     fn get_code(&self) -> &Code {
+///         &self.code
+///     }
+///     fn get_description(&self) -> String {
+///         format!("{} years warranty", self.years)
+///     }
+///     fn get_products(&self) -> Vec<&ProductAmount> {
+///         vec![]
+///     }
+///     fn get_amount(&self) -> f64 {
+///         1.0
+///     }
+///     fn get_variant(&self) -> CartItemVariant<'_> {
+///         CartItemVariant::Custom(self)
+///     }
+///     fn get_unit_price(&self) -> f64 {
+///         self.price
+///     }
+///     fn get_regular_unit_price(&self) -> f64 {
+///         self.price
+///     }
+/// }
+///
+/// impl CustomCartItem for Warranty {
+///     fn get_kind(&self) -> &str {
+///         "warranty"
+///     }
+///     fn get_tax_class(&self) -> Option<&str> {
+///         Some("services")
+///     }
+///     fn to_json(&self) -> Result<serde_json::Value, ErrorVariant> {
+///         serde_json::to_value(self).map_err(|e| ErrorVariant::from_json_error(e, "warranty"))
+///     }
+/// }
+///
+/// let terminal = TerminalBuilder::new()
+///     .with_cart_item_kinds(CartItemKinds::new().with_kind::<Warranty>("warranty"))
+///     .build()
+///     .unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AAAAB").unwrap();
+/// terminal
+///     .push_item(Warranty { id: ItemId::new(), code: Code::from("W2"), years: 2, price: 3.5 })
+///     .unwrap();
+///
+/// let expected = "\
+/// B: 1 x 12.00 = 12.00
+/// PA: 1 x 7.00 = 7.00
+///   4 x A @ 2.00
+/// W2: 2 years = 3.50
+/// Total: 22.50";
+/// assert_eq!(terminal.get_cart().unwrap().to_string(), expected);
+///
+/// let path = std::env::temp_dir().join(format!("warranty-{}.json", std::process::id()));
+/// terminal.save_state(&path).unwrap();
+/// terminal.reset_cart().unwrap();
+/// assert!(terminal.restore_state(&path).unwrap());
+/// std::fs::remove_file(&path).unwrap();
+///
+/// let sale = terminal.checkout().unwrap();
+/// assert_eq!(sale.get_totals().get_total(), 22.5);
+/// let warranty = sale.get_products().iter().find(|p| p.get_code() == "W2").unwrap();
+/// assert_eq!(warranty.get_tax_class(), Some("services"));
+/// # }
+/// # #[cfg(not(feature = "json"))]
+/// # fn main() {}
+/// ```
+pub trait CustomCartItem: CartItem {
+    /// Name of the kind of the item, under which it is saved and read back by [CartItemKinds]
+    fn get_kind(&self) -> &str;
+
+    /// Tax class of the item, the standard rate of the tax table if none
+    fn get_tax_class(&self) -> Option<&str> {
+        None
     }
 
-    fn get_price(&self) -> f64 {
-        self.get_products()
-            .iter()
-            .fold(0.0, |price, p| price + p.get_price())
+    /// Contents of the item, saved with the state of the terminal
+    #[cfg(feature = "json")]
+    fn to_json(&self) -> Result<serde_json::Value, ErrorVariant>;
+}
+
+#[cfg(feature = "json")]
+type ItemReader =
+    Arc<dyn Fn(serde_json::Value) -> Result<Box<dyn CartItem>, ErrorVariant> + Send + Sync>;
+
+/// Kinds of [CustomCartItem] a cart reads back when its saved state is restored, registered at
+/// runtime with [TerminalBuilder::with_cart_item_kinds](crate::prelude::TerminalBuilder::with_cart_item_kinds)
+#[cfg(feature = "json")]
+#[derive(Clone, Default)]
+pub struct CartItemKinds {
+    readers: HashMap<String, ItemReader>,
+}
+
+#[cfg(feature = "json")]
+impl CartItemKinds {
+    pub fn new() -> Self {
+        CartItemKinds::default()
+    }
+
+    /// Read the items of `kind` as `I`, from the contents written by [CustomCartItem::to_json]
+    pub fn with_kind<I>(mut self, kind: &str) -> Self
+    where
+        I: CustomCartItem + DeserializeOwned + 'static,
+    {
+        let reader: ItemReader = Arc::new(|item| {
+            serde_json::from_value::<I>(item)
+                .map(|item| Box::new(item) as Box<dyn CartItem>)
+                .map_err(|e| ErrorVariant::from_json_error(e, "cart item"))
+        });
+        self.readers.insert(kind.to_string(), reader);
+        self
     }
 
-    fn get_total(&self) -> f64 {
-        self.get_amount() * self.get_price()
+    /// Registered kinds, sorted
+    pub fn get_kinds(&self) -> Vec<&str> {
+        let mut kinds: Vec<&str> = self.readers.keys().map(String::as_str).collect();
+        kinds.sort_unstable();
+        kinds
     }
 
-    fn get_total_discount(&self) -> f64 {
-        self.get_price() * self.get_amount() - self.get_total()
+    /// Item of `kind` read from its contents
+    pub(crate) fn read(
+        &self,
+        kind: &str,
+        item: serde_json::Value,
+    ) -> Result<Box<dyn CartItem>, ErrorVariant> {
+        let reader = self
+            .readers
+            .get(kind)
+            .ok_or_else(|| ErrorVariant::EntityNotFound {
+                store: "cart item kinds".to_string(),
+                code: kind.to_string(),
+            })?;
+        reader(item)
     }
 }
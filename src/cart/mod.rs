@@ -1,92 +1,378 @@
+use crate::clock::{IdGenerator, RandomIds};
+use crate::gift_card::GIFT_CARD_CODE;
+use crate::kiosk::Hold;
+use crate::loyalty::{LoyaltyRedemption, LOYALTY_CODE};
 use crate::prelude::{
-    CartGroupFuture, CartItem, CartItemProduct, CartItemPromotion, Database, ErrorVariant,
-    Optimizer, ProductAmount, ProductAmountGroupFuture,
+    Alignment, CartGroupFuture, CartItem, CartItemProduct, CartItemPromotion, Code, CustomCartItem,
+    Customer, Database, DatabaseReader, DefaultOptimizerStrategy, ErrorVariant, GiftCardLoad,
+    ItemId, OptimizerStrategy, ProductAmount, SavingsReport, Table,
 };
-use futures::prelude::*;
+#[cfg(feature = "json")]
+use crate::prelude::{CartItemKinds, CartItemVariant, Product};
+use crate::receipt::round_cents;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::sync::Arc;
 
 pub mod fut;
 pub mod item;
 pub mod optimizer;
 pub mod optimizer_candidate;
+pub mod savings;
+
+/// Scans, customer, gift card loads and redeemed points of a cart, enough to build it again
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CartState {
+    history: Vec<ProductAmount>,
+    customer: Option<Customer>,
+    gift_card_loads: Vec<GiftCardLoad>,
+    loyalty_redemption: Option<LoyaltyRedemption>,
+    #[serde(default)]
+    approved: Vec<Code>,
+    #[serde(default)]
+    custom_items: Vec<CustomItemState>,
+}
+
+/// [CustomCartItem] of a [CartState], read back by the reader of its kind
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomItemState {
+    kind: String,
+    item: serde_json::Value,
+}
 
 #[derive(Clone)]
 pub struct Cart {
-    database: Database,
+    database: DatabaseReader,
     items: Vec<Box<dyn CartItem>>,
+    history: Vec<ProductAmount>,
+    strategy: Arc<dyn OptimizerStrategy>,
+    ids: Arc<dyn IdGenerator>,
+    #[cfg(feature = "json")]
+    kinds: Arc<CartItemKinds>,
+    customer: Option<Customer>,
+    gift_card_loads: Vec<GiftCardLoad>,
+    loyalty_redemption: Option<LoyaltyRedemption>,
+    /// Restricted products approved by a supervisor
+    approved: Vec<Code>,
+    /// Database revision of the last optimization, if the items didn't change since then
+    optimized_revision: Option<u64>,
+    /// Counter increased by every change of the items or of the optimization settings
+    generation: u64,
 }
 
 impl Cart {
     pub fn new(database: Database) -> Self {
         let items = vec![];
-        Cart { database, items }
+        let history = vec![];
+        let strategy = Arc::new(DefaultOptimizerStrategy);
+        Cart {
+            database: database.reader(),
+            items,
+            history,
+            strategy,
+            ids: Arc::new(RandomIds),
+            #[cfg(feature = "json")]
+            kinds: Arc::default(),
+            customer: None,
+            gift_card_loads: vec![],
+            loyalty_redemption: None,
+            approved: vec![],
+            optimized_revision: None,
+            generation: 0,
+        }
+    }
+
+    /// Read-only view of the database of the cart
+    pub fn get_database(&self) -> &DatabaseReader {
+        &self.database
+    }
+
+    pub(crate) fn set_database(&mut self, database: Database) {
+        self.database = database.reader();
+        self.invalidate_optimization();
+    }
+
+    /// Record a change of the cart, discarding the cached optimization
+    pub(crate) fn invalidate_optimization(&mut self) {
+        self.optimized_revision = None;
+        self.generation += 1;
+    }
+
+    /// Counter of the changes of the cart, to detect a concurrent change of a snapshot
+    pub(crate) fn get_generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn set_optimizer_strategy(&mut self, strategy: Arc<dyn OptimizerStrategy>) {
+        self.strategy = strategy;
+        self.invalidate_optimization();
+    }
+
+    /// Generator of the identifiers of the items added from now on
+    pub fn set_id_generator(&mut self, ids: Arc<dyn IdGenerator>) {
+        self.ids = ids;
+    }
+
+    /// Kinds of the custom items read back when a saved state is restored
+    #[cfg(feature = "json")]
+    pub fn set_item_kinds(&mut self, kinds: Arc<CartItemKinds>) {
+        self.kinds = kinds;
+    }
+
+    /// Identify the customer of the sale, or forget it with `None`, optimizing the cart again
+    ///
+    /// Points redeemed by another customer are given up
+    pub fn set_customer(&mut self, customer: Option<Customer>) {
+        if self.customer.as_ref().map(|c| c.get_id()) != customer.as_ref().map(|c| c.get_id()) {
+            self.loyalty_redemption = None;
+        }
+        self.customer = customer;
+        self.invalidate_optimization();
+    }
+
+    pub fn get_customer(&self) -> Option<&Customer> {
+        self.customer.as_ref()
+    }
+
+    /// Gift cards sold by the cart, loaded when it is checked out
+    pub fn get_gift_card_loads(&self) -> &Vec<GiftCardLoad> {
+        &self.gift_card_loads
+    }
+
+    /// Redeem loyalty points as a discount of the cart, or give them up with `None`
+    pub fn set_loyalty_redemption(&mut self, redemption: Option<LoyaltyRedemption>) {
+        self.loyalty_redemption = redemption;
+        self.generation += 1;
+    }
+
+    pub fn get_loyalty_redemption(&self) -> Option<&LoyaltyRedemption> {
+        self.loyalty_redemption.as_ref()
+    }
+
+    /// Restricted products scanned into the cart and not approved yet, in the order they were
+    /// first scanned
+    ///
+    /// Holds only block the checkouts of kiosks, see
+    /// [TerminalConfig::with_kiosk_mode](crate::prelude::TerminalConfig::with_kiosk_mode)
+    pub fn get_holds(&self) -> Vec<Hold> {
+        let mut holds: Vec<Hold> = vec![];
+        for scan in &self.history {
+            let code = scan.get_code();
+            if let Some(restriction) = scan.get_product().get_restriction() {
+                if !self.is_approved(code) && holds.iter().all(|h| h.get_code() != code) {
+                    holds.push(Hold::new(code.clone(), restriction));
+                }
+            }
+        }
+        holds
+    }
+
+    /// Whether the restricted product `code` was approved for the sale
+    pub fn is_approved(&self, code: &str) -> bool {
+        self.approved.iter().any(|c| c == code)
+    }
+
+    /// Approve the restricted products held, returning them
+    #[cfg(feature = "json")]
+    pub(crate) fn approve_holds(&mut self) -> Vec<Hold> {
+        let holds = self.get_holds();
+        self.approved
+            .extend(holds.iter().map(|hold| hold.get_code().clone()));
+        holds
+    }
+
+    /// Discount of the redeemed loyalty points, up to the price of the items
+    pub fn get_loyalty_discount(&self) -> f64 {
+        self.loyalty_redemption
+            .map_or(0.0, |r| r.get_value().min(self.get_items_price()).max(0.0))
     }
 
     pub fn get_items(&self) -> &Vec<Box<dyn CartItem>> {
         &self.items
     }
 
+    /// Price of the items, less the redeemed loyalty points
     pub fn get_total_price(&self) -> f64 {
-        self.get_items().iter().map(|i| i.get_total()).sum()
+        match self.get_loyalty_discount() {
+            discount if discount > 0.0 => round_cents(self.get_items_price() - discount),
+            _ => self.get_items_price(),
+        }
     }
 
-    pub fn get_products(&self) -> Vec<ProductAmount> {
-        let items: Vec<Box<dyn CartItem>> = self
-            .get_items()
+    fn get_items_price(&self) -> f64 {
+        self.get_items()
             .iter()
-            .filter(|item| item.is_product())
-            .map(|item| item.clone())
-            .collect();
+            .fold(0.0, |total, i| total + i.get_line_total())
+    }
 
-        let mut products: Vec<ProductAmount> = vec![];
-        items.iter().for_each(|item| {
-            item.get_products()
-                .iter()
-                .for_each(|&p| products.push(p.clone()));
-        });
+    /// Number of product units in the cart, including the ones consumed by promotions
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AAAAAB").unwrap();
+    ///
+    /// let cart = terminal.get_cart().unwrap();
+    /// assert_eq!(cart.get_units().unwrap(), 6.0);
+    /// ```
+    pub fn get_units(&self) -> Result<f64, ErrorVariant> {
+        let products = self.get_flat_quantities();
+        Ok(products.iter().fold(0.0, |units, p| units + p.get_amount()))
+    }
 
-        products
+    pub fn get_products(&self) -> Vec<ProductAmount> {
+        self.get_items()
+            .iter()
+            .filter(|item| item.is_product())
+            .flat_map(|item| item.get_products())
+            .cloned()
+            .collect()
     }
 
-    pub fn remove_all_products(&mut self) {
-        let items: Vec<Box<dyn CartItem>> = self
-            .get_items()
+    /// Amount of the product `code` scanned into the cart
+    pub fn get_scanned_amount(&self, code: &str) -> f64 {
+        self.history
             .iter()
-            .filter(|item| !item.is_product())
-            .map(|item| item.clone())
-            .collect();
+            .filter(|p| p.get_code() == code)
+            .fold(0.0, |amount, p| amount + p.get_amount())
+    }
 
-        self.items = items;
+    pub fn remove_all_products(&mut self) {
+        self.items.retain(|item| !item.is_product());
+        self.invalidate_optimization();
     }
 
-    pub fn push_product(&mut self, code: &String, amount: f64) -> Result<(), ErrorVariant> {
+    pub fn push_product(&mut self, code: &str, amount: f64) -> Result<(), ErrorVariant> {
         let product = self.database.fetch_product(code)?;
-        let cart_item_product = CartItemProduct::new(product.clone(), amount);
+        self.history.push(product.generate_amount(amount));
+        let cart_item_product = CartItemProduct::with_id(self.ids.next_id(), product, amount);
         self.items.push(Box::new(cart_item_product));
+        self.invalidate_optimization();
         Ok(())
     }
 
+    /// Sell a gift card loading `amount`, as a line of `amount` units of
+    /// [GIFT_CARD_CODE](crate::gift_card::GIFT_CARD_CODE) priced 1.0, reverted by [Cart::undo]
+    #[cfg(feature = "json")]
+    pub fn push_gift_card(&mut self, number: &str, amount: f64) {
+        let product = Product::new(GIFT_CARD_CODE, 1.0);
+        self.history.push(product.generate_amount(amount));
+        self.push_product_amount(product.generate_amount(amount));
+        self.gift_card_loads.push(GiftCardLoad::new(number, amount));
+    }
+
+    /// Revert the last [Cart::push_product], returning the removed product amount
+    ///
+    /// Promotions are unwrapped into their products, and will be applied again by the next
+    /// [Cart::optimize_promotions]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let terminal = TerminalBuilder::new().with_database(database.clone()).build().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let mut cart = Cart::new(database.clone());
+    /// cart.push_product("A", 4.0).unwrap();
+    /// cart.push_product("B", 1.0).unwrap();
+    /// cart.push_product("A", 1.0).unwrap();
+    /// assert_eq!(cart.optimize_promotions().unwrap().get_total_price(), 21.0);
+    ///
+    /// let undone = cart.undo().unwrap().unwrap();
+    /// assert_eq!(undone.get_code(), "A");
+    /// assert_eq!(cart.optimize_promotions().unwrap().get_total_price(), 19.0);
+    ///
+    /// cart.undo().unwrap();
+    /// cart.undo().unwrap();
+    /// assert!(cart.undo().unwrap().is_none());
+    /// assert!(cart.get_items().is_empty());
+    /// ```
+    pub fn undo(&mut self) -> Result<Option<ProductAmount>, ErrorVariant> {
+        let last = match self.history.pop() {
+            Some(last) => last,
+            None => return Ok(None),
+        };
+
+        if last.get_code() == GIFT_CARD_CODE {
+            self.gift_card_loads.pop();
+        }
+
+        let mut products = self.get_flat_quantities();
+        let index = ProductAmount::get_index_of_product(&products, last.get_code())?;
+        products[index].dec_amount(*last.get_amount())?;
+
+        let custom = self.take_custom_items();
+        products
+            .into_iter()
+            .filter(|p| p.get_amount() > &0.0)
+            .for_each(|p| self.push_product_amount(p));
+        self.items.extend(custom);
+        self.invalidate_optimization();
+
+        Ok(Some(last))
+    }
+
     pub fn push_product_amount(&mut self, product_amount: ProductAmount) {
         let product = product_amount.get_product().clone();
         let amount = *product_amount.get_amount();
-        let cart_item_product = CartItemProduct::new(product, amount);
+        let cart_item_product = CartItemProduct::with_id(self.ids.next_id(), product, amount);
         self.items.push(Box::new(cart_item_product));
+        self.invalidate_optimization();
+    }
+
+    /// Add an item of a kind defined outside the crate, such as a fee or a warranty
+    ///
+    /// See [CustomCartItem] for an example
+    pub fn push_custom_item<I: CustomCartItem + 'static>(&mut self, item: I) {
+        self.items.push(Box::new(item));
+        self.invalidate_optimization();
     }
 
-    pub fn push_promotion(&mut self, code: &String, amount: f64) -> Result<(), ErrorVariant> {
+    /// Remove the custom item `id`, returning it
+    pub fn remove_custom_item(&mut self, id: &ItemId) -> Option<Box<dyn CartItem>> {
+        let index = self
+            .items
+            .iter()
+            .position(|item| item.is_custom() && item.get_id() == id)?;
+        self.invalidate_optimization();
+        Some(self.items.remove(index))
+    }
+
+    /// Empty the items of the cart, returning its custom items
+    fn take_custom_items(&mut self) -> Vec<Box<dyn CartItem>> {
+        std::mem::take(&mut self.items)
+            .into_iter()
+            .filter(|item| item.is_custom())
+            .collect()
+    }
+
+    pub fn push_promotion(&mut self, code: &str, amount: f64) -> Result<(), ErrorVariant> {
         let promotion = self.database.fetch_promotion(code)?;
-        let cart_item_promotion = CartItemPromotion::new(promotion.clone(), amount);
+        let cart_item_promotion = CartItemPromotion::with_id(self.ids.next_id(), promotion, amount);
         self.items.push(Box::new(cart_item_promotion));
+        self.invalidate_optimization();
         Ok(())
     }
 
     pub fn consume_available_products_for_promotion(
         &mut self,
-        promotion_code: &String,
+        promotion_code: &str,
     ) -> Result<(), ErrorVariant> {
         let promotion = self.database.fetch_promotion(promotion_code)?;
         let products = self.get_products();
-        let products = ProductAmountGroupFuture::new(products).wait()?;
+        let products = ProductAmount::group(products);
         let products = promotion.consume_items(products)?;
         self.remove_all_products();
         for p in products {
@@ -95,44 +381,336 @@ impl Cart {
         Ok(())
     }
 
+    pub fn get_savings_report(&self) -> SavingsReport {
+        SavingsReport::new(self)
+    }
+
+    /// Group all items of a cart into an optimal size vec of ProductAmount
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let mut database = Database::new();
+    ///
+    /// database.append(Product::new("Foo".to_string(), 1.0));
+    /// database.append(Product::new("Bar".to_string(), 2.0));
+    ///
+    /// let mut cart = Cart::new(database);
+    /// cart.push_product("Foo", 15.0).unwrap();
+    /// cart.push_product("Bar", 35.0).unwrap();
+    /// cart.push_product("Foo", 4.0).unwrap();
+    /// cart.push_product("Foo", 12.0).unwrap();
+    ///
+    /// let mut v_min = vec![];
+    /// v_min.push(ProductAmount::new(Product::new("Foo".to_string(), 1.0), 31.0));
+    /// v_min.push(ProductAmount::new(Product::new("Bar".to_string(), 1.0), 35.0));
+    ///
+    /// assert_eq!(cart.get_flat_quantities(), v_min);
+    /// ```
+    pub fn get_flat_quantities(&self) -> Vec<ProductAmount> {
+        fut::group_items(&self.items)
+    }
+
+    /// Future of [Cart::get_flat_quantities], grouped when created without copying the items
+    ///
+    /// Promotions are unwrapped into their products, and products are in the order they first
+    /// appear in the cart
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::future::Future;
+    /// # use std::sync::Arc;
+    /// # use std::task::{Context, Poll, Wake, Waker};
+    /// # struct Noop;
+    /// # impl Wake for Noop {
+    /// #     fn wake(self: Arc<Self>) {}
+    /// # }
+    /// # fn block_on<F: Future>(future: F) -> F::Output {
+    /// #     let waker = Waker::from(Arc::new(Noop));
+    /// #     let mut cx = Context::from_waker(&waker);
+    /// #     let mut future = Box::pin(future);
+    /// #     loop {
+    /// #         if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+    /// #             return output;
+    /// #         }
+    /// #     }
+    /// # }
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let terminal = TerminalBuilder::new().with_database(database.clone()).build().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let mut cart = Cart::new(database);
+    /// cart.push_product("B", 1.0).unwrap();
+    /// cart.push_promotion("PA", 1.0).unwrap();
+    /// cart.push_product("A", 1.0).unwrap();
+    ///
+    /// let future = cart.get_flat_quantities_future();
+    /// cart.push_product("C", 1.0).unwrap();
+    ///
+    /// let products = block_on(future).unwrap();
+    /// let products: Vec<(&str, f64)> = products
+    ///     .iter()
+    ///     .map(|p| (p.get_code().as_str(), *p.get_amount()))
+    ///     .collect();
+    /// assert_eq!(products, [("B", 1.0), ("A", 5.0)]);
+    /// assert_eq!(cart.get_items().len(), 4);
+    /// ```
     pub fn get_flat_quantities_future(&self) -> CartGroupFuture {
-        CartGroupFuture::new(&self)
+        CartGroupFuture::new(self)
+    }
+
+    /// Whether the items already are the optimization for the current database revision
+    pub(crate) fn is_optimized(&self) -> bool {
+        self.optimized_revision == Some(self.database.get_revision())
     }
 
-    /// Optimize the cart items composition with [Optimizer](crate::cart::optimizer::Optimizer)
+    /// Optimize the cart items composition with its [OptimizerStrategy], by default the
+    /// [Optimizer](crate::cart::optimizer::Optimizer)
+    ///
+    /// Promotions already in the cart are unwrapped and optimized again, so the operation is
+    /// idempotent. The result is cached until the items or the database change
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let terminal = TerminalBuilder::new().with_database(database.clone()).build().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let mut cart = Cart::new(database.clone());
+    /// cart.push_product("A", 5.0).unwrap();
+    ///
+    /// assert_eq!(cart.optimize_promotions().unwrap().get_total_price(), 9.0);
+    /// assert_eq!(cart.optimize_promotions().unwrap().get_total_price(), 9.0);
+    /// assert_eq!(cart.get_items().len(), 2);
+    ///
+    /// let promotion = database.fetch_promotion("PA").unwrap();
+    /// database.update(promotion.with_new_pricing(5.0).unwrap()).unwrap();
+    /// assert_eq!(cart.optimize_promotions().unwrap().get_total_price(), 7.0);
+    /// ```
+    ///
+    /// The strategy only runs again once the cart or the database changed
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use store_terminal::prelude::*;
+    ///
+    /// #[derive(Default)]
+    /// struct Counting(AtomicUsize);
+    ///
+    /// impl OptimizerStrategy for Counting {
+    ///     fn get_optimal_products_promotions(
+    ///         &self,
+    ///         products: Vec<ProductAmount>,
+    ///         database: &DatabaseReader,
+    ///     ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///         DefaultOptimizerStrategy.get_optimal_products_promotions(products, database)
+    ///     }
+    /// }
+    ///
+    /// let database = Database::new();
+    /// let terminal = TerminalBuilder::new().with_database(database.clone()).build().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let counting = Arc::new(Counting::default());
+    /// let mut cart = Cart::new(database.clone());
+    /// cart.set_optimizer_strategy(counting.clone());
+    /// cart.push_product("A", 4.0).unwrap();
+    ///
+    /// cart.optimize_promotions().unwrap();
+    /// cart.optimize_promotions().unwrap();
+    /// assert_eq!(counting.0.load(Ordering::SeqCst), 1);
+    ///
+    /// cart.push_product("B", 1.0).unwrap();
+    /// assert_eq!(cart.optimize_promotions().unwrap().get_total_price(), 19.0);
+    /// assert_eq!(counting.0.load(Ordering::SeqCst), 2);
+    ///
+    /// database.append(Product::new("E".to_string(), 1.0)).unwrap();
+    /// cart.optimize_promotions().unwrap();
+    /// assert_eq!(counting.0.load(Ordering::SeqCst), 3);
+    /// ```
     pub fn optimize_promotions(&mut self) -> Result<&Cart, ErrorVariant> {
-        let products = self.get_flat_quantities_future().wait()?;
-        let mut optimizer = Optimizer::new(products, self.database.clone());
-        let (products, promotions) = optimizer.get_optimal_products_promotions()?;
-        self.remove_all_products();
+        if self.is_optimized() {
+            return Ok(self);
+        }
+        let revision = self.database.get_revision();
+
+        let products = self.get_flat_quantities();
+        let (products, promotions) = self.strategy.get_optimal_customer_promotions(
+            products,
+            &self.database,
+            self.customer.as_ref(),
+        )?;
+        let custom = self.take_custom_items();
         products
             .iter()
             .for_each(|p| self.push_product_amount(p.clone()));
         for p in promotions {
             self.push_promotion(p.get_code(), 1.0)?;
         }
+        self.items.extend(custom);
+        self.optimized_revision = Some(revision);
         Ok(self)
     }
 
+    #[cfg(feature = "json")]
+    pub(crate) fn get_state(&self) -> Result<CartState, ErrorVariant> {
+        let custom_items = self
+            .items
+            .iter()
+            .filter_map(|item| match item.get_variant() {
+                CartItemVariant::Custom(custom) => Some(custom),
+                _ => None,
+            })
+            .map(|custom| {
+                Ok(CustomItemState {
+                    kind: custom.get_kind().to_string(),
+                    item: custom.to_json()?,
+                })
+            })
+            .collect::<Result<_, ErrorVariant>>()?;
+
+        Ok(CartState {
+            history: self.history.clone(),
+            customer: self.customer.clone(),
+            gift_card_loads: self.gift_card_loads.clone(),
+            loyalty_redemption: self.loyalty_redemption,
+            approved: self.approved.clone(),
+            custom_items,
+        })
+    }
+
+    /// Replace the contents of the cart with the scans of `state`, at the prices they were
+    /// scanned at, and its custom items
+    ///
+    /// Fails, leaving the cart as it was, if a custom item is of an unknown kind
+    #[cfg(feature = "json")]
+    pub(crate) fn set_state(&mut self, state: CartState) -> Result<(), ErrorVariant> {
+        let custom = state
+            .custom_items
+            .into_iter()
+            .map(|custom| self.kinds.read(&custom.kind, custom.item))
+            .collect::<Result<Vec<_>, ErrorVariant>>()?;
+
+        self.items = vec![];
+        for scan in &state.history {
+            self.push_product_amount(scan.clone());
+        }
+        self.items.extend(custom);
+        self.history = state.history;
+        self.customer = state.customer;
+        self.gift_card_loads = state.gift_card_loads;
+        self.loyalty_redemption = state.loyalty_redemption;
+        self.approved = state.approved;
+        self.invalidate_optimization();
+        Ok(())
+    }
+
     pub fn reset(&mut self) -> Result<(), ErrorVariant> {
         self.items = vec![];
+        self.history = vec![];
+        self.customer = None;
+        self.gift_card_loads = vec![];
+        self.loyalty_redemption = None;
+        self.approved = vec![];
+        self.invalidate_optimization();
         Ok(())
     }
+
+    /// Items of the cart, with their code, description, amount and total, and the cart total
+    #[cfg(any(feature = "ffi", feature = "server"))]
+    pub(crate) fn summary_json(&self) -> serde_json::Value {
+        let items: Vec<serde_json::Value> = self
+            .items
+            .iter()
+            .map(|item| {
+                serde_json::json!({
+                    "code": item.get_code(),
+                    "description": item.get_description(),
+                    "amount": item.get_amount(),
+                    "total": item.get_line_total(),
+                })
+            })
+            .collect();
+
+        serde_json::json!({ "items": items, "total": self.get_total_price() })
+    }
+
+    /// Tabular representation of the cart items, one row per item
+    pub fn to_table(&self) -> Table {
+        let mut table = Table::new(vec![
+            ("Code", Alignment::Left),
+            ("Description", Alignment::Left),
+            ("Qty", Alignment::Right),
+            ("Unit price", Alignment::Right),
+            ("Total", Alignment::Right),
+        ]);
+
+        for item in self.get_items() {
+            table.push_row(vec![
+                item.get_code().to_string(),
+                item.get_description(),
+                format!("{}", item.get_amount()),
+                format!("{:.2}", item.get_unit_price()),
+                format!("{:.2}", item.get_line_total()),
+            ]);
+        }
+        if let Some(redemption) = self.loyalty_redemption {
+            table.push_row(vec![
+                LOYALTY_CODE.to_string(),
+                "Loyalty points".to_string(),
+                format!("{}", redemption.get_points()),
+                String::new(),
+                format!("{:.2}", -self.get_loyalty_discount()),
+            ]);
+        }
+
+        table
+    }
 }
 
+/// One item per line, the products of promotions indented below them, followed by the
+/// redeemed loyalty points and the total
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AAAAB").unwrap();
+///
+/// let expected = "\
+/// B: 1 x 12.00 = 12.00
+/// PA: 1 x 7.00 = 7.00
+///   4 x A @ 2.00
+/// Total: 19.00";
+/// assert_eq!(terminal.get_cart().unwrap().to_string(), expected);
+/// ```
 impl fmt::Display for Cart {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let items_fmt = self
-            .get_items()
-            .iter()
-            .fold(String::from(""), |s, i| format!("{}\n{}", s, i));
-
-        write!(
-            f,
-            r#"Items: {}
-Total: {}"#,
-            items_fmt,
-            self.get_total_price()
-        )
+        for item in self.get_items() {
+            writeln!(f, "{}", item)?;
+        }
+        if let Some(redemption) = self.loyalty_redemption {
+            writeln!(
+                f,
+                "{}: {} points = {:.2}",
+                LOYALTY_CODE,
+                redemption.get_points(),
+                -self.get_loyalty_discount()
+            )?;
+        }
+        write!(f, "Total: {:.2}", self.get_total_price())
     }
 }
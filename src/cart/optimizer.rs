@@ -1,28 +1,97 @@
-use crate::prelude::{Database, ErrorVariant, OptimizerCandidate, ProductAmount, Promotion};
+use crate::prelude::{
+    Code, Customer, DatabaseReader, ErrorVariant, OptimizerCandidate, ProductAmount, Promotion,
+};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone)]
+/// Timed step of an optimization, reported to an [OptimizerTracer]
+///
+/// The optimizer works in rounds, numbered from 1: every round fetches the promotions the
+/// remaining products may take, then simulates each of them, keeping the cheapest candidate
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptimizerSpan {
+    /// Whole optimization of `products` product lines, applying `promotions` in `rounds`
+    OptimizePromotions {
+        products: usize,
+        promotions: usize,
+        rounds: usize,
+        elapsed: Duration,
+    },
+    /// Fetch of the `promotions` possible in a round
+    FetchPossiblePromotions {
+        round: usize,
+        promotions: usize,
+        elapsed: Duration,
+    },
+    /// Simulation of promotion `code` in a round, `accepted` when it lowered the price
+    SimulatePromotion {
+        round: usize,
+        code: Code,
+        accepted: bool,
+        elapsed: Duration,
+    },
+}
+
+/// Receiver of the [OptimizerSpan]s of an [Optimizer], to find out where a slow basket spends
+/// its time
+///
+/// [Metrics](crate::metrics::Metrics) implements it, recording the duration of every step
+pub trait OptimizerTracer: Send + Sync {
+    fn record(&self, span: &OptimizerSpan);
+}
+
+/// Tracer shared with other parts of the application
+impl<T: OptimizerTracer + ?Sized> OptimizerTracer for Arc<T> {
+    fn record(&self, span: &OptimizerSpan) {
+        (**self).record(span)
+    }
+}
+
+#[derive(Clone)]
 pub struct Optimizer {
-    available_items: Vec<ProductAmount>,
-    maximum_price: f64,
-    depleted_options: Vec<Vec<Promotion>>,
-    database: Database,
+    database: DatabaseReader,
     candidate: OptimizerCandidate,
+    tracer: Option<Arc<dyn OptimizerTracer>>,
+}
+
+impl fmt::Debug for Optimizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Optimizer")
+            .field("database", &self.database)
+            .field("candidate", &self.candidate)
+            .field("traced", &self.tracer.is_some())
+            .finish()
+    }
 }
 
 impl Optimizer {
-    pub fn new(available_items: Vec<ProductAmount>, database: Database) -> Self {
-        let maximum_price = available_items.iter().map(|i| i.get_total_price()).sum();
-        let depleted_options = vec![];
-        let candidate = OptimizerCandidate::new(vec![], available_items.clone());
+    pub fn new(available_items: Vec<ProductAmount>, database: DatabaseReader) -> Self {
+        let candidate = OptimizerCandidate::new(vec![], available_items);
         Optimizer {
-            available_items,
-            maximum_price,
-            depleted_options,
             database,
             candidate,
+            tracer: None,
+        }
+    }
+
+    /// Report the steps of the optimization to `tracer`
+    pub fn with_tracer(mut self, tracer: Arc<dyn OptimizerTracer>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    fn trace(&self, span: OptimizerSpan) {
+        if let Some(tracer) = &self.tracer {
+            tracer.record(&span);
         }
     }
 
+    /// Start of a span, read only when traced as WebAssembly builds have no monotonic clock
+    fn start(&self) -> Option<Instant> {
+        self.tracer.as_ref().map(|_| Instant::now())
+    }
+
     /// Return a tuple with the optimal combination for products x promotions
     ///
     /// # Example
@@ -37,65 +106,228 @@ impl Optimizer {
     /// database.append(Product::new("C".to_string(), 1.25)).unwrap();
     /// database.append(Product::new("D".to_string(), 0.15)).unwrap();
     ///
-    /// let products = vec![database.code_to_product_amount("A".to_string(), 4.0).unwrap()];
+    /// let products = vec![database.code_to_product_amount("A", 4.0).unwrap()];
     /// let promotion = Promotion::new("PA".to_string(), products, 7.0).unwrap();
     /// database.append(promotion).unwrap();
     ///
-    /// let products = vec![database.code_to_product_amount("C".to_string(), 6.0).unwrap()];
+    /// let products = vec![database.code_to_product_amount("C", 6.0).unwrap()];
     /// let promotion = Promotion::new("PC".to_string(), products, 6.0).unwrap();
     /// database.append(promotion).unwrap();
     ///
     /// let mut cart = Cart::new(database.clone());
-    /// cart.push_product(&"A".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"B".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"C".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"D".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"A".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"B".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"A".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"A".to_string(), 1.0).unwrap();
+    /// cart.push_product("A", 1.0).unwrap();
+    /// cart.push_product("B", 1.0).unwrap();
+    /// cart.push_product("C", 1.0).unwrap();
+    /// cart.push_product("D", 1.0).unwrap();
+    /// cart.push_product("A", 1.0).unwrap();
+    /// cart.push_product("B", 1.0).unwrap();
+    /// cart.push_product("A", 1.0).unwrap();
+    /// cart.push_product("A", 1.0).unwrap();
     /// cart.optimize_promotions().unwrap();
     /// assert_eq!(cart.get_total_price(), 32.4);
     ///
     /// let mut cart = Cart::new(database.clone());
-    /// cart.push_product(&"C".to_string(), 7.0).unwrap();
+    /// cart.push_product("C", 7.0).unwrap();
     /// cart.optimize_promotions().unwrap();
     /// assert_eq!(cart.get_total_price(), 7.25);
     ///
     /// let mut cart = Cart::new(database.clone());
-    /// cart.push_product(&"A".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"B".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"C".to_string(), 1.0).unwrap();
-    /// cart.push_product(&"D".to_string(), 1.0).unwrap();
+    /// cart.push_product("A", 1.0).unwrap();
+    /// cart.push_product("B", 1.0).unwrap();
+    /// cart.push_product("C", 1.0).unwrap();
+    /// cart.push_product("D", 1.0).unwrap();
     /// cart.optimize_promotions().unwrap();
     /// assert_eq!(cart.get_total_price(), 15.4);
     /// ```
     pub fn get_optimal_products_promotions(
         &mut self,
     ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+        let start = self.start();
+        let products = self.candidate.get_products().len();
+        let rounds = self.optimize(1)?;
+        self.trace(OptimizerSpan::OptimizePromotions {
+            products,
+            promotions: self.candidate.get_promotions().len(),
+            rounds,
+            elapsed: elapsed(start),
+        });
+
+        let products = self.candidate.get_products().clone();
+        let promotions = self.candidate.get_promotions().clone();
+        Ok((products, promotions))
+    }
+
+    /// Improve the candidate from `round` on, returning the number of the last round
+    fn optimize(&mut self, round: usize) -> Result<usize, ErrorVariant> {
+        let start = self.start();
         let possible_promotions = self.database.fetch_possible_promotions_with_maximum_price(
             &self.candidate.get_products().iter().collect(),
-            self.candidate.get_price().clone(),
+            *self.candidate.get_price(),
         )?;
+        self.trace(OptimizerSpan::FetchPossiblePromotions {
+            round,
+            promotions: possible_promotions.len(),
+            elapsed: elapsed(start),
+        });
 
         if possible_promotions.is_empty() {
-            let products = self.candidate.get_products().clone();
-            let promotions = self.candidate.get_promotions().clone();
-            return Ok((products, promotions));
+            return Ok(round);
         }
 
         // TODO - Very simple A* algorithm; improve to cover all possible permutations
         for prom in possible_promotions {
-            match self.candidate.simulate_promotion(prom) {
-                Ok(c) => {
-                    if c.get_price() < self.candidate.get_price() {
-                        self.candidate = c
-                    }
+            let start = self.start();
+            let code = prom.get_code().clone();
+            let mut accepted = false;
+            if let Ok(c) = self.candidate.simulate_promotion(prom) {
+                if c.get_price() < self.candidate.get_price() {
+                    self.candidate = c;
+                    accepted = true;
                 }
-                _ => (),
             }
+            self.trace(OptimizerSpan::SimulatePromotion {
+                round,
+                code,
+                accepted,
+                elapsed: elapsed(start),
+            });
+        }
+
+        self.optimize(round + 1)
+    }
+}
+
+/// Time since a traced `start`
+fn elapsed(start: Option<Instant>) -> Duration {
+    start.map_or(Duration::default(), |start| start.elapsed())
+}
+
+/// Algorithm used by a [Cart](crate::prelude::Cart) to combine its products into promotions
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// struct NoPromotions;
+///
+/// impl OptimizerStrategy for NoPromotions {
+///     fn get_optimal_products_promotions(
+///         &self,
+///         products: Vec<ProductAmount>,
+///         _database: &DatabaseReader,
+///     ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+///         Ok((products, vec![]))
+///     }
+/// }
+///
+/// let terminal = TerminalBuilder::new()
+///     .with_optimizer_strategy(NoPromotions)
+///     .build()
+///     .unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AAAA").unwrap();
+///
+/// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 8.0);
+/// ```
+pub trait OptimizerStrategy: Send + Sync {
+    /// Return a tuple with the optimal combination for products x promotions
+    fn get_optimal_products_promotions(
+        &self,
+        products: Vec<ProductAmount>,
+        database: &DatabaseReader,
+    ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant>;
+
+    /// Optimal combination for the sale of `customer`, if identified
+    ///
+    /// Strategies applying member or tier promotions and price lists override this method; by
+    /// default the customer is ignored
+    fn get_optimal_customer_promotions(
+        &self,
+        products: Vec<ProductAmount>,
+        database: &DatabaseReader,
+        _customer: Option<&Customer>,
+    ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+        self.get_optimal_products_promotions(products, database)
+    }
+}
+
+/// Strategy backed by the [Optimizer]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultOptimizerStrategy;
+
+impl OptimizerStrategy for DefaultOptimizerStrategy {
+    fn get_optimal_products_promotions(
+        &self,
+        products: Vec<ProductAmount>,
+        database: &DatabaseReader,
+    ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+        Optimizer::new(products, database.clone()).get_optimal_products_promotions()
+    }
+}
+
+/// [DefaultOptimizerStrategy] reporting the steps of its optimizations to a tracer
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Mutex;
+/// use store_terminal::cart::optimizer::{OptimizerSpan, OptimizerTracer, TracedOptimizerStrategy};
+/// use store_terminal::prelude::*;
+///
+/// #[derive(Default)]
+/// struct Spans(Mutex<Vec<OptimizerSpan>>);
+///
+/// impl OptimizerTracer for Spans {
+///     fn record(&self, span: &OptimizerSpan) {
+///         self.0.lock().unwrap().push(span.clone());
+///     }
+/// }
+///
+/// let spans = std::sync::Arc::new(Spans::default());
+/// let terminal = TerminalBuilder::new()
+///     .with_optimizer_strategy(TracedOptimizerStrategy::new(spans.clone()))
+///     .build()
+///     .unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AAAAB").unwrap();
+/// terminal.get_cart().unwrap();
+///
+/// let spans = spans.0.lock().unwrap();
+/// assert!(matches!(
+///     &spans[0],
+///     OptimizerSpan::FetchPossiblePromotions { round: 1, promotions: 1, .. }
+/// ));
+/// assert!(matches!(
+///     &spans[1],
+///     OptimizerSpan::SimulatePromotion { round: 1, accepted: true, code, .. } if code == "PA"
+/// ));
+/// assert!(matches!(
+///     spans.last().unwrap(),
+///     OptimizerSpan::OptimizePromotions { products: 2, promotions: 1, rounds: 2, .. }
+/// ));
+/// ```
+#[derive(Clone)]
+pub struct TracedOptimizerStrategy {
+    tracer: Arc<dyn OptimizerTracer>,
+}
+
+impl TracedOptimizerStrategy {
+    pub fn new<T: OptimizerTracer + 'static>(tracer: T) -> Self {
+        TracedOptimizerStrategy {
+            tracer: Arc::new(tracer),
         }
+    }
+}
 
-        self.get_optimal_products_promotions()
+impl OptimizerStrategy for TracedOptimizerStrategy {
+    fn get_optimal_products_promotions(
+        &self,
+        products: Vec<ProductAmount>,
+        database: &DatabaseReader,
+    ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+        Optimizer::new(products, database.clone())
+            .with_tracer(Arc::clone(&self.tracer))
+            .get_optimal_products_promotions()
     }
 }
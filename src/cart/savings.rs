@@ -0,0 +1,124 @@
+use crate::prelude::{Cart, Code, ProductAmount};
+
+/// Savings of a single promotion applied to a cart
+#[derive(Debug, Clone)]
+pub struct PromotionSavings {
+    code: Code,
+    amount: f64,
+    products: Vec<ProductAmount>,
+    regular_price: f64,
+    promotional_price: f64,
+}
+
+impl PromotionSavings {
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    pub fn get_amount(&self) -> f64 {
+        self.amount
+    }
+
+    /// Products consumed by the promotion
+    pub fn get_products(&self) -> &Vec<ProductAmount> {
+        &self.products
+    }
+
+    pub fn get_regular_price(&self) -> f64 {
+        self.regular_price
+    }
+
+    pub fn get_promotional_price(&self) -> f64 {
+        self.promotional_price
+    }
+
+    pub fn get_savings(&self) -> f64 {
+        self.regular_price - self.promotional_price
+    }
+}
+
+/// Summary of the promotions applied to a cart
+#[derive(Debug, Clone)]
+pub struct SavingsReport {
+    promotions: Vec<PromotionSavings>,
+    discount: f64,
+    total_price: f64,
+}
+
+impl SavingsReport {
+    /// Build the report of a cart, usually after [Cart::optimize_promotions]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AAAAABCCCCCC").unwrap();
+    ///
+    /// let report = SavingsReport::new(&terminal.get_cart().unwrap());
+    ///
+    /// assert_eq!(report.get_promotions().len(), 2);
+    /// assert_eq!(report.get_regular_price(), 29.5);
+    /// assert_eq!(report.get_total_price(), 27.0);
+    /// assert_eq!(report.get_total_savings(), 2.5);
+    /// ```
+    pub fn new(cart: &Cart) -> Self {
+        let promotions = cart
+            .get_items()
+            .iter()
+            .filter(|item| item.is_promotion())
+            .map(|item| {
+                let amount = item.get_amount();
+                let products: Vec<ProductAmount> = item
+                    .get_products()
+                    .iter()
+                    .map(|&p| {
+                        let mut p = p.clone();
+                        p.inc_amount(p.get_amount() * (amount - 1.0));
+                        p
+                    })
+                    .collect();
+
+                PromotionSavings {
+                    code: item.get_code().clone(),
+                    amount,
+                    products,
+                    regular_price: item.get_regular_unit_price() * amount,
+                    promotional_price: item.get_line_total(),
+                }
+            })
+            .collect();
+
+        SavingsReport {
+            promotions,
+            discount: cart.get_loyalty_discount(),
+            total_price: cart.get_total_price(),
+        }
+    }
+
+    pub fn get_promotions(&self) -> &Vec<PromotionSavings> {
+        &self.promotions
+    }
+
+    /// Discount of the whole cart, from redeemed loyalty points
+    pub fn get_discount(&self) -> f64 {
+        self.discount
+    }
+
+    /// Price of the cart without any promotion or discount
+    pub fn get_regular_price(&self) -> f64 {
+        self.total_price + self.get_total_savings()
+    }
+
+    pub fn get_total_price(&self) -> f64 {
+        self.total_price
+    }
+
+    pub fn get_total_savings(&self) -> f64 {
+        self.promotions
+            .iter()
+            .fold(self.discount, |t, p| t + p.get_savings())
+    }
+}
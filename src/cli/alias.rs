@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+const MAXIMUM_EXPANSIONS: usize = 16;
+
+/// Command shorthands expanded before a line is interpreted
+///
+/// Keys may have more than one word; the longest key that prefixes the line wins and the
+/// expansion is repeated, so `c p` becomes `cart p` and then `cart print`
+#[derive(Debug, Clone)]
+pub struct Aliases {
+    map: HashMap<String, String>,
+}
+
+impl Aliases {
+    pub fn new(custom: HashMap<String, String>) -> Self {
+        let mut map: HashMap<String, String> = vec![
+            ("c", "cart"),
+            ("cart p", "cart print"),
+            ("cart r", "cart reset"),
+            ("cart s", "cart scan"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        custom.into_iter().for_each(|(k, v)| {
+            map.insert(normalize(&k), v);
+        });
+
+        Aliases { map }
+    }
+
+    pub fn get_map(&self) -> &HashMap<String, String> {
+        &self.map
+    }
+
+    pub fn expand(&self, line: &str) -> String {
+        let mut line = line.trim().to_string();
+
+        for _ in 0..MAXIMUM_EXPANSIONS {
+            match self.expand_once(&line) {
+                Some(expanded) => line = expanded,
+                None => break,
+            }
+        }
+
+        line
+    }
+
+    fn expand_once(&self, line: &str) -> Option<String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        (1..=words.len()).rev().find_map(|size| {
+            let key = normalize(&words[..size].join(" "));
+            self.map.get(&key).map(|expansion| {
+                let mut expanded = vec![expansion.as_str()];
+                expanded.extend_from_slice(&words[size..]);
+                expanded.join(" ")
+            })
+        })
+    }
+}
+
+impl Default for Aliases {
+    fn default() -> Self {
+        Aliases::new(HashMap::new())
+    }
+}
+
+fn normalize(key: &str) -> String {
+    key.split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
@@ -0,0 +1,61 @@
+use crate::cli::context::Verbosity;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Disable colored output
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Path of the configuration file
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Print only results and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Report every scanned code
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// Catalog to load instead of the demo products and promotions
+    #[arg(long, global = true)]
+    pub db: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Args {
+    pub fn get_verbosity(&self) -> Verbosity {
+        match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (_, true) => Verbosity::Verbose,
+            _ => Verbosity::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the interactive session (default)
+    Repl,
+
+    /// Scan the given codes into an empty cart and print the result
+    Scan {
+        /// Sets of codes to scan
+        #[arg(required_unless_present = "file")]
+        codes: Vec<String>,
+
+        /// File with one code (optionally with a quantity) per line
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Print only the optimized cart total
+        #[arg(long)]
+        print_total: bool,
+    },
+}
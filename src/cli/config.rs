@@ -0,0 +1,82 @@
+use crate::cli::alias::Aliases;
+use crate::cli::locale::{Locale, LocaleConfig};
+use crate::cli::messages::Messages;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_CONFIG_PATH: &str = "store-terminal.json";
+
+/// Configuration file of the command-line interface
+///
+/// ```json
+/// {"aliases": {"t": "cart print", "x": "checkout"}, "locale": "pt-BR", "language": "pt-BR"}
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    aliases: HashMap<String, String>,
+    locale: Option<LocaleConfig>,
+    language: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    aliases: Aliases,
+    locale: Locale,
+    messages: Messages,
+}
+
+impl Config {
+    /// Load the configuration from `path`, or from [DEFAULT_CONFIG_PATH] if it exists
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let path = match path {
+            Some(p) => p.to_path_buf(),
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => PathBuf::from(DEFAULT_CONFIG_PATH),
+            None => return Ok(Config::default()),
+        };
+
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("unable to read `{}`: {}", path.display(), e))?;
+        let file: ConfigFile = serde_json::from_str(&contents)
+            .map_err(|e| format!("unable to parse `{}`: {}", path.display(), e))?;
+
+        let locale = match file.locale {
+            Some(locale) => Locale::from_config(locale)
+                .map_err(|e| format!("invalid locale in `{}`: {}", path.display(), e))?,
+            None => Locale::default(),
+        };
+
+        let messages = match file.language {
+            Some(language) => Messages::from_language(&language).ok_or_else(|| {
+                format!(
+                    "unknown language `{}` in `{}`, expected one of {}",
+                    language,
+                    path.display(),
+                    Messages::get_languages().join(", ")
+                )
+            })?,
+            None => Messages::default(),
+        };
+
+        Ok(Config {
+            aliases: Aliases::new(file.aliases),
+            locale,
+            messages,
+        })
+    }
+
+    pub fn get_aliases(&self) -> &Aliases {
+        &self.aliases
+    }
+
+    pub fn get_locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Texts of the interface in the configured language
+    pub fn get_messages(&self) -> &Messages {
+        &self.messages
+    }
+}
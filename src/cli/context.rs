@@ -0,0 +1,65 @@
+use crate::cli::config::Config;
+use crate::cli::style::Style;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+/// Presentation settings shared by every command
+#[derive(Debug, Clone)]
+pub struct Context {
+    config: Config,
+    style: Style,
+    verbosity: Verbosity,
+}
+
+impl Context {
+    pub fn new(config: Config, style: Style, verbosity: Verbosity) -> Self {
+        Context {
+            config,
+            style,
+            verbosity,
+        }
+    }
+
+    pub fn get_config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn get_style(&self) -> &Style {
+        &self.style
+    }
+
+    pub fn get_verbosity(&self) -> Verbosity {
+        self.verbosity
+    }
+
+    /// Text of the message `key` in the configured language
+    pub fn text(&self, key: &str) -> String {
+        self.config.get_messages().text(key)
+    }
+
+    /// Text of the message `key` in the configured language, with its placeholders replaced by
+    /// `args`
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        self.config.get_messages().format(key, args)
+    }
+
+    /// Print a message unless the output is quiet
+    pub fn info<T: Display>(&self, message: T) {
+        if self.verbosity >= Verbosity::Normal {
+            println!("{}", message);
+        }
+    }
+
+    /// Print a message only if the output is verbose
+    pub fn detail<T: Display>(&self, message: T) {
+        if self.verbosity >= Verbosity::Verbose {
+            println!("{}", message);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use store_terminal::prelude::*;
+
+/// A product or promotion code is not in the catalog
+pub const NOT_FOUND: i32 = 3;
+/// The cart doesn't have enough items for the operation, the tenders don't cover its total, the
+/// customer can't redeem loyalty points, or the shift is not open or already open
+pub const NOT_ENOUGH_ITEMS: i32 = 4;
+/// Malformed input: catalog JSON, scan entries, barcodes, quantities, weight readings, customer
+/// identifications, payment details, URLs, fiscal journals or configuration
+pub const PARSE_ERROR: i32 = 5;
+/// A file or device couldn't be read or written
+pub const IO_ERROR: i32 = 6;
+/// A product or promotion code is already in the catalog, or a session is already open
+pub const ALREADY_EXISTS: i32 = 7;
+/// The operator credentials are invalid, the operator may not perform the operation, or
+/// restricted products wait for the approval of a supervisor
+pub const PERMISSION_DENIED: i32 = 8;
+/// Internal failure of the terminal, such as a poisoned lock
+pub const INTERNAL_ERROR: i32 = 70;
+
+pub fn code(error: &ErrorVariant) -> i32 {
+    match error {
+        ErrorVariant::ProductNotFound { .. }
+        | ErrorVariant::PromotionNotFound { .. }
+        | ErrorVariant::EntityNotFound { .. } => NOT_FOUND,
+        ErrorVariant::ProductAlreadyExists { .. }
+        | ErrorVariant::PromotionAlreadyExists { .. }
+        | ErrorVariant::SessionAlreadyExists { .. } => ALREADY_EXISTS,
+        ErrorVariant::NotEnoughItems { .. }
+        | ErrorVariant::InsufficientPayment { .. }
+        | ErrorVariant::LoyaltyUnavailable { .. }
+        | ErrorVariant::ShiftUnavailable { .. } => NOT_ENOUGH_ITEMS,
+        ErrorVariant::InvalidQuantity { .. }
+        | ErrorVariant::InvalidBarcode { .. }
+        | ErrorVariant::InvalidPaymentDetails { .. }
+        | ErrorVariant::InvalidUrl { .. }
+        | ErrorVariant::InvalidJournal { .. }
+        | ErrorVariant::InvalidWeight { .. }
+        | ErrorVariant::InvalidCustomerId { .. }
+        | ErrorVariant::JsonParseError { .. } => PARSE_ERROR,
+        ErrorVariant::IoError { .. } | ErrorVariant::PeripheralUnavailable { .. } => IO_ERROR,
+        ErrorVariant::InvalidCredentials { .. }
+        | ErrorVariant::PermissionDenied { .. }
+        | ErrorVariant::ApprovalRequired { .. } => PERMISSION_DENIED,
+        ErrorVariant::ArcUnlockError => INTERNAL_ERROR,
+    }
+}
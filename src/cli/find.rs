@@ -0,0 +1,68 @@
+use crate::cli::context::Context;
+use std::ops::Bound;
+use store_terminal::prelude::*;
+
+type PriceTerm = fn(f64) -> Term;
+
+/// Single term of a `db find` query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    CodePrefix(String),
+    PriceLessThan(f64),
+    PriceLessOrEqual(f64),
+    PriceGreaterThan(f64),
+    PriceGreaterOrEqual(f64),
+    PriceEqual(f64),
+}
+
+impl Term {
+    pub fn parse(term: &str, ctx: &Context) -> Result<Self, String> {
+        let lowercase = term.to_lowercase();
+        let condition = match lowercase.strip_prefix("price") {
+            Some(condition) if !condition.is_empty() => condition,
+            _ => return Ok(Term::CodePrefix(term.to_string())),
+        };
+
+        let operators: [(&str, PriceTerm); 5] = [
+            ("<=", Term::PriceLessOrEqual),
+            (">=", Term::PriceGreaterOrEqual),
+            ("<", Term::PriceLessThan),
+            (">", Term::PriceGreaterThan),
+            ("=", Term::PriceEqual),
+        ];
+
+        operators
+            .iter()
+            .find_map(|(operator, term)| {
+                condition
+                    .strip_prefix(operator)
+                    .map(|value| value.parse::<f64>().map(term))
+            })
+            .and_then(Result::ok)
+            .ok_or_else(|| ctx.format("search-term-invalid", &[("term", &term)]))
+    }
+
+    /// Narrow `filter` with the term
+    pub fn apply(self, filter: Filter) -> Filter {
+        match self {
+            Term::CodePrefix(prefix) => filter.with_code_prefix(prefix),
+            Term::PriceLessThan(v) => filter.with_price_range(..v),
+            Term::PriceLessOrEqual(v) => filter.with_price_range(..=v),
+            Term::PriceGreaterThan(v) => {
+                filter.with_price_range((Bound::Excluded(v), Bound::Unbounded))
+            }
+            Term::PriceGreaterOrEqual(v) => filter.with_price_range(v..),
+            Term::PriceEqual(v) => filter.with_price_range(v..=v),
+        }
+    }
+}
+
+/// Parse a `db find` query such as `A price<5`; all the terms must match
+pub fn parse_query<'a, I: IntoIterator<Item = &'a str>>(
+    terms: I,
+    ctx: &Context,
+) -> Result<Filter, String> {
+    terms.into_iter().try_fold(Filter::new(), |filter, term| {
+        Ok(Term::parse(term, ctx)?.apply(filter))
+    })
+}
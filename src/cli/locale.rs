@@ -0,0 +1,119 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolPosition {
+    Before,
+    After,
+}
+
+/// Number and currency formatting rules
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Locale {
+    decimal_separator: String,
+    grouping_separator: String,
+    currency_symbol: String,
+    symbol_position: SymbolPosition,
+    decimals: usize,
+}
+
+/// A locale is configured either by a preset name (`"pt-BR"`) or by its explicit rules
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum LocaleConfig {
+    Preset(String),
+    Custom(Locale),
+}
+
+impl Locale {
+    pub fn from_preset(name: &str) -> Option<Self> {
+        let (decimal, grouping, symbol, position) = match name.to_lowercase().as_str() {
+            "en-us" => (".", ",", "$", SymbolPosition::Before),
+            "en-gb" => (".", ",", "£", SymbolPosition::Before),
+            "de-de" => (",", ".", "€", SymbolPosition::After),
+            "fr-fr" => (",", "\u{202f}", "€", SymbolPosition::After),
+            "pt-br" => (",", ".", "R$", SymbolPosition::Before),
+            "pt-pt" => (",", "\u{a0}", "€", SymbolPosition::After),
+            _ => return None,
+        };
+
+        Some(Locale {
+            decimal_separator: decimal.to_string(),
+            grouping_separator: grouping.to_string(),
+            currency_symbol: symbol.to_string(),
+            symbol_position: position,
+            decimals: 2,
+        })
+    }
+
+    pub fn from_config(config: LocaleConfig) -> Result<Self, String> {
+        match config {
+            LocaleConfig::Preset(name) => {
+                Locale::from_preset(&name).ok_or_else(|| format!("unknown locale `{}`", name))
+            }
+            LocaleConfig::Custom(locale) => Ok(locale),
+        }
+    }
+
+    /// Format a monetary value with the currency symbol, `1234.5` as `R$ 1.234,50`
+    pub fn format_money(&self, value: f64) -> String {
+        let number = self.format_fixed(value.abs(), self.decimals);
+        let sign = if value < 0.0 && number.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            "-"
+        } else {
+            ""
+        };
+
+        match (self.currency_symbol.is_empty(), self.symbol_position) {
+            (true, _) => format!("{}{}", sign, number),
+            (false, SymbolPosition::Before) => {
+                format!("{}{} {}", sign, self.currency_symbol, number)
+            }
+            (false, SymbolPosition::After) => {
+                format!("{}{} {}", sign, number, self.currency_symbol)
+            }
+        }
+    }
+
+    /// Format a quantity, omitting insignificant decimals
+    pub fn format_quantity(&self, value: f64) -> String {
+        let number = format!("{:.3}", value);
+        let number = number.trim_end_matches('0').trim_end_matches('.');
+        number.replacen('.', &self.decimal_separator, 1)
+    }
+
+    fn format_fixed(&self, value: f64, decimals: usize) -> String {
+        let number = format!("{:.*}", decimals, value);
+        let (integer, fraction) = match number.find('.') {
+            Some(i) => (&number[..i], Some(&number[i + 1..])),
+            None => (number.as_str(), None),
+        };
+
+        let digits: Vec<char> = integer.chars().collect();
+        let mut grouped = String::new();
+        for (i, digit) in digits.iter().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push_str(&self.grouping_separator);
+            }
+            grouped.push(*digit);
+        }
+
+        match fraction {
+            Some(fraction) => format!("{}{}{}", grouped, self.decimal_separator, fraction),
+            None => grouped,
+        }
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            decimal_separator: ".".to_string(),
+            grouping_separator: ",".to_string(),
+            currency_symbol: String::new(),
+            symbol_position: SymbolPosition::Before,
+            decimals: 2,
+        }
+    }
+}
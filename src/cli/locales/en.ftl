@@ -0,0 +1,106 @@
+# Texts of the command-line interface in English, the fallback of the other bundles
+
+## Errors
+config-error = Error loading configuration: { $error }
+error = Error: { $error }
+
+## Session
+banner = { $name } v{ $version } by [{ $authors }]
+initialized = Terminal initialized!
+bye = Bye!
+prompt = [{ $units } items, { $total }] >
+out-of-stock = Warning: only { $stock } of `{ $code }` in stock
+low-stock = Warning: stock of `{ $code }` down to { $stock }, reorder at { $threshold }
+command-unknown = Command `{ $command }` not recognized!
+
+## Sales
+sale-closed = Sale #{ $id } closed, total { $total }
+gift-card-balance = Balance of gift card `{ $number }`: { $balance }
+gift-card-missing = Gift card number not provided!
+undo-scan = Scan of { $products } undone
+undo-price-override = Price override undone
+undo-nothing = Nothing to undo
+
+## Cart
+cart-reset = Cart reset!
+cart-command-unknown = Cart command `{ $command }` not recognized!
+cart-command-missing = Cart command not provided!
+file-missing = File not provided!
+code-missing = Code not provided!
+items-scanned = { $units } item(s) scanned
+scanned-code = Scanned code { $code } x { $quantity }
+file-unreadable = Unable to read `{ $path }`: { $error }
+entry-invalid = Invalid scan entry `{ $entry }`
+entry-invalid-line = Invalid scan entry `{ $entry }` at line { $line }
+
+## Database
+catalog-exported = Catalog exported to `{ $path }`
+catalog-imported = Catalog imported from `{ $path }`
+path-missing = Path not provided!
+db-command-unknown = Database command `{ $command }` not recognized!
+search-term-invalid = Invalid search term `{ $term }`
+products-found = { $count } product(s) found
+stock-quantity-missing = Stock quantity not provided!
+stock-invalid = Invalid stock of `{ $code }`
+stock-updated = Stock of `{ $code }` updated
+stock-level = Stock of `{ $code }`: { $stock }
+code-or-quantity-missing = Code or quantity not provided!
+
+## Promotion wizard
+wizard-code = Promotion code:
+wizard-code-empty = The code can't be empty
+wizard-code-exists = Promotion `{ $code }` exists and will be replaced
+wizard-product = Product code (empty to finish):
+wizard-product-required = At least one product is required
+wizard-product-unknown = Product `{ $code }` not found
+wizard-amount = Amount of { $code }:
+wizard-amount-invalid = Invalid amount `{ $amount }`
+wizard-price = Promotion price (regular price { $regular }):
+wizard-price-invalid = Invalid price `{ $price }`
+wizard-price-not-lower = The promotion price is not lower than the regular price
+wizard-confirm = Append { $code } ({ $description }) for { $price }? [y/N]
+wizard-yes = y
+wizard-appended = Promotion `{ $code }` appended!
+wizard-replaced = Promotion `{ $code }` replaced!
+wizard-cancelled = Wizard cancelled
+
+## Tables
+column-code = Code
+column-description = Description
+column-quantity = Qty
+column-unit-price = Unit price
+column-total = Total
+column-promotion = Promotion
+column-items = Items
+column-regular-price = Regular price
+column-promotional-price = Promotional price
+column-savings = Savings
+column-price = Price
+column-stock = Stock
+column-reorder-at = Reorder at
+total = Total: { $total }
+regular-price = Regular price: { $total }
+savings = Savings: { $total }
+
+## Help
+help-title = Available commands:
+help-aliases = Aliases:
+help-cart-print = Print the current contents
+help-cart-reset = Reset the contents
+help-cart-savings = Summarize the applied promotions and savings
+help-cart-scan = Scan the given codes, separated by spaces or commas (`A*3` or `3xA` for quantities)
+help-cart-scan-file = Scan the codes listed in a file, one per line
+help-checkout = Close the sale and empty the cart
+help-db = Print the database contents
+help-db-export = Save the catalog into a file
+help-db-import = Replace the catalog with the contents of a file
+help-db-find = Search products by code prefix and price (`A price<5`)
+help-db-stock = Print the stock on hand of the tracked products
+help-db-stock-set = Set the stock of a product, optionally followed by its reorder threshold
+help-db-receive = Add a delivery to the stock, followed by its reference
+help-db-adjust = Correct the stock by a signed quantity, followed by the reason
+help-db-wizard = Create a promotion step by step
+help-giftcard = Print the balance of a gift card
+help-undo = Revert the last scan or price override
+help-help = Show this menu
+help-quit = Quit
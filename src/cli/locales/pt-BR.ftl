@@ -0,0 +1,106 @@
+# Textos da interface de linha de comando em português do Brasil
+
+## Erros
+config-error = Erro ao carregar a configuração: { $error }
+error = Erro: { $error }
+
+## Sessão
+banner = { $name } v{ $version } por [{ $authors }]
+initialized = Terminal inicializado!
+bye = Até logo!
+prompt = [{ $units } itens, { $total }] >
+out-of-stock = Aviso: apenas { $stock } de `{ $code }` em estoque
+low-stock = Aviso: estoque de `{ $code }` em { $stock }, repor em { $threshold }
+command-unknown = Comando `{ $command }` não reconhecido!
+
+## Vendas
+sale-closed = Venda #{ $id } fechada, total { $total }
+gift-card-balance = Saldo do vale-presente `{ $number }`: { $balance }
+gift-card-missing = Número do vale-presente não informado!
+undo-scan = Leitura de { $products } desfeita
+undo-price-override = Alteração de preço desfeita
+undo-nothing = Nada a desfazer
+
+## Carrinho
+cart-reset = Carrinho esvaziado!
+cart-command-unknown = Comando de carrinho `{ $command }` não reconhecido!
+cart-command-missing = Comando de carrinho não informado!
+file-missing = Arquivo não informado!
+code-missing = Código não informado!
+items-scanned = { $units } item(ns) lido(s)
+scanned-code = Código { $code } lido x { $quantity }
+file-unreadable = Não foi possível ler `{ $path }`: { $error }
+entry-invalid = Leitura inválida `{ $entry }`
+entry-invalid-line = Leitura inválida `{ $entry }` na linha { $line }
+
+## Banco de dados
+catalog-exported = Catálogo exportado para `{ $path }`
+catalog-imported = Catálogo importado de `{ $path }`
+path-missing = Caminho não informado!
+db-command-unknown = Comando de banco de dados `{ $command }` não reconhecido!
+search-term-invalid = Termo de busca inválido `{ $term }`
+products-found = { $count } produto(s) encontrado(s)
+stock-quantity-missing = Quantidade em estoque não informada!
+stock-invalid = Estoque inválido de `{ $code }`
+stock-updated = Estoque de `{ $code }` atualizado
+stock-level = Estoque de `{ $code }`: { $stock }
+code-or-quantity-missing = Código ou quantidade não informados!
+
+## Assistente de promoções
+wizard-code = Código da promoção:
+wizard-code-empty = O código não pode ser vazio
+wizard-code-exists = A promoção `{ $code }` existe e será substituída
+wizard-product = Código do produto (vazio para terminar):
+wizard-product-required = Ao menos um produto é necessário
+wizard-product-unknown = Produto `{ $code }` não encontrado
+wizard-amount = Quantidade de { $code }:
+wizard-amount-invalid = Quantidade inválida `{ $amount }`
+wizard-price = Preço da promoção (preço normal { $regular }):
+wizard-price-invalid = Preço inválido `{ $price }`
+wizard-price-not-lower = O preço da promoção não é menor que o preço normal
+wizard-confirm = Incluir { $code } ({ $description }) por { $price }? [s/N]
+wizard-yes = s
+wizard-appended = Promoção `{ $code }` incluída!
+wizard-replaced = Promoção `{ $code }` substituída!
+wizard-cancelled = Assistente cancelado
+
+## Tabelas
+column-code = Código
+column-description = Descrição
+column-quantity = Qtd
+column-unit-price = Preço unitário
+column-total = Total
+column-promotion = Promoção
+column-items = Itens
+column-regular-price = Preço normal
+column-promotional-price = Preço promocional
+column-savings = Economia
+column-price = Preço
+column-stock = Estoque
+column-reorder-at = Repor em
+total = Total: { $total }
+regular-price = Preço normal: { $total }
+savings = Economia: { $total }
+
+## Ajuda
+help-title = Comandos disponíveis:
+help-aliases = Atalhos:
+help-cart-print = Exibe o conteúdo atual
+help-cart-reset = Esvazia o carrinho
+help-cart-savings = Resume as promoções aplicadas e a economia
+help-cart-scan = Lê os códigos informados, separados por espaços ou vírgulas (`A*3` ou `3xA` para quantidades)
+help-cart-scan-file = Lê os códigos listados em um arquivo, um por linha
+help-checkout = Fecha a venda e esvazia o carrinho
+help-db = Exibe o conteúdo do banco de dados
+help-db-export = Salva o catálogo em um arquivo
+help-db-import = Substitui o catálogo pelo conteúdo de um arquivo
+help-db-find = Busca produtos por prefixo do código e preço (`A price<5`)
+help-db-stock = Exibe o estoque dos produtos controlados
+help-db-stock-set = Define o estoque de um produto, opcionalmente seguido do ponto de reposição
+help-db-receive = Soma uma entrega ao estoque, seguida da sua referência
+help-db-adjust = Corrige o estoque por uma quantidade com sinal, seguida do motivo
+help-db-wizard = Cria uma promoção passo a passo
+help-giftcard = Exibe o saldo de um vale-presente
+help-undo = Desfaz a última leitura ou alteração de preço
+help-help = Mostra este menu
+help-quit = Sai
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Bundles of the user-facing texts, by language tag
+const BUNDLES: [(&str, &str); 2] = [
+    ("en", include_str!("locales/en.ftl")),
+    ("pt-BR", include_str!("locales/pt-BR.ftl")),
+];
+
+/// Language of the texts unless configured otherwise, and fallback of the missing ones
+const DEFAULT_LANGUAGE: &str = "en";
+
+/// Texts of the interface in a language, looked up by key
+///
+/// Bundles follow a subset of the Fluent syntax: one `key = text` message per line, `#`
+/// comments and `{ $name }` placeholders. Messages missing from a bundle are taken from the
+/// English one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Messages {
+    entries: HashMap<String, String>,
+}
+
+impl Messages {
+    /// Messages of the bundle tagged `language`, such as `pt-BR`, ignoring the case
+    pub fn from_language(language: &str) -> Option<Self> {
+        let (_, bundle) = BUNDLES
+            .iter()
+            .find(|(tag, _)| tag.eq_ignore_ascii_case(language))?;
+
+        let mut messages = Messages::default();
+        messages.entries.extend(parse(bundle));
+        Some(messages)
+    }
+
+    /// Tags of the available languages
+    pub fn get_languages() -> Vec<&'static str> {
+        BUNDLES.iter().map(|(tag, _)| *tag).collect()
+    }
+
+    /// Text of the message `key`, or the key itself if no bundle has it
+    pub fn text(&self, key: &str) -> String {
+        self.format(key, &[])
+    }
+
+    /// Text of the message `key` with its `{ $name }` placeholders replaced by `args`
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = match self.entries.get(key) {
+            Some(text) => text.clone(),
+            None => return key.to_string(),
+        };
+        for (name, value) in args {
+            text = text.replace(&format!("{{ ${} }}", name), &value.to_string());
+        }
+        text
+    }
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Messages {
+            entries: BUNDLES
+                .iter()
+                .filter(|(tag, _)| *tag == DEFAULT_LANGUAGE)
+                .flat_map(|(_, bundle)| parse(bundle))
+                .collect(),
+        }
+    }
+}
+
+fn parse(bundle: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    bundle
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, text)| (key.trim().to_string(), text.trim().to_string()))
+}
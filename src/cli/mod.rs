@@ -0,0 +1,13 @@
+pub mod alias;
+pub mod args;
+pub mod config;
+pub mod context;
+pub mod exit;
+pub mod find;
+pub mod locale;
+pub mod messages;
+pub mod print;
+pub mod repl;
+pub mod scan;
+pub mod style;
+pub mod wizard;
@@ -0,0 +1,172 @@
+use crate::cli::context::Context;
+use store_terminal::prelude::*;
+
+pub fn print_cart(cart: &Cart, ctx: &Context) {
+    let style = ctx.get_style();
+    let locale = ctx.get_config().get_locale();
+
+    let mut table = Table::new(vec![
+        (ctx.text("column-code").as_str(), Alignment::Left),
+        (ctx.text("column-description").as_str(), Alignment::Left),
+        (ctx.text("column-quantity").as_str(), Alignment::Right),
+        (ctx.text("column-unit-price").as_str(), Alignment::Right),
+        (ctx.text("column-total").as_str(), Alignment::Right),
+    ]);
+    for item in cart.get_items() {
+        table.push_row(vec![
+            item.get_code().to_string(),
+            item.get_description(),
+            locale.format_quantity(item.get_amount()),
+            locale.format_money(item.get_unit_price()),
+            locale.format_money(item.get_line_total()),
+        ]);
+    }
+
+    println!("{}", table.render_header());
+    for (row, item) in table.render_rows().iter().zip(cart.get_items()) {
+        if item.is_promotion() {
+            println!("{}", style.green(row));
+        } else {
+            println!("{}", row);
+        }
+    }
+    println!(
+        "{}",
+        style.bold(ctx.format(
+            "total",
+            &[("total", &locale.format_money(cart.get_total_price()))]
+        ))
+    );
+}
+
+pub fn print_savings(report: &SavingsReport, ctx: &Context) {
+    let style = ctx.get_style();
+    let locale = ctx.get_config().get_locale();
+
+    let mut table = Table::new(vec![
+        (ctx.text("column-promotion").as_str(), Alignment::Left),
+        (ctx.text("column-items").as_str(), Alignment::Left),
+        (ctx.text("column-regular-price").as_str(), Alignment::Right),
+        (
+            ctx.text("column-promotional-price").as_str(),
+            Alignment::Right,
+        ),
+        (ctx.text("column-savings").as_str(), Alignment::Right),
+    ]);
+    for p in report.get_promotions() {
+        let items: Vec<String> = p
+            .get_products()
+            .iter()
+            .map(|i| {
+                format!(
+                    "{} x {}",
+                    locale.format_quantity(*i.get_amount()),
+                    i.get_code()
+                )
+            })
+            .collect();
+        table.push_row(vec![
+            p.get_code().to_string(),
+            items.join(", "),
+            locale.format_money(p.get_regular_price()),
+            locale.format_money(p.get_promotional_price()),
+            locale.format_money(p.get_savings()),
+        ]);
+    }
+
+    println!("{}", table.render_header());
+    for row in table.render_rows() {
+        println!("{}", style.green(row));
+    }
+    let regular_price = locale.format_money(report.get_regular_price());
+    println!(
+        "{}",
+        ctx.format("regular-price", &[("total", &regular_price)])
+    );
+    let total = locale.format_money(report.get_total_price());
+    println!("{}", ctx.format("total", &[("total", &total)]));
+    let savings = locale.format_money(report.get_total_savings());
+    println!(
+        "{}",
+        style.bold(ctx.format("savings", &[("total", &savings)]))
+    );
+}
+
+pub fn print_catalog(catalog: &Catalog, ctx: &Context) {
+    let locale = ctx.get_config().get_locale();
+
+    let mut table = Table::new(vec![
+        (ctx.text("column-code").as_str(), Alignment::Left),
+        (ctx.text("column-description").as_str(), Alignment::Left),
+        (ctx.text("column-price").as_str(), Alignment::Right),
+    ]);
+    for p in catalog.get_promotions() {
+        table.push_row(vec![
+            p.get_code().to_string(),
+            p.get_description(),
+            locale.format_money(*p.get_price()),
+        ]);
+    }
+    for p in catalog.get_products() {
+        table.push_row(vec![
+            p.get_code().to_string(),
+            String::new(),
+            locale.format_money(*p.get_price()),
+        ]);
+    }
+    println!("{}", table);
+}
+
+pub fn print_products(products: &[Product], ctx: &Context) {
+    let locale = ctx.get_config().get_locale();
+
+    let mut table = Table::new(vec![
+        (ctx.text("column-code").as_str(), Alignment::Left),
+        (ctx.text("column-price").as_str(), Alignment::Right),
+    ]);
+    for p in products {
+        table.push_row(vec![
+            p.get_code().to_string(),
+            locale.format_money(*p.get_price()),
+        ]);
+    }
+    println!("{}", table);
+}
+
+pub fn print_error<T: std::fmt::Display>(message: T, ctx: &Context) {
+    eprintln!("{}", ctx.get_style().red(message));
+}
+
+pub fn print_warning<T: std::fmt::Display>(message: T, ctx: &Context) {
+    eprintln!("{}", ctx.get_style().yellow(message));
+}
+
+pub fn print_stock(levels: &[StockLevel], ctx: &Context) {
+    let style = ctx.get_style();
+    let locale = ctx.get_config().get_locale();
+
+    let mut table = Table::new(vec![
+        (ctx.text("column-code").as_str(), Alignment::Left),
+        (ctx.text("column-stock").as_str(), Alignment::Right),
+        (ctx.text("column-reorder-at").as_str(), Alignment::Right),
+    ]);
+    for level in levels {
+        table.push_row(vec![
+            level.get_code().to_string(),
+            locale.format_quantity(level.get_quantity()),
+            level
+                .get_reorder_threshold()
+                .map(|t| locale.format_quantity(t))
+                .unwrap_or_default(),
+        ]);
+    }
+
+    println!("{}", table.render_header());
+    for (row, level) in table.render_rows().iter().zip(levels) {
+        if level.is_low() {
+            println!("{}", style.yellow(row));
+        } else {
+            println!("{}", row);
+        }
+    }
+}
@@ -0,0 +1,458 @@
+use crate::cli::context::{Context, Verbosity};
+use crate::cli::print::{
+    print_cart, print_catalog, print_error, print_products, print_savings, print_stock,
+    print_warning,
+};
+use crate::cli::wizard::{self, Prompt};
+use crate::cli::{find, scan};
+use std::io::{self, stdout, BufRead, Error, Lines, StdinLock, Write};
+use std::path::Path;
+use std::str::SplitWhitespace;
+use store_terminal::prelude::*;
+
+const NAME: Option<&'static str> = option_env!("CARGO_PKG_NAME");
+const VERSION_MAJOR: Option<&'static str> = option_env!("CARGO_PKG_VERSION_MAJOR");
+const VERSION_MINOR: Option<&'static str> = option_env!("CARGO_PKG_VERSION_MINOR");
+const AUTHORS: Option<&'static str> = option_env!("CARGO_PKG_AUTHORS");
+
+/// Commands of the help menu, padded with tabs, and the keys of their descriptions
+const HELP: [(&str, &str); 19] = [
+    ("cart print\t\t", "help-cart-print"),
+    ("cart reset\t\t", "help-cart-reset"),
+    ("cart savings\t\t", "help-cart-savings"),
+    ("cart scan [code]\t", "help-cart-scan"),
+    ("cart scan --file [path]\t", "help-cart-scan-file"),
+    ("checkout\t\t", "help-checkout"),
+    ("db\t\t\t", "help-db"),
+    ("db export [path]\t", "help-db-export"),
+    ("db import [path]\t", "help-db-import"),
+    ("db find [query]\t\t", "help-db-find"),
+    ("db stock\t\t", "help-db-stock"),
+    ("db stock [code] [qty]\t", "help-db-stock-set"),
+    ("db receive [code] [qty]\t", "help-db-receive"),
+    ("db adjust [code] [qty]\t", "help-db-adjust"),
+    ("db wizard promotion\t", "help-db-wizard"),
+    ("giftcard [number]\t", "help-giftcard"),
+    ("undo\t\t\t", "help-undo"),
+    ("h\t\t\t", "help-help"),
+    ("q\t\t\t", "help-quit"),
+];
+
+macro_rules! fetch_text {
+    ($x:expr) => {
+        $x.unwrap_or("(undefined)")
+    };
+}
+
+enum State {
+    Executing,
+    ShouldFinish,
+}
+
+struct Input<'a> {
+    lines: Lines<StdinLock<'a>>,
+}
+
+impl Prompt for Input<'_> {
+    fn ask(&mut self, question: &str) -> Option<String> {
+        get_line(&mut self.lines, question)
+    }
+}
+
+pub fn repl<F>(init: F, ctx: &Context) -> Result<(), ErrorVariant>
+where
+    F: FnOnce() -> Result<Terminal, ErrorVariant>,
+{
+    let version = format!(
+        "{}.{}",
+        fetch_text!(VERSION_MAJOR),
+        fetch_text!(VERSION_MINOR)
+    );
+    ctx.info(ctx.format(
+        "banner",
+        &[
+            ("name", &fetch_text!(NAME)),
+            ("version", &version),
+            ("authors", &fetch_text!(AUTHORS)),
+        ],
+    ));
+
+    let terminal = init()?;
+    watch_stock(&terminal, ctx)?;
+    ctx.info(ctx.text("initialized"));
+
+    if ctx.get_verbosity() > Verbosity::Quiet {
+        print_help(ctx);
+    }
+
+    let stdin = io::stdin();
+
+    let mut input = Input {
+        lines: stdin.lock().lines(),
+    };
+    let mut state = State::Executing;
+    while let State::Executing = state {
+        state = if let Some(line) = input.ask(&get_prompt(&terminal, ctx)) {
+            let line = ctx.get_config().get_aliases().expand(&line);
+            proc_command(line, &mut input, &terminal, ctx).unwrap_or_else(|e| {
+                print_error(ctx.format("error", &[("error", &e)]), ctx);
+                state
+            })
+        } else {
+            State::ShouldFinish
+        };
+    }
+
+    ctx.info(ctx.text("bye"));
+    Ok(())
+}
+
+/// Warn the cashier about the stock of the products scanned and sold
+fn watch_stock(terminal: &Terminal, ctx: &Context) -> Result<SubscriptionId, ErrorVariant> {
+    let ctx = ctx.clone();
+    terminal.subscribe(move |event| {
+        let locale = ctx.get_config().get_locale();
+        match event {
+            TerminalEvent::OutOfStock { code, stock } => print_warning(
+                ctx.format(
+                    "out-of-stock",
+                    &[("code", code), ("stock", &locale.format_quantity(*stock))],
+                ),
+                &ctx,
+            ),
+            TerminalEvent::LowStock {
+                code,
+                stock,
+                threshold,
+            } => print_warning(
+                ctx.format(
+                    "low-stock",
+                    &[
+                        ("code", code),
+                        ("stock", &locale.format_quantity(*stock)),
+                        ("threshold", &locale.format_quantity(*threshold)),
+                    ],
+                ),
+                &ctx,
+            ),
+            _ => (),
+        }
+    })
+}
+
+fn get_prompt(terminal: &Terminal, ctx: &Context) -> String {
+    let locale = ctx.get_config().get_locale();
+    terminal
+        .get_cart()
+        .and_then(|cart| Ok((cart.get_units()?, cart.get_total_price())))
+        .map(|(units, total)| {
+            ctx.format(
+                "prompt",
+                &[
+                    ("units", &locale.format_quantity(units)),
+                    ("total", &locale.format_money(total)),
+                ],
+            )
+        })
+        .unwrap_or_else(|_| ">".to_string())
+}
+
+/// Read a line after printing `prompt`, followed by a space
+fn get_line(iterator: &mut Lines<StdinLock>, prompt: &str) -> Option<String> {
+    print!("{} ", prompt);
+    if stdout().flush().is_err() {
+        return None;
+    }
+    iterator
+        .next()
+        .unwrap_or(Err(Error::other("No input provided")))
+        .map(|l| Some(l.trim().to_owned()))
+        .unwrap_or(None)
+}
+
+fn proc_command(
+    line: String,
+    input: &mut Input,
+    terminal: &Terminal,
+    ctx: &Context,
+) -> Result<State, ErrorVariant> {
+    let mut iter = line.split_whitespace();
+
+    match iter.next() {
+        Some(c) if c.to_lowercase() == "q" => return Ok(State::ShouldFinish),
+        Some(c) if c.to_lowercase() == "h" => print_help(ctx),
+        Some(c) if c.to_lowercase() == "cart" => return proc_command_cart(iter, terminal, ctx),
+        Some(c) if c.to_lowercase() == "db" => return proc_command_db(iter, input, terminal, ctx),
+        Some(c) if c.to_lowercase() == "checkout" => proc_command_checkout(terminal, ctx)?,
+        Some(c) if c.to_lowercase() == "giftcard" => proc_command_gift_card(iter, terminal, ctx)?,
+        Some(c) if c.to_lowercase() == "undo" => proc_command_undo(terminal, ctx)?,
+        None => (),
+        _ => {
+            print_error(ctx.format("command-unknown", &[("command", &line)]), ctx);
+            print_help(ctx);
+        }
+    }
+
+    Ok(State::Executing)
+}
+
+fn proc_command_checkout(terminal: &Terminal, ctx: &Context) -> Result<(), ErrorVariant> {
+    let sale = terminal.checkout()?;
+    let total = ctx
+        .get_config()
+        .get_locale()
+        .format_money(sale.get_totals().get_total());
+    ctx.info(ctx.format("sale-closed", &[("id", &sale.get_id()), ("total", &total)]));
+    Ok(())
+}
+
+fn proc_command_gift_card(
+    mut iter: SplitWhitespace,
+    terminal: &Terminal,
+    ctx: &Context,
+) -> Result<(), ErrorVariant> {
+    match iter.next() {
+        Some(number) => {
+            let card = terminal.fetch_gift_card(number)?;
+            let balance = ctx
+                .get_config()
+                .get_locale()
+                .format_money(card.get_balance());
+            ctx.info(ctx.format(
+                "gift-card-balance",
+                &[("number", &card.get_number()), ("balance", &balance)],
+            ));
+        }
+        None => print_error(ctx.text("gift-card-missing"), ctx),
+    }
+    Ok(())
+}
+
+fn proc_command_undo(terminal: &Terminal, ctx: &Context) -> Result<(), ErrorVariant> {
+    let locale = ctx.get_config().get_locale();
+    match terminal.undo()? {
+        Some(Undone::Scan(products)) => {
+            let products: Vec<String> = products
+                .iter()
+                .map(|p| {
+                    format!(
+                        "{} x {}",
+                        locale.format_quantity(*p.get_amount()),
+                        p.get_code()
+                    )
+                })
+                .collect();
+            ctx.info(ctx.format("undo-scan", &[("products", &products.join(", "))]));
+        }
+        Some(Undone::PriceOverride) => ctx.info(ctx.text("undo-price-override")),
+        None => ctx.info(ctx.text("undo-nothing")),
+    }
+    Ok(())
+}
+
+fn proc_command_cart(
+    mut iter: SplitWhitespace,
+    terminal: &Terminal,
+    ctx: &Context,
+) -> Result<State, ErrorVariant> {
+    match iter.next() {
+        Some(c) if c.to_lowercase() == "print" => print_cart(&terminal.get_cart()?, ctx),
+        Some(c) if c.to_lowercase() == "savings" => {
+            print_savings(&terminal.get_cart()?.get_savings_report(), ctx)
+        }
+        Some(c) if c.to_lowercase() == "reset" => {
+            terminal.reset_cart()?;
+            ctx.info(ctx.text("cart-reset"));
+        }
+        Some(c) if c.to_lowercase() == "scan" => {
+            return proc_command_cart_scan(iter, terminal, ctx)
+        }
+        Some(c) => {
+            print_error(ctx.format("cart-command-unknown", &[("command", &c)]), ctx);
+            print_help(ctx);
+        }
+        None => {
+            print_error(ctx.text("cart-command-missing"), ctx);
+            print_help(ctx);
+        }
+    }
+
+    Ok(State::Executing)
+}
+
+fn proc_command_cart_scan(
+    mut iter: SplitWhitespace,
+    terminal: &Terminal,
+    ctx: &Context,
+) -> Result<State, ErrorVariant> {
+    let db = terminal.get_db()?;
+    let entries = match iter.clone().next() {
+        Some("--file") => match iter.nth(1) {
+            Some(path) => scan::parse_file(Path::new(path), ctx),
+            None => Err(ctx.text("file-missing")),
+        },
+        _ => scan::parse_entries(iter, |c| db.contains_product(c).unwrap_or(false), ctx),
+    };
+
+    match entries {
+        Ok(ref entries) if entries.is_empty() => {
+            print_error(ctx.text("code-missing"), ctx);
+            print_help(ctx);
+        }
+        Ok(entries) => {
+            let units = scan::scan_entries(terminal, entries, ctx)?;
+            let units = ctx.get_config().get_locale().format_quantity(units);
+            ctx.info(ctx.format("items-scanned", &[("units", &units)]));
+        }
+        Err(e) => print_error(e, ctx),
+    }
+
+    Ok(State::Executing)
+}
+
+fn proc_command_db(
+    mut iter: SplitWhitespace,
+    input: &mut Input,
+    terminal: &Terminal,
+    ctx: &Context,
+) -> Result<State, ErrorVariant> {
+    match (iter.next(), iter.clone().next()) {
+        (None, _) => print_catalog(&terminal.get_db()?.get_catalog()?, ctx),
+        (Some(c), Some(e)) if c.to_lowercase() == "wizard" && e.to_lowercase() == "promotion" => {
+            wizard::promotion_wizard(input, terminal, ctx)?
+        }
+        (Some(c), Some(_)) if c.to_lowercase() == "find" => {
+            return proc_command_db_find(iter, terminal, ctx)
+        }
+        (Some(c), _) if c.to_lowercase() == "stock" => {
+            return proc_command_db_stock(iter, terminal, ctx)
+        }
+        (Some(c), _) if ["receive", "adjust"].contains(&c.to_lowercase().as_str()) => {
+            return proc_command_db_movement(&c.to_lowercase(), iter, terminal, ctx)
+        }
+        (Some(c), Some(path)) if c.to_lowercase() == "export" => {
+            terminal.get_db()?.export(Path::new(path))?;
+            ctx.info(ctx.format("catalog-exported", &[("path", &path)]));
+        }
+        (Some(c), Some(path)) if c.to_lowercase() == "import" => {
+            terminal.change_catalog(|database| database.import(Path::new(path)))?;
+            ctx.info(ctx.format("catalog-imported", &[("path", &path)]));
+        }
+        (Some(c), None) if ["export", "import"].contains(&c.to_lowercase().as_str()) => {
+            print_error(ctx.text("path-missing"), ctx);
+            print_help(ctx);
+        }
+        (Some(c), _) => {
+            print_error(ctx.format("db-command-unknown", &[("command", &c)]), ctx);
+            print_help(ctx);
+        }
+    }
+
+    Ok(State::Executing)
+}
+
+fn proc_command_db_find(
+    iter: SplitWhitespace,
+    terminal: &Terminal,
+    ctx: &Context,
+) -> Result<State, ErrorVariant> {
+    let filter = match find::parse_query(iter, ctx) {
+        Ok(filter) => filter,
+        Err(e) => {
+            print_error(e, ctx);
+            return Ok(State::Executing);
+        }
+    };
+
+    let products = terminal.get_db()?.find_products(&filter)?;
+
+    print_products(&products, ctx);
+    ctx.info(ctx.format("products-found", &[("count", &products.len())]));
+
+    Ok(State::Executing)
+}
+
+fn proc_command_db_stock(
+    mut iter: SplitWhitespace,
+    terminal: &Terminal,
+    ctx: &Context,
+) -> Result<State, ErrorVariant> {
+    let inventory = terminal.get_db()?.get_inventory();
+    let code = match iter.next() {
+        Some(code) => code,
+        None => {
+            print_stock(&inventory.get_levels(), ctx);
+            return Ok(State::Executing);
+        }
+    };
+
+    let quantity = iter.next().map(|q| q.parse::<f64>());
+    let threshold = iter.next().map(|t| t.parse::<f64>());
+    match (quantity, threshold) {
+        (Some(Ok(quantity)), None) => inventory.set_stock(code, quantity)?,
+        (Some(Ok(quantity)), Some(Ok(threshold))) => {
+            inventory.set_stock(code, quantity)?;
+            inventory.set_reorder_threshold(code, Some(threshold))?;
+        }
+        (None, _) => {
+            print_error(ctx.text("stock-quantity-missing"), ctx);
+            print_help(ctx);
+            return Ok(State::Executing);
+        }
+        _ => {
+            print_error(ctx.format("stock-invalid", &[("code", &code)]), ctx);
+            return Ok(State::Executing);
+        }
+    }
+    ctx.info(ctx.format("stock-updated", &[("code", &code)]));
+
+    Ok(State::Executing)
+}
+
+/// Record a delivery or a correction of the stock of a product, with the rest of the line as
+/// its reference or reason
+fn proc_command_db_movement(
+    command: &str,
+    mut iter: SplitWhitespace,
+    terminal: &Terminal,
+    ctx: &Context,
+) -> Result<State, ErrorVariant> {
+    let (code, quantity) = match (iter.next(), iter.next().map(|q| q.parse::<f64>())) {
+        (Some(code), Some(Ok(quantity))) => (code, quantity),
+        (Some(code), Some(Err(_))) => {
+            print_error(ctx.format("stock-invalid", &[("code", &code)]), ctx);
+            return Ok(State::Executing);
+        }
+        _ => {
+            print_error(ctx.text("code-or-quantity-missing"), ctx);
+            print_help(ctx);
+            return Ok(State::Executing);
+        }
+    };
+    let note = iter.collect::<Vec<&str>>().join(" ");
+
+    let inventory = terminal.get_db()?.get_inventory();
+    let stock = match command {
+        "receive" => inventory.receive(code, quantity, &note)?,
+        _ => inventory.adjust(code, quantity, &note)?,
+    };
+    let stock = ctx.get_config().get_locale().format_quantity(stock);
+    ctx.info(ctx.format("stock-level", &[("code", &code), ("stock", &stock)]));
+
+    Ok(State::Executing)
+}
+
+fn print_help(ctx: &Context) {
+    println!("{}", ctx.text("help-title"));
+    for (command, key) in HELP.iter() {
+        println!("{}{}", command, ctx.text(key));
+    }
+
+    let mut aliases: Vec<(&String, &String)> =
+        ctx.get_config().get_aliases().get_map().iter().collect();
+    aliases.sort();
+    println!("{}", ctx.text("help-aliases"));
+    for (alias, command) in aliases {
+        println!("{}\t\t\t{}", alias, command);
+    }
+}
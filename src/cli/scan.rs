@@ -0,0 +1,163 @@
+use crate::cli::context::Context;
+use std::fs;
+use std::path::Path;
+use store_terminal::prelude::*;
+
+/// Scan the parsed entries into the terminal, returning the number of scanned units
+pub fn scan_entries(
+    terminal: &Terminal,
+    entries: Vec<(String, f64)>,
+    ctx: &Context,
+) -> Result<f64, ErrorVariant> {
+    let mut units = 0.0;
+
+    for (code, quantity) in entries {
+        if quantity.fract() == 0.0 && quantity <= f64::from(u32::MAX) {
+            terminal.scan_with_quantity(&code, quantity as u32)?;
+        } else {
+            terminal.scan_with_weight(&code, quantity)?;
+        }
+        let quantity_text = ctx.get_config().get_locale().format_quantity(quantity);
+        ctx.detail(ctx.format(
+            "scanned-code",
+            &[("code", &code), ("quantity", &quantity_text)],
+        ));
+        units += quantity;
+    }
+
+    Ok(units)
+}
+
+/// Parse the entries of a scan command into aggregated `(code, quantity)` pairs
+///
+/// Entries are further split by commas. A token for which `is_code` holds is taken as an exact
+/// product code, so multi-character barcodes work as they are. Accepted syntaxes are `A*3` and
+/// `3xA` for explicit quantities; any other token is interpreted as a sequence of single
+/// character codes if every character is a code, so `CCC` is three units of `C`, and is
+/// otherwise taken as a whole, to be reported unknown
+pub fn parse_entries<'a, I, F>(
+    entries: I,
+    is_code: F,
+    ctx: &Context,
+) -> Result<Vec<(String, f64)>, String>
+where
+    I: IntoIterator<Item = &'a str>,
+    F: Fn(&str) -> bool,
+{
+    let mut aggregated = vec![];
+
+    for entry in entries
+        .into_iter()
+        .flat_map(|e| e.split(','))
+        .filter(|e| !e.is_empty())
+    {
+        if is_code(entry) {
+            aggregate(&mut aggregated, (entry.to_string(), 1.0));
+            continue;
+        }
+
+        match parse_quantity_entry(entry)
+            .map_err(|e| ctx.format("entry-invalid", &[("entry", &e)]))?
+        {
+            Some(pair) => aggregate(&mut aggregated, pair),
+            None if entry.chars().all(|c| is_code(&c.to_string())) => entry
+                .chars()
+                .for_each(|c| aggregate(&mut aggregated, (c.to_string(), 1.0))),
+            None => aggregate(&mut aggregated, (entry.to_string(), 1.0)),
+        }
+    }
+
+    Ok(aggregated)
+}
+
+/// Read a batch file with one code per line into aggregated `(code, quantity)` pairs
+///
+/// Besides the `A*3` and `3xA` syntaxes, a line may carry the quantity after a comma or a
+/// whitespace (`A,3` or `A 3`), as exported by most handheld scanners. Empty lines and lines
+/// starting with `#` are ignored
+pub fn parse_file(path: &Path, ctx: &Context) -> Result<Vec<(String, f64)>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        ctx.format(
+            "file-unreadable",
+            &[("path", &path.display()), ("error", &e)],
+        )
+    })?;
+
+    let mut aggregated = vec![];
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let pair = parse_line(line).map_err(|e| {
+            ctx.format(
+                "entry-invalid-line",
+                &[("entry", &e), ("line", &(number + 1))],
+            )
+        })?;
+        aggregate(&mut aggregated, pair);
+    }
+
+    Ok(aggregated)
+}
+
+/// Parse a line of a batch file, failing with the invalid entry
+fn parse_line(line: &str) -> Result<(String, f64), String> {
+    let mut parts = line
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty());
+
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(code), Some(quantity), None) => parse_quantity(quantity)
+            .map(|q| (code.to_string(), q))
+            .ok_or_else(|| line.to_string()),
+        (Some(_), None, None) => {
+            Ok(parse_quantity_entry(line)?.unwrap_or_else(|| (line.to_string(), 1.0)))
+        }
+        _ => Err(line.to_string()),
+    }
+}
+
+/// Parse an entry with an explicit quantity, failing with the entry if it is invalid
+fn parse_quantity_entry(entry: &str) -> Result<Option<(String, f64)>, String> {
+    let invalid = || entry.to_string();
+
+    if let Some(index) = entry.find('*') {
+        let (code, quantity) = (&entry[..index], &entry[index + 1..]);
+        let quantity = parse_quantity(quantity).ok_or_else(invalid)?;
+        if code.is_empty() {
+            return Err(invalid());
+        }
+        return Ok(Some((code.to_string(), quantity)));
+    }
+
+    let digits = entry
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(entry.len());
+    if digits > 0 && entry[digits..].starts_with(['x', 'X']) {
+        let code = &entry[digits + 1..];
+        let quantity = parse_quantity(&entry[..digits]).ok_or_else(invalid)?;
+        if code.is_empty() {
+            return Err(invalid());
+        }
+        return Ok(Some((code.to_string(), quantity)));
+    }
+
+    Ok(None)
+}
+
+fn parse_quantity(quantity: &str) -> Option<f64> {
+    quantity
+        .parse::<f64>()
+        .ok()
+        .filter(|q| q.is_finite() && *q > 0.0)
+}
+
+fn aggregate(aggregated: &mut Vec<(String, f64)>, (code, quantity): (String, f64)) {
+    match aggregated.iter_mut().find(|(c, _)| c == &code) {
+        Some((_, q)) => *q += quantity,
+        None => aggregated.push((code, quantity)),
+    }
+}
@@ -0,0 +1,55 @@
+use std::env;
+use std::fmt::Display;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+
+/// ANSI styling for the terminal output
+///
+/// Colors are disabled by the `--no-color` flag or by a non-empty `NO_COLOR` environment
+/// variable, as described in <https://no-color.org>
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    enabled: bool,
+}
+
+impl Style {
+    pub fn new(enabled: bool) -> Self {
+        Style { enabled }
+    }
+
+    pub fn from_env(no_color_flag: bool) -> Self {
+        let no_color_env = env::var_os("NO_COLOR")
+            .map(|v| !v.is_empty())
+            .unwrap_or(false);
+
+        Style::new(!no_color_flag && !no_color_env)
+    }
+
+    pub fn bold<T: Display>(&self, text: T) -> String {
+        self.paint(BOLD, text)
+    }
+
+    pub fn red<T: Display>(&self, text: T) -> String {
+        self.paint(RED, text)
+    }
+
+    pub fn green<T: Display>(&self, text: T) -> String {
+        self.paint(GREEN, text)
+    }
+
+    pub fn yellow<T: Display>(&self, text: T) -> String {
+        self.paint(YELLOW, text)
+    }
+
+    fn paint<T: Display>(&self, code: &str, text: T) -> String {
+        if self.enabled {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::cli::context::Context;
+use crate::cli::print::print_error;
+use store_terminal::prelude::*;
+
+/// Source of answers for interactive commands
+pub trait Prompt {
+    /// Ask a question, returning `None` if the input was closed
+    fn ask(&mut self, question: &str) -> Option<String>;
+}
+
+/// Guide the user through the creation of a promotion, validating every product against the
+/// catalog before appending the result into the database
+pub fn promotion_wizard<P: Prompt>(
+    prompt: &mut P,
+    terminal: &Terminal,
+    ctx: &Context,
+) -> Result<(), ErrorVariant> {
+    let database = terminal.get_db()?;
+    let locale = ctx.get_config().get_locale();
+
+    let code = match ask_until(prompt, &ctx.text("wizard-code"), ctx, |answer| {
+        if answer.is_empty() {
+            Err(ctx.text("wizard-code-empty"))
+        } else {
+            Ok(answer.to_string())
+        }
+    }) {
+        Some(code) => code,
+        None => return cancel(ctx),
+    };
+    if database.fetch_promotion(&code).is_ok() {
+        ctx.info(ctx.format("wizard-code-exists", &[("code", &code)]));
+    }
+
+    let mut products: Vec<ProductAmount> = vec![];
+    loop {
+        let product = ask_until(
+            prompt,
+            &ctx.text("wizard-product"),
+            ctx,
+            |answer| match answer {
+                "" if products.is_empty() => Err(ctx.text("wizard-product-required")),
+                "" => Ok(None),
+                c => database
+                    .fetch_product(c)
+                    .map(Some)
+                    .map_err(|_| ctx.format("wizard-product-unknown", &[("code", &c)])),
+            },
+        );
+
+        let product = match product {
+            Some(Some(product)) => product,
+            Some(None) => break,
+            None => return cancel(ctx),
+        };
+
+        let question = ctx.format("wizard-amount", &[("code", product.get_code())]);
+        let amount = match ask_until(prompt, &question, ctx, |answer| {
+            answer
+                .parse::<f64>()
+                .ok()
+                .filter(|a| a.is_finite() && *a > 0.0)
+                .ok_or_else(|| ctx.format("wizard-amount-invalid", &[("amount", &answer)]))
+        }) {
+            Some(amount) => amount,
+            None => return cancel(ctx),
+        };
+
+        products.push(product.generate_amount(amount));
+    }
+
+    let regular_price = products.iter().fold(0.0, |t, p| t + p.get_total_price());
+    let question = ctx.format(
+        "wizard-price",
+        &[("regular", &locale.format_money(regular_price))],
+    );
+    let price = match ask_until(prompt, &question, ctx, |answer| {
+        answer
+            .parse::<f64>()
+            .ok()
+            .filter(|p| p.is_finite() && *p >= 0.0)
+            .ok_or_else(|| ctx.format("wizard-price-invalid", &[("price", &answer)]))
+    }) {
+        Some(price) => price,
+        None => return cancel(ctx),
+    };
+    if price >= regular_price {
+        ctx.info(ctx.text("wizard-price-not-lower"));
+    }
+
+    let promotion = Promotion::new(code, products, price)?;
+    let question = ctx.format(
+        "wizard-confirm",
+        &[
+            ("code", promotion.get_code()),
+            ("description", &promotion.get_description()),
+            ("price", &locale.format_money(*promotion.get_price())),
+        ],
+    );
+    match prompt.ask(&question) {
+        Some(ref answer) if answer.to_lowercase().starts_with(&ctx.text("wizard-yes")) => {
+            let code = promotion.get_code().clone();
+            match terminal.change_catalog(|database| database.upsert(promotion))? {
+                AppendOutcome::Inserted => {
+                    ctx.info(ctx.format("wizard-appended", &[("code", &code)]))
+                }
+                AppendOutcome::Updated(_) => {
+                    ctx.info(ctx.format("wizard-replaced", &[("code", &code)]))
+                }
+            }
+            Ok(())
+        }
+        _ => cancel(ctx),
+    }
+}
+
+fn ask_until<P, T, F>(prompt: &mut P, question: &str, ctx: &Context, mut validate: F) -> Option<T>
+where
+    P: Prompt,
+    F: FnMut(&str) -> Result<T, String>,
+{
+    loop {
+        let answer = prompt.ask(question)?;
+        match validate(answer.trim()) {
+            Ok(value) => return Some(value),
+            Err(e) => print_error(e, ctx),
+        }
+    }
+}
+
+fn cancel(ctx: &Context) -> Result<(), ErrorVariant> {
+    ctx.info(ctx.text("wizard-cancelled"));
+    Ok(())
+}
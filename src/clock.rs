@@ -0,0 +1,161 @@
+//! Sources of the timestamps and item identifiers of a terminal
+//!
+//! Terminals read the time from their [Clock], stamping sales, refunds, shifts and audit
+//! entries, and carts name their items with their [IdGenerator]. Tests and replay tooling give
+//! a [ManualClock] and [SequentialIds] to
+//! [TerminalBuilder](crate::prelude::TerminalBuilder) to produce the same sales, receipts and
+//! audit trails on every run
+
+use crate::prelude::ItemId;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Current time of a terminal
+pub trait Clock: Send + Sync {
+    /// Seconds since the Unix epoch
+    fn now(&self) -> u64;
+}
+
+/// Clock shared with other parts of the application
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> u64 {
+        (**self).now()
+    }
+}
+
+/// Time of the system, the clock of terminals unless built otherwise
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs())
+    }
+}
+
+/// Read from the host, as WebAssembly has no system clock
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        extern "C" {
+            /// Milliseconds since the Unix epoch, `Date.now` in JavaScript hosts
+            fn store_terminal_now() -> f64;
+        }
+
+        let millis = unsafe { store_terminal_now() };
+        (millis / 1000.0) as u64
+    }
+}
+
+/// Clock standing still until set or advanced
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use store_terminal::audit::AuditAction;
+/// use store_terminal::clock::{ManualClock, SequentialIds};
+/// use store_terminal::prelude::*;
+///
+/// let clock = Arc::new(ManualClock::new(1_760_000_000));
+/// let terminal = TerminalBuilder::new()
+///     .with_clock(clock.clone())
+///     .with_id_generator(SequentialIds::new(1))
+///     .build()
+///     .unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// terminal.scan("AB").unwrap();
+/// let ids: Vec<String> = terminal
+///     .get_cart()
+///     .unwrap()
+///     .get_items()
+///     .iter()
+///     .map(|item| item.get_id().to_string())
+///     .collect();
+/// assert_eq!(ids[0], "00000000-0000-0000-0000-000000000003");
+///
+/// let sale = terminal.checkout().unwrap();
+/// assert_eq!(sale.get_timestamp(), 1_760_000_000);
+///
+/// clock.advance(90);
+/// assert_eq!(terminal.refund(1, &[("B", 1.0)]).unwrap().get_timestamp(), 1_760_000_090);
+/// terminal.reset().unwrap();
+/// let entry = terminal.audit_log().get_entries().pop().unwrap();
+/// assert_eq!((entry.get_action(), entry.get_timestamp()), (&AuditAction::DatabaseReset, 1_760_000_090));
+/// ```
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: AtomicU64,
+}
+
+impl ManualClock {
+    /// Clock at `now` seconds since the Unix epoch
+    pub fn new(now: u64) -> Self {
+        ManualClock {
+            now: AtomicU64::new(now),
+        }
+    }
+
+    pub fn set(&self, now: u64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    /// Move the clock `seconds` forward
+    pub fn advance(&self, seconds: u64) {
+        self.now.fetch_add(seconds, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+/// Identifiers of the items added to a cart
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> ItemId;
+}
+
+/// Generator shared with other parts of the application
+impl<G: IdGenerator + ?Sized> IdGenerator for Arc<G> {
+    fn next_id(&self) -> ItemId {
+        (**self).next_id()
+    }
+}
+
+/// Identifiers of [ItemId::new], the generator of carts unless set otherwise
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn next_id(&self) -> ItemId {
+        ItemId::new()
+    }
+}
+
+/// Identifiers counting up from a first one
+#[derive(Debug, Default)]
+pub struct SequentialIds {
+    next: AtomicU64,
+}
+
+impl SequentialIds {
+    pub fn new(first: u64) -> Self {
+        SequentialIds {
+            next: AtomicU64::new(first),
+        }
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> ItemId {
+        ItemId::from(u128::from(self.next.fetch_add(1, Ordering::SeqCst)))
+    }
+}
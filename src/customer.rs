@@ -0,0 +1,249 @@
+//! Customer of a sale, identified mid-transaction by a loyalty card or a phone number
+//!
+//! The [Customer] is set on the cart by [Terminal::set_customer](crate::prelude::Terminal::set_customer)
+//! or read from the [CustomerReader](crate::peripheral::CustomerReader) of the terminal, and
+//! is given to the [OptimizerStrategy](crate::prelude::OptimizerStrategy), so member promotions
+//! and price lists can key off its identifier, tier and segments
+//!
+//! Customer accounts, with their name, tier and tax exemption, are kept in the
+//! [DatabaseStore](crate::prelude::DatabaseStore) named [CUSTOMER_STORE] and attached to the
+//! cart by [Terminal::attach_customer](crate::prelude::Terminal::attach_customer)
+
+use crate::prelude::ErrorVariant;
+#[cfg(feature = "json")]
+use crate::prelude::TerminalEntityInterface;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+/// Name of the store of the customer accounts in the [Database](crate::prelude::Database)
+pub const CUSTOMER_STORE: &str = "customers";
+
+/// Input the customer was identified with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "json",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Identification {
+    /// Loyalty card swiped on a magnetic stripe reader
+    Magstripe,
+    /// Card or phone tapped on an NFC reader
+    Nfc,
+    /// Phone number keyed in by the cashier or the customer
+    Phone,
+}
+
+/// Customer identified for the sale in progress
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::customer::{Customer, Identification};
+/// use store_terminal::prelude::*;
+///
+/// let swiped = Customer::from_magstripe(";6012345678901234=2512?").unwrap();
+/// assert_eq!(swiped.get_id(), "6012345678901234");
+/// assert_eq!(swiped.get_identification(), Identification::Magstripe);
+/// assert_eq!(
+///     Customer::from_magstripe("%B6012345678901234^DOE/JANE^2512?").unwrap().get_id(),
+///     "6012345678901234"
+/// );
+///
+/// assert_eq!(Customer::from_nfc(&[0x04, 0xa2, 0x3b, 0x1f]).unwrap().get_id(), "04A23B1F");
+/// assert_eq!(
+///     Customer::from_phone("+1 (555) 010-4477").unwrap().get_id(),
+///     "+15550104477"
+/// );
+/// assert_eq!(
+///     Customer::from_phone("555-01").unwrap_err(),
+///     ErrorVariant::InvalidCustomerId { input: "555-01".to_string() }
+/// );
+///
+/// let member = swiped.with_segment("staff");
+/// assert!(member.has_segment("staff"));
+/// assert!(!member.has_segment("press"));
+///
+/// let account = Customer::new("6012345678901234", Identification::Magstripe)
+///     .with_name("Jane Doe")
+///     .with_tier("gold")
+///     .with_tax_exemption(true);
+/// let member = member.with_account(&account);
+/// assert_eq!(member.get_name(), Some("Jane Doe"));
+/// assert!(member.has_tier("gold"));
+/// assert!(member.is_tax_exempt());
+/// assert!(member.has_segment("staff"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Customer {
+    id: String,
+    identification: Identification,
+    #[cfg_attr(feature = "json", serde(default))]
+    name: Option<String>,
+    #[cfg_attr(feature = "json", serde(default))]
+    tier: Option<String>,
+    #[cfg_attr(feature = "json", serde(default))]
+    tax_exempt: bool,
+    #[cfg_attr(feature = "json", serde(default))]
+    segments: Vec<String>,
+}
+
+impl Customer {
+    /// Customer with an identifier already validated, such as a member number looked up by a driver
+    pub fn new(id: &str, identification: Identification) -> Self {
+        Customer {
+            id: id.to_string(),
+            identification,
+            name: None,
+            tier: None,
+            tax_exempt: false,
+            segments: vec![],
+        }
+    }
+
+    /// Customer of the account number of a track 1 (`%B...^`) or track 2 (`;...=`) swipe
+    pub fn from_magstripe(track: &str) -> Result<Self, ErrorVariant> {
+        let track = track.trim();
+        let account = if let Some(track1) = track.strip_prefix('%') {
+            track1.strip_prefix('B').unwrap_or(track1).split('^').next()
+        } else if let Some(track2) = track.strip_prefix(';') {
+            track2.split(['=', '?']).next()
+        } else {
+            None
+        };
+
+        match account {
+            Some(account) if is_digits(account, 1, 19) => {
+                Ok(Customer::new(account, Identification::Magstripe))
+            }
+            _ => Err(invalid(track)),
+        }
+    }
+
+    /// Customer of the UID of an NFC tag, 4, 7 or 10 bytes long
+    pub fn from_nfc(uid: &[u8]) -> Result<Self, ErrorVariant> {
+        let id: String = uid.iter().map(|byte| format!("{:02X}", byte)).collect();
+        match uid.len() {
+            4 | 7 | 10 => Ok(Customer::new(&id, Identification::Nfc)),
+            _ => Err(invalid(&id)),
+        }
+    }
+
+    /// Customer of a phone number of 7 to 15 digits, ignoring spaces, dots, dashes and
+    /// parentheses, and keeping a leading `+`
+    pub fn from_phone(number: &str) -> Result<Self, ErrorVariant> {
+        let trimmed = number.trim();
+        let (plus, rest) = match trimmed.strip_prefix('+') {
+            Some(rest) => ("+", rest),
+            None => ("", trimmed),
+        };
+        let digits: String = rest
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '.' | '-' | '(' | ')'))
+            .collect();
+
+        if is_digits(&digits, 7, 15) {
+            Ok(Customer::new(
+                &format!("{}{}", plus, digits),
+                Identification::Phone,
+            ))
+        } else {
+            Err(invalid(number))
+        }
+    }
+
+    /// Add a segment of the customer, such as a loyalty tier, for the promotions to key off
+    pub fn with_segment(mut self, segment: &str) -> Self {
+        self.segments.push(segment.to_string());
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Loyalty tier of the customer, such as `gold`, for tier promotions and earn rates
+    pub fn with_tier(mut self, tier: &str) -> Self {
+        self.tier = Some(tier.to_string());
+        self
+    }
+
+    /// Exempt the sales of the customer from the taxes of their receipts
+    pub fn with_tax_exemption(mut self, exempt: bool) -> Self {
+        self.tax_exempt = exempt;
+        self
+    }
+
+    /// Customer identified as it is, with the name, tier, tax exemption and segments of its
+    /// stored `account`
+    pub fn with_account(mut self, account: &Customer) -> Self {
+        self.name = account.name.clone();
+        self.tier = account.tier.clone();
+        self.tax_exempt = account.tax_exempt;
+        for segment in &account.segments {
+            if !self.has_segment(segment) {
+                self.segments.push(segment.clone());
+            }
+        }
+        self
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_identification(&self) -> Identification {
+        self.identification
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn get_tier(&self) -> Option<&str> {
+        self.tier.as_deref()
+    }
+
+    pub fn has_tier(&self, tier: &str) -> bool {
+        self.tier.as_deref() == Some(tier)
+    }
+
+    pub fn is_tax_exempt(&self) -> bool {
+        self.tax_exempt
+    }
+
+    pub fn get_segments(&self) -> &Vec<String> {
+        &self.segments
+    }
+
+    pub fn has_segment(&self, segment: &str) -> bool {
+        self.segments.iter().any(|s| s == segment)
+    }
+}
+
+#[cfg(feature = "json")]
+impl TerminalEntityInterface for Customer {
+    fn get_syntax_example() -> &'static str {
+        r#"{"id": "M-1042", "identification": "phone", "name": "Jane Doe", "tier": "gold", "tax_exempt": false}"#
+    }
+
+    fn from_json(json: String) -> Result<Self, ErrorVariant> {
+        serde_json::from_str(&json).map_err(|e| ErrorVariant::from_json_error(e, "Customer"))
+    }
+
+    fn to_json(&self) -> Result<String, ErrorVariant> {
+        serde_json::to_string(self).map_err(|e| ErrorVariant::from_json_error(e, "Customer"))
+    }
+}
+
+fn is_digits(s: &str, min: usize, max: usize) -> bool {
+    (min..=max).contains(&s.len()) && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn invalid(input: &str) -> ErrorVariant {
+    ErrorVariant::InvalidCustomerId {
+        input: input.to_string(),
+    }
+}
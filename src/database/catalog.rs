@@ -0,0 +1,73 @@
+use crate::database::store::Stores;
+use crate::prelude::{Database, ErrorVariant, Product, Promotion};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "json")]
+use std::fs;
+#[cfg(feature = "json")]
+use std::path::Path;
+
+/// Serializable snapshot of the products and promotions of a [Database](crate::prelude::Database)
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(default, deny_unknown_fields))]
+pub struct Catalog {
+    products: Vec<Product>,
+    promotions: Vec<Promotion>,
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Stores::is_empty"))]
+    stores: Stores,
+}
+
+impl Catalog {
+    pub fn new(products: Vec<Product>, promotions: Vec<Promotion>) -> Self {
+        Catalog {
+            products,
+            promotions,
+            stores: Stores::new(),
+        }
+    }
+
+    pub(crate) fn with_stores(mut self, stores: Stores) -> Self {
+        self.stores = stores;
+        self
+    }
+
+    pub fn get_products(&self) -> &Vec<Product> {
+        &self.products
+    }
+
+    pub fn get_promotions(&self) -> &Vec<Promotion> {
+        &self.promotions
+    }
+
+    /// Read a catalog from a JSON file
+    #[cfg(feature = "json")]
+    pub fn read(path: &Path) -> Result<Self, ErrorVariant> {
+        let contents = fs::read_to_string(path).map_err(|e| ErrorVariant::IoError {
+            path: path.display().to_string(),
+            source: e.to_string(),
+        })?;
+        serde_json::from_str(&contents).map_err(|e| ErrorVariant::from_json_error(e, "Catalog"))
+    }
+
+    /// Write the catalog as a JSON file
+    #[cfg(feature = "json")]
+    pub fn write(&self, path: &Path) -> Result<(), ErrorVariant> {
+        let contents = serde_json::to_string_pretty(&self)
+            .map_err(|e| ErrorVariant::from_json_error(e, "Catalog"))?;
+        fs::write(path, contents).map_err(|e| ErrorVariant::IoError {
+            path: path.display().to_string(),
+            source: e.to_string(),
+        })
+    }
+
+    pub(crate) fn into_parts(self) -> (Vec<Product>, Vec<Promotion>, Stores) {
+        (self.products, self.promotions, self.stores)
+    }
+
+    /// Append all the entities of the catalog into a database, or none of them if any is
+    /// invalid; see [Database::append_all]
+    pub fn append_into(self, database: &Database) -> Result<(), ErrorVariant> {
+        database.merge(self)
+    }
+}
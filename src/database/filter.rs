@@ -0,0 +1,114 @@
+use crate::prelude::Product;
+use std::ops::{Bound, RangeBounds};
+
+/// Criteria of [Database::find_products](crate::prelude::Database::find_products)
+///
+/// An empty filter matches every product, and every criterion narrows the matches
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let filter = Filter::new()
+///     .with_code_prefix("A")
+///     .with_price_range(1.0..)
+///     .with_price_range(..=5.0);
+///
+/// assert!(filter.matches(&Product::new("AB".to_string(), 5.0)));
+/// assert!(!filter.matches(&Product::new("AB".to_string(), 0.5)));
+/// assert!(!filter.matches(&Product::new("B".to_string(), 2.0)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    code_prefix: Option<String>,
+    category: Option<String>,
+    min_price: Bound<f64>,
+    max_price: Bound<f64>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter {
+            code_prefix: None,
+            category: None,
+            min_price: Bound::Unbounded,
+            max_price: Bound::Unbounded,
+        }
+    }
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Filter::default()
+    }
+
+    /// Match only the products with a code starting with `prefix`
+    pub fn with_code_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.code_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Match only the products of `category`
+    pub fn with_category<S: Into<String>>(mut self, category: S) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// Match only the products with a price within `range`, intersected with any previous range
+    pub fn with_price_range<R: RangeBounds<f64>>(mut self, range: R) -> Self {
+        self.min_price = tighter(self.min_price, range.start_bound().cloned(), |a, b| a > b);
+        self.max_price = tighter(self.max_price, range.end_bound().cloned(), |a, b| a < b);
+        self
+    }
+
+    pub fn get_code_prefix(&self) -> Option<&str> {
+        self.code_prefix.as_deref()
+    }
+
+    pub fn get_category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+
+    pub fn get_price_range(&self) -> (Bound<f64>, Bound<f64>) {
+        (self.min_price, self.max_price)
+    }
+
+    pub fn matches(&self, product: &Product) -> bool {
+        let code_matches = self
+            .code_prefix
+            .as_ref()
+            .map(|prefix| product.get_code().starts_with(prefix.as_str()))
+            .unwrap_or(true);
+
+        let category_matches = self
+            .category
+            .as_deref()
+            .map(|category| product.get_category() == Some(category))
+            .unwrap_or(true);
+
+        code_matches
+            && category_matches
+            && (self.min_price, self.max_price).contains(product.get_price())
+    }
+}
+
+/// Most restrictive of two bounds on the same side, where `stricter(a, b)` holds if the value
+/// `a` restricts more than `b`
+fn tighter(a: Bound<f64>, b: Bound<f64>, stricter: fn(f64, f64) -> bool) -> Bound<f64> {
+    let value = |bound: &Bound<f64>| match bound {
+        Bound::Included(v) | Bound::Excluded(v) => Some(*v),
+        Bound::Unbounded => None,
+    };
+
+    match (value(&a), value(&b)) {
+        (None, _) => b,
+        (_, None) => a,
+        (Some(x), Some(y)) if stricter(x, y) => a,
+        (Some(x), Some(y)) if stricter(y, x) => b,
+        _ => match a {
+            Bound::Excluded(_) => a,
+            _ => b,
+        },
+    }
+}
@@ -1,12 +1,42 @@
-use crate::prelude::{ErrorVariant, Product, ProductAmount, Promotion};
-use std::collections::HashMap;
+use crate::lock;
+use crate::prelude::{
+    Catalog, Code, DatabaseReader, DatabaseStats, DatabaseStore, ErrorVariant, Filter, Inventory,
+    Product, ProductAmount, Promotion, TerminalEntityInterface,
+};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::sync::{Arc, Mutex};
+#[cfg(feature = "json")]
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use store::Stores;
 
+pub mod catalog;
+pub mod filter;
+pub mod reader;
+pub mod stats;
+pub mod store;
+
+/// Shared storage of products, promotions and application-defined entities
+///
+/// Clones share the same storage. Every collection is guarded by a [RwLock], so fetches, queries,
+/// listings, exports and [fmt::Display] run concurrently, taking read locks only. Write locks
+/// are taken by [DatabaseAppend] operations, removals, [Database::append_all], [Database::import],
+/// [Database::reset] and [DatabaseStore] changes; [Database::append_all] and
+/// [Database::import] hold the locks of all collections while committing. A collection left
+/// poisoned by a panicking thread is recovered as it was left
+///
+/// Product lookups resolve the [aliases](Product::with_aliases) of the products, indexed as
+/// the products are written
 #[derive(Debug, Clone)]
 pub struct Database {
-    hm_product: Arc<Mutex<HashMap<String, Product>>>,
-    hm_promotion: Arc<Mutex<HashMap<String, Promotion>>>,
+    hm_product: Arc<RwLock<HashMap<Code, Product>>>,
+    /// Code of the product of every alias
+    hm_alias: Arc<RwLock<HashMap<Code, Code>>>,
+    hm_promotion: Arc<RwLock<HashMap<Code, Promotion>>>,
+    hm_store: Arc<RwLock<Stores>>,
+    inventory: Inventory,
+    revision: Arc<AtomicU64>,
 }
 
 impl Database {
@@ -25,8 +55,8 @@ impl Database {
     /// let promotion_code = String::from("Some Promotion");
     ///
     /// let products = vec![
-    ///     database.code_to_product_amount("Foo".to_string(), 2.0).unwrap(),
-    ///     database.code_to_product_amount("Bar".to_string(), 1.0).unwrap(),
+    ///     database.code_to_product_amount("Foo", 2.0).unwrap(),
+    ///     database.code_to_product_amount("Bar", 1.0).unwrap(),
     /// ];
     /// let promotion = Promotion::new("Some Promotion".to_string(), products, 5.0).unwrap();
     /// database.append(promotion).unwrap();
@@ -45,68 +75,278 @@ impl Database {
     ///     .for_each(|(key, &prod)| assert_eq!(prod, &v_base[key]));
     /// ```
     pub fn new() -> Self {
-        let hm_product = Arc::new(Mutex::new(HashMap::new()));
-        let hm_promotion = Arc::new(Mutex::new(HashMap::new()));
+        let hm_product = Arc::new(RwLock::new(HashMap::new()));
+        let hm_alias = Arc::new(RwLock::new(HashMap::new()));
+        let hm_promotion = Arc::new(RwLock::new(HashMap::new()));
+        let hm_store = Arc::new(RwLock::new(Stores::new()));
+        let revision = Arc::new(AtomicU64::new(0));
 
         Database {
             hm_product,
+            hm_alias,
             hm_promotion,
+            hm_store,
+            inventory: Inventory::new(),
+            revision,
         }
     }
 
+    /// Counter increased by every change of the database, to detect stale derived data
+    pub(crate) fn get_revision(&self) -> u64 {
+        self.revision.load(Ordering::SeqCst)
+    }
+
+    fn bump_revision(&self) {
+        self.revision.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// A collection poisoned by a panicking writer is recovered as it was left, and the
+    /// revision is bumped so derived data is computed again
+    fn recover<T>(&self, collection: &RwLock<T>) {
+        if lock::clear_poison(collection) {
+            self.bump_revision();
+        }
+    }
+
+    fn read<'a, T>(&self, collection: &'a RwLock<T>) -> RwLockReadGuard<'a, T> {
+        self.recover(collection);
+        lock::read(collection)
+    }
+
+    fn write<'a, T>(&self, collection: &'a RwLock<T>) -> RwLockWriteGuard<'a, T> {
+        self.recover(collection);
+        lock::write(collection)
+    }
+
+    /// Stock on hand of the products
+    pub fn get_inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    /// Stock on hand of the product `code`, or `None` if its stock is not tracked
+    pub fn stock(&self, code: &str) -> Result<Option<f64>, ErrorVariant> {
+        Ok(self.inventory.get_stock(code))
+    }
+
+    /// Typed handle to the application-defined entities stored under `name`
+    pub fn store<T: TerminalEntityInterface>(&self, name: &str) -> DatabaseStore<T> {
+        DatabaseStore::new(self.clone(), name)
+    }
+
+    pub(crate) fn with_stores<F, R>(&self, f: F) -> Result<R, ErrorVariant>
+    where
+        F: FnOnce(&Stores) -> R,
+    {
+        Ok(f(&self.read(&self.hm_store)))
+    }
+
+    pub(crate) fn with_stores_mut<F, R>(&self, f: F) -> Result<R, ErrorVariant>
+    where
+        F: FnOnce(&mut Stores) -> R,
+    {
+        Ok(f(&mut self.write(&self.hm_store)))
+    }
+
+    /// Read-only view of the database
+    pub fn reader(&self) -> DatabaseReader {
+        DatabaseReader::new(self.clone())
+    }
+
     pub fn code_to_product_amount(
         &self,
-        code: String,
+        code: &str,
         amount: f64,
     ) -> Result<ProductAmount, ErrorVariant> {
-        let product = self.fetch_product(&code)?;
+        let product = self.fetch_product(code)?;
         let product_amount = ProductAmount::new(product, amount);
         Ok(product_amount)
     }
 
-    pub fn fetch_promotion(&self, code: &String) -> Result<Promotion, ErrorVariant> {
-        let promotion = {
-            self.hm_promotion
-                .lock()
-                .map_err(|_| ErrorVariant::ArcUnlockError)?
-                .get(code)
-                .map(|p| Ok(p))
-                .unwrap_or(Err(ErrorVariant::PromotionNotFound))?
-                .clone()
-        };
+    pub fn fetch_promotion(&self, code: &str) -> Result<Promotion, ErrorVariant> {
+        self.with_promotion(code, Promotion::clone)
+    }
+
+    /// Run `f` with a reference to the promotion, without cloning it
+    pub fn with_promotion<F, R>(&self, code: &str, f: F) -> Result<R, ErrorVariant>
+    where
+        F: FnOnce(&Promotion) -> R,
+    {
+        self.read(&self.hm_promotion)
+            .get(code)
+            .map(f)
+            .ok_or_else(|| ErrorVariant::PromotionNotFound {
+                code: code.to_string(),
+            })
+    }
 
-        Ok(promotion)
+    /// Fetch a product by its code or one of its aliases
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// database.append(Product::new("A".to_string(), 2.0)).unwrap();
+    ///
+    /// assert_eq!(database.fetch_product("A").unwrap().get_price(), &2.0);
+    /// assert_eq!(
+    ///     database.fetch_product("Z").unwrap_err(),
+    ///     ErrorVariant::ProductNotFound { code: "Z".to_string() }
+    /// );
+    /// ```
+    pub fn fetch_product(&self, code: &str) -> Result<Product, ErrorVariant> {
+        self.with_product(code, Product::clone)
     }
 
-    pub fn fetch_product(&self, code: &String) -> Result<Product, ErrorVariant> {
-        let product = {
-            self.hm_product
-                .lock()
-                .map_err(|_| ErrorVariant::ArcUnlockError)?
+    /// Run `f` with a reference to the product, without cloning it
+    ///
+    /// The database is locked while `f` runs, so `f` must not access the database
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// database.append(Product::new("A".to_string(), 2.0)).unwrap();
+    ///
+    /// let price = database.with_product("A", |p| *p.get_price()).unwrap();
+    /// assert_eq!(price, 2.0);
+    /// assert!(database.with_product("Z", |p| *p.get_price()).is_err());
+    /// ```
+    pub fn with_product<F, R>(&self, code: &str, f: F) -> Result<R, ErrorVariant>
+    where
+        F: FnOnce(&Product) -> R,
+    {
+        let products = self.read(&self.hm_product);
+        let product = match products.get(code) {
+            Some(product) => Some(product),
+            None => self
+                .read(&self.hm_alias)
                 .get(code)
-                .map(|p| Ok(p))
-                .unwrap_or(Err(ErrorVariant::ProductNotFound))?
-                .clone()
+                .and_then(|code| products.get(code)),
         };
+        product.map(f).ok_or_else(|| ErrorVariant::ProductNotFound {
+            code: code.to_string(),
+        })
+    }
 
-        Ok(product)
+    /// Remove a product, returning it
+    ///
+    /// Promotions with the product are kept, and reported by [DatabaseStats] as broken
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// database.append(Product::new("A", 2.0)).unwrap();
+    ///
+    /// assert_eq!(database.remove_product("A").unwrap(), Product::new("A", 2.0));
+    /// assert!(!database.contains_product("A").unwrap());
+    /// assert!(database.remove_product("A").is_err());
+    /// ```
+    pub fn remove_product(&self, code: &str) -> Result<Product, ErrorVariant> {
+        let removed = self.write(&self.hm_product).remove(code);
+        if let Some(product) = &removed {
+            unindex_aliases(&mut self.write(&self.hm_alias), product);
+        }
+        self.bump_revision();
+        removed.ok_or_else(|| ErrorVariant::ProductNotFound {
+            code: code.to_string(),
+        })
     }
 
-    pub fn fetch_products(&self, products_code: Vec<String>) -> Result<Vec<Product>, ErrorVariant> {
-        let mut products = vec![];
+    /// Remove a promotion, returning it
+    pub fn remove_promotion(&self, code: &str) -> Result<Promotion, ErrorVariant> {
+        let removed = self.write(&self.hm_promotion).remove(code);
+        self.bump_revision();
+        removed.ok_or_else(|| ErrorVariant::PromotionNotFound {
+            code: code.to_string(),
+        })
+    }
 
-        for c in products_code {
-            products.push(self.fetch_product(&c)?);
+    /// Check if `code` is a registered product code
+    pub fn contains_product(&self, code: &str) -> Result<bool, ErrorVariant> {
+        match self.with_product(code, |_| ()) {
+            Ok(()) => Ok(true),
+            Err(ErrorVariant::ProductNotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
         }
+    }
+
+    pub fn fetch_products<I>(&self, products_code: I) -> Result<Vec<Product>, ErrorVariant>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        products_code
+            .into_iter()
+            .map(|c| self.fetch_product(c.as_ref()))
+            .collect()
+    }
+
+    /// Return all products that satisfy a predicate, sorted by code
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// database.append(Product::new("A1".to_string(), 2.0)).unwrap();
+    /// database.append(Product::new("A2".to_string(), 12.0)).unwrap();
+    /// database.append(Product::new("B1".to_string(), 1.25)).unwrap();
+    ///
+    /// let products = database
+    ///     .find_products_by(|p| p.get_code().starts_with("A") && p.get_price() < &5.0)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(products, vec![Product::new("A1".to_string(), 2.0)]);
+    /// ```
+    pub fn find_products_by<F>(&self, predicate: F) -> Result<Vec<Product>, ErrorVariant>
+    where
+        F: Fn(&Product) -> bool,
+    {
+        let mut products: Vec<Product> = {
+            self.read(&self.hm_product)
+                .values()
+                .filter(|p| predicate(p))
+                .cloned()
+                .collect()
+        };
+        products.sort();
 
         Ok(products)
     }
 
+    /// Products matching the filter, sorted by code
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let filter = Filter::new().with_price_range(1.0..10.0);
+    /// let products = terminal.get_db().unwrap().find_products(&filter).unwrap();
+    ///
+    /// let codes: Vec<&str> = products.iter().map(|p| p.get_code().as_str()).collect();
+    /// assert_eq!(codes, vec!["A", "C"]);
+    /// ```
+    pub fn find_products(&self, filter: &Filter) -> Result<Vec<Product>, ErrorVariant> {
+        self.find_products_by(|p| filter.matches(p))
+    }
+
     pub fn fetch_possible_promotions(
         &self,
         products: &Vec<&ProductAmount>,
     ) -> Result<Vec<Promotion>, ErrorVariant> {
-        self.fetch_possible_promotions_with_maximum_price(products, std::f64::INFINITY)
+        self.fetch_possible_promotions_with_maximum_price(products, f64::INFINITY)
     }
 
     /// Return all possible promotions for a given set of products
@@ -124,24 +364,24 @@ impl Database {
     /// database.append(Product::new("C".to_string(), 1.25)).unwrap();
     /// database.append(Product::new("D".to_string(), 0.15)).unwrap();
     ///
-    /// let products = vec![database.code_to_product_amount("A".to_string(), 4.0).unwrap()];
+    /// let products = vec![database.code_to_product_amount("A", 4.0).unwrap()];
     /// let promotion = Promotion::new("PA".to_string(), products, 7.0).unwrap();
     /// database.append(promotion).unwrap();
     ///
-    /// let products = vec![database.code_to_product_amount("C".to_string(), 6.0).unwrap()];
+    /// let products = vec![database.code_to_product_amount("C", 6.0).unwrap()];
     /// let promotion = Promotion::new("PC".to_string(), products, 6.0).unwrap();
     /// database.append(promotion).unwrap();
     ///
     /// let mut products = vec![];
     /// products.push(
     ///     database
-    ///         .fetch_product(&"A".to_string())
+    ///         .fetch_product("A")
     ///         .unwrap()
     ///         .generate_amount(9.0),
     /// );
     /// products.push(
     ///     database
-    ///         .fetch_product(&"C".to_string())
+    ///         .fetch_product("C")
     ///         .unwrap()
     ///         .generate_amount(9.0),
     /// );
@@ -149,7 +389,7 @@ impl Database {
     /// let mut possible = database
     ///     .fetch_possible_promotions_with_maximum_price(&param, 6.5)
     ///     .unwrap();
-    /// let expect = database.fetch_promotion(&"PC".to_string()).unwrap();
+    /// let expect = database.fetch_promotion("PC").unwrap();
     /// assert_eq!(possible.pop().unwrap(), expect);
     /// ```
     pub fn fetch_possible_promotions_with_maximum_price(
@@ -158,84 +398,571 @@ impl Database {
         maximum_price: f64,
     ) -> Result<Vec<Promotion>, ErrorVariant> {
         Ok(self
-            .hm_promotion
-            .lock()
-            .map_err(|_| ErrorVariant::ArcUnlockError)?
+            .read(&self.hm_promotion)
             .values()
             .filter(|promotion| {
                 promotion.get_price() < &maximum_price && promotion.is_contained_by(products)
             })
-            .map(|p| p.clone())
+            .cloned()
             .collect())
     }
 
-    pub fn reset(&self) -> Result<(), ErrorVariant> {
-        {
-            self.hm_product
-                .lock()
-                .map_err(|_| ErrorVariant::ArcUnlockError)
-                .and_then(|mut hm_product| Ok(hm_product.clear()))?;
+    /// Snapshot of all products, sorted by code
+    pub fn products(&self) -> Result<Vec<Product>, ErrorVariant> {
+        self.find_products_by(|_| true)
+    }
+
+    /// Snapshot of all promotions, sorted by code
+    pub fn promotions(&self) -> Result<Vec<Promotion>, ErrorVariant> {
+        let mut promotions: Vec<Promotion> =
+            { self.read(&self.hm_promotion).values().cloned().collect() };
+        promotions.sort_by(|a, b| a.get_code().cmp(b.get_code()));
+
+        Ok(promotions)
+    }
+
+    /// Page of at most `limit` products, sorted by code, skipping the first `offset`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// let database = terminal.get_db().unwrap();
+    ///
+    /// let codes = |page: Vec<Product>| -> Vec<Code> {
+    ///     page.iter().map(|p| p.get_code().clone()).collect()
+    /// };
+    ///
+    /// assert_eq!(codes(database.list_products(0, 3).unwrap()), vec!["A", "B", "C"]);
+    /// assert_eq!(codes(database.list_products(3, 3).unwrap()), vec!["D"]);
+    /// assert!(database.list_products(6, 3).unwrap().is_empty());
+    /// assert_eq!(database.promotions().unwrap()[0].get_code(), "PA");
+    /// ```
+    pub fn list_products(&self, offset: usize, limit: usize) -> Result<Vec<Product>, ErrorVariant> {
+        Ok(self
+            .products()?
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .collect())
+    }
+
+    /// Counts and price statistics of the catalog, to check its health after an import
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let terminal = TerminalBuilder::new().with_database(database.clone()).build().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let stats = database.stats().unwrap();
+    /// assert_eq!(stats.get_product_count(), 4);
+    /// assert_eq!(stats.get_promotion_count(), 2);
+    /// assert_eq!(stats.get_min_price(), Some(0.15));
+    /// assert_eq!(stats.get_max_price(), Some(12.0));
+    /// assert_eq!(stats.get_average_price(), Some(3.85));
+    /// assert_eq!(stats.get_broken_promotion_count(), 0);
+    ///
+    /// let promotions = database.promotions().unwrap();
+    /// database.reset().unwrap();
+    /// for promotion in promotions {
+    ///     database.append(promotion).unwrap();
+    /// }
+    /// assert_eq!(database.stats().unwrap().get_broken_promotion_count(), 2);
+    /// ```
+    pub fn stats(&self) -> Result<DatabaseStats, ErrorVariant> {
+        Ok(DatabaseStats::new(&self.products()?, &self.promotions()?))
+    }
+
+    /// Snapshot of all products and promotions, sorted by code
+    pub fn get_catalog(&self) -> Result<Catalog, ErrorVariant> {
+        let stores = self.with_stores(|stores| stores.clone())?;
+        Ok(Catalog::new(self.products()?, self.promotions()?).with_stores(stores))
+    }
+
+    /// Persist the catalog into a JSON file
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    /// use std::env;
+    ///
+    /// let path = env::temp_dir().join("store-terminal-export-doctest.json");
+    ///
+    /// let database = Database::new();
+    /// database.append(Product::new("A".to_string(), 2.0)).unwrap();
+    /// let products = vec![database.code_to_product_amount("A", 4.0).unwrap()];
+    /// database.append(Promotion::new("PA".to_string(), products, 7.0).unwrap()).unwrap();
+    /// database.export(&path).unwrap();
+    ///
+    /// let restored = Database::new();
+    /// restored.append(Product::new("Z".to_string(), 1.0)).unwrap();
+    /// restored.import(&path).unwrap();
+    ///
+    /// assert!(restored.fetch_product("Z").is_err());
+    /// assert_eq!(restored.fetch_product("A").unwrap().get_price(), &2.0);
+    /// assert_eq!(restored.fetch_promotion("PA").unwrap().get_price(), &7.0);
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn export(&self, path: &Path) -> Result<(), ErrorVariant> {
+        self.get_catalog()?.write(path)
+    }
+
+    /// Replace the products and promotions of the database with a catalog persisted by
+    /// [Database::export]
+    ///
+    /// Application-defined stores of the catalog replace the stores of the same name, and the
+    /// other stores are kept. The database is left unchanged if the catalog is invalid
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{env, fs};
+    /// use store_terminal::gift_card::{GiftCard, GIFT_CARD_STORE};
+    /// use store_terminal::prelude::*;
+    ///
+    /// let path = env::temp_dir().join(format!("price-list-{}.json", std::process::id()));
+    /// fs::write(&path, r#"{"products": [{"code": "A", "price": 2.5}]}"#).unwrap();
+    ///
+    /// let database = Database::new();
+    /// database.append(Product::new("Z", 1.0)).unwrap();
+    /// let cards = database.store::<GiftCard>(GIFT_CARD_STORE);
+    /// let mut card = GiftCard::new("6035-0001");
+    /// card.load(50.0).unwrap();
+    /// cards.upsert("6035-0001", &card).unwrap();
+    ///
+    /// database.import(&path).unwrap();
+    /// fs::remove_file(&path).unwrap();
+    ///
+    /// assert!(database.fetch_product("Z").is_err());
+    /// assert_eq!(database.fetch_product("A").unwrap().get_price(), &2.5);
+    /// assert_eq!(cards.fetch("6035-0001").unwrap().get_balance(), 50.0);
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn import(&self, path: &Path) -> Result<(), ErrorVariant> {
+        self.replace(Catalog::read(path)?)
+    }
+
+    /// Append the products and promotions only if all of them are valid, so a failure leaves
+    /// the database unchanged
+    ///
+    /// Every product of a promotion must be either in the database or in `products`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let a = Product::new("A", 2.0);
+    /// let b = Product::new("B", 12.0);
+    /// let pb = Promotion::new("PB", vec![b.generate_amount(2.0)], 20.0).unwrap();
+    ///
+    /// assert_eq!(
+    ///     database.append_all(vec![a.clone()], vec![pb.clone()]).unwrap_err(),
+    ///     ErrorVariant::ProductNotFound { code: "B".to_string() }
+    /// );
+    /// assert!(database.products().unwrap().is_empty());
+    ///
+    /// database.append_all(vec![a, b], vec![pb]).unwrap();
+    /// assert_eq!(database.stats().unwrap().get_product_count(), 2);
+    /// ```
+    pub fn append_all(
+        &self,
+        products: Vec<Product>,
+        promotions: Vec<Promotion>,
+    ) -> Result<(), ErrorVariant> {
+        self.commit(products, promotions, Stores::new(), false)
+    }
+
+    /// Replace the products, promotions and the stores carried by a catalog, or leave the
+    /// database unchanged if the catalog is invalid
+    #[cfg(feature = "json")]
+    pub(crate) fn replace(&self, catalog: Catalog) -> Result<(), ErrorVariant> {
+        let (products, promotions, stores) = catalog.into_parts();
+        self.commit(products, promotions, stores, true)
+    }
+
+    /// Append all the entities of a catalog, including its application-defined stores
+    pub(crate) fn merge(&self, catalog: Catalog) -> Result<(), ErrorVariant> {
+        let (products, promotions, stores) = catalog.into_parts();
+        self.commit(products, promotions, stores, false)
+    }
+
+    /// Validate and write the entities holding both locks, optionally replacing the contents
+    fn commit(
+        &self,
+        products: Vec<Product>,
+        promotions: Vec<Promotion>,
+        stores: Stores,
+        replace: bool,
+    ) -> Result<(), ErrorVariant> {
+        let mut hm_product = self.write(&self.hm_product);
+        let mut hm_promotion = self.write(&self.hm_promotion);
+        let mut hm_store = self.write(&self.hm_store);
+        let mut hm_alias = self.write(&self.hm_alias);
+
+        let incoming: HashSet<&Code> = products.iter().map(|p| p.get_code()).collect();
+        for promotion in &promotions {
+            for p in promotion.get_products() {
+                let code = p.get_code();
+                if !incoming.contains(code) && (replace || !hm_product.contains_key(code)) {
+                    return Err(ErrorVariant::ProductNotFound {
+                        code: code.to_string(),
+                    });
+                }
+            }
         }
-        {
-            self.hm_promotion
-                .lock()
-                .map_err(|_| ErrorVariant::ArcUnlockError)
-                .and_then(|mut hm_promotion| Ok(hm_promotion.clear()))?;
+
+        match replace {
+            true => check_aliases(&HashMap::new(), &HashMap::new(), &products)?,
+            false => check_aliases(&hm_product, &hm_alias, &products)?,
+        }
+
+        if replace {
+            hm_product.clear();
+            hm_promotion.clear();
+            hm_alias.clear();
+            for name in stores.keys() {
+                hm_store.remove(name);
+            }
         }
+        for product in products {
+            let (code, aliases) = (product.get_code().clone(), product.get_aliases().clone());
+            if let Some(previous) = hm_product.insert(code.clone(), product) {
+                unindex_aliases(&mut hm_alias, &previous);
+            }
+            index_aliases(&mut hm_alias, &code, aliases);
+        }
+        for promotion in promotions {
+            hm_promotion.insert(promotion.get_code().clone(), promotion);
+        }
+        for (name, entities) in stores {
+            hm_store.entry(name).or_default().extend(entities);
+        }
+        self.bump_revision();
+
+        Ok(())
+    }
+
+    pub fn reset(&self) -> Result<(), ErrorVariant> {
+        self.write(&self.hm_product).clear();
+        self.write(&self.hm_alias).clear();
+        self.write(&self.hm_promotion).clear();
+        self.with_stores_mut(|stores| stores.clear())?;
+        self.inventory.clear();
+        self.bump_revision();
         Ok(())
     }
 }
 
+impl Default for Database {
+    fn default() -> Self {
+        Database::new()
+    }
+}
+
+/// What a write into the [Database] did
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppendOutcome<T> {
+    /// The code was not registered
+    Inserted,
+    /// The code was registered, and its previous entity was replaced
+    Updated(T),
+}
+
 pub trait DatabaseAppend<T> {
-    fn append(&self, entity: T) -> Result<(), ErrorVariant>;
+    /// Insert the entity, replacing any entity with the same code
+    ///
+    /// Use [DatabaseAppend::upsert] to know if an entity was replaced
+    fn append(&self, entity: T) -> Result<(), ErrorVariant> {
+        self.upsert(entity).map(|_| ())
+    }
+
+    /// Insert the entity, failing if its code is already registered
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let outcome = database.insert_new(Product::new("A".to_string(), 2.0)).unwrap();
+    /// assert_eq!(outcome, AppendOutcome::Inserted);
+    ///
+    /// assert_eq!(
+    ///     database.insert_new(Product::new("A".to_string(), 3.0)).unwrap_err(),
+    ///     ErrorVariant::ProductAlreadyExists { code: "A".to_string() }
+    /// );
+    /// assert_eq!(database.fetch_product("A").unwrap().get_price(), &2.0);
+    /// ```
+    fn insert_new(&self, entity: T) -> Result<AppendOutcome<T>, ErrorVariant>;
+
+    /// Replace the entity with the same code, failing if the code is not registered
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// assert_eq!(
+    ///     database.update(Product::new("A".to_string(), 3.0)).unwrap_err(),
+    ///     ErrorVariant::ProductNotFound { code: "A".to_string() }
+    /// );
+    ///
+    /// database.append(Product::new("A".to_string(), 2.0)).unwrap();
+    /// let outcome = database.update(Product::new("A".to_string(), 3.0)).unwrap();
+    /// assert_eq!(outcome, AppendOutcome::Updated(Product::new("A".to_string(), 2.0)));
+    /// assert_eq!(database.fetch_product("A").unwrap().get_price(), &3.0);
+    /// ```
+    fn update(&self, entity: T) -> Result<AppendOutcome<T>, ErrorVariant>;
+
+    /// Insert the entity, or replace the entity with the same code
+    fn upsert(&self, entity: T) -> Result<AppendOutcome<T>, ErrorVariant>;
 }
 
-impl DatabaseAppend<Product> for Database {
-    fn append(&self, entity: Product) -> Result<(), ErrorVariant> {
-        let code = entity.get_code().clone();
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WriteMode {
+    InsertNew,
+    Update,
+    Upsert,
+}
 
+fn write_entry<T>(
+    hm: &mut HashMap<Code, T>,
+    code: Code,
+    entity: T,
+    mode: WriteMode,
+    not_found: fn(String) -> ErrorVariant,
+    already_exists: fn(String) -> ErrorVariant,
+) -> Result<AppendOutcome<T>, ErrorVariant> {
+    match (hm.contains_key(&code), mode) {
+        (true, WriteMode::InsertNew) => Err(already_exists(code.to_string())),
+        (false, WriteMode::Update) => Err(not_found(code.to_string())),
+        _ => Ok(match hm.insert(code, entity) {
+            Some(previous) => AppendOutcome::Updated(previous),
+            None => AppendOutcome::Inserted,
+        }),
+    }
+}
+
+impl Database {
+    fn write_product(
+        &self,
+        entity: Product,
+        mode: WriteMode,
+    ) -> Result<AppendOutcome<Product>, ErrorVariant> {
+        let mut products = self.write(&self.hm_product);
+        let mut aliases = self.write(&self.hm_alias);
+        if mode != WriteMode::InsertNew || !products.contains_key(entity.get_code()) {
+            check_aliases(&products, &aliases, std::slice::from_ref(&entity))?;
+        }
+        let (code, indexed) = (entity.get_code().clone(), entity.get_aliases().clone());
+        let outcome = write_entry(
+            &mut products,
+            code.clone(),
+            entity,
+            mode,
+            |code| ErrorVariant::ProductNotFound { code },
+            |code| ErrorVariant::ProductAlreadyExists { code },
+        )?;
+
+        if let AppendOutcome::Updated(previous) = &outcome {
+            unindex_aliases(&mut aliases, previous);
+        }
+        index_aliases(&mut aliases, &code, indexed);
+        drop((products, aliases));
+        self.bump_revision();
+        Ok(outcome)
+    }
+
+    fn write_promotion(
+        &self,
+        entity: Promotion,
+        mode: WriteMode,
+    ) -> Result<AppendOutcome<Promotion>, ErrorVariant> {
+        let outcome = write_entry(
+            &mut self.write(&self.hm_promotion),
+            entity.get_code().clone(),
+            entity,
+            mode,
+            |code| ErrorVariant::PromotionNotFound { code },
+            |code| ErrorVariant::PromotionAlreadyExists { code },
+        )?;
+        self.bump_revision();
+        Ok(outcome)
+    }
+}
+
+/// Check that the `incoming` products, once written over the `products` and `aliases` of the
+/// database, take no code or alias of another product as an alias, nor an alias of a product
+/// left as it is as a code
+fn check_aliases(
+    products: &HashMap<Code, Product>,
+    aliases: &HashMap<Code, Code>,
+    incoming: &[Product],
+) -> Result<(), ErrorVariant> {
+    let taken = |code: &Code| ErrorVariant::ProductAlreadyExists {
+        code: code.to_string(),
+    };
+    let codes: HashSet<&Code> = incoming.iter().map(|p| p.get_code()).collect();
+    let mut owners: HashMap<&Code, &Code> = HashMap::new();
+    for product in incoming {
+        let code = product.get_code();
+        if aliases
+            .get(code)
+            .is_some_and(|owner| !codes.contains(owner))
         {
-            self.hm_product
-                .lock()
-                .map_err(|_| ErrorVariant::ArcUnlockError)
-                .and_then(|mut hm_product| Ok(hm_product.insert(code, entity)))?;
+            return Err(taken(code));
         }
+        for alias in product.get_aliases() {
+            // The aliases of the incoming products are indexed again as they are written
+            let kept = |owner: &Code| owner != code && !codes.contains(owner);
+            if codes.contains(alias)
+                || products.contains_key(alias)
+                || aliases.get(alias).is_some_and(kept)
+            {
+                return Err(taken(alias));
+            }
+            match owners.insert(alias, code) {
+                Some(owner) if owner != code => return Err(taken(alias)),
+                _ => (),
+            }
+        }
+    }
+    Ok(())
+}
 
-        Ok(())
+/// Map the aliases `indexed` to the product `code`
+fn index_aliases(aliases: &mut HashMap<Code, Code>, code: &Code, indexed: Vec<Code>) {
+    for alias in indexed {
+        aliases.insert(alias, code.clone());
+    }
+}
+
+/// Forget the aliases of `product` not taken by another product since
+fn unindex_aliases(aliases: &mut HashMap<Code, Code>, product: &Product) {
+    for alias in product.get_aliases() {
+        if aliases.get(alias) == Some(product.get_code()) {
+            aliases.remove(alias);
+        }
+    }
+}
+
+impl DatabaseAppend<Product> for Database {
+    fn insert_new(&self, entity: Product) -> Result<AppendOutcome<Product>, ErrorVariant> {
+        self.write_product(entity, WriteMode::InsertNew)
+    }
+
+    fn update(&self, entity: Product) -> Result<AppendOutcome<Product>, ErrorVariant> {
+        self.write_product(entity, WriteMode::Update)
+    }
+
+    fn upsert(&self, entity: Product) -> Result<AppendOutcome<Product>, ErrorVariant> {
+        self.write_product(entity, WriteMode::Upsert)
     }
 }
 
 impl DatabaseAppend<Promotion> for Database {
-    fn append(&self, entity: Promotion) -> Result<(), ErrorVariant> {
-        let code = entity.get_code().clone();
+    fn insert_new(&self, entity: Promotion) -> Result<AppendOutcome<Promotion>, ErrorVariant> {
+        self.write_promotion(entity, WriteMode::InsertNew)
+    }
 
-        {
-            self.hm_promotion
-                .lock()
-                .map_err(|_| ErrorVariant::ArcUnlockError)
-                .and_then(|mut hm_promotion| Ok(hm_promotion.insert(code, entity)))?;
-        }
+    fn update(&self, entity: Promotion) -> Result<AppendOutcome<Promotion>, ErrorVariant> {
+        self.write_promotion(entity, WriteMode::Update)
+    }
 
-        Ok(())
+    fn upsert(&self, entity: Promotion) -> Result<AppendOutcome<Promotion>, ErrorVariant> {
+        self.write_promotion(entity, WriteMode::Upsert)
+    }
+}
+
+/// The whole catalog as a single JSON document, in the format of [Catalog]
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let json = terminal.get_db().unwrap().to_json().unwrap();
+/// let database = Database::from_json(json).unwrap();
+///
+/// assert_eq!(database.stats().unwrap().get_product_count(), 4);
+/// assert_eq!(database.fetch_promotion("PC").unwrap().get_price(), &6.0);
+/// ```
+#[cfg(feature = "json")]
+impl TerminalEntityInterface for Database {
+    fn get_syntax_example() -> &'static str {
+        r#"{"products":[{"code":"A","price":2.0}],"promotions":[{"code":"PA","products":[{"product":{"code":"A","price":2.0},"amount":4.0}],"price":7.0}]}"#
+    }
+
+    fn from_json(json: String) -> Result<Self, ErrorVariant> {
+        let catalog = serde_json::from_str::<Catalog>(json.as_str())
+            .map_err(|e| ErrorVariant::from_json_error(e, "Database"))?;
+        let database = Database::new();
+        catalog.append_into(&database)?;
+        Ok(database)
+    }
+
+    fn to_json(&self) -> Result<String, ErrorVariant> {
+        serde_json::to_string(&self.get_catalog()?)
+            .map_err(|e| ErrorVariant::from_json_error(e, "Database"))
     }
 }
 
+/// Products and then promotions, each sorted by code under a header, so the same catalog is
+/// always rendered the same way
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let expected = "\
+/// Products
+/// A @ 2.00
+/// B @ 12.00
+/// C @ 1.25
+/// D @ 0.15
+///
+/// Promotions
+/// PA @ 7.00
+///   4 x A @ 2.00
+/// PC @ 6.00
+///   6 x C @ 1.25";
+/// assert_eq!(terminal.get_db().unwrap().to_string(), expected);
+/// ```
 impl fmt::Display for Database {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let buffer = {
-            self.hm_promotion
-                .lock()
-                .map_err(|_| fmt::Error)?
-                .values()
-                .fold(String::from(""), |b, p| format!("{}\n{:?}", b, p))
-        };
-        let buffer = {
-            self.hm_product
-                .lock()
-                .map_err(|_| fmt::Error)?
-                .values()
-                .fold(buffer, |b, p| format!("{}\n{:?}", b, p))
-        };
-        write!(f, "{}", buffer)
+        let mut products: Vec<Product> =
+            { self.read(&self.hm_product).values().cloned().collect() };
+        products.sort();
+        let mut promotions: Vec<Promotion> =
+            { self.read(&self.hm_promotion).values().cloned().collect() };
+        promotions.sort_by(|a, b| a.get_code().cmp(b.get_code()));
+
+        write!(f, "Products")?;
+        for p in &products {
+            write!(f, "\n{}", p)?;
+        }
+        write!(f, "\n\nPromotions")?;
+        for p in &promotions {
+            write!(f, "\n{}", p)?;
+        }
+        Ok(())
     }
 }
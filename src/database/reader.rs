@@ -0,0 +1,172 @@
+use crate::clock::Clock;
+#[cfg(feature = "json")]
+use crate::prelude::TerminalEntityInterface;
+use crate::prelude::{
+    Catalog, Database, DatabaseStats, ErrorVariant, Filter, Inventory, Product, ProductAmount,
+    Promotion,
+};
+use std::fmt;
+#[cfg(feature = "json")]
+use std::path::Path;
+use std::sync::Arc;
+
+/// Read-only view of a [Database]
+///
+/// Exposes the fetch and query operations only, so it can be handed to code that must not
+/// change the catalog. Changes made through the [Database] are visible to its readers
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let database = Database::new();
+/// let reader = database.reader();
+///
+/// database.append(Product::new("A".to_string(), 2.0)).unwrap();
+/// assert_eq!(reader.fetch_product("A").unwrap().get_price(), &2.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatabaseReader {
+    database: Database,
+    inventory: Inventory,
+}
+
+impl DatabaseReader {
+    pub(crate) fn new(database: Database) -> Self {
+        DatabaseReader {
+            inventory: database.get_inventory().clone(),
+            database,
+        }
+    }
+
+    /// Reader stamping the stock movements recorded through it with `clock`
+    pub(crate) fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.inventory = self.inventory.with_clock(clock);
+        self
+    }
+
+    /// Writable handle of the viewed database, restricted to the crate
+    pub(crate) fn get_database(&self) -> &Database {
+        &self.database
+    }
+
+    pub(crate) fn get_revision(&self) -> u64 {
+        self.database.get_revision()
+    }
+
+    /// Stock on hand of the products, which is not part of the catalog
+    pub fn get_inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    /// Stock on hand of the product `code`, or `None` if its stock is not tracked
+    pub fn stock(&self, code: &str) -> Result<Option<f64>, ErrorVariant> {
+        self.database.stock(code)
+    }
+
+    pub fn code_to_product_amount(
+        &self,
+        code: &str,
+        amount: f64,
+    ) -> Result<ProductAmount, ErrorVariant> {
+        self.database.code_to_product_amount(code, amount)
+    }
+
+    pub fn fetch_promotion(&self, code: &str) -> Result<Promotion, ErrorVariant> {
+        self.database.fetch_promotion(code)
+    }
+
+    pub fn with_promotion<F, R>(&self, code: &str, f: F) -> Result<R, ErrorVariant>
+    where
+        F: FnOnce(&Promotion) -> R,
+    {
+        self.database.with_promotion(code, f)
+    }
+
+    pub fn fetch_product(&self, code: &str) -> Result<Product, ErrorVariant> {
+        self.database.fetch_product(code)
+    }
+
+    pub fn with_product<F, R>(&self, code: &str, f: F) -> Result<R, ErrorVariant>
+    where
+        F: FnOnce(&Product) -> R,
+    {
+        self.database.with_product(code, f)
+    }
+
+    pub fn contains_product(&self, code: &str) -> Result<bool, ErrorVariant> {
+        self.database.contains_product(code)
+    }
+
+    pub fn fetch_products<I>(&self, products_code: I) -> Result<Vec<Product>, ErrorVariant>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.database.fetch_products(products_code)
+    }
+
+    pub fn find_products_by<F>(&self, predicate: F) -> Result<Vec<Product>, ErrorVariant>
+    where
+        F: Fn(&Product) -> bool,
+    {
+        self.database.find_products_by(predicate)
+    }
+
+    pub fn find_products(&self, filter: &Filter) -> Result<Vec<Product>, ErrorVariant> {
+        self.database.find_products(filter)
+    }
+
+    pub fn fetch_possible_promotions(
+        &self,
+        products: &Vec<&ProductAmount>,
+    ) -> Result<Vec<Promotion>, ErrorVariant> {
+        self.database.fetch_possible_promotions(products)
+    }
+
+    pub fn fetch_possible_promotions_with_maximum_price(
+        &self,
+        products: &Vec<&ProductAmount>,
+        maximum_price: f64,
+    ) -> Result<Vec<Promotion>, ErrorVariant> {
+        self.database
+            .fetch_possible_promotions_with_maximum_price(products, maximum_price)
+    }
+
+    pub fn products(&self) -> Result<Vec<Product>, ErrorVariant> {
+        self.database.products()
+    }
+
+    pub fn promotions(&self) -> Result<Vec<Promotion>, ErrorVariant> {
+        self.database.promotions()
+    }
+
+    pub fn list_products(&self, offset: usize, limit: usize) -> Result<Vec<Product>, ErrorVariant> {
+        self.database.list_products(offset, limit)
+    }
+
+    pub fn stats(&self) -> Result<DatabaseStats, ErrorVariant> {
+        self.database.stats()
+    }
+
+    pub fn get_catalog(&self) -> Result<Catalog, ErrorVariant> {
+        self.database.get_catalog()
+    }
+
+    #[cfg(feature = "json")]
+    pub fn export(&self, path: &Path) -> Result<(), ErrorVariant> {
+        self.database.export(path)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, ErrorVariant> {
+        self.database.to_json()
+    }
+}
+
+impl fmt::Display for DatabaseReader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.database)
+    }
+}
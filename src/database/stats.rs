@@ -0,0 +1,72 @@
+use crate::prelude::{Code, Product, Promotion};
+use std::collections::HashSet;
+
+/// Summary of the contents of a [Database](crate::prelude::Database)
+///
+/// Price statistics refer to the products only, and are `None` for an empty catalog
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseStats {
+    product_count: usize,
+    promotion_count: usize,
+    min_price: Option<f64>,
+    max_price: Option<f64>,
+    average_price: Option<f64>,
+    broken_promotion_count: usize,
+}
+
+impl DatabaseStats {
+    pub(crate) fn new(products: &[Product], promotions: &[Promotion]) -> Self {
+        let prices = products.iter().map(|p| *p.get_price());
+        let min_price = prices.clone().reduce(f64::min);
+        let max_price = prices.clone().reduce(f64::max);
+        let average_price = match products.len() {
+            0 => None,
+            n => Some(prices.fold(0.0, |total, price| total + price) / n as f64),
+        };
+
+        let codes: HashSet<&Code> = products.iter().map(|p| p.get_code()).collect();
+        let broken_promotion_count = promotions
+            .iter()
+            .filter(|promotion| {
+                promotion
+                    .get_products()
+                    .iter()
+                    .any(|p| !codes.contains(p.get_code()))
+            })
+            .count();
+
+        DatabaseStats {
+            product_count: products.len(),
+            promotion_count: promotions.len(),
+            min_price,
+            max_price,
+            average_price,
+            broken_promotion_count,
+        }
+    }
+
+    pub fn get_product_count(&self) -> usize {
+        self.product_count
+    }
+
+    pub fn get_promotion_count(&self) -> usize {
+        self.promotion_count
+    }
+
+    pub fn get_min_price(&self) -> Option<f64> {
+        self.min_price
+    }
+
+    pub fn get_max_price(&self) -> Option<f64> {
+        self.max_price
+    }
+
+    pub fn get_average_price(&self) -> Option<f64> {
+        self.average_price
+    }
+
+    /// Number of promotions referencing products missing from the database
+    pub fn get_broken_promotion_count(&self) -> usize {
+        self.broken_promotion_count
+    }
+}
@@ -0,0 +1,176 @@
+use crate::prelude::{AppendOutcome, Database, ErrorVariant, TerminalEntityInterface};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+/// Entities of every application-defined store, as JSON documents indexed by store and code
+pub(crate) type Stores = BTreeMap<String, BTreeMap<String, Document>>;
+
+/// JSON document of a stored entity, parsed only when the `json` feature is enabled
+#[cfg(feature = "json")]
+pub(crate) type Document = serde_json::Value;
+#[cfg(not(feature = "json"))]
+pub(crate) type Document = String;
+
+/// Typed handle to a named collection of application-defined entities of a [Database]
+///
+/// Entities are kept as JSON documents through [TerminalEntityInterface], share the locking
+/// of the database, and are persisted by [Database::export] and [Database::import]
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "json")]
+/// # fn main() {
+/// use serde::{Deserialize, Serialize};
+/// use store_terminal::prelude::*;
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Locker {
+///     aisle: u32,
+/// }
+///
+/// impl TerminalEntityInterface for Locker {
+///     fn get_syntax_example() -> &'static str {
+///         r#"{"aisle": 4}"#
+///     }
+///
+///     fn from_json(json: String) -> Result<Self, ErrorVariant> {
+///         serde_json::from_str(&json).map_err(|e| ErrorVariant::from_json_error(e, "Locker"))
+///     }
+///
+///     fn to_json(&self) -> Result<String, ErrorVariant> {
+///         serde_json::to_string(self).map_err(|e| ErrorVariant::from_json_error(e, "Locker"))
+///     }
+/// }
+///
+/// let database = Database::new();
+/// let lockers = database.store::<Locker>("lockers");
+///
+/// lockers.upsert("L-1", &Locker { aisle: 4 }).unwrap();
+/// assert_eq!(lockers.fetch("L-1").unwrap(), Locker { aisle: 4 });
+/// assert_eq!(lockers.get_codes().unwrap(), vec!["L-1"]);
+///
+/// let moved = lockers.modify("L-1", |locker| Ok(Locker { aisle: locker.unwrap().aisle + 1 }));
+/// assert_eq!(moved.unwrap(), Locker { aisle: 5 });
+///
+/// let restored = Database::from_json(database.to_json().unwrap()).unwrap();
+/// assert_eq!(
+///     restored.store::<Locker>("lockers").fetch("L-1").unwrap(),
+///     Locker { aisle: 5 }
+/// );
+/// # }
+/// # #[cfg(not(feature = "json"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatabaseStore<T> {
+    database: Database,
+    name: String,
+    entity: PhantomData<fn() -> T>,
+}
+
+impl<T: TerminalEntityInterface> DatabaseStore<T> {
+    pub(crate) fn new(database: Database, name: &str) -> Self {
+        DatabaseStore {
+            database,
+            name: name.to_string(),
+            entity: PhantomData,
+        }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn fetch(&self, code: &str) -> Result<T, ErrorVariant> {
+        let json = self.database.with_stores(|stores| {
+            stores
+                .get(&self.name)
+                .and_then(|store| store.get(code))
+                .map(|value| value.to_string())
+        })?;
+
+        match json {
+            Some(json) => T::from_json(json),
+            None => Err(ErrorVariant::EntityNotFound {
+                store: self.name.clone(),
+                code: code.to_string(),
+            }),
+        }
+    }
+
+    /// Insert the entity, or replace the entity with the same code
+    pub fn upsert(&self, code: &str, entity: &T) -> Result<AppendOutcome<T>, ErrorVariant> {
+        let value = parse_document(entity.to_json()?)?;
+
+        let previous = self.database.with_stores_mut(|stores| {
+            stores
+                .entry(self.name.clone())
+                .or_default()
+                .insert(code.to_string(), value)
+        })?;
+
+        match previous {
+            Some(previous) => T::from_json(previous.to_string()).map(AppendOutcome::Updated),
+            None => Ok(AppendOutcome::Inserted),
+        }
+    }
+
+    /// Replace the entity `code` by `f` applied to it, or to `None` if it is not stored, so
+    /// concurrent changes of the entity aren't lost
+    ///
+    /// `f` runs holding the write lock of the stores, and must not access the database. The
+    /// entity is left unchanged if `f` fails
+    pub fn modify<F>(&self, code: &str, f: F) -> Result<T, ErrorVariant>
+    where
+        F: FnOnce(Option<T>) -> Result<T, ErrorVariant>,
+    {
+        self.database.with_stores_mut(|stores| {
+            let current = stores
+                .get(&self.name)
+                .and_then(|store| store.get(code))
+                .map(|value| T::from_json(value.to_string()))
+                .transpose()?;
+            let entity = f(current)?;
+            let value = parse_document(entity.to_json()?)?;
+            stores
+                .entry(self.name.clone())
+                .or_default()
+                .insert(code.to_string(), value);
+            Ok(entity)
+        })?
+    }
+
+    /// Remove the entity, returning it if it was stored
+    pub fn remove(&self, code: &str) -> Result<Option<T>, ErrorVariant> {
+        let previous = self.database.with_stores_mut(|stores| {
+            stores
+                .get_mut(&self.name)
+                .and_then(|store| store.remove(code))
+        })?;
+
+        previous
+            .map(|value| T::from_json(value.to_string()))
+            .transpose()
+    }
+
+    /// Codes of the stored entities, sorted
+    pub fn get_codes(&self) -> Result<Vec<String>, ErrorVariant> {
+        self.database.with_stores(|stores| {
+            stores
+                .get(&self.name)
+                .map(|store| store.keys().cloned().collect())
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[cfg(feature = "json")]
+fn parse_document(json: String) -> Result<Document, ErrorVariant> {
+    serde_json::from_str(&json).map_err(|e| ErrorVariant::from_json_error(e, "DatabaseStore"))
+}
+
+#[cfg(not(feature = "json"))]
+fn parse_document(json: String) -> Result<Document, ErrorVariant> {
+    Ok(json)
+}
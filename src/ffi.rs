@@ -0,0 +1,250 @@
+//! C ABI of the terminal, for embedding the pricing engine in other point-of-sale software
+//!
+//! A terminal is created with [store_terminal_create] and released with [store_terminal_free].
+//! Operations take and return NUL-terminated UTF-8 strings; every returned string is a JSON
+//! document, `{"error": "<message>"}` on failure, and must be released with
+//! [store_terminal_string_free]. The declarations are in `include/store_terminal.h`
+//!
+//! The same exports back the WebAssembly build, wrapped for JavaScript by
+//! `bindings/store_terminal.js`. The module imports `env.store_terminal_now`, returning the
+//! milliseconds since the Unix epoch read by [SystemClock](crate::clock::SystemClock), as the
+//! target has no system clock
+//!
+//! # Example
+//!
+//! ```
+//! use std::ffi::{CStr, CString};
+//! use store_terminal::ffi::*;
+//!
+//! unsafe fn json(s: *mut std::os::raw::c_char) -> serde_json::Value {
+//!     let value = serde_json::from_str(CStr::from_ptr(s).to_str().unwrap()).unwrap();
+//!     store_terminal_string_free(s);
+//!     value
+//! }
+//!
+//! let catalog = r#"{"products": [{"code": "A", "price": 2.5}], "promotions": []}"#;
+//! let catalog = CString::new(catalog).unwrap();
+//! let codes = CString::new("A A X").unwrap();
+//!
+//! unsafe {
+//!     let terminal = store_terminal_create(catalog.as_ptr());
+//!     assert!(!terminal.is_null());
+//!
+//!     let report = json(store_terminal_scan(terminal, codes.as_ptr()));
+//!     assert_eq!(report["unknown"], serde_json::json!(["X"]));
+//!     assert_eq!(json(store_terminal_get_total(terminal))["total"], 5.0);
+//!
+//!     let catalog = CString::new(r#"{"products": [{"code": "B", "price": 1.0}], "promotions": []}"#);
+//!     let loaded = json(store_terminal_load_catalog(terminal, catalog.unwrap().as_ptr()));
+//!     assert_eq!(loaded["products"], 1);
+//!     assert_eq!(json(store_terminal_get_cart(terminal))["items"][0]["amount"], 2.0);
+//!
+//!     let receipt = json(store_terminal_checkout(terminal));
+//!     assert_eq!(receipt["items"][0]["code"], "A");
+//!     assert_eq!(receipt["total"], 5.0);
+//!     assert_eq!(json(store_terminal_get_total(terminal))["total"], 0.0);
+//!
+//!     store_terminal_free(terminal);
+//! }
+//! ```
+
+use crate::prelude::{Catalog, Database, Terminal, TerminalBuilder, TerminalEntityInterface};
+use serde_json::{json, Value};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Create a terminal with the catalog JSON document, or an empty one if `catalog` is null
+///
+/// Returns null if the catalog is invalid
+///
+/// # Safety
+///
+/// `catalog` must be null or a valid NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn store_terminal_create(catalog: *const c_char) -> *mut Terminal {
+    let terminal = panic::catch_unwind(|| {
+        let database = if catalog.is_null() {
+            Database::new()
+        } else {
+            Database::from_json(read_str(catalog)?.to_string()).map_err(|e| e.to_string())?
+        };
+        TerminalBuilder::new()
+            .with_database(database)
+            .build()
+            .map_err(|e| e.to_string())
+    });
+
+    match terminal {
+        Ok(Ok(terminal)) => Box::into_raw(Box::new(terminal)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Release a terminal created by [store_terminal_create]
+///
+/// # Safety
+///
+/// `terminal` must be null or returned by [store_terminal_create], and not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn store_terminal_free(terminal: *mut Terminal) {
+    if !terminal.is_null() {
+        drop(Box::from_raw(terminal));
+    }
+}
+
+/// Scan the codes, separated by whitespaces or commas, as [Terminal::scan]
+///
+/// Returns `{"accepted": [...], "unknown": [...], "total": <scanned total>}`
+///
+/// # Safety
+///
+/// `terminal` must be returned by [store_terminal_create], and `codes` must be a valid
+/// NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn store_terminal_scan(
+    terminal: *const Terminal,
+    codes: *const c_char,
+) -> *mut c_char {
+    respond(terminal, |terminal| {
+        terminal
+            .scan(read_str(codes)?)
+            .map(|report| report.summary_json())
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// Append the products and promotions of the catalog JSON document to the database
+///
+/// Returns `{"products": <count>, "promotions": <count>}` of the loaded catalog
+///
+/// # Safety
+///
+/// `terminal` must be returned by [store_terminal_create], and `catalog` must be a valid
+/// NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn store_terminal_load_catalog(
+    terminal: *const Terminal,
+    catalog: *const c_char,
+) -> *mut c_char {
+    respond(terminal, |terminal| {
+        let catalog: Catalog =
+            serde_json::from_str(read_str(catalog)?).map_err(|e| e.to_string())?;
+        let counts = json!({
+            "products": catalog.get_products().len(),
+            "promotions": catalog.get_promotions().len(),
+        });
+        terminal
+            .change_catalog(|database| catalog.append_into(database))
+            .map_err(|e| e.to_string())?;
+        Ok(counts)
+    })
+}
+
+/// Items of the optimized cart and its total, in the format of [store_terminal_checkout]
+///
+/// # Safety
+///
+/// `terminal` must be returned by [store_terminal_create]
+#[no_mangle]
+pub unsafe extern "C" fn store_terminal_get_cart(terminal: *const Terminal) -> *mut c_char {
+    respond(terminal, |terminal| {
+        terminal
+            .get_cart()
+            .map(|cart| cart.summary_json())
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// Total of the optimized cart, as `{"total": <total>}`
+///
+/// # Safety
+///
+/// `terminal` must be returned by [store_terminal_create]
+#[no_mangle]
+pub unsafe extern "C" fn store_terminal_get_total(terminal: *const Terminal) -> *mut c_char {
+    respond(terminal, |terminal| {
+        let cart = terminal.get_cart().map_err(|e| e.to_string())?;
+        Ok(json!({ "total": cart.get_total_price() }))
+    })
+}
+
+/// Close the sale, returning the items of the optimized cart and its total, and empty the cart
+///
+/// Returns `{"id", "timestamp", "items": [{"code", "description", "amount", "total"}, ...],
+/// "tenders": [], "total": <total>, "change": 0.0}`
+///
+/// # Safety
+///
+/// `terminal` must be returned by [store_terminal_create]
+#[no_mangle]
+pub unsafe extern "C" fn store_terminal_checkout(terminal: *const Terminal) -> *mut c_char {
+    respond(terminal, |terminal| {
+        terminal
+            .checkout()
+            .map(|sale| sale.summary_json())
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// Buffer of `len` zeroed bytes owned by the terminal library, for hosts that can't allocate
+/// in its memory themselves, such as JavaScript with the WebAssembly build
+#[no_mangle]
+pub extern "C" fn store_terminal_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
+/// Release a buffer returned by [store_terminal_alloc]
+///
+/// # Safety
+///
+/// `buffer` must be null or returned by [store_terminal_alloc] with the same `len`, and not
+/// used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn store_terminal_dealloc(buffer: *mut u8, len: usize) {
+    if !buffer.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, len)));
+    }
+}
+
+/// Release a string returned by the terminal
+///
+/// # Safety
+///
+/// `s` must be null or returned by a function of this module, and not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn store_terminal_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, String> {
+    if s.is_null() {
+        return Err("null string".to_string());
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|e| format!("invalid UTF-8 string: {}", e))
+}
+
+/// Run `f` with the terminal, returning its result or error as a JSON string
+unsafe fn respond<F>(terminal: *const Terminal, f: F) -> *mut c_char
+where
+    F: FnOnce(&Terminal) -> Result<Value, String>,
+{
+    let response = match terminal.as_ref() {
+        None => Err("null terminal".to_string()),
+        Some(terminal) => panic::catch_unwind(AssertUnwindSafe(|| f(terminal)))
+            .unwrap_or_else(|_| Err("terminal panicked".to_string())),
+    };
+
+    let document = match response {
+        Ok(value) => value,
+        Err(message) => json!({ "error": message }),
+    };
+    CString::new(document.to_string())
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
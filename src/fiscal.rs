@@ -0,0 +1,293 @@
+//! Fiscal journal of the completed sales, for the certified exports required by tax authorities
+//!
+//! Every sale is appended to a [FiscalJournal] as a [FiscalRecord] chained to the previous one
+//! by its SHA-256 hash and signed with HMAC-SHA256, so removing, reordering or altering a
+//! record is detected by [FiscalJournal::verify]. The journal is stored as JSON lines, and
+//! the sales of a day are exported as a SAF-T-like XML audit file
+
+use crate::clock::Clock;
+use crate::digest;
+use crate::prelude::{ErrorVariant, Receipt};
+use crate::receipt::escape_html;
+use crate::sale::history::datetime;
+use serde::{Deserialize, Serialize};
+
+/// Previous hash of the first record of a journal
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Item of a [FiscalRecord]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiscalLine {
+    code: String,
+    description: String,
+    amount: f64,
+    unit_price: f64,
+    total: f64,
+}
+
+/// Sale appended to a [FiscalJournal]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiscalRecord {
+    sequence: u64,
+    timestamp: u64,
+    terminal: String,
+    lines: Vec<FiscalLine>,
+    total: f64,
+    tax: f64,
+    previous_hash: String,
+    hash: String,
+    signature: String,
+}
+
+impl FiscalRecord {
+    /// Position of the sale in the journal, starting at 1
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn get_total(&self) -> f64 {
+        self.total
+    }
+
+    pub fn get_hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Hash of the chained content of the record, without its hash and signature
+    fn digest(&self) -> String {
+        let mut content = serde_json::to_value(self).unwrap_or_default();
+        if let Some(content) = content.as_object_mut() {
+            content.remove("hash");
+            content.remove("signature");
+        }
+        digest::hex(&digest::sha256(content.to_string().as_bytes()))
+    }
+
+    fn sign(&self, key: &[u8]) -> String {
+        digest::hex(&digest::hmac_sha256(key, self.hash.as_bytes()))
+    }
+}
+
+/// Append-only, hash-chained and signed journal of the sales of a terminal
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::fiscal::FiscalJournal;
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// let mut journal = FiscalJournal::new("terminal-1", "signing key");
+///
+/// terminal.scan("AB").unwrap();
+/// let receipt = Receipt::from_sale(&terminal.checkout().unwrap()).with_tax("VAT", 0.2);
+/// journal.record_at(&receipt, 1_760_000_000);
+/// terminal.scan("AAAA").unwrap();
+/// journal.record_at(&Receipt::from_sale(&terminal.checkout().unwrap()), 1_760_003_600);
+/// assert!(journal.verify().is_ok());
+///
+/// let xml = journal.to_saft_xml("2025-10-09");
+/// assert!(xml.contains("<NumberOfEntries>2</NumberOfEntries>"));
+/// assert!(xml.contains("<SystemEntryDate>2025-10-09T08:53:20</SystemEntryDate>"));
+/// assert!(xml.contains("<TaxPayable>2.33</TaxPayable><NetTotal>11.67</NetTotal>"));
+/// assert!(journal.to_saft_xml("2025-10-10").contains("<NumberOfEntries>0</NumberOfEntries>"));
+///
+/// // Altering a stored sale breaks the chain
+/// let tampered = journal.to_json_lines().replacen("\"total\":14.0", "\"total\":4.0", 1);
+/// let tampered = FiscalJournal::from_json_lines("terminal-1", "signing key", &tampered).unwrap();
+/// assert_eq!(
+///     tampered.verify().unwrap_err(),
+///     ErrorVariant::InvalidJournal { sequence: 1 }
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiscalJournal {
+    terminal_id: String,
+    key: Vec<u8>,
+    records: Vec<FiscalRecord>,
+}
+
+impl FiscalJournal {
+    /// Empty journal of the terminal `terminal_id`, signed with `key`
+    pub fn new(terminal_id: &str, key: &str) -> Self {
+        FiscalJournal {
+            terminal_id: terminal_id.to_string(),
+            key: key.as_bytes().to_vec(),
+            records: vec![],
+        }
+    }
+
+    /// Journal stored with [FiscalJournal::to_json_lines]; see [FiscalJournal::verify] to check it
+    pub fn from_json_lines(
+        terminal_id: &str,
+        key: &str,
+        lines: &str,
+    ) -> Result<Self, ErrorVariant> {
+        let records = lines
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|e| ErrorVariant::from_json_error(e, "fiscal record"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(FiscalJournal {
+            records,
+            ..FiscalJournal::new(terminal_id, key)
+        })
+    }
+
+    pub fn get_records(&self) -> &Vec<FiscalRecord> {
+        &self.records
+    }
+
+    /// Append the sale of `receipt`, closed now by `clock`, usually
+    /// [Terminal::get_clock](crate::prelude::Terminal::get_clock)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use store_terminal::clock::ManualClock;
+    /// use store_terminal::fiscal::FiscalJournal;
+    /// use store_terminal::prelude::*;
+    ///
+    /// let clock = Arc::new(ManualClock::new(1_760_000_000));
+    /// let terminal = TerminalBuilder::new().with_clock(clock).build().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// let mut journal = FiscalJournal::new("terminal-1", "signing key");
+    ///
+    /// terminal.scan("AB").unwrap();
+    /// let receipt = Receipt::from_sale(&terminal.checkout().unwrap());
+    /// assert_eq!(journal.record(&receipt, terminal.get_clock()).get_timestamp(), 1_760_000_000);
+    /// ```
+    pub fn record(&mut self, receipt: &Receipt, clock: &dyn Clock) -> &FiscalRecord {
+        self.record_at(receipt, clock.now())
+    }
+
+    /// Append the sale of `receipt`, closed at `timestamp` seconds since the Unix epoch
+    pub fn record_at(&mut self, receipt: &Receipt, timestamp: u64) -> &FiscalRecord {
+        let previous = self.records.last();
+        let mut record = FiscalRecord {
+            sequence: previous.map_or(1, |r| r.sequence + 1),
+            timestamp,
+            terminal: self.terminal_id.clone(),
+            lines: receipt
+                .get_lines()
+                .iter()
+                .map(|line| FiscalLine {
+                    code: line.get_code().to_string(),
+                    description: line.get_description().to_string(),
+                    amount: line.get_amount(),
+                    unit_price: line.get_unit_price(),
+                    total: line.get_total(),
+                })
+                .collect(),
+            total: receipt.get_totals().get_total(),
+            tax: receipt.get_totals().get_total_tax(),
+            previous_hash: previous.map_or(GENESIS_HASH.to_string(), |r| r.hash.clone()),
+            hash: String::new(),
+            signature: String::new(),
+        };
+        record.hash = record.digest();
+        record.signature = record.sign(&self.key);
+
+        self.records.push(record);
+        &self.records[self.records.len() - 1]
+    }
+
+    /// Check the sequence, chain and signature of every record
+    pub fn verify(&self) -> Result<(), ErrorVariant> {
+        let mut previous_hash = GENESIS_HASH;
+        for (i, record) in self.records.iter().enumerate() {
+            if record.sequence != i as u64 + 1
+                || record.previous_hash != previous_hash
+                || record.hash != record.digest()
+                || record.signature != record.sign(&self.key)
+            {
+                return Err(ErrorVariant::InvalidJournal {
+                    sequence: record.sequence,
+                });
+            }
+            previous_hash = &record.hash;
+        }
+        Ok(())
+    }
+
+    /// One JSON document per record, to append to the journal file
+    pub fn to_json_lines(&self) -> String {
+        self.records
+            .iter()
+            .filter_map(|record| serde_json::to_string(record).ok())
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    /// Audit file of the sales of `date`, as `YYYY-MM-DD` in UTC
+    pub fn to_saft_xml(&self, date: &str) -> String {
+        let records: Vec<&FiscalRecord> = self
+            .records
+            .iter()
+            .filter(|record| datetime(record.timestamp).starts_with(date))
+            .collect();
+        let total = records.iter().fold(0.0, |total, r| total + r.total);
+
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<AuditFile>\n<Header>\
+             <SoftwareID>{}</SoftwareID><SoftwareVersion>{}</SoftwareVersion>\
+             <TerminalID>{}</TerminalID><SelectionDate>{}</SelectionDate></Header>\n\
+             <SourceDocuments><SalesInvoices>\n\
+             <NumberOfEntries>{}</NumberOfEntries><TotalCredit>{:.2}</TotalCredit>\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            escape_html(&self.terminal_id),
+            escape_html(date),
+            records.len(),
+            total
+        );
+
+        for record in records {
+            let entry_date = datetime(record.timestamp);
+            xml.push_str(&format!(
+                "<Invoice><InvoiceNo>{}/{}</InvoiceNo><InvoiceDate>{}</InvoiceDate>\
+                 <SystemEntryDate>{}</SystemEntryDate><Hash>{}</Hash><Signature>{}</Signature>\n",
+                escape_html(&record.terminal),
+                record.sequence,
+                &entry_date[..10],
+                entry_date,
+                record.hash,
+                record.signature
+            ));
+            for (number, line) in record.lines.iter().enumerate() {
+                xml.push_str(&format!(
+                    "<Line><LineNumber>{}</LineNumber><ProductCode>{}</ProductCode>\
+                     <ProductDescription>{}</ProductDescription><Quantity>{}</Quantity>\
+                     <UnitPrice>{:.2}</UnitPrice><CreditAmount>{:.2}</CreditAmount></Line>\n",
+                    number + 1,
+                    escape_html(&line.code),
+                    escape_html(&line.description),
+                    line.amount,
+                    line.unit_price,
+                    line.total
+                ));
+            }
+            xml.push_str(&format!(
+                "<DocumentTotals><TaxPayable>{:.2}</TaxPayable><NetTotal>{:.2}</NetTotal>\
+                 <GrossTotal>{:.2}</GrossTotal></DocumentTotals></Invoice>\n",
+                record.tax,
+                record.total - record.tax,
+                record.total
+            ));
+        }
+
+        xml.push_str("</SalesInvoices></SourceDocuments>\n</AuditFile>\n");
+        xml
+    }
+}
@@ -0,0 +1,178 @@
+//! Gift cards, sold as cart lines loading their balance and redeemed as a tender
+//!
+//! Cards are kept in the [DatabaseStore] named [GIFT_CARD_STORE], so they are shared by the
+//! lanes of a database and persisted with its catalog. A card sold by
+//! [Terminal::sell_gift_card](crate::prelude::Terminal::sell_gift_card) is loaded when its
+//! sale is checked out, and drawn down by
+//! [Terminal::checkout_with_gift_card](crate::prelude::Terminal::checkout_with_gift_card)
+
+use crate::prelude::ErrorVariant;
+#[cfg(feature = "json")]
+use crate::prelude::{DatabaseStore, TerminalEntityInterface};
+use crate::receipt::round_cents;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+/// Name of the store of the gift cards in the [Database](crate::prelude::Database)
+pub const GIFT_CARD_STORE: &str = "gift-cards";
+
+/// Code of the cart lines of the gift cards sold, priced 1.0 per unit of balance
+pub const GIFT_CARD_CODE: &str = "GIFT-CARD";
+
+/// Stored value card of the store, identified by its number
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::gift_card::GiftCard;
+/// use store_terminal::prelude::*;
+///
+/// let mut card = GiftCard::new("6035-0001");
+/// card.load(50.0).unwrap();
+/// card.redeem(19.0).unwrap();
+/// assert_eq!(card.get_balance(), 31.0);
+///
+/// assert_eq!(
+///     card.redeem(40.0).unwrap_err(),
+///     ErrorVariant::NotEnoughItems {
+///         code: "6035-0001".to_string(),
+///         requested: 40.0,
+///         available: 31.0,
+///     }
+/// );
+/// assert!(card.load(-5.0).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct GiftCard {
+    number: String,
+    balance: f64,
+}
+
+impl GiftCard {
+    /// Card issued with an empty balance
+    pub fn new(number: &str) -> Self {
+        GiftCard {
+            number: number.to_string(),
+            balance: 0.0,
+        }
+    }
+
+    pub fn get_number(&self) -> &str {
+        &self.number
+    }
+
+    pub fn get_balance(&self) -> f64 {
+        self.balance
+    }
+
+    /// Add `amount` to the balance
+    pub fn load(&mut self, amount: f64) -> Result<(), ErrorVariant> {
+        self.check_amount(amount)?;
+        self.balance = round_cents(self.balance + amount);
+        Ok(())
+    }
+
+    /// Draw `amount` from the balance, which can't go below zero
+    pub fn redeem(&mut self, amount: f64) -> Result<(), ErrorVariant> {
+        self.check_amount(amount)?;
+        if round_cents(amount) > self.balance {
+            return Err(ErrorVariant::NotEnoughItems {
+                code: self.number.clone(),
+                requested: amount,
+                available: self.balance,
+            });
+        }
+        self.balance = round_cents(self.balance - amount);
+        Ok(())
+    }
+
+    fn check_amount(&self, amount: f64) -> Result<(), ErrorVariant> {
+        if amount.is_finite() && amount > 0.0 {
+            Ok(())
+        } else {
+            Err(ErrorVariant::InvalidQuantity {
+                code: self.number.clone(),
+                quantity: amount,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl TerminalEntityInterface for GiftCard {
+    fn get_syntax_example() -> &'static str {
+        r#"{"number": "6035-0001", "balance": 50.0}"#
+    }
+
+    fn from_json(json: String) -> Result<Self, ErrorVariant> {
+        serde_json::from_str(&json).map_err(|e| ErrorVariant::from_json_error(e, "GiftCard"))
+    }
+
+    fn to_json(&self) -> Result<String, ErrorVariant> {
+        serde_json::to_string(self).map_err(|e| ErrorVariant::from_json_error(e, "GiftCard"))
+    }
+}
+
+/// Balance loaded onto a gift card by a sale, applied when the sale is checked out
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct GiftCardLoad {
+    number: String,
+    amount: f64,
+}
+
+impl GiftCardLoad {
+    pub fn new(number: &str, amount: f64) -> Self {
+        GiftCardLoad {
+            number: number.to_string(),
+            amount,
+        }
+    }
+
+    pub fn get_number(&self) -> &str {
+        &self.number
+    }
+
+    pub fn get_amount(&self) -> f64 {
+        self.amount
+    }
+}
+
+/// Draw up to `amount` from the balance of the stored card `number`, returning the amount drawn
+#[cfg(feature = "json")]
+pub(crate) fn draw(
+    cards: &DatabaseStore<GiftCard>,
+    number: &str,
+    amount: f64,
+) -> Result<f64, ErrorVariant> {
+    let mut drawn = 0.0;
+    cards.modify(number, |card| {
+        let mut card = card.ok_or_else(|| ErrorVariant::EntityNotFound {
+            store: GIFT_CARD_STORE.to_string(),
+            code: number.to_string(),
+        })?;
+        drawn = round_cents(card.get_balance().min(amount));
+        if drawn > 0.0 {
+            card.redeem(drawn)?;
+        }
+        Ok(card)
+    })?;
+    Ok(drawn)
+}
+
+/// Load `amount` onto the stored card `number`, issuing it if it is not stored
+#[cfg(feature = "json")]
+pub(crate) fn credit(
+    cards: &DatabaseStore<GiftCard>,
+    number: &str,
+    amount: f64,
+) -> Result<(), ErrorVariant> {
+    cards
+        .modify(number, |card| {
+            let mut card = card.unwrap_or_else(|| GiftCard::new(number));
+            card.load(amount)?;
+            Ok(card)
+        })
+        .map(|_| ())
+}
@@ -0,0 +1,503 @@
+//! Stock on hand of the products, tracked per code
+//!
+//! Products without a stock are not tracked, and are always available. Checkouts decrement
+//! the stock of the products sold, alerting when it drops below the reorder threshold of the
+//! product, and the [StockPolicy] of the terminal decides what a scan taking more than the
+//! stock on hand does. Deliveries and corrections are recorded in a journal of
+//! [StockMovement]s
+
+use crate::clock::{Clock, SystemClock};
+use crate::lock;
+use crate::prelude::{Code, ErrorVariant, SoldProduct};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// What a terminal does when a scan takes more of a product than its stock on hand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StockPolicy {
+    /// Accept the scan, and emit [TerminalEvent::OutOfStock](crate::prelude::TerminalEvent::OutOfStock)
+    #[default]
+    Warn,
+    /// Reject the scan with [ErrorVariant::NotEnoughItems]
+    Block,
+}
+
+/// Shared stock on hand per product code, held by the [Database](crate::prelude::Database)
+///
+/// Clones share the same stock. Sales accepted by [StockPolicy::Warn] may take the stock below
+/// zero, showing how much was sold without being counted. Movements are stamped by the clock of
+/// the handle, the system time unless set by [Inventory::with_clock]; the inventory of
+/// [Terminal::get_db](crate::prelude::Terminal::get_db) uses the clock of the terminal
+///
+/// # Example
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let inventory = terminal.get_db().unwrap().get_inventory();
+/// inventory.set_stock("A", 5.0).unwrap();
+/// assert!(inventory.set_stock("B", -1.0).is_err());
+///
+/// terminal.scan("AAAAB").unwrap();
+/// terminal.checkout().unwrap();
+///
+/// assert_eq!(terminal.get_db().unwrap().stock("A").unwrap(), Some(1.0));
+/// assert_eq!(terminal.get_db().unwrap().stock("B").unwrap(), None);
+/// assert_eq!(inventory.get_levels()[0].get_quantity(), 1.0);
+///
+/// let events = Arc::new(Mutex::new(vec![]));
+/// let log = events.clone();
+/// terminal.subscribe(move |event| log.lock().unwrap().push(event.clone())).unwrap();
+///
+/// terminal.scan("AA").unwrap();
+/// assert_eq!(
+///     events.lock().unwrap()[2],
+///     TerminalEvent::OutOfStock { code: Code::from("A"), stock: 1.0 }
+/// );
+/// ```
+#[derive(Clone)]
+pub struct Inventory {
+    stock: Arc<RwLock<Stock>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Inventory {
+            stock: Arc::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl fmt::Debug for Inventory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inventory")
+            .field("stock", &self.stock)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Stock {
+    levels: HashMap<Code, StockLevel>,
+    journal: Vec<StockMovement>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Inventory::default()
+    }
+
+    /// Handle to the same stock, stamping the movements it records with `clock`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use store_terminal::clock::ManualClock;
+    /// use store_terminal::prelude::*;
+    ///
+    /// let clock = Arc::new(ManualClock::new(1_760_000_000));
+    /// let terminal = TerminalBuilder::new().with_clock(clock.clone()).build().unwrap();
+    /// let inventory = terminal.get_db().unwrap().get_inventory();
+    /// inventory.receive("A", 24.0, "DN-1187").unwrap();
+    ///
+    /// let replay = Inventory::new().with_clock(Arc::new(ManualClock::new(1_700_000_000)));
+    /// replay.receive("A", 1.0, "DN-1").unwrap();
+    ///
+    /// assert_eq!(inventory.get_journal()[0].get_timestamp(), 1_760_000_000);
+    /// assert_eq!(replay.get_journal()[0].get_timestamp(), 1_700_000_000);
+    /// ```
+    pub fn with_clock(&self, clock: Arc<dyn Clock>) -> Self {
+        Inventory {
+            stock: Arc::clone(&self.stock),
+            clock,
+        }
+    }
+
+    /// Stock on hand of `code`, or `None` if it is not tracked
+    pub fn get_stock(&self, code: &str) -> Option<f64> {
+        lock::read(&self.stock)
+            .levels
+            .get(code)
+            .map(|level| level.quantity)
+    }
+
+    /// Track `quantity` on hand of `code`, replacing its previous stock
+    pub fn set_stock(&self, code: &str, quantity: f64) -> Result<(), ErrorVariant> {
+        check_quantity(code, quantity)?;
+        lock::write(&self.stock)
+            .levels
+            .entry(Code::from(code))
+            .or_insert_with(|| StockLevel::new(Code::from(code)))
+            .quantity = quantity;
+        Ok(())
+    }
+
+    /// Emit [TerminalEvent::LowStock](crate::prelude::TerminalEvent::LowStock) when a checkout
+    /// takes the stock of the tracked `code` below `threshold`, or stop with `None`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let inventory = terminal.get_db().unwrap().get_inventory();
+    /// inventory.set_stock("B", 3.0).unwrap();
+    /// inventory.set_reorder_threshold("B", Some(2.0)).unwrap();
+    /// assert!(inventory.set_reorder_threshold("A", Some(2.0)).is_err());
+    ///
+    /// let events = Arc::new(Mutex::new(vec![]));
+    /// let log = events.clone();
+    /// terminal.subscribe(move |event| log.lock().unwrap().push(event.clone())).unwrap();
+    ///
+    /// terminal.scan("B").unwrap();
+    /// terminal.checkout().unwrap();
+    /// terminal.scan("B").unwrap();
+    /// terminal.checkout().unwrap();
+    /// terminal.scan("B").unwrap();
+    /// terminal.checkout().unwrap();
+    ///
+    /// let alerts: Vec<_> = events
+    ///     .lock()
+    ///     .unwrap()
+    ///     .iter()
+    ///     .filter(|event| matches!(event, TerminalEvent::LowStock { .. }))
+    ///     .cloned()
+    ///     .collect();
+    /// assert_eq!(
+    ///     alerts,
+    ///     vec![TerminalEvent::LowStock { code: Code::from("B"), stock: 1.0, threshold: 2.0 }]
+    /// );
+    /// assert!(inventory.get_levels()[0].is_low());
+    /// ```
+    pub fn set_reorder_threshold(
+        &self,
+        code: &str,
+        threshold: Option<f64>,
+    ) -> Result<(), ErrorVariant> {
+        if let Some(threshold) = threshold {
+            check_quantity(code, threshold)?;
+        }
+        match lock::write(&self.stock).levels.get_mut(code) {
+            Some(level) => {
+                level.reorder_threshold = threshold;
+                Ok(())
+            }
+            None => Err(not_tracked(code)),
+        }
+    }
+
+    /// Add a delivery of `quantity` of `code` to its stock, tracking it if it was not, and
+    /// record it in the journal under `reference`, such as the number of the delivery note
+    ///
+    /// Returns the stock on hand after the delivery
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::inventory::MovementKind;
+    /// use store_terminal::prelude::*;
+    ///
+    /// let inventory = Inventory::new();
+    /// assert_eq!(inventory.receive("A", 24.0, "DN-1187").unwrap(), 24.0);
+    /// assert_eq!(inventory.adjust("A", -2.0, "broken in the aisle").unwrap(), 22.0);
+    /// assert!(inventory.receive("A", 0.0, "DN-1188").is_err());
+    /// assert_eq!(
+    ///     inventory.adjust("A", -30.0, "stock take").unwrap_err(),
+    ///     ErrorVariant::NotEnoughItems { code: "A".to_string(), requested: 30.0, available: 22.0 }
+    /// );
+    /// assert!(inventory.adjust("B", 1.0, "found in the back").is_err());
+    ///
+    /// let journal = inventory.get_journal();
+    /// assert_eq!(journal.len(), 2);
+    /// assert_eq!(journal[1].get_sequence(), 2);
+    /// assert_eq!(journal[1].get_delta(), -2.0);
+    /// assert_eq!(journal[1].get_quantity(), 22.0);
+    /// assert_eq!(
+    ///     journal[0].get_kind(),
+    ///     &MovementKind::Received { reference: "DN-1187".to_string() }
+    /// );
+    /// ```
+    pub fn receive(&self, code: &str, quantity: f64, reference: &str) -> Result<f64, ErrorVariant> {
+        if !quantity.is_finite() || quantity <= 0.0 {
+            return Err(ErrorVariant::InvalidQuantity {
+                code: code.to_string(),
+                quantity,
+            });
+        }
+        let mut stock = lock::write(&self.stock);
+        let Stock { levels, journal } = &mut *stock;
+        let level = levels
+            .entry(Code::from(code))
+            .or_insert_with(|| StockLevel::new(Code::from(code)));
+        let kind = MovementKind::Received {
+            reference: reference.to_string(),
+        };
+        let timestamp = self.clock.now();
+        Ok(StockMovement::record(
+            journal, level, quantity, kind, timestamp,
+        ))
+    }
+
+    /// Correct the stock of the tracked `code` by `delta`, for shrinkage, breakage or a stock
+    /// take, and record it in the journal with `reason`
+    ///
+    /// The stock can't be taken below zero. Returns the stock on hand after the correction
+    pub fn adjust(&self, code: &str, delta: f64, reason: &str) -> Result<f64, ErrorVariant> {
+        if !delta.is_finite() || delta == 0.0 {
+            return Err(ErrorVariant::InvalidQuantity {
+                code: code.to_string(),
+                quantity: delta,
+            });
+        }
+        let mut stock = lock::write(&self.stock);
+        let Stock { levels, journal } = &mut *stock;
+        let level = levels.get_mut(code).ok_or_else(|| not_tracked(code))?;
+        if level.quantity + delta < 0.0 {
+            return Err(ErrorVariant::NotEnoughItems {
+                code: code.to_string(),
+                requested: -delta,
+                available: level.quantity,
+            });
+        }
+        let kind = MovementKind::Adjusted {
+            reason: reason.to_string(),
+        };
+        let timestamp = self.clock.now();
+        Ok(StockMovement::record(
+            journal, level, delta, kind, timestamp,
+        ))
+    }
+
+    /// Deliveries and corrections of the stock, oldest first
+    pub fn get_journal(&self) -> Vec<StockMovement> {
+        lock::read(&self.stock).journal.clone()
+    }
+
+    /// Stop tracking the stock of `code`, returning its last stock
+    pub fn untrack(&self, code: &str) -> Option<f64> {
+        lock::write(&self.stock)
+            .levels
+            .remove(code)
+            .map(|level| level.quantity)
+    }
+
+    /// Stock of the tracked codes, sorted by code
+    pub fn get_levels(&self) -> Vec<StockLevel> {
+        let mut levels: Vec<StockLevel> =
+            lock::read(&self.stock).levels.values().cloned().collect();
+        levels.sort_by(|a, b| a.code.cmp(&b.code));
+        levels
+    }
+
+    /// Reserve `amount` of the tracked `code` for a cart, unless it exceeds the stock left
+    /// unreserved and `block` refuses it
+    ///
+    /// Returns the stock on hand when the amount exceeds the stock left unreserved
+    pub(crate) fn reserve(
+        &self,
+        code: &str,
+        amount: f64,
+        block: bool,
+    ) -> Result<Option<f64>, ErrorVariant> {
+        let mut stock = lock::write(&self.stock);
+        let level = match stock.levels.get_mut(code) {
+            Some(level) => level,
+            None => return Ok(None),
+        };
+        let available = level.get_available();
+        if amount > available && block {
+            return Err(ErrorVariant::NotEnoughItems {
+                code: code.to_string(),
+                requested: amount,
+                available: available.max(0.0),
+            });
+        }
+        level.reserved += amount;
+        Ok(Some(level.quantity).filter(|_| amount > available))
+    }
+
+    /// Release the stock reserved for `amount` of `code`
+    pub(crate) fn release(&self, code: &str, amount: f64) {
+        if let Some(level) = lock::write(&self.stock).levels.get_mut(code) {
+            level.reserved = (level.reserved - amount).max(0.0);
+        }
+    }
+
+    /// Take the products of a sale out of the stock of the tracked codes, and out of their
+    /// reserved stock if `reserved`, returning the levels it took below their reorder threshold
+    pub(crate) fn sell(&self, products: &[SoldProduct], reserved: bool) -> Vec<StockLevel> {
+        let mut stock = lock::write(&self.stock);
+        let mut dropped: Vec<Code> = vec![];
+        for product in products {
+            if let Some(level) = stock.levels.get_mut(product.get_code()) {
+                let was_low = level.is_low();
+                level.quantity -= product.get_amount();
+                if reserved {
+                    level.reserved = (level.reserved - product.get_amount()).max(0.0);
+                }
+                if level.is_low() && !was_low {
+                    dropped.push(level.code.clone());
+                }
+            }
+        }
+        dropped
+            .iter()
+            .filter_map(|code| stock.levels.get(code).cloned())
+            .collect()
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut stock = lock::write(&self.stock);
+        stock.levels.clear();
+        stock.journal.clear();
+    }
+}
+
+/// Stock on hand of a product, with the stock reserved by carts and the threshold to reorder
+/// it at
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockLevel {
+    code: Code,
+    quantity: f64,
+    reserved: f64,
+    reorder_threshold: Option<f64>,
+}
+
+impl StockLevel {
+    fn new(code: Code) -> Self {
+        StockLevel {
+            code,
+            quantity: 0.0,
+            reserved: 0.0,
+            reorder_threshold: None,
+        }
+    }
+
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    pub fn get_quantity(&self) -> f64 {
+        self.quantity
+    }
+
+    /// Stock held by the carts of terminals reserving it, until they are checked out or reset
+    pub fn get_reserved(&self) -> f64 {
+        self.reserved
+    }
+
+    /// Stock on hand not reserved by any cart
+    pub fn get_available(&self) -> f64 {
+        self.quantity - self.reserved
+    }
+
+    pub fn get_reorder_threshold(&self) -> Option<f64> {
+        self.reorder_threshold
+    }
+
+    /// Check if the stock is below the reorder threshold
+    pub fn is_low(&self) -> bool {
+        matches!(self.reorder_threshold, Some(threshold) if self.quantity < threshold)
+    }
+}
+
+/// Cause of a [StockMovement]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MovementKind {
+    /// Delivery received, under the reference of its delivery note or purchase order
+    Received { reference: String },
+    /// Correction of the stock, with its reason
+    Adjusted { reason: String },
+}
+
+/// Delivery or correction recorded in the journal of the [Inventory]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockMovement {
+    sequence: u64,
+    timestamp: u64,
+    code: Code,
+    delta: f64,
+    quantity: f64,
+    kind: MovementKind,
+}
+
+impl StockMovement {
+    /// Position of the movement in the journal, starting at 1
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    /// Quantity added to the stock, negative for the quantity taken out of it
+    pub fn get_delta(&self) -> f64 {
+        self.delta
+    }
+
+    /// Stock on hand after the movement
+    pub fn get_quantity(&self) -> f64 {
+        self.quantity
+    }
+
+    pub fn get_kind(&self) -> &MovementKind {
+        &self.kind
+    }
+
+    /// Apply `delta` to the stock of `level`, recording the movement made at `timestamp` in
+    /// `journal`
+    fn record(
+        journal: &mut Vec<StockMovement>,
+        level: &mut StockLevel,
+        delta: f64,
+        kind: MovementKind,
+        timestamp: u64,
+    ) -> f64 {
+        level.quantity += delta;
+        journal.push(StockMovement {
+            sequence: journal.len() as u64 + 1,
+            timestamp,
+            code: level.code.clone(),
+            delta,
+            quantity: level.quantity,
+            kind,
+        });
+        level.quantity
+    }
+}
+
+fn not_tracked(code: &str) -> ErrorVariant {
+    ErrorVariant::EntityNotFound {
+        store: "inventory".to_string(),
+        code: code.to_string(),
+    }
+}
+
+fn check_quantity(code: &str, quantity: f64) -> Result<(), ErrorVariant> {
+    if quantity.is_finite() && quantity >= 0.0 {
+        Ok(())
+    } else {
+        Err(ErrorVariant::InvalidQuantity {
+            code: code.to_string(),
+            quantity,
+        })
+    }
+}
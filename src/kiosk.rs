@@ -0,0 +1,58 @@
+//! Self-checkout kiosks, where restricted products wait for the approval of a supervisor
+//!
+//! Products are flagged with a [Restriction] by
+//! [Product::with_restriction](crate::prelude::Product::with_restriction). On terminals
+//! configured with [TerminalConfig::with_kiosk_mode](crate::prelude::TerminalConfig::with_kiosk_mode),
+//! scanning one of them places the cart on [Hold] until an operator allowed to
+//! [Permission::ApproveHold](crate::operator::Permission::ApproveHold) approves it with
+//! [Terminal::approve_holds](crate::prelude::Terminal::approve_holds), and the sale can't be
+//! checked out meanwhile
+
+use crate::prelude::Code;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Reason for a product to require the approval of a supervisor at a kiosk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "json",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Restriction {
+    /// Sold only to customers of age, such as alcohol or tobacco
+    AgeRestricted,
+    /// Expensive product, checked against theft
+    HighValue,
+}
+
+impl fmt::Display for Restriction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Restriction::AgeRestricted => write!(f, "age restricted"),
+            Restriction::HighValue => write!(f, "high value"),
+        }
+    }
+}
+
+/// Restricted product of a cart waiting for approval
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hold {
+    code: Code,
+    restriction: Restriction,
+}
+
+impl Hold {
+    pub(crate) fn new(code: Code, restriction: Restriction) -> Self {
+        Hold { code, restriction }
+    }
+
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    pub fn get_restriction(&self) -> Restriction {
+        self.restriction
+    }
+}
@@ -1,131 +1,299 @@
-use crate::prelude::{Cart, Database, DatabaseAppend, Product, Promotion};
-use std::sync::{Arc, Mutex};
+use crate::code::Code;
+use std::error::Error;
+use std::fmt;
 
+pub mod audit;
+pub mod barcode;
 pub mod cart;
+pub mod clock;
+pub mod code;
+pub mod customer;
 pub mod database;
+mod digest;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "json")]
+pub mod fiscal;
+pub mod gift_card;
+pub mod inventory;
+pub mod kiosk;
+mod lock;
+pub mod loyalty;
+pub mod metrics;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod operator;
+pub mod payment;
+pub mod peripheral;
 pub mod prelude;
 pub mod product;
 pub mod promotion;
+pub mod receipt;
+pub mod sale;
+#[cfg(feature = "scanner")]
+pub mod scanner;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+pub mod table;
+pub mod tax;
+pub mod terminal;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
-#[derive(Debug)]
+pub use crate::payment::Tender;
+pub use crate::terminal::{Terminal, Undone};
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ErrorVariant {
     ArcUnlockError,
-    ProductNotFound,
-    PromotionNotFound,
-    NotEnoughItems,
-    JsonParseError,
-}
-
-pub trait WithNewPricing: Sized {
-    fn with_new_pricing(&self, price: f64) -> Result<Self, ErrorVariant>;
+    ProductNotFound {
+        code: String,
+    },
+    PromotionNotFound {
+        code: String,
+    },
+    ProductAlreadyExists {
+        code: String,
+    },
+    PromotionAlreadyExists {
+        code: String,
+    },
+    SessionAlreadyExists {
+        id: String,
+    },
+    NotEnoughItems {
+        code: String,
+        requested: f64,
+        available: f64,
+    },
+    EntityNotFound {
+        store: String,
+        code: String,
+    },
+    InvalidQuantity {
+        code: String,
+        quantity: f64,
+    },
+    InvalidBarcode {
+        code: String,
+    },
+    InvalidPaymentDetails {
+        field: String,
+        value: String,
+    },
+    InvalidUrl {
+        url: String,
+    },
+    InvalidJournal {
+        sequence: u64,
+    },
+    PeripheralUnavailable {
+        peripheral: String,
+    },
+    InvalidWeight {
+        reading: String,
+    },
+    InvalidCustomerId {
+        input: String,
+    },
+    InsufficientPayment {
+        total: f64,
+        paid: f64,
+    },
+    LoyaltyUnavailable {
+        reason: String,
+    },
+    ShiftUnavailable {
+        reason: String,
+    },
+    InvalidCredentials {
+        operator: String,
+    },
+    PermissionDenied {
+        operation: String,
+        role: String,
+    },
+    /// Restricted products of the cart wait for the approval of a supervisor
+    ApprovalRequired {
+        codes: Vec<String>,
+    },
+    /// `line` and `column` locate the error in the input, both 0 when writing JSON
+    JsonParseError {
+        source: String,
+        entity: &'static str,
+        line: usize,
+        column: usize,
+    },
+    IoError {
+        path: String,
+        source: String,
+    },
 }
 
-pub trait TerminalEntityInterface: Sized {
-    fn get_syntax_example() -> &'static str;
-    fn from_json(json: String) -> Result<Self, ErrorVariant>;
-    fn to_json(&self) -> Result<String, ErrorVariant>;
-}
-
-pub struct Terminal {
-    database: Database,
-    cart: Arc<Mutex<Cart>>,
+impl fmt::Display for ErrorVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorVariant::ArcUnlockError => write!(f, "unable to lock the terminal state"),
+            ErrorVariant::ProductNotFound { code } => write!(f, "product `{}` not found", code),
+            ErrorVariant::PromotionNotFound { code } => {
+                write!(f, "promotion `{}` not found", code)
+            }
+            ErrorVariant::ProductAlreadyExists { code } => {
+                write!(f, "product `{}` already exists", code)
+            }
+            ErrorVariant::PromotionAlreadyExists { code } => {
+                write!(f, "promotion `{}` already exists", code)
+            }
+            ErrorVariant::SessionAlreadyExists { id } => {
+                write!(f, "session `{}` already exists", id)
+            }
+            ErrorVariant::NotEnoughItems {
+                code,
+                requested,
+                available,
+            } => write!(
+                f,
+                "not enough items of `{}`: {} requested, {} available",
+                code, requested, available
+            ),
+            ErrorVariant::EntityNotFound { store, code } => {
+                write!(f, "`{}` not found in store `{}`", code, store)
+            }
+            ErrorVariant::InvalidQuantity { code, quantity } => {
+                write!(f, "invalid quantity {} of `{}`", quantity, code)
+            }
+            ErrorVariant::InvalidBarcode { code } => write!(f, "invalid barcode `{}`", code),
+            ErrorVariant::InvalidPaymentDetails { field, value } => {
+                write!(f, "invalid payment {} `{}`", field, value)
+            }
+            ErrorVariant::InvalidUrl { url } => write!(f, "invalid URL `{}`", url),
+            ErrorVariant::InvalidJournal { sequence } => {
+                write!(f, "fiscal journal altered at record {}", sequence)
+            }
+            ErrorVariant::PeripheralUnavailable { peripheral } => {
+                write!(f, "no {} attached to the terminal", peripheral)
+            }
+            ErrorVariant::InvalidWeight { reading } => {
+                write!(f, "invalid weight reading `{}`", reading)
+            }
+            ErrorVariant::InvalidCustomerId { input } => {
+                write!(f, "invalid customer identification `{}`", input)
+            }
+            ErrorVariant::InsufficientPayment { total, paid } => {
+                write!(
+                    f,
+                    "tenders of {:.2} don't cover the total of {:.2}",
+                    paid, total
+                )
+            }
+            ErrorVariant::LoyaltyUnavailable { reason } => {
+                write!(f, "loyalty points unavailable: {}", reason)
+            }
+            ErrorVariant::ShiftUnavailable { reason } => write!(f, "shift unavailable: {}", reason),
+            ErrorVariant::InvalidCredentials { operator } => {
+                write!(f, "invalid credentials of operator `{}`", operator)
+            }
+            ErrorVariant::PermissionDenied { operation, role } => {
+                write!(f, "{} requires a {} login", operation, role)
+            }
+            ErrorVariant::ApprovalRequired { codes } => {
+                write!(f, "supervisor approval required for {}", codes.join(", "))
+            }
+            ErrorVariant::JsonParseError {
+                source,
+                entity,
+                line: 0,
+                ..
+            } => write!(f, "invalid JSON for {}: {}", entity, source),
+            ErrorVariant::JsonParseError {
+                source,
+                entity,
+                line,
+                column,
+            } => write!(
+                f,
+                "invalid JSON for {} at line {}, column {}: {}",
+                entity, line, column, source
+            ),
+            ErrorVariant::IoError { path, source } => {
+                write!(f, "unable to access `{}`: {}", path, source)
+            }
+        }
+    }
 }
 
-impl Terminal {
-    pub fn new() -> Result<Self, ErrorVariant> {
-        let database = Database::new();
-        let cart = Arc::new(Mutex::new(Cart::new(database.clone())));
+impl Error for ErrorVariant {}
 
-        let terminal = Terminal { cart, database };
-
-        Ok(terminal)
-    }
-
-    /// Scanner interface
+#[cfg(feature = "json")]
+impl ErrorVariant {
+    /// Error of reading or writing `entity` as JSON, located in the input
     ///
     /// # Example
     ///
     /// ```
     /// use store_terminal::prelude::*;
     ///
-    /// let terminal = Terminal::new().unwrap();
-    /// terminal.init().unwrap();
-    ///
-    /// terminal.scan("ABCDABAA".to_string()).unwrap();
-    /// terminal.scan("CCCCCCC".to_string()).unwrap();
-    ///
-    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 39.65);
+    /// let e = Product::from_json(r#"{"code": "A", "price": 2.0, "colour": "red"}"#.to_string());
+    /// match e.unwrap_err() {
+    ///     ErrorVariant::JsonParseError { line, column, source, .. } => {
+    ///         assert_eq!((line, column), (1, 36));
+    ///         assert!(source.starts_with("unknown field `colour`"));
+    ///     }
+    ///     e => panic!("unexpected error {}", e),
+    /// }
     /// ```
-    pub fn scan(&self, codes: String) -> Result<(), ErrorVariant> {
-        let mut codes = codes;
-        while let Some(c) = codes.pop() {
-            print!("Scanning code {}...", c);
-            {
-                self.cart
-                    .lock()
-                    .map_err(|_| ErrorVariant::ArcUnlockError)
-                    .and_then(|mut cart| Ok(cart.push_product(&c.to_string(), 1.0)))??;
-            }
-            println!("product inserted!")
+    pub fn from_json_error(e: serde_json::Error, entity: &'static str) -> Self {
+        let (line, column) = (e.line(), e.column());
+        let source = e.to_string();
+        let location = format!(" at line {} column {}", line, column);
+        let source = match source.strip_suffix(location.as_str()) {
+            Some(message) if line > 0 => message.to_string(),
+            _ => source,
+        };
+        ErrorVariant::JsonParseError {
+            source,
+            entity,
+            line,
+            column,
         }
-        Ok(())
     }
+}
 
-    pub fn init(&self) -> Result<(), ErrorVariant> {
-        self.database.reset()?;
-        {
-            self.cart
-                .lock()
-                .map_err(|_| ErrorVariant::ArcUnlockError)
-                .and_then(|mut cart| Ok(cart.reset()))??;
+/// Parse `json` as `entity`, locating the errors serde raises once the value is read, such as
+/// failed invariants of the top-level value, at its end
+#[cfg(feature = "json")]
+pub(crate) fn from_json_str<T: serde::de::DeserializeOwned>(
+    json: &str,
+    entity: &'static str,
+) -> Result<T, ErrorVariant> {
+    serde_json::from_str(json).map_err(|e| match ErrorVariant::from_json_error(e, entity) {
+        ErrorVariant::JsonParseError {
+            source, line: 0, ..
+        } => {
+            let read = json.trim_end();
+            ErrorVariant::JsonParseError {
+                source,
+                entity,
+                line: read.lines().count(),
+                column: read.lines().last().map_or(0, |l| l.chars().count()),
+            }
         }
+        e => e,
+    })
+}
 
-        self.database.append(Product::new("A".to_string(), 2.0))?;
-        self.database.append(Product::new("B".to_string(), 12.0))?;
-        self.database.append(Product::new("C".to_string(), 1.25))?;
-        self.database.append(Product::new("D".to_string(), 0.15))?;
-
-        let products = vec![self.database.code_to_product_amount("A".to_string(), 4.0)?];
-        self.database
-            .append(Promotion::new("PA".to_string(), products, 7.0)?)?;
-
-        let products = vec![self.database.code_to_product_amount("C".to_string(), 6.0)?];
-        self.database
-            .append(Promotion::new("PC".to_string(), products, 6.0)?)?;
-
-        Ok(())
-    }
-
-    pub fn set_pricing<T: WithNewPricing>(&self, entity: T, price: f64) -> Result<(), ErrorVariant>
-    where
-        Database: DatabaseAppend<T>,
-    {
-        let entity = entity.with_new_pricing(price)?;
-        self.database.append(entity)?;
-        Ok(())
-    }
-
-    pub fn get_cart(&self) -> Result<Cart, ErrorVariant> {
-        let cart = {
-            self.cart
-                .lock()
-                .map_err(|_| ErrorVariant::ArcUnlockError)
-                .and_then(|mut cart| Ok(cart.optimize_promotions()?.clone()))?
-        };
-        Ok(cart)
-    }
-
-    pub fn reset_cart(&self) -> Result<(), ErrorVariant> {
-        {
-            self.cart
-                .lock()
-                .map_err(|_| ErrorVariant::ArcUnlockError)
-                .and_then(|mut c| Ok(c.reset()))??;
-        }
-        Ok(())
-    }
+pub trait WithNewPricing: Sized {
+    fn get_code(&self) -> &Code;
+    fn get_price(&self) -> &f64;
+    fn with_new_pricing(&self, price: f64) -> Result<Self, ErrorVariant>;
+}
 
-    pub fn get_db(&self) -> Result<&Database, ErrorVariant> {
-        Ok(&self.database)
-    }
+pub trait TerminalEntityInterface: Sized {
+    fn get_syntax_example() -> &'static str;
+    fn from_json(json: String) -> Result<Self, ErrorVariant>;
+    fn to_json(&self) -> Result<String, ErrorVariant>;
 }
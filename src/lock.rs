@@ -0,0 +1,151 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{
+    Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError,
+};
+use std::task::{Context, Poll, Waker};
+
+/// Read guard of `lock`, recovering the data left by a thread that panicked while writing it
+pub(crate) fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Write guard of `lock`, recovering the data left by a thread that panicked while writing it
+pub(crate) fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Read guard of `lock` if it is available without waiting, recovering poisoned data
+pub(crate) fn try_read<T>(lock: &RwLock<T>) -> Option<RwLockReadGuard<'_, T>> {
+    match lock.try_read() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Write guard of `lock` if it is available without waiting, recovering poisoned data
+pub(crate) fn try_write<T>(lock: &RwLock<T>) -> Option<RwLockWriteGuard<'_, T>> {
+    match lock.try_write() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Guard of `mutex`, recovering the data left by a thread that panicked while holding it
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Clear the poisoning of `lock`, returning whether it was poisoned
+pub(crate) fn clear_poison<T>(lock: &RwLock<T>) -> bool {
+    let poisoned = lock.is_poisoned();
+    if poisoned {
+        lock.clear_poison();
+    }
+    poisoned
+}
+
+/// [RwLock] waking the tasks waiting for it whenever one of its guards is dropped, so futures
+/// wait for it without spinning
+pub(crate) struct WakingRwLock<T> {
+    lock: RwLock<T>,
+    waiters: Mutex<Vec<Waker>>,
+}
+
+/// Guard of a [WakingRwLock], waking its waiting tasks once released
+pub(crate) struct WakingGuard<'a, G> {
+    guard: Option<G>,
+    waiters: &'a Mutex<Vec<Waker>>,
+}
+
+pub(crate) type WakingReadGuard<'a, T> = WakingGuard<'a, RwLockReadGuard<'a, T>>;
+pub(crate) type WakingWriteGuard<'a, T> = WakingGuard<'a, RwLockWriteGuard<'a, T>>;
+
+impl<T> WakingRwLock<T> {
+    pub(crate) fn new(value: T) -> Self {
+        WakingRwLock {
+            lock: RwLock::new(value),
+            waiters: Mutex::new(vec![]),
+        }
+    }
+
+    /// See [read]
+    pub(crate) fn read(&self) -> WakingReadGuard<'_, T> {
+        self.guard(read(&self.lock))
+    }
+
+    /// See [write]
+    pub(crate) fn write(&self) -> WakingWriteGuard<'_, T> {
+        self.guard(write(&self.lock))
+    }
+
+    /// Read guard if it is available, otherwise the task of `cx` is woken once a guard is
+    /// released
+    pub(crate) fn poll_read(&self, cx: &mut Context) -> Poll<WakingReadGuard<'_, T>> {
+        self.poll_guard(cx, try_read)
+    }
+
+    /// Write guard if it is available, otherwise the task of `cx` is woken once a guard is
+    /// released
+    pub(crate) fn poll_write(&self, cx: &mut Context) -> Poll<WakingWriteGuard<'_, T>> {
+        self.poll_guard(cx, try_write)
+    }
+
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.lock.is_poisoned()
+    }
+
+    /// See [clear_poison]
+    pub(crate) fn clear_poison(&self) -> bool {
+        clear_poison(&self.lock)
+    }
+
+    fn guard<G>(&self, guard: G) -> WakingGuard<'_, G> {
+        WakingGuard {
+            guard: Some(guard),
+            waiters: &self.waiters,
+        }
+    }
+
+    fn poll_guard<'a, G>(
+        &'a self,
+        cx: &mut Context,
+        try_lock: fn(&'a RwLock<T>) -> Option<G>,
+    ) -> Poll<WakingGuard<'a, G>> {
+        // The waiters are locked before trying, so a guard released in between wakes the task
+        let mut waiters = lock(&self.waiters);
+        match try_lock(&self.lock) {
+            Some(guard) => Poll::Ready(self.guard(guard)),
+            None => {
+                if !waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                    waiters.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<G: Deref> Deref for WakingGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard.as_deref().expect("guard released on drop")
+    }
+}
+
+impl<G: DerefMut> DerefMut for WakingGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard.as_deref_mut().expect("guard released on drop")
+    }
+}
+
+impl<G> Drop for WakingGuard<'_, G> {
+    fn drop(&mut self) {
+        drop(self.guard.take());
+        for waker in lock(self.waiters).drain(..) {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,315 @@
+//! Loyalty points earned by identified customers on checkout, and redeemed as a discount of
+//! the cart
+//!
+//! The [LoyaltyPolicy] given to
+//! [TerminalConfig::with_loyalty_policy](crate::prelude::TerminalConfig::with_loyalty_policy)
+//! sets the points earned per unit of net total and the value of a redeemed point. Balances
+//! are [LoyaltyAccount] entities kept in the [DatabaseStore] named [LOYALTY_STORE], keyed by
+//! customer identifier, so points earned on a lane are redeemed on any other
+
+use crate::prelude::{Customer, ErrorVariant};
+#[cfg(feature = "json")]
+use crate::prelude::{DatabaseStore, TerminalEntityInterface};
+use crate::receipt::round_cents;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+/// Name of the store of the loyalty accounts in the [Database](crate::prelude::Database)
+pub const LOYALTY_STORE: &str = "loyalty";
+
+/// Code of the receipt line of the points redeemed by a sale
+pub const LOYALTY_CODE: &str = "LOYALTY";
+
+/// Earn and burn rates of the loyalty program
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::customer::{Customer, Identification};
+/// use store_terminal::loyalty::LoyaltyPolicy;
+///
+/// let policy = LoyaltyPolicy::new(2.0, 0.05)
+///     .with_tier_earn_rate("gold", 3.0)
+///     .with_minimum_redemption(100);
+/// assert_eq!(policy.get_points_earned(19.99), 39);
+/// assert_eq!(policy.get_points_earned(-19.0), 0);
+/// assert_eq!(policy.get_points_value(150), 7.5);
+/// assert_eq!(policy.get_minimum_redemption(), 100);
+///
+/// let customer = Customer::new("M-1042", Identification::Phone);
+/// assert_eq!(policy.get_customer_points_earned(&customer, 10.0), 20);
+/// let customer = customer.with_tier("gold");
+/// assert_eq!(policy.get_customer_points_earned(&customer, 10.0), 30);
+///
+/// assert_eq!(LoyaltyPolicy::default().get_points_value(100), 1.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoyaltyPolicy {
+    earn_rate: f64,
+    tier_earn_rates: Vec<(String, f64)>,
+    point_value: f64,
+    minimum_redemption: u64,
+}
+
+impl LoyaltyPolicy {
+    /// Program earning `earn_rate` points per unit of net total, rounded down, and redeeming
+    /// points for `point_value` each
+    pub fn new(earn_rate: f64, point_value: f64) -> Self {
+        LoyaltyPolicy {
+            earn_rate,
+            tier_earn_rates: vec![],
+            point_value,
+            minimum_redemption: 1,
+        }
+    }
+
+    /// Points earned per unit of net total by the customers of `tier`, replacing the earn rate
+    pub fn with_tier_earn_rate(mut self, tier: &str, earn_rate: f64) -> Self {
+        self.tier_earn_rates.retain(|(t, _)| t != tier);
+        self.tier_earn_rates.push((tier.to_string(), earn_rate));
+        self
+    }
+
+    /// Fewest points redeemed at once
+    pub fn with_minimum_redemption(mut self, points: u64) -> Self {
+        self.minimum_redemption = points.max(1);
+        self
+    }
+
+    pub fn get_earn_rate(&self) -> f64 {
+        self.earn_rate
+    }
+
+    pub fn get_point_value(&self) -> f64 {
+        self.point_value
+    }
+
+    pub fn get_minimum_redemption(&self) -> u64 {
+        self.minimum_redemption
+    }
+
+    /// Earn rate of the customers of `tier`
+    pub fn get_tier_earn_rate(&self, tier: &str) -> f64 {
+        self.tier_earn_rates
+            .iter()
+            .find(|(t, _)| t == tier)
+            .map_or(self.earn_rate, |(_, rate)| *rate)
+    }
+
+    /// Points earned by a sale of net total `net`
+    pub fn get_points_earned(&self, net: f64) -> u64 {
+        points_earned(net, self.earn_rate)
+    }
+
+    /// Points earned by `customer` on a sale of net total `net`, at the earn rate of its tier
+    pub fn get_customer_points_earned(&self, customer: &Customer, net: f64) -> u64 {
+        let rate = match customer.get_tier() {
+            Some(tier) => self.get_tier_earn_rate(tier),
+            None => self.earn_rate,
+        };
+        points_earned(net, rate)
+    }
+
+    /// Discount given for `points`
+    pub fn get_points_value(&self, points: u64) -> f64 {
+        round_cents(points as f64 * self.point_value)
+    }
+}
+
+/// One point per unit spent, 100 points redeemed for 1.0
+impl Default for LoyaltyPolicy {
+    fn default() -> Self {
+        LoyaltyPolicy::new(1.0, 0.01)
+    }
+}
+
+/// Points earned at `earn_rate` per unit of `net`, rounded down
+fn points_earned(net: f64, earn_rate: f64) -> u64 {
+    let points = (round_cents(net) * earn_rate).floor();
+    if points.is_finite() && points > 0.0 {
+        points as u64
+    } else {
+        0
+    }
+}
+
+/// Points balance of a customer, identified by [Customer::get_id](crate::prelude::Customer::get_id)
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::loyalty::LoyaltyAccount;
+/// use store_terminal::prelude::*;
+///
+/// let mut account = LoyaltyAccount::new("M-1042");
+/// account.earn(120);
+/// account.redeem(100).unwrap();
+/// assert_eq!(account.get_points(), 20);
+///
+/// assert_eq!(
+///     account.redeem(50).unwrap_err(),
+///     ErrorVariant::NotEnoughItems {
+///         code: "M-1042".to_string(),
+///         requested: 50.0,
+///         available: 20.0,
+///     }
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct LoyaltyAccount {
+    customer: String,
+    points: u64,
+}
+
+impl LoyaltyAccount {
+    /// Account opened without points
+    pub fn new(customer: &str) -> Self {
+        LoyaltyAccount {
+            customer: customer.to_string(),
+            points: 0,
+        }
+    }
+
+    pub fn get_customer(&self) -> &str {
+        &self.customer
+    }
+
+    pub fn get_points(&self) -> u64 {
+        self.points
+    }
+
+    pub fn earn(&mut self, points: u64) {
+        self.points = self.points.saturating_add(points);
+    }
+
+    /// Take `points` out of the balance, which can't go below zero
+    pub fn redeem(&mut self, points: u64) -> Result<(), ErrorVariant> {
+        if points > self.points {
+            return Err(ErrorVariant::NotEnoughItems {
+                code: self.customer.clone(),
+                requested: points as f64,
+                available: self.points as f64,
+            });
+        }
+        self.points -= points;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "json")]
+impl TerminalEntityInterface for LoyaltyAccount {
+    fn get_syntax_example() -> &'static str {
+        r#"{"customer": "M-1042", "points": 250}"#
+    }
+
+    fn from_json(json: String) -> Result<Self, ErrorVariant> {
+        serde_json::from_str(&json).map_err(|e| ErrorVariant::from_json_error(e, "LoyaltyAccount"))
+    }
+
+    fn to_json(&self) -> Result<String, ErrorVariant> {
+        serde_json::to_string(self).map_err(|e| ErrorVariant::from_json_error(e, "LoyaltyAccount"))
+    }
+}
+
+/// Points redeemed against a cart, and the discount they give
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct LoyaltyRedemption {
+    points: u64,
+    value: f64,
+}
+
+impl LoyaltyRedemption {
+    pub fn new(points: u64, value: f64) -> Self {
+        LoyaltyRedemption { points, value }
+    }
+
+    pub fn get_points(&self) -> u64 {
+        self.points
+    }
+
+    pub fn get_value(&self) -> f64 {
+        self.value
+    }
+}
+
+/// Points earned and redeemed by the customer of a sale
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct LoyaltyActivity {
+    customer: String,
+    earned: u64,
+    redeemed: u64,
+}
+
+impl LoyaltyActivity {
+    pub fn new(customer: &str, earned: u64, redeemed: u64) -> Self {
+        LoyaltyActivity {
+            customer: customer.to_string(),
+            earned,
+            redeemed,
+        }
+    }
+
+    pub fn get_customer(&self) -> &str {
+        &self.customer
+    }
+
+    pub fn get_earned(&self) -> u64 {
+        self.earned
+    }
+
+    pub fn get_redeemed(&self) -> u64 {
+        self.redeemed
+    }
+}
+
+/// Take `points` out of the stored account of `customer`
+#[cfg(feature = "json")]
+pub(crate) fn debit(
+    accounts: &DatabaseStore<LoyaltyAccount>,
+    customer: &str,
+    points: u64,
+) -> Result<(), ErrorVariant> {
+    accounts
+        .modify(customer, |account| {
+            let mut account = account.unwrap_or_else(|| LoyaltyAccount::new(customer));
+            account.redeem(points)?;
+            Ok(account)
+        })
+        .map(|_| ())
+}
+
+/// Add `points` to the stored account of `customer`, opening it if it is not stored
+#[cfg(feature = "json")]
+pub(crate) fn credit(
+    accounts: &DatabaseStore<LoyaltyAccount>,
+    customer: &str,
+    points: u64,
+) -> Result<(), ErrorVariant> {
+    accounts
+        .modify(customer, |account| {
+            let mut account = account.unwrap_or_else(|| LoyaltyAccount::new(customer));
+            account.earn(points);
+            Ok(account)
+        })
+        .map(|_| ())
+}
+
+/// Undo the part of a sale reversed by a refund: give back the points it redeemed and take
+/// back the points it earned, down to an empty balance when they were spent already
+#[cfg(feature = "json")]
+pub(crate) fn reverse(
+    accounts: &DatabaseStore<LoyaltyAccount>,
+    refund: &LoyaltyActivity,
+) -> Result<(), ErrorVariant> {
+    accounts
+        .modify(&refund.customer, |account| {
+            let mut account = account.unwrap_or_else(|| LoyaltyAccount::new(&refund.customer));
+            account.earn(refund.redeemed);
+            account.redeem(refund.earned.min(account.points))?;
+            Ok(account)
+        })
+        .map(|_| ())
+}
@@ -1,133 +1,90 @@
-use std::io::{self, stdout, BufRead, Error, ErrorKind, Lines, StdinLock, Write};
-use std::str::SplitWhitespace;
+use clap::Parser;
+use std::path::{Path, PathBuf};
+use std::process;
 use store_terminal::prelude::*;
 
-const NAME: Option<&'static str> = option_env!("CARGO_PKG_NAME");
-const VERSION_MAJOR: Option<&'static str> = option_env!("CARGO_PKG_VERSION_MAJOR");
-const VERSION_MINOR: Option<&'static str> = option_env!("CARGO_PKG_VERSION_MINOR");
-const AUTHORS: Option<&'static str> = option_env!("CARGO_PKG_AUTHORS");
+mod cli;
 
-macro_rules! fetch_text {
-    ($x:expr) => {
-        $x.unwrap_or("(undefined)")
-    };
-}
-
-enum State {
-    Executing,
-    ShouldFinish,
-}
+use cli::args::{Args, Command};
+use cli::config::Config;
+use cli::context::Context;
+use cli::messages::Messages;
+use cli::print::{print_cart, print_error};
+use cli::style::Style;
 
 fn main() {
-    println!(
-        "{} v{}.{} by [{}]",
-        fetch_text!(NAME),
-        fetch_text!(VERSION_MAJOR),
-        fetch_text!(VERSION_MINOR),
-        fetch_text!(AUTHORS),
-    );
-
-    print!("Initializing...");
-    let terminal = Terminal::new().unwrap();
-    terminal.init().unwrap();
-    println!("terminal initialized!");
-
-    print_help();
-
-    let stdin = io::stdin();
-
-    let mut iterator = stdin.lock().lines();
-    let mut state = State::Executing;
-    while let State::Executing = state {
-        state = if let Some(line) = get_line(&mut iterator) {
-            proc_command(line, &terminal).unwrap_or_else(|e| {
-                println!("Error: {:?}", e);
-                state
-            })
-        } else {
-            State::ShouldFinish
-        };
-    }
-
-    println!("Bye!");
-}
+    let args = Args::parse();
+    let style = Style::from_env(args.no_color);
+
+    let config = Config::load(args.config.as_deref()).unwrap_or_else(|e| {
+        let message = Messages::default().format("config-error", &[("error", &e)]);
+        eprintln!("{}", style.red(message));
+        process::exit(cli::exit::PARSE_ERROR);
+    });
+
+    let ctx = Context::new(config, style, args.get_verbosity());
+    let db = args.db;
+
+    let result = match args.command.unwrap_or(Command::Repl) {
+        Command::Repl => cli::repl::repl(|| init_terminal(db.as_deref()), &ctx),
+        Command::Scan {
+            codes,
+            file,
+            print_total,
+        } => scan(db, codes, file, print_total, &ctx),
+    };
 
-fn get_line(iterator: &mut Lines<StdinLock>) -> Option<String> {
-    print!("> ");
-    if stdout().flush().is_err() {
-        return None;
+    if let Err(e) = result {
+        print_error(ctx.format("error", &[("error", &e)]), &ctx);
+        process::exit(cli::exit::code(&e));
     }
-    iterator
-        .next()
-        .unwrap_or(Err(Error::new(ErrorKind::Other, "No input provided")))
-        .map(|l| Some(l.trim().to_owned()))
-        .unwrap_or(None)
 }
 
-fn proc_command(line: String, terminal: &Terminal) -> Result<State, ErrorVariant> {
-    let mut iter = line.split_whitespace();
+fn init_terminal(db: Option<&Path>) -> Result<Terminal, ErrorVariant> {
+    let terminal = Terminal::new()?;
 
-    match iter.next() {
-        Some(c) if c.to_lowercase() == "q" => return Ok(State::ShouldFinish),
-        Some(c) if c.to_lowercase() == "h" => print_help(),
-        Some(c) if c.to_lowercase() == "cart" => return proc_command_cart(iter, terminal),
-        Some(c) if c.to_lowercase() == "c" => return proc_command_cart(iter, terminal),
-        Some(c) if c.to_lowercase() == "db" => println!("{}", terminal.get_db()?),
-        None => (),
-        _ => {
-            println!("Command `{}` not recognized!", line);
-            print_help();
-        }
+    match db {
+        Some(path) => terminal.change_catalog(|database| database.import(path))?,
+        None => terminal.load_demo_catalog()?,
     }
 
-    Ok(State::Executing)
+    Ok(terminal)
 }
 
-fn proc_command_cart(
-    mut iter: SplitWhitespace,
-    terminal: &Terminal,
-) -> Result<State, ErrorVariant> {
-    match iter.next() {
-        Some(c) if c.to_lowercase() == "print" => println!("{}", terminal.get_cart()?),
-        Some(c) if c.to_lowercase() == "p" => println!("{}", terminal.get_cart()?),
-        Some(c) if c.to_lowercase() == "reset" => println!("{:?}", terminal.reset_cart()?),
-        Some(c) if c.to_lowercase() == "r" => println!("{:?}", terminal.reset_cart()?),
-        Some(c) if c.to_lowercase() == "scan" => return proc_command_cart_scan(iter, terminal),
-        Some(c) if c.to_lowercase() == "s" => return proc_command_cart_scan(iter, terminal),
-        Some(c) => {
-            println!("Cart command `{}` not recognized!", c);
-            print_help();
-        }
-        None => {
-            println!("Cart command not provided!");
-            print_help();
-        }
+fn scan(
+    db: Option<PathBuf>,
+    codes: Vec<String>,
+    file: Option<PathBuf>,
+    print_total: bool,
+    ctx: &Context,
+) -> Result<(), ErrorVariant> {
+    let terminal = init_terminal(db.as_deref())?;
+
+    let mut entries = vec![];
+    if let Some(path) = file {
+        entries.push(cli::scan::parse_file(&path, ctx));
     }
-
-    Ok(State::Executing)
-}
-
-fn proc_command_cart_scan(
-    mut iter: SplitWhitespace,
-    terminal: &Terminal,
-) -> Result<State, ErrorVariant> {
-    match iter.next() {
-        Some(c) => terminal.scan(c.to_string())?,
-        None => {
-            println!("Code not provided!");
-            print_help();
-        }
+    let db = terminal.get_db()?;
+    entries.push(cli::scan::parse_entries(
+        codes.iter().map(|c| c.as_str()),
+        |c| db.contains_product(c).unwrap_or(false),
+        ctx,
+    ));
+
+    for entries in entries {
+        let entries = entries.unwrap_or_else(|e| {
+            print_error(e, ctx);
+            process::exit(cli::exit::PARSE_ERROR);
+        });
+        cli::scan::scan_entries(&terminal, entries, ctx)?;
     }
 
-    Ok(State::Executing)
-}
+    let cart = terminal.get_cart()?;
+    if print_total {
+        println!("{:.2}", cart.get_total_price());
+    } else {
+        print_cart(&cart, ctx);
+    }
 
-fn print_help() {
-    println!("Available commands:");
-    println!("&cart &print\t\tPrint the current contents");
-    println!("&cart &reset\t\tReset the contents");
-    println!("&cart &scan [code]\tScan the given set of codes");
-    println!("db\t\t\tPrint the database contents");
-    println!("h\t\t\tShow this menu");
-    println!("q\t\t\tQuit");
+    Ok(())
 }
@@ -0,0 +1,329 @@
+//! Counters and histograms of terminals, in the Prometheus text exposition format
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `store_terminal_scans_total` | counter | `terminal` |
+//! | `store_terminal_scans_undone_total` | counter | `terminal` |
+//! | `store_terminal_checkouts_total` | counter | `terminal` |
+//! | `store_terminal_errors_total` | counter | `terminal`, `error` |
+//! | `store_terminal_cart_total` | histogram | `terminal` |
+//! | `store_terminal_optimizer_duration_seconds` | histogram | |
+//! | `store_terminal_optimizer_step_duration_seconds` | histogram | `step` |
+//!
+//! Rates, such as scans per second, are computed by the monitoring system, for instance with
+//! `rate(store_terminal_scans_total[1m])`
+
+use crate::lock;
+use crate::prelude::{
+    Customer, DatabaseReader, ErrorVariant, OptimizerSpan, OptimizerStrategy, OptimizerTracer,
+    ProductAmount, Promotion, SubscriptionId, Terminal, TerminalEvent,
+};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+const SCANS: (&str, &str) = (
+    "store_terminal_scans_total",
+    "Products scanned into the cart",
+);
+const UNDONE: (&str, &str) = (
+    "store_terminal_scans_undone_total",
+    "Scans removed from the cart",
+);
+const CHECKOUTS: (&str, &str) = ("store_terminal_checkouts_total", "Sales closed");
+const ERRORS: (&str, &str) = (
+    "store_terminal_errors_total",
+    "Failed operations, by error variant",
+);
+const CART_TOTAL: (&str, &str) = ("store_terminal_cart_total", "Total of the closed sales");
+const OPTIMIZER: (&str, &str) = (
+    "store_terminal_optimizer_duration_seconds",
+    "Time taken to choose the promotions of a cart",
+);
+const OPTIMIZER_STEP: (&str, &str) = (
+    "store_terminal_optimizer_step_duration_seconds",
+    "Time taken by a step of the optimizer, by step",
+);
+
+const CART_TOTAL_BUCKETS: [f64; 9] = [1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+const OPTIMIZER_BUCKETS: [f64; 10] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Distribution of observed values over cumulative buckets
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Histogram {
+            bounds,
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(self.counts.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Series of the registry, by metric name and rendered labels
+#[derive(Debug, Default)]
+struct Series {
+    counters: BTreeMap<(&'static str, String), f64>,
+    histograms: BTreeMap<(&'static str, String), Histogram>,
+}
+
+/// Registry of the metrics of one or more terminals
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use store_terminal::metrics::Metrics;
+/// use store_terminal::prelude::*;
+///
+/// let metrics = Arc::new(Metrics::new());
+/// let terminal = TerminalBuilder::new()
+///     .with_optimizer_strategy(Metrics::timed(&metrics, DefaultOptimizerStrategy))
+///     .build()
+///     .unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// Metrics::attach(&metrics, &terminal).unwrap();
+///
+/// terminal.scan("AB X").unwrap();
+/// terminal.checkout().unwrap();
+///
+/// let text = metrics.render();
+/// assert!(text.contains("# TYPE store_terminal_scans_total counter\n"));
+/// assert!(text.contains("store_terminal_scans_total{terminal=\"terminal-1\"} 2\n"));
+/// assert!(text.contains(
+///     "store_terminal_errors_total{terminal=\"terminal-1\",error=\"ProductNotFound\"} 1\n"
+/// ));
+/// assert!(text.contains("store_terminal_cart_total_bucket{terminal=\"terminal-1\",le=\"25\"} 1\n"));
+/// assert!(text.contains("store_terminal_cart_total_sum{terminal=\"terminal-1\"} 14\n"));
+/// assert!(text.contains("store_terminal_optimizer_duration_seconds_count 1\n"));
+/// ```
+#[derive(Debug, Default)]
+pub struct Metrics {
+    series: Mutex<Series>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    /// Record the events of the terminal, until the returned subscription is removed
+    pub fn attach(
+        metrics: &Arc<Metrics>,
+        terminal: &Terminal,
+    ) -> Result<SubscriptionId, ErrorVariant> {
+        let metrics = Arc::clone(metrics);
+        let terminal_id = terminal.get_config().get_terminal_id().clone();
+        let labels = format!("terminal=\"{}\"", escape(&terminal_id));
+        terminal.subscribe(move |event| match event {
+            TerminalEvent::ScanAccepted { .. } => metrics.increment(SCANS.0, &labels),
+            TerminalEvent::ScanUndone { .. } => metrics.increment(UNDONE.0, &labels),
+            TerminalEvent::ScanRejected { error, .. } => metrics.record_error(&terminal_id, error),
+            TerminalEvent::CheckedOut { total, .. } => {
+                metrics.increment(CHECKOUTS.0, &labels);
+                metrics.observe(CART_TOTAL.0, &labels, &CART_TOTAL_BUCKETS, *total);
+            }
+            _ => (),
+        })
+    }
+
+    /// Wrap an optimizer strategy, recording the duration of every optimization
+    pub fn timed<S: OptimizerStrategy>(metrics: &Arc<Metrics>, strategy: S) -> TimedStrategy<S> {
+        TimedStrategy {
+            strategy,
+            metrics: Arc::clone(metrics),
+        }
+    }
+
+    /// Count a failure of an operation of the terminal `terminal_id`
+    pub fn record_error(&self, terminal_id: &str, error: &ErrorVariant) {
+        let labels = format!(
+            "terminal=\"{}\",error=\"{}\"",
+            escape(terminal_id),
+            variant_name(error)
+        );
+        self.increment(ERRORS.0, &labels);
+    }
+
+    /// Every series, in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let series = lock::lock(&self.series);
+        let mut text = String::new();
+
+        for (name, help) in &[SCANS, UNDONE, CHECKOUTS, ERRORS] {
+            let _ = writeln!(text, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+            for ((_, labels), value) in series.counters.iter().filter(|((n, _), _)| n == name) {
+                let _ = writeln!(text, "{}{} {}", name, braces(labels), value);
+            }
+        }
+
+        for (name, help) in &[CART_TOTAL, OPTIMIZER, OPTIMIZER_STEP] {
+            let _ = writeln!(text, "# HELP {} {}\n# TYPE {} histogram", name, help, name);
+            for ((_, labels), histogram) in series.histograms.iter().filter(|((n, _), _)| n == name)
+            {
+                let bounds = histogram
+                    .bounds
+                    .iter()
+                    .map(|bound| bound.to_string())
+                    .chain(std::iter::once("+Inf".to_string()));
+                let counts = histogram
+                    .counts
+                    .iter()
+                    .chain(std::iter::once(&histogram.count));
+                for (bound, count) in bounds.zip(counts) {
+                    let le = format!("le=\"{}\"", bound);
+                    let bucket_labels = if labels.is_empty() {
+                        le
+                    } else {
+                        format!("{},{}", labels, le)
+                    };
+                    let _ = writeln!(text, "{}_bucket{{{}}} {}", name, bucket_labels, count);
+                }
+                let _ = writeln!(text, "{}_sum{} {}", name, braces(labels), histogram.sum);
+                let _ = writeln!(text, "{}_count{} {}", name, braces(labels), histogram.count);
+            }
+        }
+
+        text
+    }
+
+    fn increment(&self, name: &'static str, labels: &str) {
+        *lock::lock(&self.series)
+            .counters
+            .entry((name, labels.to_string()))
+            .or_insert(0.0) += 1.0;
+    }
+
+    fn observe(&self, name: &'static str, labels: &str, bounds: &'static [f64], value: f64) {
+        lock::lock(&self.series)
+            .histograms
+            .entry((name, labels.to_string()))
+            .or_insert_with(|| Histogram::new(bounds))
+            .observe(value);
+    }
+}
+
+/// Durations of the fetches and simulations of the optimizer, given to a
+/// [TracedOptimizerStrategy](crate::prelude::TracedOptimizerStrategy)
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use store_terminal::metrics::Metrics;
+/// use store_terminal::prelude::*;
+///
+/// let metrics = Arc::new(Metrics::new());
+/// let strategy = TracedOptimizerStrategy::new(metrics.clone());
+/// let terminal = TerminalBuilder::new()
+///     .with_optimizer_strategy(Metrics::timed(&metrics, strategy))
+///     .build()
+///     .unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AAAAB").unwrap();
+/// terminal.checkout().unwrap();
+///
+/// let text = metrics.render();
+/// assert!(text.contains(
+///     "store_terminal_optimizer_step_duration_seconds_count{step=\"fetch_possible_promotions\"} 2\n"
+/// ));
+/// assert!(text.contains(
+///     "store_terminal_optimizer_step_duration_seconds_count{step=\"simulate_promotion\"} 1\n"
+/// ));
+/// ```
+impl OptimizerTracer for Metrics {
+    fn record(&self, span: &OptimizerSpan) {
+        let (step, elapsed) = match span {
+            OptimizerSpan::FetchPossiblePromotions { elapsed, .. } => {
+                ("fetch_possible_promotions", elapsed)
+            }
+            OptimizerSpan::SimulatePromotion { elapsed, .. } => ("simulate_promotion", elapsed),
+            // Recorded by the timed strategy
+            OptimizerSpan::OptimizePromotions { .. } => return,
+        };
+        self.observe(
+            OPTIMIZER_STEP.0,
+            &format!("step=\"{}\"", step),
+            &OPTIMIZER_BUCKETS,
+            elapsed.as_secs_f64(),
+        );
+    }
+}
+
+/// [OptimizerStrategy] recording its durations, built by [Metrics::timed]
+pub struct TimedStrategy<S> {
+    strategy: S,
+    metrics: Arc<Metrics>,
+}
+
+impl<S: OptimizerStrategy> OptimizerStrategy for TimedStrategy<S> {
+    fn get_optimal_products_promotions(
+        &self,
+        products: Vec<ProductAmount>,
+        database: &DatabaseReader,
+    ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+        self.get_optimal_customer_promotions(products, database, None)
+    }
+
+    fn get_optimal_customer_promotions(
+        &self,
+        products: Vec<ProductAmount>,
+        database: &DatabaseReader,
+        customer: Option<&Customer>,
+    ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+        let start = Instant::now();
+        let optimal = self
+            .strategy
+            .get_optimal_customer_promotions(products, database, customer);
+        self.metrics.observe(
+            OPTIMIZER.0,
+            "",
+            &OPTIMIZER_BUCKETS,
+            start.elapsed().as_secs_f64(),
+        );
+        optimal
+    }
+}
+
+/// Name of the variant of an error, such as `ProductNotFound`
+fn variant_name(error: &ErrorVariant) -> String {
+    format!("{:?}", error)
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+fn braces(labels: &str) -> String {
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels)
+    }
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
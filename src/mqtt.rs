@@ -0,0 +1,411 @@
+//! Publication of the events of a terminal to an MQTT broker, for lane telemetry
+//!
+//! Events are published with QoS 0 under `<prefix>/<terminal id>/<event>`, as JSON documents:
+//!
+//! | Event | Topic suffix | Payload |
+//! |-------|--------------|---------|
+//! | [TerminalEvent::ScanAccepted] | `scan/accepted` | `{"code", "amount"}` |
+//! | [TerminalEvent::OutOfStock] | `stock/out` | `{"code", "stock"}` |
+//! | [TerminalEvent::ScanRejected] | `scan/rejected` | `{"code", "error"}` |
+//! | [TerminalEvent::ScanUndone] | `scan/undone` | `{"code", "amount"}` |
+//! | [TerminalEvent::PromotionApplied] | `promotion/applied` | `{"code", "amount"}` |
+//! | [TerminalEvent::PriceChanged] | `price/changed` | `{"code", "previous", "price"}` |
+//! | [TerminalEvent::CatalogChanged] | `catalog/changed` | `{"code", "previous", "price"}`, `null` when inserted or removed |
+//! | [TerminalEvent::CustomerIdentified] | `customer/identified` | `{"id"}` |
+//! | [TerminalEvent::CartReset] | `cart/reset` | `{}` |
+//! | [TerminalEvent::CartRecovered] | `cart/recovered` | `{}` |
+//! | [TerminalEvent::CheckedOut] | `checkout` | `{"sale", "total"}` |
+//! | [TerminalEvent::LowStock] | `stock/low` | `{"code", "stock", "threshold"}` |
+//! | [TerminalEvent::SaleRefunded] | `sale/refunded` | `{"sale", "total"}` |
+//! | [TerminalEvent::DrawerOpened] | `drawer/opened` | `{"reason"}`, `null` on checkouts |
+//! | [TerminalEvent::ApprovalRequired] | `approval/required` | `{"code", "restriction"}` |
+//! | [TerminalEvent::HoldsApproved] | `approval/granted` | `{"approver", "codes"}` |
+//! | [TerminalEvent::AcceptFailed] | `server/accept_failed` | `{"error"}` |
+
+use crate::prelude::{ErrorVariant, SubscriptionId, Terminal, TerminalEvent};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Topic prefix used unless [MqttPublisher::with_topic_prefix] is called
+pub const DEFAULT_TOPIC_PREFIX: &str = "store-terminal";
+
+/// Timeout of the connection to the broker and of every write to it
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most messages waiting to be published, beyond which new ones are dropped
+const QUEUE_LEN: usize = 1024;
+
+/// Wait before the first attempt to reconnect, doubled up to [MAX_BACKOFF]
+const BACKOFF: Duration = Duration::from_millis(100);
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// MQTT 3.1.1 client publishing the events of terminals
+///
+/// Messages are queued and published by a background thread, so listeners of a terminal never
+/// wait for the broker. The connection has no keep alive, so the broker never drops it for
+/// inactivity; a lost connection is opened again by the background thread, waiting longer
+/// between every failed attempt, and messages that fail to be written are dropped, as QoS 0
+/// allows
+///
+/// # Example
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use std::net::TcpListener;
+/// use std::sync::Arc;
+/// use std::thread;
+/// use store_terminal::mqtt::MqttPublisher;
+/// use store_terminal::prelude::*;
+///
+/// // Broker accepting a connection and returning the first message published
+/// let broker = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let addr = broker.local_addr().unwrap().to_string();
+/// let published = thread::spawn(move || {
+///     let (mut client, _) = broker.accept().unwrap();
+///     let mut packet = |client: &mut std::net::TcpStream| {
+///         let mut header = [0; 2];
+///         client.read_exact(&mut header).unwrap();
+///         let mut body = vec![0; usize::from(header[1])];
+///         client.read_exact(&mut body).unwrap();
+///         body
+///     };
+///     packet(&mut client);
+///     client.write_all(&[0x20, 2, 0, 0]).unwrap();
+///
+///     let publish = packet(&mut client);
+///     let topic_len = usize::from(publish[1]);
+///     let topic = String::from_utf8(publish[2..2 + topic_len].to_vec()).unwrap();
+///     let payload = String::from_utf8(publish[2 + topic_len..].to_vec()).unwrap();
+///     (topic, payload)
+/// });
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let publisher = Arc::new(MqttPublisher::connect(&addr, "lane-1").unwrap());
+/// MqttPublisher::attach(&publisher, &terminal).unwrap();
+/// terminal.scan("A").unwrap();
+///
+/// let (topic, payload) = published.join().unwrap();
+/// assert_eq!(topic, "store-terminal/terminal-1/scan/accepted");
+/// assert_eq!(payload, r#"{"amount":1.0,"code":"A"}"#);
+/// ```
+///
+/// The publisher connects again when the broker drops the connection:
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use std::net::{TcpListener, TcpStream};
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::Duration;
+/// use store_terminal::mqtt::MqttPublisher;
+///
+/// let packet = |client: &mut TcpStream| {
+///     let mut header = [0; 2];
+///     client.read_exact(&mut header).unwrap();
+///     let mut body = vec![0; usize::from(header[1])];
+///     client.read_exact(&mut body).unwrap();
+///     body
+/// };
+/// let broker = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let addr = broker.local_addr().unwrap().to_string();
+/// let received = Arc::new(AtomicBool::new(false));
+/// let done = Arc::clone(&received);
+/// let broker = thread::spawn(move || {
+///     // The first connection is dropped right after it is accepted
+///     let (mut client, _) = broker.accept().unwrap();
+///     packet(&mut client);
+///     client.write_all(&[0x20, 2, 0, 0]).unwrap();
+///     drop(client);
+///
+///     let (mut client, _) = broker.accept().unwrap();
+///     packet(&mut client);
+///     client.write_all(&[0x20, 2, 0, 0]).unwrap();
+///     let publish = packet(&mut client);
+///     done.store(true, Ordering::SeqCst);
+///     publish[2..2 + usize::from(publish[1])].to_vec()
+/// });
+///
+/// let publisher = MqttPublisher::connect(&addr, "lane-1").unwrap();
+/// while !received.load(Ordering::SeqCst) {
+///     publisher.publish("lanes/1", b"up").unwrap();
+///     thread::sleep(Duration::from_millis(20));
+/// }
+/// assert_eq!(broker.join().unwrap(), b"lanes/1");
+/// ```
+pub struct MqttPublisher {
+    queue: SyncSender<Message>,
+    closed: Arc<AtomicBool>,
+    prefix: String,
+}
+
+/// Work of the background thread of a publisher
+enum Message {
+    Publish(Vec<u8>),
+    Disconnect,
+}
+
+impl MqttPublisher {
+    /// Connect to the broker at `addr` with a clean session, reconnecting with the same client
+    /// identifier when the connection is lost
+    pub fn connect(addr: &str, client_id: &str) -> Result<Self, ErrorVariant> {
+        let stream = open(addr, client_id)?;
+        let (queue, messages) = mpsc::sync_channel(QUEUE_LEN);
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let connection = Connection {
+            addr: addr.to_string(),
+            client_id: client_id.to_string(),
+            stream: Some(stream),
+            closed: Arc::clone(&closed),
+        };
+        thread::spawn(move || connection.run(messages));
+
+        Ok(MqttPublisher {
+            queue,
+            closed,
+            prefix: DEFAULT_TOPIC_PREFIX.to_string(),
+        })
+    }
+
+    pub fn with_topic_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn get_topic_prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Queue a message to be published with QoS 0, failing if the queue is full
+    pub fn publish(&self, topic: &str, payload: &[u8]) -> Result<(), ErrorVariant> {
+        let mut body = string(topic);
+        body.extend_from_slice(payload);
+        self.queue
+            .try_send(Message::Publish(packet(0x30, &body)))
+            .map_err(|e| ErrorVariant::IoError {
+                path: topic.to_string(),
+                source: match e {
+                    TrySendError::Full(_) => "publish queue full".to_string(),
+                    TrySendError::Disconnected(_) => "publisher stopped".to_string(),
+                },
+            })
+    }
+
+    /// Publish every event of the terminal, until the returned subscription is removed
+    pub fn attach(
+        publisher: &Arc<MqttPublisher>,
+        terminal: &Terminal,
+    ) -> Result<SubscriptionId, ErrorVariant> {
+        let publisher = Arc::clone(publisher);
+        let base = format!(
+            "{}/{}",
+            publisher.prefix,
+            terminal.get_config().get_terminal_id()
+        );
+        terminal.subscribe(move |event| {
+            let (suffix, payload) = event_message(event);
+            let _ = publisher.publish(
+                &format!("{}/{}", base, suffix),
+                payload.to_string().as_bytes(),
+            );
+        })
+    }
+}
+
+/// The messages queued already are published before disconnecting
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+        let _ = self.queue.try_send(Message::Disconnect);
+    }
+}
+
+/// Connection to the broker, owned by the background thread of a publisher
+struct Connection {
+    addr: String,
+    client_id: String,
+    stream: Option<TcpStream>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Connection {
+    /// Write the queued messages until the publisher is dropped
+    fn run(mut self, messages: Receiver<Message>) {
+        for message in messages {
+            match message {
+                Message::Publish(packet) => {
+                    if let Some(stream) = self.reconnect() {
+                        if stream.write_all(&packet).is_err() {
+                            self.stream = None;
+                        }
+                    }
+                }
+                Message::Disconnect => break,
+            }
+        }
+        if let Some(stream) = &mut self.stream {
+            let _ = stream.write_all(&packet(0xe0, &[]));
+        }
+    }
+
+    /// Stream to the broker, opened again if it was lost, unless the publisher is dropped
+    /// meanwhile
+    fn reconnect(&mut self) -> Option<&mut TcpStream> {
+        let mut backoff = BACKOFF;
+        while self.stream.is_none() && !self.closed.load(Ordering::SeqCst) {
+            match open(&self.addr, &self.client_id) {
+                Ok(stream) => self.stream = Some(stream),
+                Err(_) => {
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+        self.stream.as_mut()
+    }
+}
+
+/// Connect to the broker at `addr` and open a clean session
+fn open(addr: &str, client_id: &str) -> Result<TcpStream, ErrorVariant> {
+    let io_error = |e: std::io::Error| ErrorVariant::IoError {
+        path: addr.to_string(),
+        source: e.to_string(),
+    };
+    let socket = addr
+        .to_socket_addrs()
+        .map_err(io_error)?
+        .next()
+        .ok_or_else(|| ErrorVariant::IoError {
+            path: addr.to_string(),
+            source: "no address resolved".to_string(),
+        })?;
+    let mut stream = TcpStream::connect_timeout(&socket, TIMEOUT).map_err(io_error)?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(io_error)?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(io_error)?;
+
+    let mut connect = vec![0, 4, b'M', b'Q', b'T', b'T', 4, 0x02, 0, 0];
+    connect.extend(string(client_id));
+    stream
+        .write_all(&packet(0x10, &connect))
+        .map_err(io_error)?;
+
+    let mut connack = [0; 4];
+    stream.read_exact(&mut connack).map_err(io_error)?;
+    if connack[0] != 0x20 || connack[3] != 0 {
+        return Err(ErrorVariant::IoError {
+            path: addr.to_string(),
+            source: format!("connection refused by the broker, code {}", connack[3]),
+        });
+    }
+    Ok(stream)
+}
+
+/// Topic suffix and payload of an event
+fn event_message(event: &TerminalEvent) -> (&'static str, Value) {
+    match event {
+        TerminalEvent::ScanAccepted { code, amount } => (
+            "scan/accepted",
+            json!({ "code": code.as_str(), "amount": amount }),
+        ),
+        TerminalEvent::OutOfStock { code, stock } => (
+            "stock/out",
+            json!({ "code": code.as_str(), "stock": stock }),
+        ),
+        TerminalEvent::ScanRejected { code, error } => (
+            "scan/rejected",
+            json!({ "code": code, "error": error.to_string() }),
+        ),
+        TerminalEvent::ScanUndone { code, amount } => (
+            "scan/undone",
+            json!({ "code": code.as_str(), "amount": amount }),
+        ),
+        TerminalEvent::PromotionApplied { code, amount } => (
+            "promotion/applied",
+            json!({ "code": code.as_str(), "amount": amount }),
+        ),
+        TerminalEvent::PriceChanged {
+            code,
+            previous,
+            price,
+        } => (
+            "price/changed",
+            json!({ "code": code.as_str(), "previous": previous, "price": price }),
+        ),
+        TerminalEvent::CatalogChanged {
+            code,
+            previous,
+            price,
+        } => (
+            "catalog/changed",
+            json!({ "code": code.as_str(), "previous": previous, "price": price }),
+        ),
+        TerminalEvent::CustomerIdentified { id } => ("customer/identified", json!({ "id": id })),
+        TerminalEvent::CartReset => ("cart/reset", json!({})),
+        TerminalEvent::CartRecovered => ("cart/recovered", json!({})),
+        TerminalEvent::CheckedOut { sale, total } => {
+            ("checkout", json!({ "sale": sale, "total": total }))
+        }
+        TerminalEvent::LowStock {
+            code,
+            stock,
+            threshold,
+        } => (
+            "stock/low",
+            json!({ "code": code.as_str(), "stock": stock, "threshold": threshold }),
+        ),
+        TerminalEvent::SaleRefunded { sale, total } => {
+            ("sale/refunded", json!({ "sale": sale, "total": total }))
+        }
+        TerminalEvent::DrawerOpened { reason } => ("drawer/opened", json!({ "reason": reason })),
+        TerminalEvent::ApprovalRequired { code, restriction } => (
+            "approval/required",
+            json!({ "code": code.as_str(), "restriction": restriction }),
+        ),
+        TerminalEvent::HoldsApproved { approver, codes } => {
+            let codes: Vec<&str> = codes.iter().map(|c| c.as_str()).collect();
+            (
+                "approval/granted",
+                json!({ "approver": approver, "codes": codes }),
+            )
+        }
+        TerminalEvent::AcceptFailed { error } => {
+            ("server/accept_failed", json!({ "error": error }))
+        }
+    }
+}
+
+/// Control packet with its remaining length encoded as a variable byte integer
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut len = body.len();
+    loop {
+        let byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            packet.push(byte | 0x80);
+        } else {
+            packet.push(byte);
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+/// UTF-8 string prefixed by its two byte length
+fn string(s: &str) -> Vec<u8> {
+    let bytes = &s.as_bytes()[..s.len().min(u16::MAX as usize)];
+    let mut encoded = (bytes.len() as u16).to_be_bytes().to_vec();
+    encoded.extend_from_slice(bytes);
+    encoded
+}
@@ -0,0 +1,221 @@
+//! Operators of the terminal, logged in with their PIN, and the roles gating its sensitive
+//! operations
+//!
+//! Operator accounts are kept in the [DatabaseStore](crate::prelude::DatabaseStore) named
+//! [OPERATOR_STORE], so they log in on any lane of the database. The permissions are only
+//! enforced by terminals configured with
+//! [TerminalConfig::with_operator_login](crate::prelude::TerminalConfig::with_operator_login)
+//!
+//! PINs are stored as PBKDF2-HMAC-SHA256 digests, salted per operator. A short PIN can still be
+//! found from its digest by trying every combination, so the store should be kept on the
+//! terminals
+
+use crate::digest;
+#[cfg(feature = "json")]
+use crate::prelude::{ErrorVariant, TerminalEntityInterface};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Name of the store of the operator accounts in the [Database](crate::prelude::Database)
+pub const OPERATOR_STORE: &str = "operators";
+
+/// Iterations of PBKDF2 deriving the digest of the PIN of a new operator
+pub const PIN_ITERATIONS: u32 = 10_000;
+
+/// Role of an operator, each granted the permissions of the roles below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "json",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Role {
+    Cashier,
+    Supervisor,
+    Admin,
+}
+
+impl Role {
+    pub fn allows(self, permission: Permission) -> bool {
+        self >= permission.get_role()
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Cashier => write!(f, "cashier"),
+            Role::Supervisor => write!(f, "supervisor"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Sensitive operation of a terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    /// [Terminal::set_pricing](crate::prelude::Terminal::set_pricing)
+    PriceOverride,
+    /// [Terminal::reset_cart](crate::prelude::Terminal::reset_cart) of a cart with items
+    Void,
+    /// [Terminal::refund](crate::prelude::Terminal::refund)
+    Refund,
+    /// [Terminal::reset](crate::prelude::Terminal::reset)
+    DatabaseReset,
+    /// [Terminal::approve_holds](crate::prelude::Terminal::approve_holds)
+    ApproveHold,
+    /// [Terminal::change_catalog](crate::prelude::Terminal::change_catalog)
+    CatalogChange,
+}
+
+impl Permission {
+    /// Least role granted the permission
+    pub fn get_role(self) -> Role {
+        match self {
+            Permission::PriceOverride
+            | Permission::Void
+            | Permission::Refund
+            | Permission::ApproveHold
+            | Permission::CatalogChange => Role::Supervisor,
+            Permission::DatabaseReset => Role::Admin,
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Permission::PriceOverride => write!(f, "price override"),
+            Permission::Void => write!(f, "void"),
+            Permission::Refund => write!(f, "refund"),
+            Permission::DatabaseReset => write!(f, "database reset"),
+            Permission::ApproveHold => write!(f, "restricted item approval"),
+            Permission::CatalogChange => write!(f, "catalog change"),
+        }
+    }
+}
+
+/// Account of a cashier, supervisor or admin
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::operator::{Operator, Permission, Role};
+///
+/// let operator = Operator::new("op-7", "Sam", Role::Supervisor, "4821");
+/// assert!(operator.check_pin("4821"));
+/// assert!(!operator.check_pin("1234"));
+///
+/// let other = Operator::new("op-7", "Sam", Role::Supervisor, "4821");
+/// assert_ne!(operator, other);
+/// assert!(other.check_pin("4821"));
+///
+/// assert!(operator.can(Permission::Refund));
+/// assert!(!operator.can(Permission::DatabaseReset));
+/// assert!(!Role::Cashier.allows(Permission::PriceOverride));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Operator {
+    id: String,
+    name: String,
+    role: Role,
+    /// Random salt of the digest, in hexadecimal
+    salt: String,
+    /// Iterations of PBKDF2 deriving the digest
+    iterations: u32,
+    /// PBKDF2-HMAC-SHA256 of the PIN, in hexadecimal
+    pin: String,
+}
+
+impl Operator {
+    /// Operator logging in with `pin`, of which only a salted digest is kept
+    pub fn new(id: &str, name: &str, role: Role, pin: &str) -> Self {
+        let salt = new_salt();
+        Operator {
+            id: id.to_string(),
+            name: name.to_string(),
+            role,
+            salt: digest::hex(&salt),
+            iterations: PIN_ITERATIONS,
+            pin: pin_digest(pin, &salt, PIN_ITERATIONS),
+        }
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_role(&self) -> Role {
+        self.role
+    }
+
+    pub fn check_pin(&self, pin: &str) -> bool {
+        match unhex(&self.salt) {
+            Some(salt) => self.pin == pin_digest(pin, &salt, self.iterations),
+            None => false,
+        }
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        self.role.allows(permission)
+    }
+}
+
+#[cfg(feature = "json")]
+impl TerminalEntityInterface for Operator {
+    fn get_syntax_example() -> &'static str {
+        r#"{"id": "op-7", "name": "Sam", "role": "supervisor", "salt": "<hex salt>", "iterations": 10000, "pin": "<hex digest>"}"#
+    }
+
+    fn from_json(json: String) -> Result<Self, ErrorVariant> {
+        serde_json::from_str(&json).map_err(|e| ErrorVariant::from_json_error(e, "Operator"))
+    }
+
+    fn to_json(&self) -> Result<String, ErrorVariant> {
+        serde_json::to_string(self).map_err(|e| ErrorVariant::from_json_error(e, "Operator"))
+    }
+}
+
+fn pin_digest(pin: &str, salt: &[u8], iterations: u32) -> String {
+    digest::hex(&digest::pbkdf2_hmac_sha256(
+        pin.as_bytes(),
+        salt,
+        iterations,
+    ))
+}
+
+/// Bytes of a hexadecimal string, `None` if it is malformed or of odd length
+fn unhex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(feature = "uuid")]
+fn new_salt() -> [u8; 16] {
+    *uuid::Uuid::new_v4().as_bytes()
+}
+
+/// Salt from the random keys of the standard library hasher, unique within the process
+#[cfg(not(feature = "uuid"))]
+fn new_salt() -> [u8; 16] {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let mut salt = [0; 16];
+    for chunk in salt.chunks_mut(8) {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(NEXT.fetch_add(1, Ordering::Relaxed));
+        chunk.copy_from_slice(&hasher.finish().to_be_bytes());
+    }
+    salt
+}
@@ -0,0 +1,306 @@
+//! Means of payment of the sales, and payloads of payment QR codes
+//!
+//! A sale is paid with one or more [TenderAmount]s, checked by
+//! [Terminal::checkout_with_tenders](crate::prelude::Terminal::checkout_with_tenders) to cover
+//! its total. QR code payloads, shown on the customer display for app-based payment, are the
+//! text to encode in the QR code, for the total of a finalized cart
+
+use crate::ErrorVariant;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+/// Means of payment of a sale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(rename_all = "lowercase"))]
+pub enum Tender {
+    /// Notes and coins, the only tender giving change
+    Cash,
+    Card,
+    /// Store voucher or coupon with a face value
+    Voucher,
+    /// Balance of a [GiftCard](crate::gift_card::GiftCard) of the store
+    #[cfg_attr(feature = "json", serde(rename = "gift_card"))]
+    GiftCard,
+    Other,
+}
+
+impl Tender {
+    /// Lowercase name of the tender, as in JSON documents
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Tender::Cash => "cash",
+            Tender::Card => "card",
+            Tender::Voucher => "voucher",
+            Tender::GiftCard => "gift_card",
+            Tender::Other => "other",
+        }
+    }
+}
+
+/// Amount paid with a [Tender]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct TenderAmount {
+    tender: Tender,
+    amount: f64,
+}
+
+impl TenderAmount {
+    pub fn new(tender: Tender, amount: f64) -> Self {
+        TenderAmount { tender, amount }
+    }
+
+    pub fn get_tender(&self) -> Tender {
+        self.tender
+    }
+
+    pub fn get_amount(&self) -> f64 {
+        self.amount
+    }
+}
+
+/// Payment request for an amount due
+pub trait PaymentPayload {
+    /// Text of the QR code requesting `total`
+    fn to_payload(&self, total: f64) -> Result<String, ErrorVariant>;
+}
+
+/// SEPA credit transfer, in the EPC QR code format of the European Payments Council
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::payment::{PaymentPayload, SepaPayment};
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AB").unwrap();
+///
+/// let payment = SepaPayment::new("Corner Store", "DE89 3704 0044 0532 0130 00")
+///     .with_bic("COBADEFFXXX")
+///     .with_remittance("Receipt 42");
+/// let total = terminal.get_cart().unwrap().get_total_price();
+///
+/// assert_eq!(
+///     payment.to_payload(total).unwrap(),
+///     "BCD\n002\n1\nSCT\nCOBADEFFXXX\nCorner Store\nDE89370400440532013000\nEUR14.00\n\n\nReceipt 42"
+/// );
+///
+/// let invalid = SepaPayment::new("Corner Store", "DE00 3704 0044 0532 0130 00");
+/// assert!(invalid.to_payload(total).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SepaPayment {
+    name: String,
+    iban: String,
+    bic: String,
+    remittance: String,
+}
+
+impl SepaPayment {
+    /// Transfer to the account `iban` of the beneficiary `name`; spaces in `iban` are ignored
+    pub fn new(name: &str, iban: &str) -> Self {
+        SepaPayment {
+            name: name.to_string(),
+            iban: iban.chars().filter(|c| !c.is_whitespace()).collect(),
+            bic: String::new(),
+            remittance: String::new(),
+        }
+    }
+
+    /// BIC of the bank of the beneficiary, optional inside the EEA
+    pub fn with_bic(mut self, bic: &str) -> Self {
+        self.bic = bic.to_string();
+        self
+    }
+
+    /// Unstructured remittance information, such as a receipt number
+    pub fn with_remittance(mut self, remittance: &str) -> Self {
+        self.remittance = remittance.to_string();
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_iban(&self) -> &str {
+        &self.iban
+    }
+}
+
+impl PaymentPayload for SepaPayment {
+    fn to_payload(&self, total: f64) -> Result<String, ErrorVariant> {
+        check_length("name", &self.name, 1, 70)?;
+        check_length("remittance", &self.remittance, 0, 140)?;
+        if !self.bic.is_empty() && ![8, 11].contains(&self.bic.len()) {
+            return Err(invalid("bic", &self.bic));
+        }
+        if !is_valid_iban(&self.iban) {
+            return Err(invalid("iban", &self.iban));
+        }
+        if !(0.01..=999_999_999.99).contains(&total) {
+            return Err(invalid("total", &total.to_string()));
+        }
+
+        let payload = format!(
+            "BCD\n002\n1\nSCT\n{}\n{}\n{}\nEUR{:.2}\n\n\n{}",
+            self.bic, self.name, self.iban, total, self.remittance
+        );
+        Ok(payload.trim_end().to_string())
+    }
+}
+
+/// Instant payment of the Brazilian PIX system, as a static BR Code
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::payment::{PaymentPayload, PixPayment};
+///
+/// let payment = PixPayment::new("123e4567-e12b-12d1-a456-426655440000", "Fulano de Tal", "BRASILIA")
+///     .with_transaction_id("LANE3");
+///
+/// let payload = payment.to_payload(14.0).unwrap();
+/// assert!(payload.starts_with("000201"));
+/// assert!(payload.contains("0014br.gov.bcb.pix0136123e4567-e12b-12d1-a456-426655440000"));
+/// assert!(payload.contains("5303986540514.005802BR5913Fulano de Tal6008BRASILIA"));
+/// assert!(payload.contains("62090505LANE3"));
+/// assert_eq!(&payload[payload.len() - 8..payload.len() - 4], "6304");
+///
+/// assert!(PixPayment::new("key", "A name longer than the limit", "BRASILIA")
+///     .to_payload(14.0)
+///     .is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PixPayment {
+    key: String,
+    merchant_name: String,
+    merchant_city: String,
+    transaction_id: String,
+}
+
+impl PixPayment {
+    /// Payment to the PIX `key` of the merchant, such as its e-mail or a random key
+    pub fn new(key: &str, merchant_name: &str, merchant_city: &str) -> Self {
+        PixPayment {
+            key: key.to_string(),
+            merchant_name: merchant_name.to_string(),
+            merchant_city: merchant_city.to_string(),
+            transaction_id: String::new(),
+        }
+    }
+
+    /// Identifier of the sale reported back by the bank, alphanumeric
+    pub fn with_transaction_id(mut self, transaction_id: &str) -> Self {
+        self.transaction_id = transaction_id.to_string();
+        self
+    }
+
+    pub fn get_key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl PaymentPayload for PixPayment {
+    fn to_payload(&self, total: f64) -> Result<String, ErrorVariant> {
+        check_length("merchant_name", &self.merchant_name, 1, 25)?;
+        check_length("merchant_city", &self.merchant_city, 1, 15)?;
+        check_length("transaction_id", &self.transaction_id, 0, 25)?;
+        if !self
+            .transaction_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(invalid("transaction_id", &self.transaction_id));
+        }
+        if !(total > 0.0 && total.is_finite()) {
+            return Err(invalid("total", &total.to_string()));
+        }
+
+        let account = format!("{}{}", emv("00", "br.gov.bcb.pix")?, emv("01", &self.key)?);
+        let transaction_id = match self.transaction_id.as_str() {
+            "" => "***",
+            id => id,
+        };
+
+        let mut payload = [
+            emv("00", "01")?,
+            emv("26", &account)?,
+            emv("52", "0000")?,
+            emv("53", "986")?,
+            emv("54", &format!("{:.2}", total))?,
+            emv("58", "BR")?,
+            emv("59", &self.merchant_name)?,
+            emv("60", &self.merchant_city)?,
+            emv("62", &emv("05", transaction_id)?)?,
+        ]
+        .concat();
+        payload.push_str("6304");
+        let crc = crc16(payload.as_bytes());
+        payload.push_str(&format!("{:04X}", crc));
+
+        Ok(payload)
+    }
+}
+
+/// Field of an EMV merchant-presented QR code: identifier, two digit length and value
+fn emv(id: &str, value: &str) -> Result<String, ErrorVariant> {
+    if value.len() > 99 {
+        return Err(invalid(id, value));
+    }
+    Ok(format!("{}{:02}{}", id, value.len(), value))
+}
+
+/// CRC-16/CCITT-FALSE checksum of BR Codes
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xffff, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// ISO 13616 check of an IBAN without spaces
+fn is_valid_iban(iban: &str) -> bool {
+    if !(15..=34).contains(&iban.len()) || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+
+    let (country, account) = iban.split_at(4);
+    let remainder = account.chars().chain(country.chars()).try_fold(0, |r, c| {
+        let value = c.to_ascii_uppercase().to_digit(36)?;
+        Some(if value < 10 {
+            (r * 10 + value) % 97
+        } else {
+            (r * 100 + value) % 97
+        })
+    });
+    remainder == Some(1)
+}
+
+fn check_length(
+    field: &'static str,
+    value: &str,
+    min: usize,
+    max: usize,
+) -> Result<(), ErrorVariant> {
+    let len = value.chars().count();
+    if len < min || len > max {
+        return Err(invalid(field, value));
+    }
+    Ok(())
+}
+
+fn invalid(field: &str, value: &str) -> ErrorVariant {
+    ErrorVariant::InvalidPaymentDetails {
+        field: field.to_string(),
+        value: value.to_string(),
+    }
+}
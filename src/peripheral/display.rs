@@ -0,0 +1,142 @@
+use crate::peripheral::Device;
+use crate::prelude::{ErrorVariant, SubscriptionId, Terminal, TerminalEvent};
+use std::sync::Arc;
+
+/// Characters of a line of the display
+pub const LINE_WIDTH: usize = 20;
+
+/// Customer-facing 2x20 pole display, with the CD5220 command set of most VFD displays
+///
+/// Once attached to a terminal, the display shows the last scanned item over the running
+/// total, then the amount due on checkout
+///
+/// # Example
+///
+/// ```
+/// use std::io::{self, Write};
+/// use std::sync::{Arc, Mutex};
+/// use store_terminal::peripheral::{Device, PoleDisplay};
+/// use store_terminal::prelude::*;
+///
+/// #[derive(Clone, Default)]
+/// struct Output(Arc<Mutex<Vec<u8>>>);
+///
+/// impl Write for Output {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.0.lock().unwrap().write(buf)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let terminal = Arc::new(Terminal::new().unwrap());
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let output = Output::default();
+/// let display = Arc::new(PoleDisplay::new(Device::from_writer("display", output.clone())));
+/// PoleDisplay::attach(&display, &terminal).unwrap();
+///
+/// terminal.scan("AAAA").unwrap();
+/// let shown = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+/// let last = shown.rsplit("\x1bQA").next().unwrap();
+/// assert_eq!(last, "A               2.00\r\x1bQBTOTAL           7.00\r");
+/// ```
+pub struct PoleDisplay {
+    device: Device,
+    idle: (String, String),
+}
+
+impl PoleDisplay {
+    pub fn new(device: Device) -> Self {
+        PoleDisplay {
+            device,
+            idle: ("Welcome".to_string(), String::new()),
+        }
+    }
+
+    /// Lines shown while the cart is empty, `Welcome` by default
+    pub fn with_idle_message(mut self, upper: &str, lower: &str) -> Self {
+        self.idle = (upper.to_string(), lower.to_string());
+        self
+    }
+
+    pub fn get_device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Replace both lines, truncated to [LINE_WIDTH] characters; non-ASCII characters are
+    /// shown as `?`
+    pub fn show(&self, upper: &str, lower: &str) -> Result<(), ErrorVariant> {
+        let mut command = b"\x1bQA".to_vec();
+        command.extend(line(upper));
+        command.extend_from_slice(b"\r\x1bQB");
+        command.extend(line(lower));
+        command.push(b'\r');
+        self.device.write(&command)
+    }
+
+    /// Mirror the scans and checkouts of the terminal, until the returned subscription is removed
+    pub fn attach(
+        display: &Arc<PoleDisplay>,
+        terminal: &Arc<Terminal>,
+    ) -> Result<SubscriptionId, ErrorVariant> {
+        let display = Arc::clone(display);
+        // The terminal owns its listeners, so they only keep a weak reference to it
+        let terminal_ref = Arc::downgrade(terminal);
+        display.show(&display.idle.0, &display.idle.1)?;
+
+        terminal.subscribe(move |event| {
+            let terminal = match terminal_ref.upgrade() {
+                Some(terminal) => terminal,
+                None => return,
+            };
+            // As for any listener, a display failing to show an event has nobody to report to
+            let _ = match event {
+                TerminalEvent::ScanAccepted { code, amount } => terminal
+                    .get_db()
+                    .and_then(|database| database.fetch_product(code.as_str()))
+                    .and_then(|product| {
+                        let total = terminal.get_cart()?.get_total_price();
+                        display.show(
+                            &columns(
+                                code.as_str(),
+                                &format!("{:.2}", product.get_price() * amount),
+                            ),
+                            &columns("TOTAL", &format!("{:.2}", total)),
+                        )
+                    }),
+                TerminalEvent::CheckedOut { total, .. } => {
+                    display.show(&columns("TOTAL DUE", &format!("{:.2}", total)), "Thank you")
+                }
+                TerminalEvent::CartReset => display.show(&display.idle.0, &display.idle.1),
+                _ => Ok(()),
+            };
+        })
+    }
+}
+
+/// `text` padded or truncated to the width of the display
+fn line(text: &str) -> Vec<u8> {
+    let mut line: Vec<u8> = text
+        .chars()
+        .take(LINE_WIDTH)
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c as u8
+            } else {
+                b'?'
+            }
+        })
+        .collect();
+    line.resize(LINE_WIDTH, b' ');
+    line
+}
+
+/// `left` and `right` at both ends of a line, truncating `left`
+fn columns(left: &str, right: &str) -> String {
+    let room = LINE_WIDTH.saturating_sub(right.chars().count() + 1);
+    let left: String = left.chars().take(room).collect();
+    format!("{:<room$} {}", left, right, room = room)
+}
@@ -0,0 +1,120 @@
+use crate::peripheral::Device;
+use crate::prelude::ErrorVariant;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Cash drawer of a lane, opened on cash checkouts and by [Terminal::open_drawer](crate::prelude::Terminal::open_drawer)
+pub trait CashDrawer: Send + Sync {
+    /// Pulse the solenoid releasing the drawer
+    fn open(&self) -> Result<(), ErrorVariant>;
+}
+
+/// Drawer shared with other parts of the application
+impl<D: CashDrawer + ?Sized> CashDrawer for Arc<D> {
+    fn open(&self) -> Result<(), ErrorVariant> {
+        (**self).open()
+    }
+}
+
+/// Drawer connected to the kick-out port of an ESC/POS receipt printer
+///
+/// # Example
+///
+/// ```
+/// use std::io::{self, Write};
+/// use std::sync::{Arc, Mutex};
+/// use store_terminal::peripheral::{CashDrawer, Device, EscPosDrawer};
+///
+/// #[derive(Clone, Default)]
+/// struct Printer(Arc<Mutex<Vec<u8>>>);
+///
+/// impl Write for Printer {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.0.lock().unwrap().write(buf)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let printer = Printer::default();
+/// let drawer = EscPosDrawer::new(Device::from_writer("printer", printer.clone()));
+/// drawer.open().unwrap();
+/// EscPosDrawer::new(Device::from_writer("printer", printer.clone())).with_pin(5).open().unwrap();
+///
+/// assert_eq!(*printer.0.lock().unwrap(), [0x1b, 0x70, 0, 25, 250, 0x1b, 0x70, 1, 25, 250]);
+/// ```
+pub struct EscPosDrawer {
+    device: Device,
+    pin: u8,
+}
+
+impl EscPosDrawer {
+    /// Drawer on pin 2 of the kick-out connector of the printer
+    pub fn new(device: Device) -> Self {
+        EscPosDrawer { device, pin: 2 }
+    }
+
+    /// Kick-out connector pin of the drawer, 2 or 5; any other pin is taken as 2
+    pub fn with_pin(mut self, pin: u8) -> Self {
+        self.pin = pin;
+        self
+    }
+
+    pub fn get_device(&self) -> &Device {
+        &self.device
+    }
+}
+
+impl CashDrawer for EscPosDrawer {
+    fn open(&self) -> Result<(), ErrorVariant> {
+        // ESC p m t1 t2: pulse of 25 x 2ms on the pin, then 250 x 2ms off
+        let pin = if self.pin == 5 { 1 } else { 0 };
+        self.device.write(&[0x1b, 0x70, pin, 25, 250])
+    }
+}
+
+/// Drawer driven by a GPIO line, through the Linux sysfs interface
+pub struct GpioDrawer {
+    value: PathBuf,
+    pulse: Duration,
+}
+
+impl GpioDrawer {
+    /// Drawer on the exported GPIO `line`, configured as an output
+    pub fn new(line: u32) -> Self {
+        GpioDrawer::from_path(format!("/sys/class/gpio/gpio{}/value", line))
+    }
+
+    /// Drawer driven by writing `1` and then `0` to the `value` file
+    pub fn from_path<P: Into<PathBuf>>(value: P) -> Self {
+        GpioDrawer {
+            value: value.into(),
+            pulse: Duration::from_millis(100),
+        }
+    }
+
+    /// Time the line is kept high, 100ms by default
+    pub fn with_pulse(mut self, pulse: Duration) -> Self {
+        self.pulse = pulse;
+        self
+    }
+}
+
+impl CashDrawer for GpioDrawer {
+    fn open(&self) -> Result<(), ErrorVariant> {
+        let set = |level: &str| {
+            fs::write(&self.value, level).map_err(|e| ErrorVariant::IoError {
+                path: self.value.display().to_string(),
+                source: e.to_string(),
+            })
+        };
+        set("1")?;
+        thread::sleep(self.pulse);
+        set("0")
+    }
+}
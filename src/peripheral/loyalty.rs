@@ -0,0 +1,78 @@
+use crate::lock;
+use crate::prelude::{Customer, ErrorVariant};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::{Arc, Mutex};
+
+/// Driver of a loyalty card or phone number input, read by
+/// [Terminal::identify_customer](crate::prelude::Terminal::identify_customer)
+///
+/// Integrators implement it for their NFC readers, PIN pads or loyalty backends, returning
+/// the segments of the member with the [Customer]
+pub trait CustomerReader: Send + Sync {
+    /// Customer presented to the reader, or `None` when nothing was presented
+    fn read(&self) -> Result<Option<Customer>, ErrorVariant>;
+}
+
+/// Reader shared with other parts of the application
+impl<R: CustomerReader + ?Sized> CustomerReader for Arc<R> {
+    fn read(&self) -> Result<Option<Customer>, ErrorVariant> {
+        (**self).read()
+    }
+}
+
+/// Magnetic stripe reader sending one line of track data per swipe
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+/// use store_terminal::peripheral::{CustomerReader, MagstripeReader};
+///
+/// let reader = MagstripeReader::new(Cursor::new(";6012345678901234=2512?\r\n\r\nREJECTED\n"));
+/// assert_eq!(reader.read().unwrap().unwrap().get_id(), "6012345678901234");
+/// assert_eq!(reader.read().unwrap(), None);
+/// assert!(reader.read().is_err());
+/// assert_eq!(reader.read().unwrap(), None);
+/// ```
+pub struct MagstripeReader<R> {
+    port: Mutex<R>,
+}
+
+impl<R: BufRead + Send> MagstripeReader<R> {
+    pub fn new(port: R) -> Self {
+        MagstripeReader {
+            port: Mutex::new(port),
+        }
+    }
+}
+
+impl MagstripeReader<BufReader<File>> {
+    /// Reader on the serial port at `path`, already configured, for instance with `stty`
+    pub fn open(path: &str) -> Result<Self, ErrorVariant> {
+        File::open(path)
+            .map(|file| MagstripeReader::new(BufReader::new(file)))
+            .map_err(|e| ErrorVariant::IoError {
+                path: path.to_string(),
+                source: e.to_string(),
+            })
+    }
+}
+
+impl<R: BufRead + Send> CustomerReader for MagstripeReader<R> {
+    /// Next swipe, or `None` on an empty line or the end of the input
+    fn read(&self) -> Result<Option<Customer>, ErrorVariant> {
+        let mut line = String::new();
+        lock::lock(&self.port)
+            .read_line(&mut line)
+            .map_err(|e| ErrorVariant::IoError {
+                path: "magnetic stripe reader".to_string(),
+                source: e.to_string(),
+            })?;
+
+        match line.trim() {
+            "" => Ok(None),
+            track => Customer::from_magstripe(track).map(Some),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Devices driven by the terminal, such as cash drawers, customer displays, scales and loyalty
+//! card readers
+//!
+//! Most point of sale devices are written to through a character device, such as a serial port
+//! or the USB printer the drawer is connected to; a [Device] wraps that output
+
+use crate::lock;
+use crate::prelude::ErrorVariant;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+pub mod display;
+pub mod drawer;
+pub mod loyalty;
+pub mod scale;
+
+pub use self::display::PoleDisplay;
+pub use self::drawer::{CashDrawer, EscPosDrawer, GpioDrawer};
+pub use self::loyalty::{CustomerReader, MagstripeReader};
+pub use self::scale::{Dialog06Scale, NciScale, Scale};
+
+/// Devices attached to a terminal by its builder
+#[derive(Clone, Default)]
+pub(crate) struct Peripherals {
+    pub drawer: Option<Arc<dyn CashDrawer>>,
+    pub scale: Option<Arc<dyn Scale>>,
+    pub customer_reader: Option<Arc<dyn CustomerReader>>,
+}
+
+impl Peripherals {
+    pub fn get_drawer(&self) -> Result<&Arc<dyn CashDrawer>, ErrorVariant> {
+        self.drawer
+            .as_ref()
+            .ok_or_else(|| unavailable("cash drawer"))
+    }
+
+    pub fn get_scale(&self) -> Result<&Arc<dyn Scale>, ErrorVariant> {
+        self.scale.as_ref().ok_or_else(|| unavailable("scale"))
+    }
+
+    pub fn get_customer_reader(&self) -> Result<&Arc<dyn CustomerReader>, ErrorVariant> {
+        self.customer_reader
+            .as_ref()
+            .ok_or_else(|| unavailable("customer reader"))
+    }
+}
+
+fn unavailable(peripheral: &str) -> ErrorVariant {
+    ErrorVariant::PeripheralUnavailable {
+        peripheral: peripheral.to_string(),
+    }
+}
+
+/// Output to a device, shared by the threads of the terminal
+pub struct Device {
+    name: String,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Device {
+    /// Device file at `path`, such as `/dev/usb/lp0` or `/dev/ttyUSB0`
+    pub fn open(path: &str) -> Result<Self, ErrorVariant> {
+        let file =
+            OpenOptions::new()
+                .write(true)
+                .open(path)
+                .map_err(|e| ErrorVariant::IoError {
+                    path: path.to_string(),
+                    source: e.to_string(),
+                })?;
+        Ok(Device::from_writer(path, file))
+    }
+
+    /// Device behind any writer, such as a TCP connection to a network printer
+    pub fn from_writer<W: Write + Send + 'static>(name: &str, writer: W) -> Self {
+        Device {
+            name: name.to_string(),
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Send `bytes` to the device at once
+    pub fn write(&self, bytes: &[u8]) -> Result<(), ErrorVariant> {
+        let mut writer = lock::lock(&self.writer);
+        writer
+            .write_all(bytes)
+            .and_then(|_| writer.flush())
+            .map_err(|e| ErrorVariant::IoError {
+                path: self.name.clone(),
+                source: e.to_string(),
+            })
+    }
+}
@@ -0,0 +1,254 @@
+use crate::lock;
+use crate::prelude::ErrorVariant;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+const ENQ: u8 = 0x05;
+const ACK: u8 = 0x06;
+const NAK: u8 = 0x15;
+const EOT: u8 = 0x04;
+const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+const ESC: u8 = 0x1b;
+
+/// Checkout scale weighing the items scanned by [Terminal::scan_weighed](crate::prelude::Terminal::scan_weighed)
+pub trait Scale: Send + Sync {
+    /// Stable weight on the platter of an item priced `unit_price` per unit of weight
+    fn weigh(&self, unit_price: f64) -> Result<f64, ErrorVariant>;
+}
+
+/// Scale shared with other parts of the application
+impl<S: Scale + ?Sized> Scale for Arc<S> {
+    fn weigh(&self, unit_price: f64) -> Result<f64, ErrorVariant> {
+        (**self).weigh(unit_price)
+    }
+}
+
+/// Scale speaking the NCI general protocol, in the unit it is configured with
+///
+/// # Example
+///
+/// ```
+/// use std::io::{self, Cursor, Read, Write};
+/// use store_terminal::peripheral::{NciScale, Scale};
+///
+/// struct Port(Cursor<Vec<u8>>, Vec<u8>);
+///
+/// impl Read for Port {
+///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+///         self.0.read(buf)
+///     }
+/// }
+///
+/// impl Write for Port {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.1.write(buf)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let stable = Port(Cursor::new(b"\n  1.250KG\r\nS00\r\x03".to_vec()), vec![]);
+/// assert_eq!(NciScale::new(stable).weigh(3.0).unwrap(), 1.25);
+///
+/// let moving = Port(Cursor::new(b"\nS10\r\x03".to_vec()), vec![]);
+/// assert!(NciScale::new(moving).weigh(3.0).is_err());
+/// ```
+pub struct NciScale<P> {
+    port: Mutex<P>,
+}
+
+impl<P: Read + Write + Send> NciScale<P> {
+    pub fn new(port: P) -> Self {
+        NciScale {
+            port: Mutex::new(port),
+        }
+    }
+}
+
+impl NciScale<File> {
+    /// Scale on the serial port at `path`, already configured, for instance with `stty`
+    pub fn open(path: &str) -> Result<Self, ErrorVariant> {
+        open_port(path).map(NciScale::new)
+    }
+}
+
+impl<P: Read + Write + Send> Scale for NciScale<P> {
+    fn weigh(&self, _unit_price: f64) -> Result<f64, ErrorVariant> {
+        let response = {
+            let mut port = lock::lock(&self.port);
+            port.write_all(b"W\r").map_err(io_error)?;
+            read_until(&mut *port, |byte| byte == ETX)?
+        };
+        let response = String::from_utf8_lossy(&response).into_owned();
+        let invalid = || ErrorVariant::InvalidWeight {
+            reading: response.trim_matches(|c: char| c.is_control()).to_string(),
+        };
+
+        // Status bytes: motion, at zero and memory errors in the first, capacity errors in the
+        // second; the weight of a scale at zero is rejected below
+        let status = response
+            .split(['\r', '\n'])
+            .find_map(|field| field.strip_prefix('S'))
+            .ok_or_else(invalid)?
+            .as_bytes();
+        if status.len() < 2 || status[0] & 0x0d != 0 || status[1] & 0x0f != 0 {
+            return Err(invalid());
+        }
+
+        let weight = response
+            .split(['\r', '\n'])
+            .map(|field| field.trim().trim_end_matches(char::is_alphabetic).trim())
+            .find_map(|field| field.parse::<f64>().ok())
+            .ok_or_else(invalid)?;
+        positive(weight).ok_or_else(invalid)
+    }
+}
+
+/// Price computing scale speaking the Dialog 06 protocol, weighing in kilograms
+///
+/// The unit price is sent to the scale, which returns the weight of a stable item
+///
+/// # Example
+///
+/// ```
+/// use std::io::{self, Cursor, Read, Write};
+/// use store_terminal::peripheral::{Dialog06Scale, Scale};
+///
+/// struct Port(Cursor<Vec<u8>>, Vec<u8>);
+///
+/// impl Read for Port {
+///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+///         self.0.read(buf)
+///     }
+/// }
+///
+/// impl Write for Port {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.1.write(buf)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let replies = b"\x06\x0202\x1b0\x1b01500\x1b000299\x1b000449\x03".to_vec();
+/// let scale = Dialog06Scale::new(Port(Cursor::new(replies), vec![]));
+/// assert_eq!(scale.weigh(2.99).unwrap(), 1.5);
+///
+/// let busy = Port(Cursor::new(b"\x15".to_vec()), vec![]);
+/// assert!(Dialog06Scale::new(busy).weigh(2.99).is_err());
+/// ```
+pub struct Dialog06Scale<P> {
+    port: Mutex<P>,
+}
+
+impl<P: Read + Write + Send> Dialog06Scale<P> {
+    pub fn new(port: P) -> Self {
+        Dialog06Scale {
+            port: Mutex::new(port),
+        }
+    }
+}
+
+impl Dialog06Scale<File> {
+    /// Scale on the serial port at `path`, already configured, for instance with `stty`
+    pub fn open(path: &str) -> Result<Self, ErrorVariant> {
+        open_port(path).map(Dialog06Scale::new)
+    }
+}
+
+impl<P: Read + Write + Send> Scale for Dialog06Scale<P> {
+    fn weigh(&self, unit_price: f64) -> Result<f64, ErrorVariant> {
+        let mut port = lock::lock(&self.port);
+
+        // Record 01: unit price in cents
+        let cents = (unit_price * 100.0).round();
+        if !(0.0..=999_999.0).contains(&cents) {
+            return Err(ErrorVariant::InvalidWeight {
+                reading: format!("unit price {}", unit_price),
+            });
+        }
+        let mut price = vec![EOT, STX, b'0', b'1', ESC];
+        price.extend(format!("{:06}", cents).bytes());
+        price.extend_from_slice(&[ESC, ETX]);
+        port.write_all(&price).map_err(io_error)?;
+        let reply = read_until(&mut *port, |byte| byte == ACK || byte == NAK)?;
+        if reply.last() != Some(&ACK) {
+            return Err(ErrorVariant::InvalidWeight {
+                reading: "unit price refused".to_string(),
+            });
+        }
+
+        // Record 02: status, weight in grams, unit price and sale price
+        port.write_all(&[EOT, ENQ]).map_err(io_error)?;
+        let reply = read_until(&mut *port, |byte| byte == ETX || byte == NAK)?;
+        let reply = String::from_utf8_lossy(&reply).into_owned();
+        let invalid = || ErrorVariant::InvalidWeight {
+            reading: reply.trim_matches(|c: char| c.is_control()).to_string(),
+        };
+
+        let fields: Vec<&str> = reply
+            .trim_matches(|c| c == STX as char || c == ETX as char)
+            .split(ESC as char)
+            .collect();
+        match fields.as_slice() {
+            ["02", "0", weight, ..] => weight
+                .parse::<f64>()
+                .ok()
+                .and_then(|grams| positive(grams / 1000.0))
+                .ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+fn open_port(path: &str) -> Result<File, ErrorVariant> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| ErrorVariant::IoError {
+            path: path.to_string(),
+            source: e.to_string(),
+        })
+}
+
+/// Bytes read up to and including the first one matching `end`
+fn read_until<R: Read + ?Sized>(
+    port: &mut R,
+    end: impl Fn(u8) -> bool,
+) -> Result<Vec<u8>, ErrorVariant> {
+    let mut response = vec![];
+    let mut byte = [0];
+    loop {
+        match port.read(&mut byte).map_err(io_error)? {
+            0 => {
+                return Err(ErrorVariant::InvalidWeight {
+                    reading: String::from_utf8_lossy(&response).into_owned(),
+                })
+            }
+            _ => {
+                response.push(byte[0]);
+                if end(byte[0]) {
+                    return Ok(response);
+                }
+            }
+        }
+    }
+}
+
+fn positive(weight: f64) -> Option<f64> {
+    Some(weight).filter(|w| w.is_finite() && *w > 0.0)
+}
+
+fn io_error(e: std::io::Error) -> ErrorVariant {
+    ErrorVariant::IoError {
+        path: "scale".to_string(),
+        source: e.to_string(),
+    }
+}
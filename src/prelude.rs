@@ -1,11 +1,48 @@
+pub use crate::audit::{AuditAction, AuditEntry, AuditLog};
 pub use crate::cart::fut::CartGroupFuture;
-pub use crate::cart::item::{CartItem, CartItemVariant, CloneIntoDynBox};
-pub use crate::cart::optimizer::Optimizer;
+#[cfg(feature = "json")]
+pub use crate::cart::item::CartItemKinds;
+pub use crate::cart::item::{CartItem, CartItemVariant, CloneIntoDynBox, CustomCartItem, ItemId};
+pub use crate::cart::optimizer::{
+    DefaultOptimizerStrategy, Optimizer, OptimizerSpan, OptimizerStrategy, OptimizerTracer,
+    TracedOptimizerStrategy,
+};
 pub use crate::cart::optimizer_candidate::OptimizerCandidate;
+pub use crate::cart::savings::{PromotionSavings, SavingsReport};
 pub use crate::cart::Cart;
-pub use crate::database::{Database, DatabaseAppend};
+pub use crate::code::Code;
+pub use crate::customer::Customer;
+pub use crate::database::catalog::Catalog;
+pub use crate::database::filter::Filter;
+pub use crate::database::reader::DatabaseReader;
+pub use crate::database::stats::DatabaseStats;
+pub use crate::database::store::DatabaseStore;
+pub use crate::database::{AppendOutcome, Database, DatabaseAppend};
+pub use crate::gift_card::{GiftCard, GiftCardLoad};
+pub use crate::inventory::{Inventory, StockLevel, StockMovement, StockPolicy};
+pub use crate::kiosk::{Hold, Restriction};
+pub use crate::loyalty::{LoyaltyAccount, LoyaltyActivity, LoyaltyPolicy, LoyaltyRedemption};
+pub use crate::operator::{Operator, Permission, Role};
+pub use crate::payment::TenderAmount;
 pub use crate::product::extra::ProductAmount;
 pub use crate::product::fut::ProductAmountGroupFuture;
 pub use crate::product::{CartItemProduct, Product};
 pub use crate::promotion::{CartItemPromotion, Promotion};
-pub use crate::{ErrorVariant, Terminal, TerminalEntityInterface, WithNewPricing};
+pub use crate::receipt::{
+    DefaultHtmlTemplate, HtmlTemplate, PaperFormat, Receipt, ReceiptLine, ReceiptSection,
+    ReceiptTemplate, TaxLine, Totals,
+};
+pub use crate::sale::{
+    CategoryRevenue, HourlyRevenue, ProductRevenue, PromotionRevenue, QueuedSale, Sale,
+    SaleBackend, SaleQueue, SaleSequence, SalesHistory, SalesReport, Shift, ShiftReport,
+    SoldProduct, SoldPromotion, SyncConflict, SyncReport, TaxBreakdown, TaxReport, Upload, ZReport,
+};
+pub use crate::table::{Alignment, Table};
+pub use crate::tax::{TaxRate, TaxTable};
+pub use crate::terminal::asynchronous::AsyncTerminal;
+pub use crate::terminal::builder::TerminalBuilder;
+pub use crate::terminal::config::TerminalConfig;
+pub use crate::terminal::event::{SubscriptionId, TerminalEvent};
+pub use crate::terminal::scan::{Codes, ScanCodes, ScanLine, ScanReport};
+pub use crate::terminal::sessions::TerminalServer;
+pub use crate::{ErrorVariant, Tender, Terminal, TerminalEntityInterface, Undone, WithNewPricing};
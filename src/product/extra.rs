@@ -1,13 +1,45 @@
-use crate::prelude::{ErrorVariant, Product};
+use crate::prelude::{Code, ErrorVariant, Product};
+#[cfg(feature = "json")]
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::HashMap;
+#[cfg(feature = "json")]
+use std::convert::TryFrom;
+use std::fmt;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(try_from = "ProductAmountJson"))]
 pub struct ProductAmount {
     product: Product,
     amount: f64,
 }
 
+/// Product amount as written in JSON, checked before becoming a [ProductAmount]
+#[cfg(feature = "json")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProductAmountJson {
+    product: Product,
+    amount: f64,
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<ProductAmountJson> for ProductAmount {
+    type Error = String;
+
+    fn try_from(json: ProductAmountJson) -> Result<Self, String> {
+        if json.amount <= 0.0 {
+            return Err(format!(
+                "amount of product `{}` must be above 0, got {}",
+                json.product.get_code(),
+                json.amount
+            ));
+        }
+        Ok(ProductAmount::new(json.product, json.amount))
+    }
+}
+
 impl ProductAmount {
     pub fn new(product: Product, amount: f64) -> Self {
         ProductAmount { product, amount }
@@ -17,7 +49,7 @@ impl ProductAmount {
         &self.product
     }
 
-    pub fn get_code(&self) -> &String {
+    pub fn get_code(&self) -> &Code {
         self.product.get_code()
     }
 
@@ -31,7 +63,11 @@ impl ProductAmount {
 
     pub fn dec_amount(&mut self, amount: f64) -> Result<(), ErrorVariant> {
         if amount > self.amount {
-            Err(ErrorVariant::NotEnoughItems)
+            Err(ErrorVariant::NotEnoughItems {
+                code: self.get_code().to_string(),
+                requested: amount,
+                available: self.amount,
+            })
         } else {
             self.amount -= amount;
             Ok(())
@@ -46,9 +82,47 @@ impl ProductAmount {
         self.get_price() * self.amount
     }
 
+    /// Group different instances of ProductAmount into an optimal size vec
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let mut v = vec![];
+    ///
+    /// v.push(ProductAmount::new(Product::new("Foo".to_string(), 1.0), 15.0));
+    /// v.push(ProductAmount::new(Product::new("Bar".to_string(), 1.0), 35.0));
+    /// v.push(ProductAmount::new(Product::new("Foo".to_string(), 1.0), 4.0));
+    /// v.push(ProductAmount::new(Product::new("Foo".to_string(), 1.0), 12.0));
+    ///
+    /// let mut v_min = vec![];
+    ///
+    /// v_min.push(ProductAmount::new(Product::new("Foo".to_string(), 1.0), 31.0));
+    /// v_min.push(ProductAmount::new(Product::new("Bar".to_string(), 1.0), 35.0));
+    ///
+    /// assert_eq!(ProductAmount::group(v), v_min);
+    /// ```
+    pub fn group(queue: Vec<ProductAmount>) -> Vec<ProductAmount> {
+        let mut result: Vec<ProductAmount> = Vec::with_capacity(queue.len());
+        let mut indexes: HashMap<Code, usize> = HashMap::with_capacity(queue.len());
+
+        for item in queue.into_iter().rev() {
+            match indexes.get(item.get_code()) {
+                Some(&i) => result[i].inc_amount(*item.get_amount()),
+                None => {
+                    indexes.insert(item.get_code().clone(), result.len());
+                    result.push(item);
+                }
+            }
+        }
+
+        result
+    }
+
     pub fn get_index_of_product(
-        products: &Vec<ProductAmount>,
-        code: &String,
+        products: &[ProductAmount],
+        code: &str,
     ) -> Result<usize, ErrorVariant> {
         products
             .iter()
@@ -62,27 +136,34 @@ impl ProductAmount {
                     None
                 }
             })
-            .map(|p| Ok(p))
-            .unwrap_or(Err(ErrorVariant::ProductNotFound))
+            .ok_or_else(|| ErrorVariant::ProductNotFound {
+                code: code.to_string(),
+            })
+    }
+}
+
+/// Amount and product, such as `4 x A @ 2.00`
+impl fmt::Display for ProductAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} x {}", self.amount, self.product)
     }
 }
 
 impl Ord for ProductAmount {
     fn cmp(&self, other: &ProductAmount) -> Ordering {
-        match &self.partial_cmp(other) {
-            Some(c) => *c,
-            None => Ordering::Equal,
+        match self.get_product().cmp(other.get_product()) {
+            Ordering::Equal => self
+                .get_amount()
+                .partial_cmp(other.get_amount())
+                .unwrap_or(Ordering::Equal),
+            cmp => cmp,
         }
     }
 }
 
 impl PartialOrd for ProductAmount {
     fn partial_cmp(&self, other: &ProductAmount) -> Option<Ordering> {
-        let cmp = self.get_product().cmp(other.get_product());
-        match cmp {
-            Ordering::Equal => self.get_amount().partial_cmp(other.get_amount()),
-            _ => Some(cmp),
-        }
+        Some(self.cmp(other))
     }
 }
 
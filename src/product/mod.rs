@@ -1,18 +1,55 @@
+use crate::kiosk::Restriction;
+#[cfg(feature = "json")]
+use crate::prelude::TerminalEntityInterface;
 use crate::prelude::{
-    CartItem, CartItemVariant, ErrorVariant, ProductAmount, TerminalEntityInterface, WithNewPricing,
+    CartItem, CartItemVariant, Code, ErrorVariant, ItemId, ProductAmount, WithNewPricing,
 };
+#[cfg(feature = "json")]
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+#[cfg(feature = "json")]
+use std::convert::TryFrom;
 use std::fmt;
-use uuid::Uuid;
 
 pub mod extra;
 pub mod fut;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(try_from = "ProductJson"))]
 pub struct Product {
-    code: String,
+    code: Code,
     price: f64,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    tax_class: Option<String>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    restriction: Option<Restriction>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    name: Option<String>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    description: Option<String>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    category: Option<String>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    aliases: Vec<Code>,
 }
 
 impl Product {
@@ -30,11 +67,104 @@ impl Product {
     /// assert!(p1 != p2);
     /// assert!(p1 == p3);
     /// ```
-    pub fn new(code: String, price: f64) -> Self {
-        Product { code, price }
+    pub fn new<C: Into<Code>>(code: C, price: f64) -> Self {
+        let code = code.into();
+        Product {
+            code,
+            price,
+            tax_class: None,
+            restriction: None,
+            name: None,
+            description: None,
+            category: None,
+            aliases: vec![],
+        }
+    }
+
+    /// Class of the product in the [TaxTable](crate::prelude::TaxTable) of the terminal
+    pub fn with_tax_class(mut self, class: &str) -> Self {
+        self.tax_class = Some(class.to_string());
+        self
+    }
+
+    /// Restriction holding the cart at kiosks until approved by a supervisor
+    ///
+    /// See [TerminalConfig::with_kiosk_mode](crate::prelude::TerminalConfig::with_kiosk_mode)
+    pub fn with_restriction(mut self, restriction: Restriction) -> Self {
+        self.restriction = Some(restriction);
+        self
+    }
+
+    /// Name shown to customers, such as `Whole milk 1L`, on receipts and displays
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Longer text about the product, for catalogs and search
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
     }
 
-    pub fn get_code(&self) -> &String {
+    /// Category or department of the product, such as `dairy`, to build promotions and report
+    /// sales by
+    pub fn with_category(mut self, category: &str) -> Self {
+        self.category = Some(category.to_string());
+        self
+    }
+
+    /// Alternate barcodes of the product, such as the codes of other suppliers or pack sizes
+    ///
+    /// Lookups of the [Database](crate::prelude::Database) resolve an alias to the product, and
+    /// terminals scan it as the code of the product. Writing a product with an alias that is
+    /// the code or an alias of another product, or with a code that is the alias of another
+    /// product, fails with [ErrorVariant::ProductAlreadyExists](crate::prelude::ErrorVariant)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let milk = Product::new("7891000100103", 1.2)
+    ///     .with_name("Whole milk 1L")
+    ///     .with_category("dairy")
+    ///     .with_aliases(vec![Code::from("036000291452")]);
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal
+    ///     .load_catalog(vec![milk, Product::new("PEN", 0.8).with_category("office")], vec![])
+    ///     .unwrap();
+    ///
+    /// let database = terminal.get_db().unwrap();
+    /// assert_eq!(database.fetch_product("036000291452").unwrap().get_code(), "7891000100103");
+    ///
+    /// terminal.scan("036000291452 7891000100103 PEN").unwrap();
+    /// let cart = terminal.get_cart().unwrap();
+    /// assert_eq!(cart.get_items()[0].get_code(), "7891000100103");
+    /// assert_eq!(cart.get_items()[0].get_description(), "Whole milk 1L");
+    /// assert_eq!(cart.get_total_price(), 3.2);
+    ///
+    /// let dairy = database.find_products(&Filter::new().with_category("dairy")).unwrap();
+    /// assert_eq!(dairy.len(), 1);
+    ///
+    /// let taken = |code: &str| ErrorVariant::ProductAlreadyExists { code: code.to_string() };
+    /// let pencil = Product::new("PENCIL", 0.5).with_aliases(vec![Code::from("PEN")]);
+    /// let pen_twin = Product::new("PEN-2", 0.8).with_aliases(vec![Code::from("036000291452")]);
+    /// assert_eq!(terminal.change_catalog(|db| db.insert_new(pencil)).unwrap_err(), taken("PEN"));
+    /// assert_eq!(terminal.change_catalog(|db| db.upsert(pen_twin)).unwrap_err(), taken("036000291452"));
+    /// assert_eq!(
+    ///     terminal.load_catalog(vec![Product::new("036000291452", 1.0)], vec![]).unwrap_err(),
+    ///     taken("036000291452")
+    /// );
+    /// assert_eq!(terminal.get_db().unwrap().fetch_product("036000291452").unwrap().get_price(), &1.2);
+    /// ```
+    pub fn with_aliases(mut self, aliases: Vec<Code>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    pub fn get_code(&self) -> &Code {
         &self.code
     }
 
@@ -42,11 +172,100 @@ impl Product {
         &self.price
     }
 
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn get_description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn get_category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+
+    pub fn get_aliases(&self) -> &Vec<Code> {
+        &self.aliases
+    }
+
+    pub fn get_tax_class(&self) -> Option<&str> {
+        self.tax_class.as_deref()
+    }
+
+    pub fn get_restriction(&self) -> Option<Restriction> {
+        self.restriction
+    }
+
     pub fn generate_amount(&self, amount: f64) -> ProductAmount {
         ProductAmount::new(self.clone(), amount)
     }
 }
 
+/// Product as written in JSON, checked before becoming a [Product]
+#[cfg(feature = "json")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProductJson {
+    code: Code,
+    price: f64,
+    #[serde(default)]
+    tax_class: Option<String>,
+    #[serde(default)]
+    restriction: Option<Restriction>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    aliases: Vec<Code>,
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<ProductJson> for Product {
+    type Error = String;
+
+    fn try_from(json: ProductJson) -> Result<Self, String> {
+        if json.code.is_empty() {
+            return Err("product code must not be empty".to_string());
+        }
+        if json.price < 0.0 {
+            return Err(format!(
+                "price of product `{}` must not be negative, got {}",
+                json.code, json.price
+            ));
+        }
+        if let Some(alias) = json
+            .aliases
+            .iter()
+            .find(|alias| alias.is_empty() || **alias == json.code)
+        {
+            return Err(format!(
+                "alias `{}` of product `{}` must be another, non-empty code",
+                alias, json.code
+            ));
+        }
+        Ok(Product {
+            code: json.code,
+            price: json.price,
+            tax_class: json.tax_class,
+            restriction: json.restriction,
+            name: json.name,
+            description: json.description,
+            category: json.category,
+            aliases: json.aliases,
+        })
+    }
+}
+
+/// Code and unit price, such as `A @ 2.00`
+impl fmt::Display for Product {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} @ {:.2}", self.code, self.price)
+    }
+}
+
 impl Ord for Product {
     fn cmp(&self, other: &Product) -> Ordering {
         self.code.cmp(&other.code)
@@ -69,30 +288,62 @@ impl Eq for Product {}
 
 #[derive(Debug, Clone)]
 pub struct CartItemProduct {
-    id: Uuid,
+    id: ItemId,
     product_amount: ProductAmount,
 }
 
 impl CartItemProduct {
     pub fn new(product: Product, amount: f64) -> Self {
-        let product_amount = ProductAmount::new(product, amount);
-        let id = Uuid::new_v4();
+        CartItemProduct::with_id(ItemId::new(), product, amount)
+    }
 
+    pub(crate) fn with_id(id: ItemId, product: Product, amount: f64) -> Self {
+        let product_amount = ProductAmount::new(product, amount);
         CartItemProduct { id, product_amount }
     }
 }
 
+/// Code, amount, unit price and line total, such as `B: 2 x 12.00 = 24.00`
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let item = CartItemProduct::new(Product::new("B".to_string(), 12.0), 2.0);
+/// assert_eq!(item.to_string(), "B: 2 x 12.00 = 24.00");
+/// ```
 impl fmt::Display for CartItemProduct {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        write!(
+            f,
+            "{}: {} x {:.2} = {:.2}",
+            self.get_code(),
+            self.get_amount(),
+            self.get_unit_price(),
+            self.get_line_total()
+        )
     }
 }
 
 impl CartItem for CartItemProduct {
-    fn get_id(&self) -> &Uuid {
+    fn get_id(&self) -> &ItemId {
         &self.id
     }
 
+    fn get_code(&self) -> &Code {
+        self.product_amount.get_code()
+    }
+
+    /// Name of the product, if any
+    fn get_description(&self) -> String {
+        self.product_amount
+            .get_product()
+            .get_name()
+            .unwrap_or_default()
+            .to_string()
+    }
+
     fn get_products(&self) -> Vec<&ProductAmount> {
         vec![&self.product_amount]
     }
@@ -101,29 +352,65 @@ impl CartItem for CartItemProduct {
         *self.product_amount.get_amount()
     }
 
-    fn get_variant<'a>(&self) -> CartItemVariant {
-        CartItemVariant::Product(&self)
+    fn get_unit_price(&self) -> f64 {
+        *self.product_amount.get_price()
+    }
+
+    fn get_regular_unit_price(&self) -> f64 {
+        *self.product_amount.get_price()
+    }
+
+    fn get_variant(&self) -> CartItemVariant<'_> {
+        CartItemVariant::Product(self)
     }
 }
 
 impl WithNewPricing for Product {
+    fn get_code(&self) -> &Code {
+        Product::get_code(self)
+    }
+
+    fn get_price(&self) -> &f64 {
+        Product::get_price(self)
+    }
+
     fn with_new_pricing(&self, price: f64) -> Result<Self, ErrorVariant> {
-        let code = self.get_code().clone();
-        let product = Product::new(code, price);
+        let mut product = self.clone();
+        product.price = price;
         Ok(product)
     }
 }
 
+#[cfg(feature = "json")]
 impl TerminalEntityInterface for Product {
     fn get_syntax_example() -> &'static str {
-        r#"{code: "A", price: 15.3}"#
+        r#"{code: "A", price: 15.3, tax_class: "food"}"#
     }
 
+    /// Product of a JSON object of [get_syntax_example](Self::get_syntax_example), rejecting
+    /// unknown fields, empty codes and negative prices
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let product = Product::from_json(r#"{"code": "A", "price": 2.0}"#.to_string()).unwrap();
+    /// assert_eq!(product.get_price(), &2.0);
+    ///
+    /// let e = Product::from_json("{\n  \"code\": \"A\",\n  \"price\": -2.0\n}".to_string());
+    /// assert_eq!(
+    ///     e.unwrap_err().to_string(),
+    ///     "invalid JSON for Product at line 4, column 1: \
+    ///      price of product `A` must not be negative, got -2"
+    /// );
+    /// assert!(Product::from_json(r#"{"code": "", "price": 2.0}"#.to_string()).is_err());
+    /// ```
     fn from_json(json: String) -> Result<Self, ErrorVariant> {
-        serde_json::from_str::<Product>(json.as_str()).map_err(|_| ErrorVariant::JsonParseError)
+        crate::from_json_str(&json, "Product")
     }
 
     fn to_json(&self) -> Result<String, ErrorVariant> {
-        serde_json::to_string(&self).map_err(|_| ErrorVariant::JsonParseError)
+        serde_json::to_string(&self).map_err(|e| ErrorVariant::from_json_error(e, "Product"))
     }
 }
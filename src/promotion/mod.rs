@@ -1,35 +1,56 @@
+#[cfg(feature = "json")]
+use crate::prelude::TerminalEntityInterface;
 use crate::prelude::{
-    CartItem, CartItemVariant, ErrorVariant, ProductAmount, ProductAmountGroupFuture,
-    TerminalEntityInterface, WithNewPricing,
+    CartItem, CartItemVariant, Code, ErrorVariant, ItemId, ProductAmount, WithNewPricing,
 };
-use futures::prelude::*;
+#[cfg(feature = "json")]
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "json")]
+use std::convert::TryFrom;
 use std::fmt;
-use uuid::Uuid;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(try_from = "PromotionJson"))]
 pub struct Promotion {
-    code: String,
+    code: Code,
     products: Vec<ProductAmount>,
     price: f64,
+    /// Amount of each product, shared by the clones of the promotion
+    #[cfg_attr(feature = "json", serde(skip))]
+    amounts: Arc<HashMap<Code, f64>>,
 }
 
 impl Promotion {
-    pub fn new(
-        code: String,
+    pub fn new<C: Into<Code>>(
+        code: C,
         products: Vec<ProductAmount>,
         price: f64,
     ) -> Result<Self, ErrorVariant> {
-        let products = ProductAmountGroupFuture::new(products).wait()?;
-        let promotion = Promotion {
+        Ok(Promotion::grouped(code.into(), products, price))
+    }
+
+    /// Promotion of `products`, grouped by product
+    pub(crate) fn grouped(code: Code, products: Vec<ProductAmount>, price: f64) -> Self {
+        Promotion::with_products(code, ProductAmount::group(products), price)
+    }
+
+    fn with_products(code: Code, products: Vec<ProductAmount>, price: f64) -> Self {
+        let mut amounts = HashMap::with_capacity(products.len());
+        for p in &products {
+            *amounts.entry(p.get_code().clone()).or_insert(0.0) += p.get_amount();
+        }
+        Promotion {
             code,
             products,
             price,
-        };
-        Ok(promotion)
+            amounts: Arc::new(amounts),
+        }
     }
 
-    pub fn get_code(&self) -> &String {
+    pub fn get_code(&self) -> &Code {
         &self.code
     }
 
@@ -41,10 +62,37 @@ impl Promotion {
         &self.price
     }
 
+    /// Human readable list of the products that compose the promotion
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let products = vec![
+    ///     Product::new("A".to_string(), 2.0).generate_amount(4.0),
+    ///     Product::new("B".to_string(), 1.0).generate_amount(1.0),
+    /// ];
+    /// let promotion = Promotion::new("P".to_string(), products, 8.0).unwrap();
+    ///
+    /// assert_eq!(promotion.get_description(), "1 x B, 4 x A");
+    /// ```
+    pub fn get_description(&self) -> String {
+        self.products
+            .iter()
+            .map(|p| format!("{} x {}", p.get_amount(), p.get_code()))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
     /// Check if the current promotion is contained by a set of [ProductAmount](crate::prelude::ProductAmount)
     ///
     /// Will assume the argument is optimized by [CartGroupFuture](crate::prelude::CartGroupFuture)
     ///
+    /// Each product is looked up in the amounts of the promotion, computed once when it is
+    /// built, so the check is linear in the products given. A product listed several times in
+    /// the promotion, by [Promotion::new] or in JSON, is looked up by its total amount
+    ///
     /// # Example
     ///
     /// ```
@@ -57,60 +105,79 @@ impl Promotion {
     /// database.append(Product::new("C".to_string(), 100.0)).unwrap();
     ///
     /// let products = vec![
-    ///     database.code_to_product_amount("A".to_string(), 1.0).unwrap(),
-    ///     database.code_to_product_amount("A".to_string(), 1.0).unwrap(),
-    ///     database.code_to_product_amount("A".to_string(), 1.0).unwrap(),
-    ///     database.code_to_product_amount("B".to_string(), 1.0).unwrap(),
+    ///     database.code_to_product_amount("A", 1.0).unwrap(),
+    ///     database.code_to_product_amount("A", 1.0).unwrap(),
+    ///     database.code_to_product_amount("A", 1.0).unwrap(),
+    ///     database.code_to_product_amount("B", 1.0).unwrap(),
     /// ];
     /// let promotion = Promotion::new("P1".to_string(), products, 1.0).unwrap();
     /// database.append(promotion).unwrap();
     ///
     /// let test_amount = vec![
-    ///     database.fetch_product(&"A".to_string()).unwrap().generate_amount(2.0),
-    ///     database.fetch_product(&"B".to_string()).unwrap().generate_amount(2.0),
+    ///     database.fetch_product("A").unwrap().generate_amount(2.0),
+    ///     database.fetch_product("B").unwrap().generate_amount(2.0),
     /// ];
     /// let mut assert_array = vec![];
     /// for t in &test_amount {
     ///     assert_array.push(t);
     /// }
-    /// assert!(! database.fetch_promotion(&"P1".to_string()).unwrap().is_contained_by(&assert_array));
+    /// assert!(! database.fetch_promotion("P1").unwrap().is_contained_by(&assert_array));
     ///
     /// let test_amount = vec![
-    ///     database.fetch_product(&"A".to_string()).unwrap().generate_amount(3.0),
-    ///     database.fetch_product(&"B".to_string()).unwrap().generate_amount(2.0),
+    ///     database.fetch_product("A").unwrap().generate_amount(3.0),
+    ///     database.fetch_product("B").unwrap().generate_amount(2.0),
     /// ];
     /// let mut assert_array = vec![];
     /// for t in &test_amount {
     ///     assert_array.push(t);
     /// }
-    /// assert!(database.fetch_promotion(&"P1".to_string()).unwrap().is_contained_by(&assert_array));
+    /// assert!(database.fetch_promotion("P1").unwrap().is_contained_by(&assert_array));
     ///
     /// let test_amount = vec![
-    ///     database.fetch_product(&"A".to_string()).unwrap().generate_amount(4.0),
-    ///     database.fetch_product(&"B".to_string()).unwrap().generate_amount(2.0),
+    ///     database.fetch_product("A").unwrap().generate_amount(4.0),
+    ///     database.fetch_product("B").unwrap().generate_amount(2.0),
     /// ];
     /// let mut assert_array = vec![];
     /// for t in &test_amount {
     ///     assert_array.push(t);
     /// }
-    /// assert!(database.fetch_promotion(&"P1".to_string()).unwrap().is_contained_by(&assert_array));
+    /// assert!(database.fetch_promotion("P1").unwrap().is_contained_by(&assert_array));
+    /// ```
+    ///
+    /// A product listed twice in JSON is the same as its amount listed once:
+    ///
+    /// ```
+    /// # #[cfg(feature = "json")]
+    /// # fn main() {
+    /// use store_terminal::prelude::*;
+    ///
+    /// let a = r#"{"product": {"code": "A", "price": 2.0}, "amount": 1.0}"#;
+    /// let listed = format!(r#"{{"code": "P", "products": [{}, {}], "price": 3.0}}"#, a, a);
+    /// let listed = Promotion::from_json(listed).unwrap();
+    /// let grouped = r#"{"code": "P", "products": [{"product": {"code": "A", "price": 2.0}, "amount": 2.0}], "price": 3.0}"#;
+    /// let grouped = Promotion::from_json(grouped.to_string()).unwrap();
+    /// assert_eq!(listed.to_json().unwrap(), grouped.to_json().unwrap());
+    /// assert_eq!(listed.get_description(), "2 x A");
+    ///
+    /// let two = Product::new("A", 2.0).generate_amount(2.0);
+    /// let one = Product::new("A", 2.0).generate_amount(1.0);
+    /// assert!(listed.is_contained_by(&vec![&two]));
+    /// assert!(!listed.is_contained_by(&vec![&one]));
+    /// # }
+    /// # #[cfg(not(feature = "json"))]
+    /// # fn main() {}
     /// ```
     pub fn is_contained_by(&self, products: &Vec<&ProductAmount>) -> bool {
-        self.get_products()
-            .iter()
-            .fold(true, |is_contained, product| {
-                if !is_contained {
+        let mut contained = 0;
+        for product in products {
+            if let Some(amount) = self.amounts.get(product.get_code()) {
+                if amount > product.get_amount() {
                     return false;
                 }
-
-                for arg_prod in products {
-                    if product.get_code() == arg_prod.get_code() {
-                        return product.get_amount() <= arg_prod.get_amount();
-                    }
-                }
-
-                false
-            })
+                contained += 1;
+            }
+        }
+        contained == self.amounts.len()
     }
 
     pub fn consume_items(
@@ -127,11 +194,77 @@ impl Promotion {
         Ok(products
             .iter()
             .filter(|p| p.get_amount() > &0.0)
-            .map(|p| p.clone())
+            .cloned()
             .collect())
     }
 }
 
+/// Promotion as written in JSON, checked before becoming a [Promotion]
+#[cfg(feature = "json")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PromotionJson {
+    code: Code,
+    products: Vec<ProductAmount>,
+    price: f64,
+}
+
+#[cfg(feature = "json")]
+impl TryFrom<PromotionJson> for Promotion {
+    type Error = String;
+
+    fn try_from(json: PromotionJson) -> Result<Self, String> {
+        if json.code.is_empty() {
+            return Err("promotion code must not be empty".to_string());
+        }
+        if json.price < 0.0 {
+            return Err(format!(
+                "price of promotion `{}` must not be negative, got {}",
+                json.code, json.price
+            ));
+        }
+        if json.products.is_empty() {
+            return Err(format!("promotion `{}` has no products", json.code));
+        }
+        // Products listed more than once make up a single amount, as with Promotion::new
+        Ok(Promotion::grouped(json.code, json.products, json.price))
+    }
+}
+
+impl fmt::Debug for Promotion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Promotion")
+            .field("code", &self.code)
+            .field("products", &self.products)
+            .field("price", &self.price)
+            .finish()
+    }
+}
+
+/// Code and price, followed by the products of the promotion indented on their own lines
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let products = vec![Product::new("A".to_string(), 2.0).generate_amount(4.0)];
+/// let promotion = Promotion::new("PA".to_string(), products, 7.0).unwrap();
+///
+/// assert_eq!(promotion.to_string(), "PA @ 7.00\n  4 x A @ 2.00");
+/// ```
+impl fmt::Display for Promotion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} @ {:.2}", self.code, self.price)?;
+        write_products(f, &self.products)
+    }
+}
+
+/// Products of a promotion, one per indented line
+fn write_products(f: &mut fmt::Formatter, products: &[ProductAmount]) -> fmt::Result {
+    products.iter().try_for_each(|p| write!(f, "\n  {}", p))
+}
+
 impl PartialEq for Promotion {
     fn eq(&self, other: &Promotion) -> bool {
         self.get_code() == other.get_code()
@@ -142,15 +275,17 @@ impl Eq for Promotion {}
 
 #[derive(Debug, Clone)]
 pub struct CartItemPromotion {
-    id: Uuid,
+    id: ItemId,
     promotion: Promotion,
     amount: f64,
 }
 
 impl CartItemPromotion {
     pub fn new(promotion: Promotion, amount: f64) -> Self {
-        let id = Uuid::new_v4();
+        CartItemPromotion::with_id(ItemId::new(), promotion, amount)
+    }
 
+    pub(crate) fn with_id(id: ItemId, promotion: Promotion, amount: f64) -> Self {
         CartItemPromotion {
             id,
             promotion,
@@ -160,10 +295,18 @@ impl CartItemPromotion {
 }
 
 impl CartItem for CartItemPromotion {
-    fn get_id(&self) -> &Uuid {
+    fn get_id(&self) -> &ItemId {
         &self.id
     }
 
+    fn get_code(&self) -> &Code {
+        self.promotion.get_code()
+    }
+
+    fn get_description(&self) -> String {
+        self.promotion.get_description()
+    }
+
     fn get_products(&self) -> Vec<&ProductAmount> {
         self.promotion.get_products()
     }
@@ -172,22 +315,59 @@ impl CartItem for CartItemPromotion {
         self.amount
     }
 
-    fn get_price(&self) -> f64 {
+    fn get_unit_price(&self) -> f64 {
         *self.promotion.get_price()
     }
 
-    fn get_variant<'a>(&self) -> CartItemVariant {
-        CartItemVariant::Promotion(&self)
+    fn get_regular_unit_price(&self) -> f64 {
+        self.promotion
+            .products
+            .iter()
+            .fold(0.0, |price, p| price + p.get_total_price())
+    }
+
+    fn get_variant(&self) -> CartItemVariant<'_> {
+        CartItemVariant::Promotion(self)
     }
 }
 
+/// Code, amount, unit price and line total, followed by the products of one application of
+/// the promotion indented on their own lines
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let products = vec![Product::new("A".to_string(), 2.0).generate_amount(4.0)];
+/// let promotion = Promotion::new("PA".to_string(), products, 7.0).unwrap();
+/// let item = CartItemPromotion::new(promotion, 2.0);
+///
+/// assert_eq!(item.to_string(), "PA: 2 x 7.00 = 14.00\n  4 x A @ 2.00");
+/// ```
 impl fmt::Display for CartItemPromotion {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        write!(
+            f,
+            "{}: {} x {:.2} = {:.2}",
+            self.get_code(),
+            self.get_amount(),
+            self.get_unit_price(),
+            self.get_line_total()
+        )?;
+        write_products(f, &self.promotion.products)
     }
 }
 
 impl WithNewPricing for Promotion {
+    fn get_code(&self) -> &Code {
+        Promotion::get_code(self)
+    }
+
+    fn get_price(&self) -> &f64 {
+        Promotion::get_price(self)
+    }
+
     fn with_new_pricing(&self, price: f64) -> Result<Self, ErrorVariant> {
         let code = self.get_code().clone();
         let products = self.get_products().iter().map(|&p| p.clone()).collect();
@@ -196,16 +376,34 @@ impl WithNewPricing for Promotion {
     }
 }
 
+#[cfg(feature = "json")]
 impl TerminalEntityInterface for Promotion {
     fn get_syntax_example() -> &'static str {
         r#"{"code":"PA","products":[{"product":{"code":"A","price":2.0},"amount":4.0}],"price":7.0}"#
     }
 
+    /// Promotion of a JSON object of [get_syntax_example](Self::get_syntax_example), rejecting
+    /// unknown fields, empty codes, negative prices and amounts not above 0
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let promotion = Promotion::from_json(Promotion::get_syntax_example().to_string()).unwrap();
+    /// assert_eq!(promotion.get_price(), &7.0);
+    ///
+    /// let json = r#"{"code":"PA","products":[{"product":{"code":"A","price":2.0},"amount":0.0}],"price":7.0}"#;
+    /// assert_eq!(
+    ///     Promotion::from_json(json.to_string()).unwrap_err().to_string(),
+    ///     "invalid JSON for Promotion at line 1, column 75: amount of product `A` must be above 0, got 0"
+    /// );
+    /// ```
     fn from_json(json: String) -> Result<Self, ErrorVariant> {
-        serde_json::from_str::<Promotion>(json.as_str()).map_err(|_| ErrorVariant::JsonParseError)
+        crate::from_json_str(&json, "Promotion")
     }
 
     fn to_json(&self) -> Result<String, ErrorVariant> {
-        serde_json::to_string(&self).map_err(|_| ErrorVariant::JsonParseError)
+        serde_json::to_string(&self).map_err(|e| ErrorVariant::from_json_error(e, "Promotion"))
     }
 }
@@ -0,0 +1,197 @@
+use crate::prelude::Customer;
+use crate::receipt::{Receipt, ReceiptLine, ReceiptSection, Totals};
+
+/// Layout of an HTML receipt, for e-mail
+///
+/// Every part has a default rendering, so a template only overrides what it customizes. Text
+/// coming from the receipt must go through [escape_html]
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+/// use store_terminal::receipt::escape_html;
+///
+/// struct Branded;
+///
+/// impl HtmlTemplate for Branded {
+///     fn header(&self, header: &[String]) -> String {
+///         format!("<img src=\"logo.png\" alt=\"{}\">", escape_html(&header.join(" ")))
+///     }
+/// }
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AAAAB").unwrap();
+/// let receipt = Receipt::new(&terminal.get_cart().unwrap())
+///     .with_header(vec!["Fish & Chips".to_string()]);
+///
+/// let html = receipt.to_html(&DefaultHtmlTemplate);
+/// assert!(html.starts_with("<!DOCTYPE html>"));
+/// assert!(html.contains("<h1>Fish &amp; Chips</h1>"));
+/// assert!(html.contains("<td>PA (4 x A)</td>"));
+/// assert!(html.contains("<th>Total</th><td>19.00</td>"));
+///
+/// let html = receipt.to_html(&Branded);
+/// assert!(html.contains("<img src=\"logo.png\" alt=\"Fish &amp; Chips\">"));
+/// assert!(html.contains("<td>PA (4 x A)</td>"));
+/// ```
+pub trait HtmlTemplate {
+    /// Logo lines of the [ReceiptTemplate](crate::prelude::ReceiptTemplate), kept as they are
+    fn logo(&self, logo: &[String]) -> String {
+        if logo.is_empty() {
+            return String::new();
+        }
+        let lines: Vec<String> = logo.iter().map(|line| escape_html(line)).collect();
+        format!("<pre class=\"logo\">{}</pre>", lines.join("\n"))
+    }
+
+    /// Store name, as a title, followed by the other header lines
+    fn header(&self, header: &[String]) -> String {
+        let mut html = String::new();
+        if let Some((name, rest)) = header.split_first() {
+            html.push_str(&format!("<h1>{}</h1>", escape_html(name)));
+            for line in rest {
+                html.push_str(&format!("<p>{}</p>", escape_html(line)));
+            }
+        }
+        html
+    }
+
+    /// Name of the customer of the sale, shown below the header
+    fn customer(&self, customer: &Customer) -> String {
+        match customer.get_name() {
+            Some(name) => format!("<p class=\"customer\">Customer: {}</p>", escape_html(name)),
+            None => String::new(),
+        }
+    }
+
+    /// Table row of an item
+    fn line(&self, line: &ReceiptLine) -> String {
+        let label = if line.get_description().is_empty() {
+            line.get_code().to_string()
+        } else {
+            format!("{} ({})", line.get_code(), line.get_description())
+        };
+        let savings = if line.get_savings() > 0.0 {
+            format!("-{:.2}", line.get_savings())
+        } else {
+            String::new()
+        };
+        format!(
+            "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td><td>{:.2}</td></tr>",
+            escape_html(&label),
+            line.get_amount(),
+            line.get_unit_price(),
+            savings,
+            line.get_total()
+        )
+    }
+
+    /// Table of the item rows
+    fn lines(&self, rows: &str) -> String {
+        format!(
+            "<table class=\"items\"><tr><th>Item</th><th>Qty</th><th>Unit price</th>\
+             <th>Savings</th><th>Total</th></tr>{}</table>",
+            rows
+        )
+    }
+
+    fn totals(&self, totals: &Totals) -> String {
+        let mut html = String::from("<table class=\"totals\">");
+        if totals.get_savings() > 0.0 {
+            html.push_str(&format!(
+                "<tr><th>Subtotal</th><td>{:.2}</td></tr><tr><th>Savings</th><td>-{:.2}</td></tr>",
+                totals.get_regular_price(),
+                totals.get_savings()
+            ));
+        }
+        html.push_str(&format!(
+            "<tr><th>Total</th><td>{:.2}</td></tr>",
+            totals.get_total()
+        ));
+        for tax in totals.get_taxes() {
+            html.push_str(&format!(
+                "<tr><td>{} {:.2}% on {:.2}</td><td>{:.2}</td></tr>",
+                escape_html(tax.get_name()),
+                tax.get_rate() * 100.0,
+                tax.get_net(),
+                tax.get_tax()
+            ));
+        }
+        html.push_str("</table>");
+        html
+    }
+
+    /// Footer lines of the [ReceiptTemplate](crate::prelude::ReceiptTemplate)
+    fn footer(&self, footer: &[String]) -> String {
+        footer
+            .iter()
+            .map(|line| format!("<p class=\"footer\">{}</p>", escape_html(line)))
+            .collect()
+    }
+
+    /// Whole document around the rendered sections, in the order of the receipt template
+    fn document(&self, body: &str) -> String {
+        format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Receipt</title>\
+             <style>body{{font-family:sans-serif}}td,th{{padding:2px 8px;text-align:right}}\
+             td:first-child,th:first-child{{text-align:left}}</style></head>\
+             <body>{}</body></html>",
+            body
+        )
+    }
+}
+
+/// Built-in [HtmlTemplate], a plain table layout
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultHtmlTemplate;
+
+impl HtmlTemplate for DefaultHtmlTemplate {}
+
+impl Receipt {
+    /// HTML document of the receipt, laid out by `template` in the order of the sections of
+    /// its [ReceiptTemplate](crate::prelude::ReceiptTemplate)
+    pub fn to_html<T: HtmlTemplate + ?Sized>(&self, template: &T) -> String {
+        let receipt = self.get_template();
+        let body: String = receipt
+            .get_sections()
+            .iter()
+            .map(|section| match section {
+                ReceiptSection::Logo => template.logo(receipt.get_logo()),
+                ReceiptSection::Header => {
+                    let mut header = receipt.get_header().clone();
+                    header.extend(self.get_header().iter().cloned());
+                    template.header(&header)
+                }
+                ReceiptSection::Customer => self
+                    .get_customer()
+                    .map(|customer| template.customer(customer))
+                    .unwrap_or_default(),
+                ReceiptSection::Items => {
+                    let rows: String = self.get_lines().iter().map(|l| template.line(l)).collect();
+                    template.lines(&rows)
+                }
+                ReceiptSection::Totals => template.totals(self.get_totals()),
+                ReceiptSection::Footer => template.footer(receipt.get_footer()),
+            })
+            .collect();
+        template.document(&body)
+    }
+}
+
+/// Escape the characters with a meaning in HTML text and attributes
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,422 @@
+use crate::loyalty::LOYALTY_CODE;
+use crate::prelude::{Cart, Code, Customer, Sale};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+pub mod html;
+pub mod pdf;
+pub mod template;
+
+pub use self::html::{escape_html, DefaultHtmlTemplate, HtmlTemplate};
+pub use self::pdf::PaperFormat;
+pub use self::template::{ReceiptSection, ReceiptTemplate};
+
+/// Item of a [Receipt]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ReceiptLine {
+    code: Code,
+    description: String,
+    amount: f64,
+    unit_price: f64,
+    total: f64,
+    savings: f64,
+}
+
+impl ReceiptLine {
+    pub(crate) fn new(
+        code: Code,
+        description: String,
+        amount: f64,
+        unit_price: f64,
+        total: f64,
+        savings: f64,
+    ) -> Self {
+        ReceiptLine {
+            code,
+            description,
+            amount,
+            unit_price,
+            total,
+            savings,
+        }
+    }
+
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    pub fn get_description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn get_amount(&self) -> f64 {
+        self.amount
+    }
+
+    pub fn get_unit_price(&self) -> f64 {
+        self.unit_price
+    }
+
+    pub fn get_total(&self) -> f64 {
+        self.total
+    }
+
+    /// Savings of a promotion over the regular price of its products, zero for products
+    pub fn get_savings(&self) -> f64 {
+        self.savings
+    }
+}
+
+/// Tax included in the total of a [Receipt]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct TaxLine {
+    #[cfg_attr(feature = "json", serde(default))]
+    class: Option<String>,
+    name: String,
+    rate: f64,
+    net: f64,
+    tax: f64,
+}
+
+impl TaxLine {
+    /// Tax included in `gross` at `rate`, for the products of `class`
+    pub(crate) fn new(class: Option<String>, name: &str, rate: f64, gross: f64) -> Self {
+        let tax = round_cents(gross * rate / (1.0 + rate));
+        TaxLine {
+            class,
+            name: name.to_string(),
+            rate,
+            net: round_cents(gross - tax),
+            tax,
+        }
+    }
+
+    /// Tax class of the taxed products, `None` for the standard rate
+    pub fn get_class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Rate of the tax, `0.2` for 20%
+    pub fn get_rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Taxed amount, without the tax
+    pub fn get_net(&self) -> f64 {
+        self.net
+    }
+
+    pub fn get_tax(&self) -> f64 {
+        self.tax
+    }
+}
+
+/// Totals of a [Receipt]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Totals {
+    regular_price: f64,
+    savings: f64,
+    total: f64,
+    taxes: Vec<TaxLine>,
+}
+
+impl Totals {
+    pub(crate) fn new(regular_price: f64, savings: f64, total: f64) -> Self {
+        Totals {
+            regular_price,
+            savings,
+            total,
+            taxes: vec![],
+        }
+    }
+
+    pub(crate) fn with_taxes(mut self, taxes: Vec<TaxLine>) -> Self {
+        self.taxes = taxes;
+        self
+    }
+
+    /// Price of the items without any promotion
+    pub fn get_regular_price(&self) -> f64 {
+        self.regular_price
+    }
+
+    pub fn get_savings(&self) -> f64 {
+        self.savings
+    }
+
+    /// Amount due, taxes included
+    pub fn get_total(&self) -> f64 {
+        self.total
+    }
+
+    pub fn get_taxes(&self) -> &Vec<TaxLine> {
+        &self.taxes
+    }
+
+    pub fn get_total_tax(&self) -> f64 {
+        round_cents(self.taxes.iter().fold(0.0, |t, tax| t + tax.tax))
+    }
+}
+
+/// Itemized record of a sale, rendered for printers, PDF documents and HTML e-mails
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AAAAB").unwrap();
+///
+/// let receipt = Receipt::new(&terminal.get_cart().unwrap())
+///     .with_header(vec!["Corner Store".to_string(), "1 Main St.".to_string()])
+///     .with_tax("VAT", 0.25);
+///
+/// assert_eq!(receipt.get_lines().len(), 2);
+/// assert_eq!(receipt.get_lines()[1].get_code(), "PA");
+/// assert_eq!(receipt.get_lines()[1].get_savings(), 1.0);
+///
+/// let totals = receipt.get_totals();
+/// assert_eq!(totals.get_regular_price(), 20.0);
+/// assert_eq!(totals.get_total(), 19.0);
+/// assert_eq!(totals.get_total_tax(), 3.8);
+///
+/// let text = receipt.render_text(32);
+/// assert_eq!(text[0], "          Corner Store");
+/// assert_eq!(text.last().unwrap(), "VAT 25.00% on 15.20         3.80");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Receipt {
+    header: Vec<String>,
+    customer: Option<Customer>,
+    lines: Vec<ReceiptLine>,
+    totals: Totals,
+    template: ReceiptTemplate,
+}
+
+impl Receipt {
+    /// Receipt of the items of the cart, usually after [Cart::optimize_promotions]
+    pub fn new(cart: &Cart) -> Self {
+        let report = cart.get_savings_report();
+
+        let mut lines: Vec<ReceiptLine> = cart
+            .get_items()
+            .iter()
+            .map(|item| ReceiptLine {
+                code: item.get_code().clone(),
+                description: item.get_description(),
+                amount: item.get_amount(),
+                unit_price: item.get_unit_price(),
+                total: item.get_line_total(),
+                savings: item.get_discount(),
+            })
+            .collect();
+        if let Some(redemption) = cart.get_loyalty_redemption() {
+            let discount = report.get_discount();
+            lines.push(ReceiptLine {
+                code: Code::from(LOYALTY_CODE),
+                description: "Loyalty points".to_string(),
+                amount: redemption.get_points() as f64,
+                unit_price: -discount / redemption.get_points() as f64,
+                total: -discount,
+                savings: 0.0,
+            });
+        }
+
+        let totals = Totals::new(
+            report.get_regular_price(),
+            report.get_total_savings(),
+            report.get_total_price(),
+        );
+
+        Receipt {
+            header: vec![],
+            customer: cart.get_customer().cloned(),
+            lines,
+            totals,
+            template: ReceiptTemplate::new(),
+        }
+    }
+
+    /// Receipt of a sale closed by [Terminal::checkout](crate::prelude::Terminal::checkout)
+    pub fn from_sale(sale: &Sale) -> Self {
+        Receipt {
+            header: vec![],
+            customer: sale.get_customer().cloned(),
+            lines: sale.get_lines().clone(),
+            totals: sale.get_totals().clone(),
+            template: ReceiptTemplate::new(),
+        }
+    }
+
+    /// Store name, address and other lines printed above the items
+    pub fn with_header(mut self, header: Vec<String>) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Branding and layout of the store, the built-in layout unless set
+    pub fn with_template(mut self, template: ReceiptTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Tax included in the prices at `rate`, `0.2` for 20%, replacing any previous tax
+    ///
+    /// Receipts of tax exempt customers carry no tax
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::customer::Identification;
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// let customer = Customer::new("M-1042", Identification::Phone)
+    ///     .with_name("Jane Doe")
+    ///     .with_tax_exemption(true);
+    /// terminal.set_customer(customer).unwrap();
+    /// terminal.scan("AB").unwrap();
+    ///
+    /// let receipt = Receipt::from_sale(&terminal.checkout().unwrap()).with_tax("VAT", 0.25);
+    /// assert_eq!(receipt.get_customer().unwrap().get_name(), Some("Jane Doe"));
+    /// assert_eq!(receipt.get_totals().get_total_tax(), 0.0);
+    ///
+    /// let text = receipt.render_text(32);
+    /// assert_eq!(text[0], "Customer: Jane Doe");
+    /// assert_eq!(text.last().unwrap(), "Tax exempt");
+    /// ```
+    pub fn with_tax(mut self, name: &str, rate: f64) -> Self {
+        if self.is_tax_exempt() {
+            self.totals.taxes = vec![];
+            return self;
+        }
+        self.totals.taxes = vec![TaxLine::new(None, name, rate, self.totals.total)];
+        self
+    }
+
+    pub fn get_header(&self) -> &Vec<String> {
+        &self.header
+    }
+
+    /// Customer of the sale, named on the receipt when its account has a name
+    pub fn get_customer(&self) -> Option<&Customer> {
+        self.customer.as_ref()
+    }
+
+    pub fn get_template(&self) -> &ReceiptTemplate {
+        &self.template
+    }
+
+    pub fn get_lines(&self) -> &Vec<ReceiptLine> {
+        &self.lines
+    }
+
+    pub fn get_totals(&self) -> &Totals {
+        &self.totals
+    }
+
+    fn is_tax_exempt(&self) -> bool {
+        self.customer.as_ref().is_some_and(Customer::is_tax_exempt)
+    }
+
+    /// Plain text lines of at most `width` characters, as printed on a till roll, laid out by
+    /// the [ReceiptTemplate] of the receipt
+    pub fn render_text(&self, width: usize) -> Vec<String> {
+        let mut text: Vec<String> = vec![];
+        let mut previous: Option<ReceiptSection> = None;
+
+        for &section in self.template.get_sections() {
+            let lines = self.render_section(section, width);
+            if lines.is_empty() {
+                continue;
+            }
+            if previous.is_some_and(|p| p.is_text() || section.is_text()) {
+                text.push(String::new());
+            }
+            text.extend(lines);
+            previous = Some(section);
+        }
+
+        text
+    }
+
+    fn render_section(&self, section: ReceiptSection, width: usize) -> Vec<String> {
+        let centered = |lines: &[String]| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| {
+                    format!("{:^width$}", line, width = width)
+                        .trim_end()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        match section {
+            ReceiptSection::Logo => centered(self.template.get_logo()),
+            ReceiptSection::Header => {
+                let mut header = centered(self.template.get_header());
+                header.extend(centered(&self.header));
+                header
+            }
+            ReceiptSection::Customer => self
+                .customer
+                .as_ref()
+                .and_then(|c| c.get_name())
+                .map(|name| vec![format!("Customer: {}", name)])
+                .unwrap_or_default(),
+            ReceiptSection::Items => self
+                .lines
+                .iter()
+                .flat_map(|line| self.template.render_item(line, width))
+                .collect(),
+            ReceiptSection::Totals => self.render_totals(width),
+            ReceiptSection::Footer => centered(self.template.get_footer()),
+        }
+    }
+
+    fn render_totals(&self, width: usize) -> Vec<String> {
+        let totals = &self.totals;
+        let mut text = vec!["-".repeat(width)];
+        if totals.savings > 0.0 {
+            let regular_price = format!("{:.2}", totals.regular_price);
+            text.push(columns("Subtotal", &regular_price, width));
+            text.push(columns(
+                "Savings",
+                &format!("-{:.2}", totals.savings),
+                width,
+            ));
+        }
+        text.push(columns("TOTAL", &format!("{:.2}", totals.total), width));
+        for tax in &totals.taxes {
+            let label = format!("{} {:.2}% on {:.2}", tax.name, tax.rate * 100.0, tax.net);
+            text.push(columns(&label, &format!("{:.2}", tax.tax), width));
+        }
+        if self.is_tax_exempt() {
+            text.push("Tax exempt".to_string());
+        }
+
+        text
+    }
+}
+
+/// `left` and `right` at both ends of a line of `width` characters, truncating `left`
+pub(crate) fn columns(left: &str, right: &str, width: usize) -> String {
+    let room = width.saturating_sub(right.chars().count() + 1);
+    let left: String = left.chars().take(room).collect();
+    format!("{:<room$} {}", left, right, room = room)
+}
+
+pub(crate) fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
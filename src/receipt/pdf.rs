@@ -0,0 +1,190 @@
+use crate::receipt::Receipt;
+use std::io::Write;
+
+/// Font size of the receipt text, in points
+const FONT_SIZE: f64 = 9.0;
+/// Distance between the baselines of two lines, in points
+const LEADING: f64 = 11.0;
+/// Width of a Courier character, in points
+const CHAR_WIDTH: f64 = FONT_SIZE * 0.6;
+
+/// Page layout of a PDF receipt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaperFormat {
+    /// ISO A4 pages, for invoices
+    A4,
+    /// Single page of an 80mm till roll, as long as the receipt
+    Roll80mm,
+}
+
+impl PaperFormat {
+    fn get_width(&self) -> f64 {
+        match self {
+            PaperFormat::A4 => 595.0,
+            PaperFormat::Roll80mm => 226.8,
+        }
+    }
+
+    fn get_margin(&self) -> f64 {
+        match self {
+            PaperFormat::A4 => 56.0,
+            PaperFormat::Roll80mm => 10.0,
+        }
+    }
+
+    /// Characters per line
+    fn get_columns(&self) -> usize {
+        ((self.get_width() - 2.0 * self.get_margin()) / CHAR_WIDTH) as usize
+    }
+
+    /// Lines per page, or `None` if the page grows with the receipt
+    fn get_rows(&self) -> Option<usize> {
+        match self {
+            PaperFormat::A4 => Some(((842.0 - 2.0 * self.get_margin()) / LEADING) as usize),
+            PaperFormat::Roll80mm => None,
+        }
+    }
+}
+
+impl Receipt {
+    /// PDF document of the receipt, in a monospaced font
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AAAAB").unwrap();
+    ///
+    /// let receipt = Receipt::new(&terminal.get_cart().unwrap())
+    ///     .with_header(vec!["Corner Store".to_string()])
+    ///     .with_tax("VAT", 0.25);
+    ///
+    /// for format in [PaperFormat::A4, PaperFormat::Roll80mm] {
+    ///     let pdf = receipt.to_pdf(format);
+    ///     let text = String::from_utf8_lossy(&pdf);
+    ///
+    ///     assert!(pdf.starts_with(b"%PDF-1.4"));
+    ///     assert!(pdf.ends_with(b"%%EOF\n"));
+    ///     assert!(text.contains("Corner Store) Tj"));
+    ///     assert!(text.contains("/Count 1"));
+    /// }
+    /// ```
+    pub fn to_pdf(&self, format: PaperFormat) -> Vec<u8> {
+        let lines = self.render_text(format.get_columns());
+        let pages: Vec<&[String]> = match format.get_rows() {
+            Some(rows) if !lines.is_empty() => lines.chunks(rows).collect(),
+            _ => vec![&lines[..]],
+        };
+        let height = match format {
+            PaperFormat::A4 => 842.0,
+            PaperFormat::Roll80mm => 2.0 * format.get_margin() + LEADING * lines.len() as f64,
+        };
+
+        let mut pdf = PdfWriter::default();
+        // Objects 1 to 3 are the catalog, the page tree and the font; each page then takes an
+        // object for itself and another for its contents
+        let kids: Vec<String> = (0..pages.len())
+            .map(|i| format!("{} 0 R", 4 + 2 * i))
+            .collect();
+        pdf.object(b"<< /Type /Catalog /Pages 2 0 R >>");
+        pdf.object(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                pages.len()
+            )
+            .as_bytes(),
+        );
+        pdf.object(
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>",
+        );
+
+        for (i, page) in pages.iter().enumerate() {
+            let contents = 5 + 2 * i;
+            pdf.object(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                    format.get_width(),
+                    height,
+                    contents
+                )
+                .as_bytes(),
+            );
+
+            let mut stream = format!(
+                "BT /F1 {} Tf {} TL {} {} Td\n",
+                FONT_SIZE,
+                LEADING,
+                format.get_margin(),
+                height - format.get_margin() - FONT_SIZE
+            )
+            .into_bytes();
+            for line in page.iter() {
+                stream.push(b'(');
+                stream.extend(escape(line));
+                stream.extend_from_slice(b") Tj T*\n");
+            }
+            stream.extend_from_slice(b"ET");
+
+            let mut object = format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+            object.extend(stream);
+            object.extend_from_slice(b"\nendstream");
+            pdf.object(&object);
+        }
+
+        pdf.finish()
+    }
+}
+
+/// Text of a string literal, in the Windows-1252 encoding of the font
+fn escape(line: &str) -> Vec<u8> {
+    let mut escaped = vec![];
+    for c in line.chars() {
+        match c {
+            '(' | ')' | '\\' => escaped.extend_from_slice(&[b'\\', c as u8]),
+            '€' => escaped.push(0x80),
+            c if (c as u32) < 0x80 || (0xa0..=0xff).contains(&(c as u32)) => escaped.push(c as u8),
+            _ => escaped.push(b'?'),
+        }
+    }
+    escaped
+}
+
+/// Serializer of numbered objects and their cross-reference table
+#[derive(Default)]
+struct PdfWriter {
+    buffer: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl PdfWriter {
+    fn object(&mut self, body: &[u8]) {
+        if self.buffer.is_empty() {
+            self.buffer.extend_from_slice(b"%PDF-1.4\n");
+        }
+        self.offsets.push(self.buffer.len());
+        // Writing into a vector can't fail
+        let _ = writeln!(self.buffer, "{} 0 obj", self.offsets.len());
+        self.buffer.extend_from_slice(body);
+        self.buffer.extend_from_slice(b"\nendobj\n");
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let xref = self.buffer.len();
+        let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            trailer.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        trailer.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            xref
+        ));
+        self.buffer.extend_from_slice(trailer.as_bytes());
+        self.buffer
+    }
+}
@@ -0,0 +1,243 @@
+#[cfg(feature = "json")]
+use crate::prelude::ErrorVariant;
+use crate::receipt::{columns, ReceiptLine};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "json")]
+use std::fs;
+#[cfg(feature = "json")]
+use std::path::Path;
+
+/// Part of a receipt, laid out in the order of [ReceiptTemplate::with_sections]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(rename_all = "lowercase"))]
+pub enum ReceiptSection {
+    Logo,
+    Header,
+    Customer,
+    Items,
+    Totals,
+    Footer,
+}
+
+impl ReceiptSection {
+    /// Whether the section is free text, set apart from its neighbours by a blank line on till
+    /// rolls
+    pub(crate) fn is_text(self) -> bool {
+        !matches!(self, ReceiptSection::Items | ReceiptSection::Totals)
+    }
+}
+
+/// Branding and layout of the receipts of a store, shared by the text, PDF and HTML renderers
+///
+/// Each item is printed on the lines of its format, in which `{code}`, `{description}`,
+/// `{label}` (the code followed by the description in parentheses), `{amount}`,
+/// `{unit_price}`, `{total}` and `{savings}` are replaced by the values of the item. Text after
+/// a `|` is aligned to the right of the roll, and lines with `{savings}` are left out for items
+/// without savings. HTML receipts lay their items out with
+/// [HtmlTemplate::line](crate::prelude::HtmlTemplate::line) instead
+///
+/// With the `json` feature, a template is read from a file such as
+///
+/// ```json
+/// {
+///     "logo": ["*** CORNER ***"],
+///     "header": ["Corner Store", "1 Main St."],
+///     "footer": ["Thank you!"],
+///     "sections": ["logo", "header", "customer", "items", "totals", "footer"],
+///     "item_format": ["{code} {amount} x {unit_price}|{total}"]
+/// }
+/// ```
+///
+/// in which every field is optional
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("AAAAB").unwrap();
+///
+/// let template = ReceiptTemplate::new()
+///     .with_logo(vec!["*** CORNER ***".to_string()])
+///     .with_header(vec!["Corner Store".to_string()])
+///     .with_footer(vec!["Thank you!".to_string()])
+///     .with_item_format(vec!["{code} {amount} x {unit_price}|{total}".to_string()]);
+/// let receipt = Receipt::new(&terminal.get_cart().unwrap()).with_template(template);
+///
+/// let expected = vec![
+///     "         *** CORNER ***",
+///     "",
+///     "          Corner Store",
+///     "",
+///     "B 1 x 12.00                12.00",
+///     "PA 1 x 7.00                 7.00",
+///     "--------------------------------",
+///     "Subtotal                   20.00",
+///     "Savings                    -1.00",
+///     "TOTAL                      19.00",
+///     "",
+///     "           Thank you!",
+/// ];
+/// assert_eq!(receipt.render_text(32), expected);
+///
+/// let html = receipt.to_html(&DefaultHtmlTemplate);
+/// assert!(html.contains("<pre class=\"logo\">*** CORNER ***</pre><h1>Corner Store</h1>"));
+/// assert!(html.contains("<p class=\"footer\">Thank you!</p></body>"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(default, deny_unknown_fields))]
+pub struct ReceiptTemplate {
+    logo: Vec<String>,
+    header: Vec<String>,
+    footer: Vec<String>,
+    sections: Vec<ReceiptSection>,
+    item_format: Vec<String>,
+}
+
+impl ReceiptTemplate {
+    /// Template of the built-in layout, without logo, header nor footer
+    pub fn new() -> Self {
+        ReceiptTemplate {
+            logo: vec![],
+            header: vec![],
+            footer: vec![],
+            sections: vec![
+                ReceiptSection::Logo,
+                ReceiptSection::Header,
+                ReceiptSection::Customer,
+                ReceiptSection::Items,
+                ReceiptSection::Totals,
+                ReceiptSection::Footer,
+            ],
+            item_format: vec![
+                "{label}|{total}".to_string(),
+                "  {amount} x {unit_price}".to_string(),
+                "  Savings|-{savings}".to_string(),
+            ],
+        }
+    }
+
+    /// Read a template from a JSON file, the missing fields taken from [ReceiptTemplate::new]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let path = std::env::temp_dir().join(format!("receipt-{}.json", std::process::id()));
+    /// std::fs::write(&path, r#"{"footer": ["Thank you!"], "sections": ["items", "footer"]}"#).unwrap();
+    ///
+    /// let template = ReceiptTemplate::read(&path).unwrap();
+    /// assert_eq!(template.get_sections(), &vec![ReceiptSection::Items, ReceiptSection::Footer]);
+    /// assert_eq!(template.get_item_format(), ReceiptTemplate::new().get_item_format());
+    ///
+    /// std::fs::write(&path, r#"{"colour": "red"}"#).unwrap();
+    /// assert!(ReceiptTemplate::read(&path).is_err());
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn read(path: &Path) -> Result<Self, ErrorVariant> {
+        let contents = fs::read_to_string(path).map_err(|e| ErrorVariant::IoError {
+            path: path.display().to_string(),
+            source: e.to_string(),
+        })?;
+        crate::from_json_str(&contents, "receipt template")
+    }
+
+    /// Lines drawn at the top of the receipt, such as ASCII art, centered as they are
+    pub fn with_logo(mut self, logo: Vec<String>) -> Self {
+        self.logo = logo;
+        self
+    }
+
+    /// Store name, address and other lines printed above the header of the receipt
+    pub fn with_header(mut self, header: Vec<String>) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Lines printed at the end of the receipt, such as a return policy
+    pub fn with_footer(mut self, footer: Vec<String>) -> Self {
+        self.footer = footer;
+        self
+    }
+
+    /// Order of the sections of the receipt; sections left out are not printed
+    pub fn with_sections(mut self, sections: Vec<ReceiptSection>) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    /// Lines printed for each item of the receipt
+    pub fn with_item_format(mut self, item_format: Vec<String>) -> Self {
+        self.item_format = item_format;
+        self
+    }
+
+    pub fn get_logo(&self) -> &Vec<String> {
+        &self.logo
+    }
+
+    pub fn get_header(&self) -> &Vec<String> {
+        &self.header
+    }
+
+    pub fn get_footer(&self) -> &Vec<String> {
+        &self.footer
+    }
+
+    pub fn get_sections(&self) -> &Vec<ReceiptSection> {
+        &self.sections
+    }
+
+    pub fn get_item_format(&self) -> &Vec<String> {
+        &self.item_format
+    }
+
+    /// Lines of `line` in the item format, on a roll of `width` characters
+    pub(crate) fn render_item(&self, line: &ReceiptLine, width: usize) -> Vec<String> {
+        let label = if line.description.is_empty() {
+            line.code.to_string()
+        } else {
+            format!("{} ({})", line.code, line.description)
+        };
+        let fields = [
+            ("{code}", line.code.to_string()),
+            ("{description}", line.description.clone()),
+            ("{label}", label),
+            ("{amount}", line.amount.to_string()),
+            ("{unit_price}", format!("{:.2}", line.unit_price)),
+            ("{total}", format!("{:.2}", line.total)),
+            ("{savings}", format!("{:.2}", line.savings)),
+        ];
+
+        self.item_format
+            .iter()
+            .filter(|format| line.savings > 0.0 || !format.contains("{savings}"))
+            .map(|format| {
+                let fill = |text: &str| {
+                    fields
+                        .iter()
+                        .fold(text.to_string(), |text, (field, value)| {
+                            text.replace(field, value)
+                        })
+                };
+                match format.split_once('|') {
+                    Some((left, right)) => columns(&fill(left), &fill(right), width),
+                    None => fill(format),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for ReceiptTemplate {
+    fn default() -> Self {
+        ReceiptTemplate::new()
+    }
+}
@@ -0,0 +1,623 @@
+use crate::lock;
+use crate::prelude::{
+    ErrorVariant, Sale, SalesReport, Shift, ShiftReport, TaxReport, Tender, TenderAmount,
+};
+use crate::receipt::{columns, round_cents};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "json")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "json")]
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Entry of the file of a [SalesHistory]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(rename_all = "snake_case"))]
+enum Record {
+    Sale(Box<Sale>),
+    /// Day close `number`, covering the sales up to `last_sale`
+    DayClosed {
+        number: u64,
+        last_sale: u64,
+    },
+    ShiftOpened(Shift),
+    /// Close of the shift open on the terminal of the report, with its report
+    ShiftClosed(ShiftReport),
+}
+
+#[derive(Debug, Default)]
+struct History {
+    sales: Vec<Sale>,
+    /// Number of the last day close
+    closes: u64,
+    /// Number of the last sale covered by a day close
+    closed_sale: u64,
+    /// Number of the last shift opened
+    shifts: u64,
+    /// Shifts open, at most one per terminal
+    open_shifts: Vec<Shift>,
+    shift_reports: Vec<ShiftReport>,
+    #[cfg(feature = "json")]
+    file: Option<(String, File)>,
+}
+
+impl History {
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::Sale(sale) => self.sales.push(*sale),
+            Record::DayClosed { number, last_sale } => {
+                self.closes = number;
+                self.closed_sale = last_sale;
+            }
+            Record::ShiftOpened(shift) => {
+                self.shifts = shift.get_number();
+                self.open_shifts.push(shift);
+            }
+            Record::ShiftClosed(report) => {
+                let terminal = report.get_shift().get_terminal();
+                self.open_shifts
+                    .retain(|shift| shift.get_terminal() != terminal);
+                self.shift_reports.push(report);
+            }
+        }
+    }
+
+    /// Write the record to the file of the history, if any, then apply it; a record failing
+    /// to be written is not applied
+    fn append(&mut self, record: Record) -> Result<(), ErrorVariant> {
+        #[cfg(feature = "json")]
+        if let Some((path, file)) = &mut self.file {
+            serde_json::to_string(&record)
+                .map_err(|e| e.to_string())
+                .and_then(|line| {
+                    writeln!(file, "{}", line)
+                        .and_then(|()| file.sync_data())
+                        .map_err(|e| e.to_string())
+                })
+                .map_err(|source| ErrorVariant::IoError {
+                    path: path.clone(),
+                    source,
+                })?;
+        }
+
+        self.apply(record);
+        Ok(())
+    }
+
+    fn next_id(&self) -> u64 {
+        self.sales.last().map_or(1, |sale| sale.get_id() + 1)
+    }
+
+    fn shift(&self, terminal: &str) -> Option<&Shift> {
+        self.open_shifts
+            .iter()
+            .find(|shift| shift.get_terminal() == Some(terminal))
+    }
+}
+
+/// Completed sales and refunds of a terminal, with the day closes of its Z-reports and its
+/// shifts
+///
+/// Histories opened on a file keep the sales across restarts, appending one JSON document
+/// per sale, day close, or shift opening or close. A sale failing to be written is not kept,
+/// and the checkout fails with the cart left as it was. Clones share the sales, so lanes given
+/// the same history number their sales store-wide and refund each other's sales
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// terminal.scan("AAAAB").unwrap();
+/// terminal.checkout_with_tender(Tender::Card).unwrap();
+/// terminal.scan("AB").unwrap();
+/// let tenders = [TenderAmount::new(Tender::Cash, 20.0)];
+/// let sale = terminal.checkout_with_tenders(&tenders).unwrap();
+/// terminal.refund(1, &[("B", 1.0)]).unwrap();
+///
+/// let history = terminal.get_sales_history();
+/// let date = SalesHistory::date(sale.get_timestamp());
+/// let report = history.z_report(&date);
+/// assert_eq!(report.get_number(), 1);
+/// assert_eq!((report.get_sales(), report.get_refunds()), (2, 1));
+/// assert_eq!(report.get_gross(), 22.0);
+/// assert_eq!(report.get_discounts(), 1.0);
+/// assert_eq!(report.get_total(), 21.0);
+/// assert_eq!(
+///     report.get_tenders(),
+///     &vec![TenderAmount::new(Tender::Cash, 14.0), TenderAmount::new(Tender::Card, 7.0)]
+/// );
+///
+/// assert_eq!(history.close_day(&date).unwrap(), report);
+/// let next = history.z_report(&date);
+/// assert_eq!((next.get_number(), next.get_sales(), next.get_total()), (2, 0, 0.0));
+/// assert_eq!(history.get_sales().len(), 3);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SalesHistory {
+    history: Arc<Mutex<History>>,
+}
+
+impl SalesHistory {
+    /// History kept in memory only
+    pub fn new() -> Self {
+        SalesHistory::default()
+    }
+
+    /// History stored in the file at `path`, created if missing, restoring its sales
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let path = std::env::temp_dir().join(format!("sales-{}.jsonl", std::process::id()));
+    /// let path = path.to_str().unwrap();
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_sales_history(SalesHistory::open(path).unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AB").unwrap();
+    /// let sale = terminal.checkout_with_tender(Tender::Cash).unwrap();
+    /// terminal.get_sales_history().close_day("1970-01-01").unwrap();
+    ///
+    /// let restored = SalesHistory::open(path).unwrap();
+    /// assert_eq!(restored.fetch(1).unwrap(), sale);
+    /// assert_eq!(restored.z_report("1970-01-01").get_number(), 2);
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn open(path: &str) -> Result<Self, ErrorVariant> {
+        let io_error = |e: std::io::Error| ErrorVariant::IoError {
+            path: path.to_string(),
+            source: e.to_string(),
+        };
+
+        let mut history = History::default();
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                    let record = serde_json::from_str(line)
+                        .map_err(|e| ErrorVariant::from_json_error(e, "sales history"))?;
+                    history.apply(record);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => return Err(io_error(e)),
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+        history.file = Some((path.to_string(), file));
+
+        Ok(SalesHistory {
+            history: Arc::new(Mutex::new(history)),
+        })
+    }
+
+    /// UTC date of a sale timestamp, as `YYYY-MM-DD`
+    pub fn date(timestamp: u64) -> String {
+        datetime(timestamp)[..10].to_string()
+    }
+
+    /// Sales and refunds, oldest first
+    pub fn get_sales(&self) -> Vec<Sale> {
+        lock::lock(&self.history).sales.clone()
+    }
+
+    pub fn fetch(&self, id: u64) -> Result<Sale, ErrorVariant> {
+        lock::lock(&self.history)
+            .sales
+            .iter()
+            .find(|sale| sale.get_id() == id)
+            .cloned()
+            .ok_or_else(|| sale_not_found(id))
+    }
+
+    /// Store a sale, numbered by `number` from the number following the last sale
+    pub(crate) fn push<N>(&self, sale: Sale, number: N) -> Result<Sale, ErrorVariant>
+    where
+        N: FnOnce(u64) -> Result<u64, ErrorVariant>,
+    {
+        let mut history = lock::lock(&self.history);
+        let sale = sale.with_id(number(history.next_id())?);
+        history.append(Record::Sale(Box::new(sale.clone())))?;
+        Ok(sale)
+    }
+
+    /// Store the refund of `lines` of the sale `sale_id` closed on `terminal` at `timestamp`,
+    /// numbered as by [SalesHistory::push]
+    pub(crate) fn refund<N>(
+        &self,
+        sale_id: u64,
+        lines: &[(&str, f64)],
+        operator: Option<String>,
+        terminal: &str,
+        timestamp: u64,
+        number: N,
+    ) -> Result<Sale, ErrorVariant>
+    where
+        N: FnOnce(u64) -> Result<u64, ErrorVariant>,
+    {
+        let mut history = lock::lock(&self.history);
+        let refund = {
+            let original = history
+                .sales
+                .iter()
+                .find(|sale| sale.get_id() == sale_id)
+                .ok_or_else(|| sale_not_found(sale_id))?;
+            let previous: Vec<&Sale> = history
+                .sales
+                .iter()
+                .filter(|sale| sale.get_refunded_sale() == Some(sale_id))
+                .collect();
+            original
+                .refund(lines, &previous, timestamp)?
+                .with_id(number(history.next_id())?)
+                .with_operator(operator)
+                .with_terminal(terminal)
+        };
+        history.append(Record::Sale(Box::new(refund.clone())))?;
+        Ok(refund)
+    }
+
+    /// Shift open on `terminal`, if any
+    pub fn get_shift(&self, terminal: &str) -> Option<Shift> {
+        lock::lock(&self.history).shift(terminal).cloned()
+    }
+
+    /// Reports of the closed shifts, oldest first
+    pub fn get_shift_reports(&self) -> Vec<ShiftReport> {
+        lock::lock(&self.history).shift_reports.clone()
+    }
+
+    /// Open the next shift of `terminal` at `opened` with `float` in the drawer
+    pub(crate) fn open_shift(
+        &self,
+        float: f64,
+        operator: Option<String>,
+        terminal: &str,
+        opened: u64,
+    ) -> Result<Shift, ErrorVariant> {
+        check_cash("float", float)?;
+        let mut history = lock::lock(&self.history);
+        if let Some(shift) = history.shift(terminal) {
+            return Err(ErrorVariant::ShiftUnavailable {
+                reason: format!("shift {} is already open", shift.get_number()),
+            });
+        }
+        let shift = Shift::new(
+            history.shifts + 1,
+            opened,
+            round_cents(float),
+            operator,
+            terminal,
+            history.next_id(),
+        );
+        history.append(Record::ShiftOpened(shift.clone()))?;
+        Ok(shift)
+    }
+
+    /// Close the shift open on `terminal` at `closed`, reconciling the `counted` cash with its
+    /// sales
+    pub(crate) fn close_shift(
+        &self,
+        counted: f64,
+        terminal: &str,
+        closed: u64,
+    ) -> Result<ShiftReport, ErrorVariant> {
+        check_cash("counted cash", counted)?;
+        let mut history = lock::lock(&self.history);
+        let report = match history.shift(terminal) {
+            Some(shift) => shift.report(&history.sales, closed, counted),
+            None => {
+                return Err(ErrorVariant::ShiftUnavailable {
+                    reason: "no shift is open".to_string(),
+                })
+            }
+        };
+        history.append(Record::ShiftClosed(report.clone()))?;
+        Ok(report)
+    }
+
+    /// Summary of the sales of `date`, as `YYYY-MM-DD` in UTC, since the last day close
+    pub fn z_report(&self, date: &str) -> ZReport {
+        let history = lock::lock(&self.history);
+        let mut report = ZReport {
+            number: history.closes + 1,
+            date: date.to_string(),
+            sales: 0,
+            refunds: 0,
+            gross: 0.0,
+            discounts: 0.0,
+            tax: 0.0,
+            total: 0.0,
+            tenders: vec![],
+        };
+
+        let sales = history.sales.iter().filter(|sale| {
+            sale.get_id() > history.closed_sale && datetime(sale.get_timestamp()).starts_with(date)
+        });
+        for sale in sales {
+            match sale.get_refunded_sale() {
+                Some(_) => report.refunds += 1,
+                None => report.sales += 1,
+            }
+            let totals = sale.get_totals();
+            report.gross += totals.get_regular_price();
+            report.discounts += totals.get_savings();
+            report.tax += totals.get_total_tax();
+            report.total += totals.get_total();
+
+            for tender in sale.get_tenders() {
+                add_tender(
+                    &mut report.tenders,
+                    tender.get_tender(),
+                    tender.get_amount(),
+                );
+            }
+            if sale.get_change_due() > 0.0 {
+                add_tender(&mut report.tenders, Tender::Cash, -sale.get_change_due());
+            }
+        }
+
+        report.gross = round_cents(report.gross);
+        report.discounts = round_cents(report.discounts);
+        report.tax = round_cents(report.tax);
+        report.total = round_cents(report.total);
+        report
+    }
+
+    /// Aggregates of the sales and refunds of the dates from `from` to `to`, as `YYYY-MM-DD`
+    /// in UTC
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AAAAB").unwrap();
+    /// let sale = terminal.checkout().unwrap();
+    /// terminal.scan("AB").unwrap();
+    /// terminal.checkout().unwrap();
+    /// terminal.refund(2, &[("B", 1.0)]).unwrap();
+    ///
+    /// let date = SalesHistory::date(sale.get_timestamp());
+    /// let report = terminal.get_sales_history().report(&date, &date);
+    /// assert_eq!((report.get_sales(), report.get_refunds()), (2, 1));
+    /// assert_eq!(report.get_revenue(), 21.0);
+    /// assert_eq!(report.get_average_basket(), 16.5);
+    /// assert_eq!(report.get_average_units(), 3.5);
+    /// assert_eq!(report.get_hours().iter().map(|h| h.get_sales()).sum::<usize>(), 2);
+    ///
+    /// let products = report.get_products();
+    /// assert_eq!((products[0].get_code().as_str(), products[0].get_amount()), ("A", 5.0));
+    /// assert_eq!(products[0].get_revenue(), 9.0);
+    /// assert_eq!((products[1].get_code().as_str(), products[1].get_amount()), ("B", 1.0));
+    /// assert_eq!(products[1].get_revenue(), 12.0);
+    ///
+    /// let promotion = &report.get_promotions()[0];
+    /// assert_eq!(promotion.get_code(), "PA");
+    /// assert_eq!((promotion.get_redemptions(), promotion.get_savings()), (1.0, 1.0));
+    ///
+    /// #[cfg(feature = "json")]
+    /// {
+    ///     let json = serde_json::to_value(&report).unwrap();
+    ///     assert_eq!(json["promotions"][0]["revenue"], 7.0);
+    /// }
+    /// assert_eq!(terminal.get_sales_history().report("1970-01-01", "1970-01-02").get_sales(), 0);
+    /// ```
+    pub fn report(&self, from: &str, to: &str) -> SalesReport {
+        SalesReport::new(&lock::lock(&self.history).sales, from, to)
+    }
+
+    /// Net and tax amounts per tax rate of the sales and refunds of the dates from `from` to
+    /// `to`, as `YYYY-MM-DD` in UTC
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::customer::Identification;
+    /// use store_terminal::prelude::*;
+    ///
+    /// let table = TaxTable::new("VAT", 0.2).with_class("food", "VAT reduced", 0.05);
+    /// let config = TerminalConfig::default().with_tax_table(table);
+    /// let terminal = TerminalBuilder::new().with_config(config).build().unwrap();
+    /// terminal
+    ///     .load_catalog(
+    ///         vec![Product::new("BREAD", 2.1).with_tax_class("food"), Product::new("PEN", 1.2)],
+    ///         vec![],
+    ///     )
+    ///     .unwrap();
+    ///
+    /// terminal.scan("BREAD BREAD PEN").unwrap();
+    /// let sale = terminal.checkout().unwrap();
+    /// terminal.scan("PEN PEN").unwrap();
+    /// terminal.checkout().unwrap();
+    /// terminal.refund(1, &[("BREAD", 1.0)]).unwrap();
+    ///
+    /// let customer = Customer::new("M-7", Identification::Phone).with_tax_exemption(true);
+    /// terminal.set_customer(customer).unwrap();
+    /// terminal.scan("PEN").unwrap();
+    /// terminal.checkout().unwrap();
+    ///
+    /// let date = SalesHistory::date(sale.get_timestamp());
+    /// let report = terminal.get_sales_history().tax_report(&date, &date);
+    /// let rates = report.get_rates();
+    /// assert_eq!((rates[0].get_class(), rates[0].get_rate()), (None, 0.2));
+    /// assert_eq!((rates[0].get_net(), rates[0].get_tax()), (3.0, 0.6));
+    /// assert_eq!((rates[1].get_class(), rates[1].get_name()), (Some("food"), "VAT reduced"));
+    /// assert_eq!((rates[1].get_net(), rates[1].get_tax()), (2.0, 0.1));
+    /// assert_eq!((report.get_net(), report.get_tax(), report.get_untaxed()), (5.0, 0.7, 1.2));
+    ///
+    /// let text = report.render_text(32);
+    /// assert_eq!(text[4], "VAT 20.00% (standard)");
+    /// assert_eq!(text.last().unwrap(), &format!("Untaxed{:>25}", "1.20"));
+    /// ```
+    pub fn tax_report(&self, from: &str, to: &str) -> TaxReport {
+        TaxReport::new(&lock::lock(&self.history).sales, from, to)
+    }
+
+    /// Close the day, returning its [ZReport]; later reports only cover the following sales
+    pub fn close_day(&self, date: &str) -> Result<ZReport, ErrorVariant> {
+        let report = self.z_report(date);
+        let mut history = lock::lock(&self.history);
+        let last_sale = history.next_id() - 1;
+        history.append(Record::DayClosed {
+            number: report.number,
+            last_sale,
+        })?;
+        Ok(report)
+    }
+}
+
+/// Summary of the sales of a day, printed at the day close
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZReport {
+    number: u64,
+    date: String,
+    sales: usize,
+    refunds: usize,
+    gross: f64,
+    discounts: f64,
+    tax: f64,
+    total: f64,
+    tenders: Vec<TenderAmount>,
+}
+
+impl ZReport {
+    /// Number of the day close, starting at 1
+    pub fn get_number(&self) -> u64 {
+        self.number
+    }
+
+    pub fn get_date(&self) -> &str {
+        &self.date
+    }
+
+    /// Count of the sales, without the refunds
+    pub fn get_sales(&self) -> usize {
+        self.sales
+    }
+
+    pub fn get_refunds(&self) -> usize {
+        self.refunds
+    }
+
+    /// Regular price of the items sold, less the refunds
+    pub fn get_gross(&self) -> f64 {
+        self.gross
+    }
+
+    /// Savings of the promotions
+    pub fn get_discounts(&self) -> f64 {
+        self.discounts
+    }
+
+    /// Tax included in the totals
+    pub fn get_tax(&self) -> f64 {
+        self.tax
+    }
+
+    /// Amount sold, less the refunds
+    pub fn get_total(&self) -> f64 {
+        self.total
+    }
+
+    /// Amount taken per tender, cash without the change given back, less the refunds paid back
+    pub fn get_tenders(&self) -> &Vec<TenderAmount> {
+        &self.tenders
+    }
+
+    /// Plain text lines of at most `width` characters, as printed on a till roll
+    pub fn render_text(&self, width: usize) -> Vec<String> {
+        let mut text = vec![
+            columns("Z-REPORT", &format!("#{}", self.number), width),
+            columns("Date", &self.date, width),
+            "-".repeat(width),
+            columns("Sales", &self.sales.to_string(), width),
+            columns("Refunds", &self.refunds.to_string(), width),
+            columns("Gross", &format!("{:.2}", self.gross), width),
+            columns("Discounts", &format!("-{:.2}", self.discounts), width),
+            columns("TOTAL", &format!("{:.2}", self.total), width),
+            columns("Tax", &format!("{:.2}", self.tax), width),
+            "-".repeat(width),
+        ];
+        for tender in &self.tenders {
+            let name = tender.get_tender().get_name().to_uppercase();
+            text.push(columns(
+                &name,
+                &format!("{:.2}", tender.get_amount()),
+                width,
+            ));
+        }
+        text
+    }
+}
+
+/// Add `amount` to the total of `tender`, keeping the tenders in the order of [Tender]
+pub(super) fn add_tender(tenders: &mut Vec<TenderAmount>, tender: Tender, amount: f64) {
+    match tenders.iter().position(|t| t.get_tender() == tender) {
+        Some(i) => {
+            let total = round_cents(tenders[i].get_amount() + amount);
+            tenders[i] = TenderAmount::new(tender, total);
+        }
+        None => tenders.push(TenderAmount::new(tender, round_cents(amount))),
+    }
+    tenders.sort_by_key(|t| t.get_tender() as u8);
+}
+
+fn check_cash(code: &str, amount: f64) -> Result<(), ErrorVariant> {
+    if amount.is_finite() && amount >= 0.0 {
+        Ok(())
+    } else {
+        Err(ErrorVariant::InvalidQuantity {
+            code: code.to_string(),
+            quantity: amount,
+        })
+    }
+}
+
+fn sale_not_found(id: u64) -> ErrorVariant {
+    ErrorVariant::EntityNotFound {
+        store: "sales".to_string(),
+        code: id.to_string(),
+    }
+}
+
+/// UTC date and time of a Unix timestamp, as `YYYY-MM-DDThh:mm:ss`
+pub(crate) fn datetime(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+
+    // Civil date of a day count, from Howard Hinnant's `civil_from_days`
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,656 @@
+//! Immutable records of the sales closed by [Terminal::checkout](crate::prelude::Terminal::checkout)
+//!
+//! A [Sale] keeps the items, totals and tenders of the finalized cart, so receipts, reports
+//! and refunds are produced from what was actually sold rather than from the live cart.
+//! Refunds are sales of negative amounts, linked to the sale they refund
+
+use crate::clock::{Clock, SystemClock};
+use crate::prelude::{
+    Cart, CartItemVariant, Code, Customer, ErrorVariant, GiftCardLoad, LoyaltyActivity, Receipt,
+    ReceiptLine, TaxRate, TaxTable, Tender, TenderAmount, Totals,
+};
+use crate::receipt::round_cents;
+use crate::tax;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+pub mod history;
+pub mod report;
+pub mod sequence;
+pub mod shift;
+pub mod sync;
+
+pub use self::history::{SalesHistory, ZReport};
+pub use self::report::{
+    CategoryRevenue, HourlyRevenue, ProductRevenue, PromotionRevenue, SalesReport, TaxBreakdown,
+    TaxReport,
+};
+pub use self::sequence::SaleSequence;
+pub use self::shift::{Shift, ShiftReport};
+pub use self::sync::{QueuedSale, SaleBackend, SaleQueue, SyncConflict, SyncReport, Upload};
+
+/// Units of a product sold by a [Sale], with the price of their promotions allocated to them
+///
+/// The price of a promotion is shared by its products in proportion to their regular prices,
+/// so a product of a promotion is refunded at its share
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct SoldProduct {
+    code: Code,
+    amount: f64,
+    regular_price: f64,
+    total: f64,
+    #[cfg_attr(feature = "json", serde(default))]
+    tax_class: Option<String>,
+    #[cfg_attr(feature = "json", serde(default))]
+    category: Option<String>,
+}
+
+impl SoldProduct {
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    /// Units sold, negative for refunds
+    pub fn get_amount(&self) -> f64 {
+        self.amount
+    }
+
+    /// Price of the units without any promotion
+    pub fn get_regular_price(&self) -> f64 {
+        self.regular_price
+    }
+
+    /// Price paid for the units, promotions included
+    pub fn get_total(&self) -> f64 {
+        self.total
+    }
+
+    /// Tax class of the product when it was sold
+    pub fn get_tax_class(&self) -> Option<&str> {
+        self.tax_class.as_deref()
+    }
+
+    /// Category of the product when it was sold
+    pub fn get_category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+}
+
+/// Promotion applied by a [Sale]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct SoldPromotion {
+    code: Code,
+    applications: f64,
+    total: f64,
+    savings: f64,
+}
+
+impl SoldPromotion {
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    /// Times the promotion was applied
+    pub fn get_applications(&self) -> f64 {
+        self.applications
+    }
+
+    /// Price paid for the applications
+    pub fn get_total(&self) -> f64 {
+        self.total
+    }
+
+    /// Regular price of the products of the applications, less their total
+    pub fn get_savings(&self) -> f64 {
+        self.savings
+    }
+}
+
+/// Sale finalized by a checkout of a [Terminal](crate::prelude::Terminal)
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// terminal.scan("AAAAB").unwrap();
+/// let sale = terminal.checkout_with_tender(Tender::Card).unwrap();
+/// assert_eq!(sale.get_id(), 1);
+/// assert_eq!(sale.get_lines().len(), 2);
+/// assert_eq!(sale.get_totals().get_regular_price(), 20.0);
+/// assert_eq!(sale.get_totals().get_total(), 19.0);
+/// assert_eq!(sale.get_tenders()[0].get_tender(), Tender::Card);
+/// assert_eq!(sale.get_tenders()[0].get_amount(), 19.0);
+/// assert!(sale.is_paid());
+///
+/// terminal.scan("B").unwrap();
+/// assert_eq!(terminal.checkout().unwrap().get_id(), 2);
+/// assert!(terminal.get_cart().unwrap().get_items().is_empty());
+///
+/// assert_eq!(terminal.fetch_sale(1).unwrap(), sale);
+/// assert_eq!(terminal.get_sales().unwrap().len(), 2);
+/// assert!(terminal.fetch_sale(3).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Sale {
+    id: u64,
+    timestamp: u64,
+    lines: Vec<ReceiptLine>,
+    totals: Totals,
+    tenders: Vec<TenderAmount>,
+    products: Vec<SoldProduct>,
+    #[cfg_attr(feature = "json", serde(default))]
+    promotions: Vec<SoldPromotion>,
+    refunded_sale: Option<u64>,
+    #[cfg_attr(feature = "json", serde(default))]
+    gift_card_loads: Vec<GiftCardLoad>,
+    #[cfg_attr(feature = "json", serde(default))]
+    loyalty: Option<LoyaltyActivity>,
+    #[cfg_attr(feature = "json", serde(default))]
+    customer: Option<Customer>,
+    #[cfg_attr(feature = "json", serde(default))]
+    operator: Option<String>,
+    #[cfg_attr(feature = "json", serde(default))]
+    terminal: Option<String>,
+}
+
+impl Sale {
+    /// Unpaid sale of the items of an optimized cart, closed now
+    ///
+    /// The sale is numbered when the terminal stores it, and is 0 until then
+    pub fn new(cart: &Cart) -> Self {
+        let receipt = Receipt::new(cart);
+        let promotions = cart
+            .get_items()
+            .iter()
+            .zip(receipt.get_lines())
+            .filter(|(item, _)| item.is_promotion())
+            .map(|(_, line)| SoldPromotion {
+                code: line.get_code().clone(),
+                applications: line.get_amount(),
+                total: line.get_total(),
+                savings: line.get_savings(),
+            })
+            .collect();
+
+        Sale {
+            id: 0,
+            timestamp: SystemClock.now(),
+            lines: receipt.get_lines().clone(),
+            totals: receipt.get_totals().clone(),
+            tenders: vec![],
+            products: sold_products(cart),
+            promotions,
+            refunded_sale: None,
+            gift_card_loads: cart.get_gift_card_loads().clone(),
+            loyalty: None,
+            customer: cart.get_customer().cloned(),
+            operator: None,
+            terminal: None,
+        }
+    }
+
+    pub(crate) fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub(crate) fn with_id(mut self, id: u64) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Taxes of the products at the rates of `table`; sales to tax exempt customers carry none
+    pub(crate) fn with_taxes(mut self, table: &TaxTable) -> Self {
+        let exempt = self.customer.as_ref().is_some_and(Customer::is_tax_exempt);
+        if !exempt {
+            self.totals = self.totals.with_taxes(table.tax_lines(&self.products));
+        }
+        self
+    }
+
+    pub(crate) fn with_operator(mut self, operator: Option<String>) -> Self {
+        self.operator = operator;
+        self
+    }
+
+    pub(crate) fn with_terminal(mut self, terminal: &str) -> Self {
+        self.terminal = Some(terminal.to_string());
+        self
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn with_loyalty(mut self, loyalty: Option<LoyaltyActivity>) -> Self {
+        self.loyalty = loyalty;
+        self
+    }
+
+    /// Pay `amount` of the sale with `tender`
+    ///
+    /// Only cash may exceed the amount due, the excess being the change. Sales are paid by
+    /// [Terminal::checkout_with_tenders](crate::prelude::Terminal::checkout_with_tenders)
+    /// before they are stored, so a stored sale is never changed
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AB").unwrap();
+    ///
+    /// let mut sale = Sale::new(&terminal.get_cart().unwrap());
+    /// sale.add_tender(Tender::Voucher, 5.0).unwrap();
+    /// assert_eq!(sale.get_amount_due(), 9.0);
+    /// assert!(sale.add_tender(Tender::Card, 10.0).is_err());
+    /// assert_eq!(
+    ///     sale.check_paid().unwrap_err(),
+    ///     ErrorVariant::InsufficientPayment { total: 14.0, paid: 5.0 }
+    /// );
+    ///
+    /// sale.add_tender(Tender::Cash, 20.0).unwrap();
+    /// assert_eq!(sale.get_paid(), 25.0);
+    /// assert_eq!(sale.get_amount_due(), 0.0);
+    /// assert_eq!(sale.get_change_due(), 11.0);
+    /// assert!(sale.check_paid().is_ok());
+    /// ```
+    pub fn add_tender(&mut self, tender: Tender, amount: f64) -> Result<(), ErrorVariant> {
+        let invalid = || ErrorVariant::InvalidPaymentDetails {
+            field: format!("{} amount", tender.get_name()),
+            value: amount.to_string(),
+        };
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(invalid());
+        }
+        if tender != Tender::Cash && round_cents(amount) > self.get_amount_due() {
+            return Err(invalid());
+        }
+
+        self.tenders.push(TenderAmount::new(tender, amount));
+        Ok(())
+    }
+
+    /// Number of the sale on its terminal, starting at 1
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn get_lines(&self) -> &Vec<ReceiptLine> {
+        &self.lines
+    }
+
+    pub fn get_totals(&self) -> &Totals {
+        &self.totals
+    }
+
+    pub fn get_tenders(&self) -> &Vec<TenderAmount> {
+        &self.tenders
+    }
+
+    /// Products of the sale, one per code, with their share of the promotions
+    pub fn get_products(&self) -> &Vec<SoldProduct> {
+        &self.products
+    }
+
+    /// Promotions applied by the sale; refunds apply none
+    pub fn get_promotions(&self) -> &Vec<SoldPromotion> {
+        &self.promotions
+    }
+
+    /// Number of the sale refunded by this one, for refunds
+    pub fn get_refunded_sale(&self) -> Option<u64> {
+        self.refunded_sale
+    }
+
+    /// Gift cards sold by the sale, with the balance they were loaded with
+    pub fn get_gift_card_loads(&self) -> &Vec<GiftCardLoad> {
+        &self.gift_card_loads
+    }
+
+    /// Identifier of the operator logged in when the sale was closed
+    pub fn get_operator(&self) -> Option<&str> {
+        self.operator.as_deref()
+    }
+
+    /// Identifier of the terminal that closed the sale or refund
+    pub fn get_terminal(&self) -> Option<&str> {
+        self.terminal.as_deref()
+    }
+
+    /// Customer identified for the sale
+    pub fn get_customer(&self) -> Option<&Customer> {
+        self.customer.as_ref()
+    }
+
+    /// Loyalty points earned and redeemed by the customer of the sale; on a refund, the earned
+    /// points taken back and the redeemed points given back
+    pub fn get_loyalty(&self) -> Option<&LoyaltyActivity> {
+        self.loyalty.as_ref()
+    }
+
+    /// Total of the sale earning loyalty points, leaving out the gift cards sold
+    pub fn get_net_total(&self) -> f64 {
+        let loads = self
+            .gift_card_loads
+            .iter()
+            .fold(0.0, |total, load| total + load.get_amount());
+        round_cents(self.totals.get_total() - loads)
+    }
+
+    /// Refund of `lines`, as codes and amounts of products of the sale, not refunded by the
+    /// `previous` refunds yet, closed at `timestamp`
+    pub(crate) fn refund(
+        &self,
+        lines: &[(&str, f64)],
+        previous: &[&Sale],
+        timestamp: u64,
+    ) -> Result<Sale, ErrorVariant> {
+        let mut products: Vec<SoldProduct> = vec![];
+        for (code, amount) in lines {
+            if !amount.is_finite() || *amount <= 0.0 {
+                return Err(ErrorVariant::InvalidQuantity {
+                    code: code.to_string(),
+                    quantity: *amount,
+                });
+            }
+
+            let sold = self.products.iter().find(|p| p.code == *code);
+            let refunded = previous
+                .iter()
+                .map(|refund| returned(&refund.products, code))
+                .sum::<f64>()
+                + returned(&products, code);
+            let available = sold.map_or(0.0, |p| p.amount) - refunded;
+            let sold = match sold {
+                Some(sold) if *amount <= available => sold,
+                _ => {
+                    return Err(ErrorVariant::NotEnoughItems {
+                        code: code.to_string(),
+                        requested: *amount,
+                        available: available.max(0.0),
+                    })
+                }
+            };
+
+            let share = amount / sold.amount;
+            products.push(SoldProduct {
+                code: sold.code.clone(),
+                amount: -amount,
+                regular_price: -round_cents(sold.regular_price * share),
+                total: -round_cents(sold.total * share),
+                tax_class: sold.tax_class.clone(),
+                category: sold.category.clone(),
+            });
+        }
+
+        let lines = products
+            .iter()
+            .map(|p| {
+                let unit_price = round_cents(p.total / p.amount);
+                ReceiptLine::new(
+                    p.code.clone(),
+                    String::new(),
+                    p.amount,
+                    unit_price,
+                    p.total,
+                    0.0,
+                )
+            })
+            .collect();
+        let regular_price = round_cents(products.iter().map(|p| p.regular_price).sum());
+        let total = round_cents(products.iter().map(|p| p.total).sum());
+
+        // Refunded products are taxed back at the rates of the sale
+        let taxes = tax::tax_lines(&products, |class| {
+            let taxes = self.totals.get_taxes();
+            taxes
+                .iter()
+                .find(|tax| tax.get_class() == class)
+                .or_else(|| taxes.iter().find(|tax| tax.get_class().is_none()))
+                .map(|tax| {
+                    let rate = TaxRate::new(tax.get_name(), tax.get_rate());
+                    (tax.get_class().map(str::to_string), rate)
+                })
+        });
+
+        let loyalty = self.refund_loyalty(&products, previous);
+        Ok(Sale {
+            id: 0,
+            timestamp,
+            lines,
+            totals: Totals::new(regular_price, round_cents(regular_price - total), total)
+                .with_taxes(taxes),
+            tenders: self.refund_tenders(total),
+            products,
+            promotions: vec![],
+            refunded_sale: Some(self.id),
+            gift_card_loads: vec![],
+            loyalty,
+            customer: self.customer.clone(),
+            operator: None,
+            terminal: None,
+        })
+    }
+
+    /// Points reversed by the refund of `products`, the share of the loyalty activity of the
+    /// sale refunded so far less the points reversed by the `previous` refunds
+    fn refund_loyalty(
+        &self,
+        products: &[SoldProduct],
+        previous: &[&Sale],
+    ) -> Option<LoyaltyActivity> {
+        let activity = self.loyalty.as_ref()?;
+        let total = |products: &[SoldProduct]| products.iter().map(|p| p.total).sum::<f64>();
+        let sold = total(&self.products);
+        let refunded = -previous
+            .iter()
+            .map(|refund| total(&refund.products))
+            .sum::<f64>()
+            - total(products);
+        let share = match sold > 0.0 {
+            true => (refunded / sold).min(1.0),
+            false => 1.0,
+        };
+        let reversed = |points: u64, pick: fn(&LoyaltyActivity) -> u64| {
+            let before: u64 = previous
+                .iter()
+                .filter_map(|r| r.get_loyalty())
+                .map(pick)
+                .sum();
+            ((points as f64 * share).round() as u64).saturating_sub(before)
+        };
+        Some(LoyaltyActivity::new(
+            activity.get_customer(),
+            reversed(activity.get_earned(), LoyaltyActivity::get_earned),
+            reversed(activity.get_redeemed(), LoyaltyActivity::get_redeemed),
+        ))
+    }
+
+    /// Tenders paying back `total`, a negative amount, in proportion to the amount each tender
+    /// of the sale kept, cash less the change given
+    fn refund_tenders(&self, total: f64) -> Vec<TenderAmount> {
+        let mut kept = vec![];
+        for tender in &self.tenders {
+            history::add_tender(&mut kept, tender.get_tender(), tender.get_amount());
+        }
+        history::add_tender(&mut kept, Tender::Cash, -self.get_change_due());
+        kept.retain(|t| t.get_amount() > 0.0);
+
+        let paid: f64 = kept.iter().map(|t| t.get_amount()).sum();
+        let mut left = total;
+        let last = kept.len().saturating_sub(1);
+        kept.iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let amount = match i == last {
+                    true => round_cents(left),
+                    false => round_cents(total * t.get_amount() / paid),
+                };
+                left -= amount;
+                TenderAmount::new(t.get_tender(), amount)
+            })
+            .collect()
+    }
+
+    /// Sum of the tenders
+    pub fn get_paid(&self) -> f64 {
+        round_cents(
+            self.tenders
+                .iter()
+                .fold(0.0, |paid, t| paid + t.get_amount()),
+        )
+    }
+
+    /// Part of the total not paid yet
+    pub fn get_amount_due(&self) -> f64 {
+        round_cents(self.totals.get_total() - self.get_paid()).max(0.0)
+    }
+
+    /// Cash given back to the customer, for tenders over the total; refunds give no change
+    pub fn get_change_due(&self) -> f64 {
+        round_cents(self.get_paid() - self.totals.get_total().max(0.0)).max(0.0)
+    }
+
+    pub fn is_paid(&self) -> bool {
+        self.get_amount_due() == 0.0
+    }
+
+    /// Check the tenders cover the total
+    pub fn check_paid(&self) -> Result<(), ErrorVariant> {
+        if self.is_paid() {
+            Ok(())
+        } else {
+            Err(ErrorVariant::InsufficientPayment {
+                total: self.totals.get_total(),
+                paid: self.get_paid(),
+            })
+        }
+    }
+
+    /// Identifier, timestamp, items with their code, description, amount and total, tenders
+    /// and total of the sale
+    #[cfg(any(feature = "ffi", feature = "server", feature = "webhook"))]
+    pub(crate) fn summary_json(&self) -> serde_json::Value {
+        let items: Vec<serde_json::Value> = self
+            .lines
+            .iter()
+            .map(|line| {
+                serde_json::json!({
+                    "code": line.get_code(),
+                    "description": line.get_description(),
+                    "amount": line.get_amount(),
+                    "total": line.get_total(),
+                })
+            })
+            .collect();
+        let tenders: Vec<serde_json::Value> = self
+            .tenders
+            .iter()
+            .map(|tender| {
+                serde_json::json!({
+                    "tender": tender.get_tender().get_name(),
+                    "amount": tender.get_amount(),
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "id": self.id,
+            "timestamp": self.timestamp,
+            "items": items,
+            "tenders": tenders,
+            "total": self.totals.get_total(),
+            "change": self.get_change_due(),
+        })
+    }
+}
+
+/// Products of the items of a cart, with the prices of the promotions allocated to them
+fn sold_products(cart: &Cart) -> Vec<SoldProduct> {
+    let mut products: Vec<SoldProduct> = vec![];
+    for item in cart.get_items() {
+        let regular = item
+            .get_products()
+            .iter()
+            .fold(0.0, |total, p| total + p.get_total_price());
+        let (applications, price) = match item.get_variant() {
+            CartItemVariant::Product(_) => (1.0, regular),
+            CartItemVariant::Promotion(_) => (item.get_amount(), item.get_unit_price()),
+            // Sold under their own code, as they are not made of catalog products
+            CartItemVariant::Custom(custom) => {
+                sell(
+                    &mut products,
+                    SoldProduct {
+                        code: custom.get_code().clone(),
+                        amount: custom.get_amount(),
+                        regular_price: custom.get_regular_unit_price() * custom.get_amount(),
+                        total: custom.get_line_total(),
+                        tax_class: custom.get_tax_class().map(str::to_string),
+                        category: None,
+                    },
+                );
+                continue;
+            }
+        };
+
+        for product in item.get_products() {
+            let share = if regular > 0.0 {
+                product.get_total_price() / regular
+            } else {
+                0.0
+            };
+            sell(
+                &mut products,
+                SoldProduct {
+                    code: product.get_code().clone(),
+                    amount: applications * product.get_amount(),
+                    regular_price: applications * product.get_total_price(),
+                    total: applications * price * share,
+                    tax_class: product.get_product().get_tax_class().map(str::to_string),
+                    category: product.get_product().get_category().map(str::to_string),
+                },
+            );
+        }
+    }
+
+    // Redeemed points are shared by the products in proportion to their totals
+    let discount = cart.get_loyalty_discount();
+    let total = products.iter().fold(0.0, |total, p| total + p.total);
+    if discount > 0.0 && total > 0.0 {
+        let share = (total - discount) / total;
+        products.iter_mut().for_each(|p| p.total *= share);
+    }
+    products
+}
+
+/// Add `sold` to the product of the same code in `products`, if any
+fn sell(products: &mut Vec<SoldProduct>, sold: SoldProduct) {
+    match products.iter_mut().find(|p| p.code == sold.code) {
+        Some(product) => {
+            product.amount += sold.amount;
+            product.regular_price += sold.regular_price;
+            product.total += sold.total;
+        }
+        None => products.push(sold),
+    }
+}
+
+/// Units of `code` returned by refunded `products`
+fn returned(products: &[SoldProduct], code: &str) -> f64 {
+    products
+        .iter()
+        .filter(|p| p.code == *code)
+        .fold(0.0, |total, p| total - p.amount)
+}
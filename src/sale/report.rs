@@ -0,0 +1,521 @@
+use crate::prelude::{Code, Sale};
+use crate::receipt::{columns, round_cents};
+use crate::sale::history::datetime;
+#[cfg(feature = "json")]
+use serde::Serialize;
+
+/// Sales and revenue of an hour of the day
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct HourlyRevenue {
+    hour: u8,
+    sales: usize,
+    revenue: f64,
+}
+
+impl HourlyRevenue {
+    /// Hour of the day in UTC, from 0 to 23
+    pub fn get_hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// Count of the sales, without the refunds
+    pub fn get_sales(&self) -> usize {
+        self.sales
+    }
+
+    /// Total of the sales, less the refunds
+    pub fn get_revenue(&self) -> f64 {
+        self.revenue
+    }
+}
+
+/// Units sold and revenue of a product
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct ProductRevenue {
+    code: Code,
+    amount: f64,
+    revenue: f64,
+}
+
+impl ProductRevenue {
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    /// Units sold, less the units refunded
+    pub fn get_amount(&self) -> f64 {
+        self.amount
+    }
+
+    /// Price paid for the units, with their share of the promotions, less the refunds
+    pub fn get_revenue(&self) -> f64 {
+        self.revenue
+    }
+}
+
+/// Units sold and revenue of the products of a category
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct CategoryRevenue {
+    category: String,
+    amount: f64,
+    revenue: f64,
+}
+
+impl CategoryRevenue {
+    pub fn get_category(&self) -> &str {
+        &self.category
+    }
+
+    /// Units sold, less the units refunded
+    pub fn get_amount(&self) -> f64 {
+        self.amount
+    }
+
+    /// Price paid for the units, with their share of the promotions, less the refunds
+    pub fn get_revenue(&self) -> f64 {
+        self.revenue
+    }
+}
+
+/// Redemptions and revenue of a promotion
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct PromotionRevenue {
+    code: Code,
+    sales: usize,
+    redemptions: f64,
+    revenue: f64,
+    savings: f64,
+}
+
+impl PromotionRevenue {
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    /// Count of the sales applying the promotion
+    pub fn get_sales(&self) -> usize {
+        self.sales
+    }
+
+    /// Times the promotion was applied
+    pub fn get_redemptions(&self) -> f64 {
+        self.redemptions
+    }
+
+    /// Price paid for the applications
+    pub fn get_revenue(&self) -> f64 {
+        self.revenue
+    }
+
+    /// Savings given to the customers
+    pub fn get_savings(&self) -> f64 {
+        self.savings
+    }
+}
+
+/// Aggregates of the stored sales of a date range, for dashboards
+///
+/// Built by [SalesHistory::report](crate::prelude::SalesHistory::report). Unlike the
+/// [ZReport](crate::prelude::ZReport), it covers every sale of the range whether its day is
+/// closed or not, and serializes to JSON with the `json` feature
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct SalesReport {
+    from: String,
+    to: String,
+    sales: usize,
+    refunds: usize,
+    revenue: f64,
+    average_basket: f64,
+    average_units: f64,
+    hours: Vec<HourlyRevenue>,
+    products: Vec<ProductRevenue>,
+    categories: Vec<CategoryRevenue>,
+    promotions: Vec<PromotionRevenue>,
+}
+
+impl SalesReport {
+    /// Report of the `sales` of the dates from `from` to `to`, as `YYYY-MM-DD` in UTC
+    pub(crate) fn new<'a, I>(sales: I, from: &str, to: &str) -> Self
+    where
+        I: IntoIterator<Item = &'a Sale>,
+    {
+        let mut report = SalesReport {
+            from: from.to_string(),
+            to: to.to_string(),
+            sales: 0,
+            refunds: 0,
+            revenue: 0.0,
+            average_basket: 0.0,
+            average_units: 0.0,
+            hours: vec![],
+            products: vec![],
+            categories: vec![],
+            promotions: vec![],
+        };
+
+        // Total and units of the sales, without the refunds
+        let (mut sold, mut units) = (0.0, 0.0);
+        for sale in sales {
+            let time = datetime(sale.get_timestamp());
+            if time[..10] < *from || time[..10] > *to {
+                continue;
+            }
+            let total = sale.get_totals().get_total();
+            let refund = sale.get_refunded_sale().is_some();
+            if refund {
+                report.refunds += 1;
+            } else {
+                report.sales += 1;
+                sold += total;
+            }
+            report.revenue += total;
+            report.add_hour(time[11..13].parse().unwrap_or(0), !refund, total);
+
+            for product in sale.get_products() {
+                if !refund {
+                    units += product.get_amount();
+                }
+                report.add_product(
+                    product.get_code(),
+                    product.get_amount(),
+                    product.get_total(),
+                );
+                if let Some(category) = product.get_category() {
+                    report.add_category(category, product.get_amount(), product.get_total());
+                }
+            }
+            for promotion in sale.get_promotions() {
+                report.add_promotion(
+                    promotion.get_code(),
+                    promotion.get_applications(),
+                    promotion.get_total(),
+                    promotion.get_savings(),
+                );
+            }
+        }
+
+        if report.sales > 0 {
+            report.average_basket = round_cents(sold / report.sales as f64);
+            report.average_units = units / report.sales as f64;
+        }
+        report.revenue = round_cents(report.revenue);
+        report.hours.sort_by_key(|h| h.hour);
+        report
+            .hours
+            .iter_mut()
+            .for_each(|h| h.revenue = round_cents(h.revenue));
+        report.products.sort_by(|a, b| a.code.cmp(&b.code));
+        report
+            .products
+            .iter_mut()
+            .for_each(|p| p.revenue = round_cents(p.revenue));
+        report
+            .categories
+            .sort_by(|a, b| a.category.cmp(&b.category));
+        report
+            .categories
+            .iter_mut()
+            .for_each(|c| c.revenue = round_cents(c.revenue));
+        report.promotions.sort_by(|a, b| a.code.cmp(&b.code));
+        report.promotions.iter_mut().for_each(|p| {
+            p.revenue = round_cents(p.revenue);
+            p.savings = round_cents(p.savings);
+        });
+        report
+    }
+
+    fn add_hour(&mut self, hour: u8, sale: bool, total: f64) {
+        let index = match self.hours.iter().position(|h| h.hour == hour) {
+            Some(index) => index,
+            None => {
+                self.hours.push(HourlyRevenue {
+                    hour,
+                    sales: 0,
+                    revenue: 0.0,
+                });
+                self.hours.len() - 1
+            }
+        };
+        let hourly = &mut self.hours[index];
+        hourly.sales += usize::from(sale);
+        hourly.revenue += total;
+    }
+
+    fn add_product(&mut self, code: &Code, amount: f64, total: f64) {
+        match self.products.iter_mut().find(|p| &p.code == code) {
+            Some(product) => {
+                product.amount += amount;
+                product.revenue += total;
+            }
+            None => self.products.push(ProductRevenue {
+                code: code.clone(),
+                amount,
+                revenue: total,
+            }),
+        }
+    }
+
+    fn add_category(&mut self, category: &str, amount: f64, total: f64) {
+        match self.categories.iter_mut().find(|c| c.category == category) {
+            Some(revenue) => {
+                revenue.amount += amount;
+                revenue.revenue += total;
+            }
+            None => self.categories.push(CategoryRevenue {
+                category: category.to_string(),
+                amount,
+                revenue: total,
+            }),
+        }
+    }
+
+    fn add_promotion(&mut self, code: &Code, applications: f64, total: f64, savings: f64) {
+        match self.promotions.iter_mut().find(|p| &p.code == code) {
+            Some(promotion) => {
+                promotion.sales += 1;
+                promotion.redemptions += applications;
+                promotion.revenue += total;
+                promotion.savings += savings;
+            }
+            None => self.promotions.push(PromotionRevenue {
+                code: code.clone(),
+                sales: 1,
+                redemptions: applications,
+                revenue: total,
+                savings,
+            }),
+        }
+    }
+
+    /// First date of the report, as `YYYY-MM-DD`
+    pub fn get_from(&self) -> &str {
+        &self.from
+    }
+
+    /// Last date of the report, as `YYYY-MM-DD`
+    pub fn get_to(&self) -> &str {
+        &self.to
+    }
+
+    /// Count of the sales, without the refunds
+    pub fn get_sales(&self) -> usize {
+        self.sales
+    }
+
+    pub fn get_refunds(&self) -> usize {
+        self.refunds
+    }
+
+    /// Total of the sales, less the refunds
+    pub fn get_revenue(&self) -> f64 {
+        self.revenue
+    }
+
+    /// Sales and revenue per hour of the day, for the hours with any sale or refund
+    pub fn get_hours(&self) -> &Vec<HourlyRevenue> {
+        &self.hours
+    }
+
+    /// Units sold and revenue per product, sorted by code
+    pub fn get_products(&self) -> &Vec<ProductRevenue> {
+        &self.products
+    }
+
+    /// Units sold and revenue per category, sorted by category
+    ///
+    /// Products sold without a category are left out
+    pub fn get_categories(&self) -> &Vec<CategoryRevenue> {
+        &self.categories
+    }
+
+    /// Redemptions and revenue per promotion, sorted by code
+    pub fn get_promotions(&self) -> &Vec<PromotionRevenue> {
+        &self.promotions
+    }
+
+    /// Average total of a sale, refunds aside
+    pub fn get_average_basket(&self) -> f64 {
+        self.average_basket
+    }
+
+    /// Average units of a sale, refunds aside
+    pub fn get_average_units(&self) -> f64 {
+        self.average_units
+    }
+}
+
+/// Net and tax amounts of a tax rate, for the products of a tax class
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct TaxBreakdown {
+    class: Option<String>,
+    name: String,
+    rate: f64,
+    net: f64,
+    tax: f64,
+}
+
+impl TaxBreakdown {
+    /// Tax class of the products, `None` for the standard rate
+    pub fn get_class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Rate of the tax, `0.2` for 20%
+    pub fn get_rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Taxed amount, without the tax, less the refunds
+    pub fn get_net(&self) -> f64 {
+        self.net
+    }
+
+    /// Tax collected, less the tax refunded
+    pub fn get_tax(&self) -> f64 {
+        self.tax
+    }
+
+    /// Net amount with the tax
+    pub fn get_gross(&self) -> f64 {
+        round_cents(self.net + self.tax)
+    }
+}
+
+/// Net and tax amounts per tax rate of the stored sales of a date range, for tax filings
+///
+/// Built by [SalesHistory::tax_report](crate::prelude::SalesHistory::tax_report). A class
+/// taxed at different rates over the range, as when a rate changes, has a breakdown per rate
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct TaxReport {
+    from: String,
+    to: String,
+    rates: Vec<TaxBreakdown>,
+    untaxed: f64,
+}
+
+impl TaxReport {
+    /// Report of the `sales` of the dates from `from` to `to`, as `YYYY-MM-DD` in UTC
+    pub(crate) fn new<'a, I>(sales: I, from: &str, to: &str) -> Self
+    where
+        I: IntoIterator<Item = &'a Sale>,
+    {
+        let mut report = TaxReport {
+            from: from.to_string(),
+            to: to.to_string(),
+            rates: vec![],
+            untaxed: 0.0,
+        };
+
+        for sale in sales {
+            let date = datetime(sale.get_timestamp());
+            if date[..10] < *from || date[..10] > *to {
+                continue;
+            }
+            let mut taxed = 0.0;
+            for line in sale.get_totals().get_taxes() {
+                taxed += line.get_net() + line.get_tax();
+                let class = line.get_class();
+                let breakdown = report.rates.iter_mut().find(|b| {
+                    b.class.as_deref() == class
+                        && b.name == line.get_name()
+                        && b.rate == line.get_rate()
+                });
+                match breakdown {
+                    Some(breakdown) => {
+                        breakdown.net += line.get_net();
+                        breakdown.tax += line.get_tax();
+                    }
+                    None => report.rates.push(TaxBreakdown {
+                        class: class.map(str::to_string),
+                        name: line.get_name().to_string(),
+                        rate: line.get_rate(),
+                        net: line.get_net(),
+                        tax: line.get_tax(),
+                    }),
+                }
+            }
+            report.untaxed += sale.get_totals().get_total() - taxed;
+        }
+
+        report.untaxed = round_cents(report.untaxed);
+        report.rates.sort_by(|a, b| {
+            a.class.cmp(&b.class).then(
+                a.rate
+                    .partial_cmp(&b.rate)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+        });
+        report.rates.iter_mut().for_each(|b| {
+            b.net = round_cents(b.net);
+            b.tax = round_cents(b.tax);
+        });
+        report
+    }
+
+    /// First date of the report, as `YYYY-MM-DD`
+    pub fn get_from(&self) -> &str {
+        &self.from
+    }
+
+    /// Last date of the report, as `YYYY-MM-DD`
+    pub fn get_to(&self) -> &str {
+        &self.to
+    }
+
+    /// Breakdowns sorted by class, the standard rate first, then by rate
+    pub fn get_rates(&self) -> &Vec<TaxBreakdown> {
+        &self.rates
+    }
+
+    /// Total of the sales carrying no tax, as gift cards and sales to tax exempt customers,
+    /// less the refunds
+    pub fn get_untaxed(&self) -> f64 {
+        self.untaxed
+    }
+
+    pub fn get_net(&self) -> f64 {
+        round_cents(self.rates.iter().map(|b| b.net).sum())
+    }
+
+    pub fn get_tax(&self) -> f64 {
+        round_cents(self.rates.iter().map(|b| b.tax).sum())
+    }
+
+    /// Plain text lines of at most `width` characters, a net and a tax line per rate
+    pub fn render_text(&self, width: usize) -> Vec<String> {
+        let mut text = vec![
+            "TAX REPORT".to_string(),
+            columns("From", &self.from, width),
+            columns("To", &self.to, width),
+            "-".repeat(width),
+        ];
+        for breakdown in &self.rates {
+            let class = breakdown.class.as_deref().unwrap_or("standard");
+            let label = format!(
+                "{} {:.2}% ({})",
+                breakdown.name,
+                breakdown.rate * 100.0,
+                class
+            );
+            text.push(label.chars().take(width).collect());
+            text.push(columns("  Net", &format!("{:.2}", breakdown.net), width));
+            text.push(columns("  Tax", &format!("{:.2}", breakdown.tax), width));
+        }
+        text.push("-".repeat(width));
+        text.push(columns("Net", &format!("{:.2}", self.get_net()), width));
+        text.push(columns("Tax", &format!("{:.2}", self.get_tax()), width));
+        text.push(columns("Untaxed", &format!("{:.2}", self.untaxed), width));
+        text
+    }
+}
@@ -0,0 +1,166 @@
+use crate::lock;
+use crate::prelude::ErrorVariant;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default)]
+struct Numbers {
+    /// Last number given per terminal identifier
+    last: BTreeMap<String, u64>,
+    path: Option<String>,
+}
+
+impl Numbers {
+    /// Replace the file of the sequence, if any, with the numbers of `last`
+    ///
+    /// The numbers are written to a temporary file renamed over the previous one, so a crash
+    /// leaves either the previous numbers or the new ones
+    fn save(&self, last: &BTreeMap<String, u64>) -> Result<(), ErrorVariant> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let content: String = last
+            .iter()
+            .map(|(terminal, number)| format!("{} {}\n", terminal, number))
+            .collect();
+        let temp = format!("{}.tmp", path);
+        File::create(&temp)
+            .and_then(|mut file| {
+                file.write_all(content.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|()| fs::rename(&temp, path))
+            .map_err(|e| ErrorVariant::IoError {
+                path: path.clone(),
+                source: e.to_string(),
+            })
+    }
+}
+
+/// Numbering of the sales and refunds of the terminals, so a sale number, printed on its
+/// receipt, is never given twice nor goes backwards
+///
+/// Sequences opened on a file store the last number of every terminal identifier, and a number
+/// is stored before it is given, so a restart carries on after it. A number lost to a crash is
+/// skipped rather than given again. Clones share the numbers, so one sequence can number the
+/// lanes of a store
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let path = std::env::temp_dir().join(format!("sequence-{}", std::process::id()));
+/// let path = path.to_str().unwrap();
+/// let lane = || {
+///     TerminalBuilder::new()
+///         .with_config(TerminalConfig::new("lane-2".to_string()))
+///         .with_sale_sequence(SaleSequence::open(path).unwrap())
+///         .build()
+///         .unwrap()
+/// };
+///
+/// let terminal = lane();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("A").unwrap();
+/// assert_eq!(terminal.checkout().unwrap().get_id(), 1);
+/// drop(terminal);
+///
+/// // The sales history is kept in memory, and is lost by the restart
+/// let terminal = lane();
+/// terminal.load_demo_catalog().unwrap();
+/// terminal.scan("B").unwrap();
+/// assert_eq!(terminal.checkout().unwrap().get_id(), 2);
+/// assert_eq!(SaleSequence::open(path).unwrap().get_last("lane-2"), 2);
+/// assert_eq!(SaleSequence::open(path).unwrap().get_last("lane-3"), 0);
+/// # std::fs::remove_file(path).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SaleSequence {
+    numbers: Arc<Mutex<Numbers>>,
+}
+
+impl SaleSequence {
+    /// Sequence kept in memory only, numbering on from the sales history
+    pub fn new() -> Self {
+        SaleSequence::default()
+    }
+
+    /// Sequence stored in the file at `path`, created on the first number given
+    pub fn open(path: &str) -> Result<Self, ErrorVariant> {
+        let mut numbers = Numbers {
+            last: BTreeMap::new(),
+            path: Some(path.to_string()),
+        };
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(ErrorVariant::IoError {
+                    path: path.to_string(),
+                    source: e.to_string(),
+                })
+            }
+        };
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let parsed = line
+                .rsplit_once(' ')
+                .and_then(|(terminal, number)| Some((terminal, number.parse::<u64>().ok()?)));
+            match parsed {
+                Some((terminal, number)) => {
+                    numbers.last.insert(terminal.to_string(), number);
+                }
+                None => {
+                    return Err(ErrorVariant::IoError {
+                        path: path.to_string(),
+                        source: format!("invalid sequence line `{}`", line),
+                    })
+                }
+            }
+        }
+
+        Ok(SaleSequence {
+            numbers: Arc::new(Mutex::new(numbers)),
+        })
+    }
+
+    /// Last number given to the sales of `terminal`, 0 if none
+    pub fn get_last(&self, terminal: &str) -> u64 {
+        lock::lock(&self.numbers)
+            .last
+            .get(terminal)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Store and give the next number of `terminal`, not below `at_least`
+    pub(crate) fn next(&self, terminal: &str, at_least: u64) -> Result<u64, ErrorVariant> {
+        let mut numbers = lock::lock(&self.numbers);
+        let last = numbers.last.get(terminal).copied().unwrap_or(0);
+        let number = (last + 1).max(at_least);
+
+        let mut next = numbers.last.clone();
+        next.insert(terminal.to_string(), number);
+        numbers.save(&next)?;
+        numbers.last = next;
+        Ok(number)
+    }
+
+    /// Carry on after `last` for `terminal`, if it is beyond the last number given
+    #[cfg(feature = "json")]
+    pub(crate) fn restore(&self, terminal: &str, last: u64) -> Result<(), ErrorVariant> {
+        let mut numbers = lock::lock(&self.numbers);
+        if numbers.last.get(terminal).copied().unwrap_or(0) >= last {
+            return Ok(());
+        }
+
+        let mut next = numbers.last.clone();
+        next.insert(terminal.to_string(), last);
+        numbers.save(&next)?;
+        numbers.last = next;
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use crate::prelude::{Sale, Tender, TenderAmount};
+use crate::receipt::{columns, round_cents};
+use crate::sale::history::{add_tender, datetime};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+/// Shift of a cashier on a terminal, from [Terminal::open_shift](crate::prelude::Terminal::open_shift)
+/// to [Terminal::close_shift](crate::prelude::Terminal::close_shift)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Shift {
+    number: u64,
+    opened: u64,
+    float: f64,
+    operator: Option<String>,
+    /// Number of the first sale of the shift
+    first_sale: u64,
+    #[cfg_attr(feature = "json", serde(default))]
+    terminal: Option<String>,
+}
+
+impl Shift {
+    pub(crate) fn new(
+        number: u64,
+        opened: u64,
+        float: f64,
+        operator: Option<String>,
+        terminal: &str,
+        first_sale: u64,
+    ) -> Self {
+        Shift {
+            number,
+            opened,
+            float,
+            operator,
+            first_sale,
+            terminal: Some(terminal.to_string()),
+        }
+    }
+
+    /// Number of the shift, starting at 1
+    pub fn get_number(&self) -> u64 {
+        self.number
+    }
+
+    /// Timestamp of the opening, in seconds since the Unix epoch
+    pub fn get_opened(&self) -> u64 {
+        self.opened
+    }
+
+    /// Cash put in the drawer at the opening
+    pub fn get_float(&self) -> f64 {
+        self.float
+    }
+
+    /// Identifier of the operator who opened the shift, if any
+    pub fn get_operator(&self) -> Option<&str> {
+        self.operator.as_deref()
+    }
+
+    /// Identifier of the terminal of the shift, whose drawer it reconciles
+    pub fn get_terminal(&self) -> Option<&str> {
+        self.terminal.as_deref()
+    }
+
+    /// Reconciliation of the cash `counted` in the drawer at `closed` against the sales
+    /// closed on the terminal of the shift
+    pub(crate) fn report<'a, I>(&self, sales: I, closed: u64, counted: f64) -> ShiftReport
+    where
+        I: IntoIterator<Item = &'a Sale>,
+    {
+        let mut report = ShiftReport {
+            shift: self.clone(),
+            closed,
+            sales: 0,
+            refunds: 0,
+            tenders: vec![],
+            cash_refunds: 0.0,
+            expected: 0.0,
+            counted: round_cents(counted),
+        };
+
+        let sales = sales.into_iter().filter(|sale| {
+            sale.get_id() >= self.first_sale && sale.get_terminal() == self.get_terminal()
+        });
+        for sale in sales {
+            if sale.get_refunded_sale().is_some() {
+                report.refunds += 1;
+                report.cash_refunds -= match sale.get_tenders() {
+                    tenders if tenders.is_empty() => sale.get_totals().get_total(),
+                    tenders => tenders
+                        .iter()
+                        .filter(|t| t.get_tender() == Tender::Cash)
+                        .map(|t| t.get_amount())
+                        .sum(),
+                };
+                continue;
+            }
+            report.sales += 1;
+            for tender in sale.get_tenders() {
+                add_tender(
+                    &mut report.tenders,
+                    tender.get_tender(),
+                    tender.get_amount(),
+                );
+            }
+            if sale.get_change_due() > 0.0 {
+                add_tender(&mut report.tenders, Tender::Cash, -sale.get_change_due());
+            }
+        }
+
+        report.cash_refunds = round_cents(report.cash_refunds);
+        let cash = report
+            .tenders
+            .iter()
+            .find(|t| t.get_tender() == Tender::Cash)
+            .map_or(0.0, |t| t.get_amount());
+        report.expected = round_cents(self.float + cash - report.cash_refunds);
+        report
+    }
+}
+
+/// Over/short report of a closed [Shift], comparing the cash expected in the drawer with the
+/// cash counted
+///
+/// Refunds are paid back in the tenders of their sale, and only their cash comes out of the
+/// drawer. Refunds of sales closed without tenders are taken as paid back from the drawer
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ShiftReport {
+    shift: Shift,
+    closed: u64,
+    sales: usize,
+    refunds: usize,
+    tenders: Vec<TenderAmount>,
+    cash_refunds: f64,
+    expected: f64,
+    counted: f64,
+}
+
+impl ShiftReport {
+    pub fn get_shift(&self) -> &Shift {
+        &self.shift
+    }
+
+    /// Timestamp of the close, in seconds since the Unix epoch
+    pub fn get_closed(&self) -> u64 {
+        self.closed
+    }
+
+    /// Count of the sales, without the refunds
+    pub fn get_sales(&self) -> usize {
+        self.sales
+    }
+
+    pub fn get_refunds(&self) -> usize {
+        self.refunds
+    }
+
+    /// Amount taken per tender, cash without the change given back
+    pub fn get_tenders(&self) -> &Vec<TenderAmount> {
+        &self.tenders
+    }
+
+    /// Cash paid back by the refunds
+    pub fn get_cash_refunds(&self) -> f64 {
+        self.cash_refunds
+    }
+
+    /// Float plus the cash taken, less the cash refunds
+    pub fn get_expected(&self) -> f64 {
+        self.expected
+    }
+
+    pub fn get_counted(&self) -> f64 {
+        self.counted
+    }
+
+    /// Counted less expected cash: over when positive, short when negative
+    pub fn get_difference(&self) -> f64 {
+        round_cents(self.counted - self.expected)
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.get_difference() == 0.0
+    }
+
+    /// Plain text lines of at most `width` characters, as printed on a till roll
+    pub fn render_text(&self, width: usize) -> Vec<String> {
+        let mut text = vec![
+            columns("SHIFT", &format!("#{}", self.shift.number), width),
+            columns("Opened", &datetime(self.shift.opened), width),
+            columns("Closed", &datetime(self.closed), width),
+        ];
+        if let Some(terminal) = &self.shift.terminal {
+            text.push(columns("Terminal", terminal, width));
+        }
+        if let Some(operator) = &self.shift.operator {
+            text.push(columns("Operator", operator, width));
+        }
+        text.push("-".repeat(width));
+        text.push(columns("Sales", &self.sales.to_string(), width));
+        text.push(columns("Refunds", &self.refunds.to_string(), width));
+        for tender in &self.tenders {
+            let name = tender.get_tender().get_name().to_uppercase();
+            text.push(columns(
+                &name,
+                &format!("{:.2}", tender.get_amount()),
+                width,
+            ));
+        }
+        text.push("-".repeat(width));
+        text.push(columns("Float", &format!("{:.2}", self.shift.float), width));
+        text.push(columns(
+            "Cash refunds",
+            &format!("-{:.2}", self.cash_refunds),
+            width,
+        ));
+        text.push(columns("Expected", &format!("{:.2}", self.expected), width));
+        text.push(columns("Counted", &format!("{:.2}", self.counted), width));
+        let difference = self.get_difference();
+        let label = if difference < 0.0 { "SHORT" } else { "OVER" };
+        text.push(columns(label, &format!("{:.2}", difference.abs()), width));
+        text
+    }
+}
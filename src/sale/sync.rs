@@ -0,0 +1,466 @@
+use crate::lock;
+use crate::prelude::{ErrorVariant, Sale};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "json")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "json")]
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Answer of a [SaleBackend] to an upload it could process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Upload {
+    /// Sale stored by the backend
+    Accepted,
+    /// Same sale already stored, as when the answer to a previous upload was lost
+    Duplicate,
+    /// Different sale stored under the same terminal and number, as after a sequence reset;
+    /// the sale is set aside for the head office to resolve
+    Conflict { reason: String },
+}
+
+/// Head office system the completed sales and refunds of a terminal are uploaded to, such as a
+/// Postgres database or a REST service
+///
+/// Integrators implement it for their backend. An error means the backend could not be
+/// reached, and the sale is uploaded again by the next sync; backends should fail fast, as the
+/// checkouts wait for them
+pub trait SaleBackend: Send + Sync {
+    /// Upload `sale` of `terminal`
+    fn upload(&self, terminal: &str, sale: &Sale) -> Result<Upload, ErrorVariant>;
+}
+
+/// Backend shared with other parts of the application
+impl<B: SaleBackend + ?Sized> SaleBackend for Arc<B> {
+    fn upload(&self, terminal: &str, sale: &Sale) -> Result<Upload, ErrorVariant> {
+        (**self).upload(terminal, sale)
+    }
+}
+
+/// Sale of a terminal waiting in a [SaleQueue]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct QueuedSale {
+    terminal: String,
+    sale: Sale,
+}
+
+impl QueuedSale {
+    /// Identifier of the terminal of the sale
+    pub fn get_terminal(&self) -> &str {
+        &self.terminal
+    }
+
+    pub fn get_sale(&self) -> &Sale {
+        &self.sale
+    }
+
+    fn is(&self, terminal: &str, id: u64) -> bool {
+        self.terminal == terminal && self.sale.get_id() == id
+    }
+}
+
+/// Sale the backend answered with [Upload::Conflict]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct SyncConflict {
+    queued: QueuedSale,
+    reason: String,
+}
+
+impl SyncConflict {
+    pub fn get_terminal(&self) -> &str {
+        self.queued.get_terminal()
+    }
+
+    pub fn get_sale(&self) -> &Sale {
+        self.queued.get_sale()
+    }
+
+    /// Reason given by the backend
+    pub fn get_reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+/// Outcome of a [SaleQueue::sync]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncReport {
+    uploaded: usize,
+    duplicates: usize,
+    conflicts: usize,
+    pending: usize,
+    error: Option<ErrorVariant>,
+}
+
+impl SyncReport {
+    /// Sales accepted by the backend
+    pub fn get_uploaded(&self) -> usize {
+        self.uploaded
+    }
+
+    /// Sales the backend already had
+    pub fn get_duplicates(&self) -> usize {
+        self.duplicates
+    }
+
+    /// Sales set aside as conflicts
+    pub fn get_conflicts(&self) -> usize {
+        self.conflicts
+    }
+
+    /// Sales still waiting for the backend
+    pub fn get_pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Error of the backend stopping the sync, when it could not be reached
+    pub fn get_error(&self) -> Option<&ErrorVariant> {
+        self.error.as_ref()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.pending == 0
+    }
+}
+
+/// Entry of the file of a [SaleQueue]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(rename_all = "snake_case"))]
+enum Record {
+    Queued(Box<QueuedSale>),
+    /// Sale `sale` of `terminal` stored by the backend
+    Synced {
+        terminal: String,
+        sale: u64,
+    },
+    Conflict {
+        terminal: String,
+        sale: u64,
+        reason: String,
+    },
+    /// Conflict of sale `sale` of `terminal` resolved
+    Resolved {
+        terminal: String,
+        sale: u64,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    pending: Vec<QueuedSale>,
+    conflicts: Vec<SyncConflict>,
+    #[cfg(feature = "json")]
+    file: Option<(String, File)>,
+}
+
+impl Queue {
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::Queued(queued) => self.pending.push(*queued),
+            Record::Synced { terminal, sale } => self.pending.retain(|q| !q.is(&terminal, sale)),
+            Record::Conflict {
+                terminal,
+                sale,
+                reason,
+            } => {
+                if let Some(i) = self.pending.iter().position(|q| q.is(&terminal, sale)) {
+                    let queued = self.pending.remove(i);
+                    self.conflicts.push(SyncConflict { queued, reason });
+                }
+            }
+            Record::Resolved { terminal, sale } => {
+                self.conflicts.retain(|c| !c.queued.is(&terminal, sale))
+            }
+        }
+    }
+
+    /// Write the record to the file of the queue, if any, then apply it
+    ///
+    /// The file is emptied once nothing is left pending nor in conflict, so it only grows
+    /// through an outage
+    fn append(&mut self, record: Record) -> Result<(), ErrorVariant> {
+        #[cfg(feature = "json")]
+        let written = match &mut self.file {
+            Some((path, file)) => serde_json::to_string(&record)
+                .map_err(|e| e.to_string())
+                .and_then(|line| {
+                    writeln!(file, "{}", line)
+                        .and_then(|()| file.sync_data())
+                        .map_err(|e| e.to_string())
+                })
+                .map_err(|source| ErrorVariant::IoError {
+                    path: path.clone(),
+                    source,
+                }),
+            None => Ok(()),
+        };
+        #[cfg(not(feature = "json"))]
+        let written = Ok(());
+
+        self.apply(record);
+
+        #[cfg(feature = "json")]
+        if let (Ok(()), true, true, Some((path, file))) = (
+            &written,
+            self.pending.is_empty(),
+            self.conflicts.is_empty(),
+            &self.file,
+        ) {
+            return file.set_len(0).map_err(|e| ErrorVariant::IoError {
+                path: path.clone(),
+                source: e.to_string(),
+            });
+        }
+        written
+    }
+}
+
+/// Completed sales and refunds waiting to be uploaded to the [SaleBackend] of the queue, so the
+/// terminal keeps selling through network outages
+///
+/// Given to [TerminalBuilder::with_sale_queue](crate::prelude::TerminalBuilder::with_sale_queue),
+/// every sale and refund is queued and a sync is attempted; the sales left pending by an
+/// unreachable backend are uploaded in order by the next sync, such as one of
+/// [Terminal::sync_sales](crate::prelude::Terminal::sync_sales). Queues opened on a file keep
+/// the pending sales across restarts
+///
+/// # Example
+///
+/// ```
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::sync::{Arc, Mutex};
+/// use store_terminal::prelude::*;
+///
+/// #[derive(Default)]
+/// struct HeadOffice {
+///     online: AtomicBool,
+///     sales: Mutex<Vec<(String, u64)>>,
+/// }
+///
+/// impl SaleBackend for HeadOffice {
+///     fn upload(&self, terminal: &str, sale: &Sale) -> Result<Upload, ErrorVariant> {
+///         if !self.online.load(Ordering::SeqCst) {
+///             return Err(ErrorVariant::IoError {
+///                 path: "head office".to_string(),
+///                 source: "connection refused".to_string(),
+///             });
+///         }
+///         let mut sales = self.sales.lock().unwrap();
+///         let key = (terminal.to_string(), sale.get_id());
+///         if sales.contains(&key) {
+///             return Ok(Upload::Duplicate);
+///         }
+///         if sale.get_totals().get_total() > 100.0 {
+///             return Ok(Upload::Conflict { reason: "over the limit".to_string() });
+///         }
+///         sales.push(key);
+///         Ok(Upload::Accepted)
+///     }
+/// }
+///
+/// let office = Arc::new(HeadOffice::default());
+/// let terminal = TerminalBuilder::new()
+///     .with_sale_queue(SaleQueue::new().with_backend(office.clone()))
+///     .build()
+///     .unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// // The till keeps working through the outage
+/// terminal.scan("AB").unwrap();
+/// terminal.checkout().unwrap();
+/// terminal.scan("BBBBBBBBB").unwrap();
+/// terminal.checkout().unwrap();
+/// assert_eq!(terminal.get_sale_queue().get_pending().len(), 2);
+///
+/// office.online.store(true, Ordering::SeqCst);
+/// terminal.scan("A").unwrap();
+/// terminal.checkout().unwrap();
+///
+/// let queue = terminal.get_sale_queue();
+/// assert!(queue.get_pending().is_empty());
+/// assert_eq!(*office.sales.lock().unwrap(), vec![("terminal-1".to_string(), 1), ("terminal-1".to_string(), 3)]);
+/// let conflict = &queue.get_conflicts()[0];
+/// assert_eq!((conflict.get_sale().get_id(), conflict.get_reason()), (2, "over the limit"));
+///
+/// queue.resolve(conflict.get_terminal(), 2).unwrap();
+/// assert!(queue.get_conflicts().is_empty());
+/// ```
+#[derive(Default)]
+pub struct SaleQueue {
+    queue: Mutex<Queue>,
+    backend: Option<Arc<dyn SaleBackend>>,
+}
+
+impl SaleQueue {
+    /// Queue kept in memory only
+    pub fn new() -> Self {
+        SaleQueue::default()
+    }
+
+    /// Queue stored in the file at `path`, created if missing, restoring its pending sales and
+    /// conflicts
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// struct Offline;
+    ///
+    /// impl SaleBackend for Offline {
+    ///     fn upload(&self, _: &str, _: &Sale) -> Result<Upload, ErrorVariant> {
+    ///         Err(ErrorVariant::InvalidUrl { url: "http://office".to_string() })
+    ///     }
+    /// }
+    ///
+    /// let path = std::env::temp_dir().join(format!("queue-{}.jsonl", std::process::id()));
+    /// let path = path.to_str().unwrap();
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_sale_queue(SaleQueue::open(path).unwrap().with_backend(Offline))
+    ///     .build()
+    ///     .unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AB").unwrap();
+    /// let sale = terminal.checkout().unwrap();
+    ///
+    /// let report = terminal.sync_sales().unwrap();
+    /// assert_eq!((report.get_uploaded(), report.get_pending()), (0, 1));
+    /// assert!(report.get_error().is_some());
+    ///
+    /// let restored = SaleQueue::open(path).unwrap();
+    /// assert_eq!(restored.get_pending()[0].get_sale(), &sale);
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn open(path: &str) -> Result<Self, ErrorVariant> {
+        let io_error = |e: std::io::Error| ErrorVariant::IoError {
+            path: path.to_string(),
+            source: e.to_string(),
+        };
+
+        let mut queue = Queue::default();
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                    let record = serde_json::from_str(line)
+                        .map_err(|e| ErrorVariant::from_json_error(e, "sale queue"))?;
+                    queue.apply(record);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => return Err(io_error(e)),
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+        queue.file = Some((path.to_string(), file));
+
+        Ok(SaleQueue {
+            queue: Mutex::new(queue),
+            backend: None,
+        })
+    }
+
+    /// Backend the sales are uploaded to; without one, no sale is queued
+    pub fn with_backend<B: SaleBackend + 'static>(mut self, backend: B) -> Self {
+        self.backend = Some(Arc::new(backend));
+        self
+    }
+
+    pub fn has_backend(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    /// Sales waiting for the backend, oldest first
+    pub fn get_pending(&self) -> Vec<QueuedSale> {
+        lock::lock(&self.queue).pending.clone()
+    }
+
+    /// Sales set aside by the backend, until resolved
+    pub fn get_conflicts(&self) -> Vec<SyncConflict> {
+        lock::lock(&self.queue).conflicts.clone()
+    }
+
+    /// Drop the conflict of sale `id` of `terminal`, once handled by the head office
+    pub fn resolve(&self, terminal: &str, id: u64) -> Result<(), ErrorVariant> {
+        let mut queue = lock::lock(&self.queue);
+        if !queue.conflicts.iter().any(|c| c.queued.is(terminal, id)) {
+            return Err(ErrorVariant::EntityNotFound {
+                store: "sync conflicts".to_string(),
+                code: format!("{}/{}", terminal, id),
+            });
+        }
+        queue.append(Record::Resolved {
+            terminal: terminal.to_string(),
+            sale: id,
+        })
+    }
+
+    /// Queue `sale` of `terminal`, if the queue has a backend
+    pub(crate) fn push(&self, terminal: &str, sale: &Sale) -> Result<(), ErrorVariant> {
+        if self.backend.is_none() {
+            return Ok(());
+        }
+        lock::lock(&self.queue).append(Record::Queued(Box::new(QueuedSale {
+            terminal: terminal.to_string(),
+            sale: sale.clone(),
+        })))
+    }
+
+    /// Upload the pending sales in order, stopping at the first the backend can't be reached
+    /// for
+    ///
+    /// The queue is not locked during the uploads, so checkouts carry on; a sale uploaded twice
+    /// by concurrent syncs is answered with [Upload::Duplicate]. Errors are those of the file of
+    /// the queue
+    pub fn sync(&self) -> Result<SyncReport, ErrorVariant> {
+        let mut report = SyncReport {
+            uploaded: 0,
+            duplicates: 0,
+            conflicts: 0,
+            pending: 0,
+            error: None,
+        };
+        let backend = match &self.backend {
+            Some(backend) => backend,
+            None => return Ok(report),
+        };
+
+        for queued in self.get_pending() {
+            let terminal = queued.terminal.clone();
+            let sale = queued.sale.get_id();
+            let record = match backend.upload(&terminal, &queued.sale) {
+                Ok(Upload::Accepted) => {
+                    report.uploaded += 1;
+                    Record::Synced { terminal, sale }
+                }
+                Ok(Upload::Duplicate) => {
+                    report.duplicates += 1;
+                    Record::Synced { terminal, sale }
+                }
+                Ok(Upload::Conflict { reason }) => {
+                    report.conflicts += 1;
+                    Record::Conflict {
+                        terminal,
+                        sale,
+                        reason,
+                    }
+                }
+                Err(e) => {
+                    report.error = Some(e);
+                    break;
+                }
+            };
+            lock::lock(&self.queue).append(record)?;
+        }
+
+        report.pending = lock::lock(&self.queue).pending.len();
+        Ok(report)
+    }
+}
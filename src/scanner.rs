@@ -0,0 +1,238 @@
+//! Physical barcode scanners, attached as serial ports or keyboard wedges
+//!
+//! A [Scanner] reads the raw output of the device from any [Read] source: the device file of a
+//! serial port, such as `/dev/ttyUSB0` configured beforehand with `stty`, or the standard input
+//! for a USB-HID scanner in keyboard wedge mode. The framing of the device is described by a
+//! [ScannerConfig]
+//!
+//! # Example
+//!
+//! ```
+//! use store_terminal::prelude::*;
+//! use store_terminal::scanner::{Scanner, ScannerConfig, Symbology};
+//!
+//! let terminal = Terminal::new().unwrap();
+//! terminal.load_demo_catalog().unwrap();
+//!
+//! // STX prefix, symbology identifier and CR suffix, as sent by most serial scanners
+//! let device: &[u8] = b"\x02]C0A\r\x02]E0B\r";
+//! let config = ScannerConfig::new().with_prefix("\x02").with_symbology_identifier(true);
+//! let mut scanner = Scanner::new(device, config);
+//!
+//! let code = scanner.next_code().unwrap().unwrap();
+//! assert_eq!(code.get_code(), "A");
+//! assert_eq!(code.get_symbology(), Some(&Symbology::Code128));
+//!
+//! assert_eq!(scanner.run(&terminal).unwrap(), 1);
+//! assert_eq!(terminal.get_cart().unwrap().get_total_price(), 12.0);
+//!
+//! // Codes ended by an ETX suffix only
+//! let device: &[u8] = b"A\x03A\x03";
+//! let mut scanner = Scanner::new(device, ScannerConfig::new().with_suffix("\x03"));
+//! assert_eq!(scanner.run(&terminal).unwrap(), 2);
+//! assert_eq!(terminal.get_cart().unwrap().get_total_price(), 16.0);
+//! ```
+
+use crate::prelude::{ErrorVariant, Terminal};
+use std::fs::File;
+use std::io::{BufReader, Bytes, Read};
+use std::path::Path;
+
+/// Symbology reported by an AIM identifier, such as `]E0` for EAN-13
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbology {
+    Code39,
+    Code128,
+    Ean,
+    Interleaved2Of5,
+    Codabar,
+    DataMatrix,
+    QrCode,
+    Pdf417,
+    /// Any other symbology, by its AIM code character
+    Other(char),
+}
+
+impl Symbology {
+    fn from_aim(c: char) -> Self {
+        match c {
+            'A' => Symbology::Code39,
+            'C' => Symbology::Code128,
+            'E' => Symbology::Ean,
+            'I' => Symbology::Interleaved2Of5,
+            'F' => Symbology::Codabar,
+            'd' => Symbology::DataMatrix,
+            'Q' => Symbology::QrCode,
+            'L' => Symbology::Pdf417,
+            c => Symbology::Other(c),
+        }
+    }
+}
+
+/// Code read by a [Scanner]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannedCode {
+    code: String,
+    symbology: Option<Symbology>,
+}
+
+impl ScannedCode {
+    pub fn get_code(&self) -> &str {
+        &self.code
+    }
+
+    /// Symbology of the barcode, if the scanner sends AIM identifiers
+    pub fn get_symbology(&self) -> Option<&Symbology> {
+        self.symbology.as_ref()
+    }
+}
+
+/// Framing of the codes sent by a scanner
+///
+/// Codes end with a carriage return or a line feed, or with the suffix when one is set
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScannerConfig {
+    prefix: String,
+    suffix: String,
+    symbology_identifier: bool,
+}
+
+impl ScannerConfig {
+    pub fn new() -> Self {
+        ScannerConfig::default()
+    }
+
+    /// Characters sent before every code, stripped when present
+    pub fn with_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Characters sent after every code, such as ETX, ending the code
+    pub fn with_suffix<S: Into<String>>(mut self, suffix: S) -> Self {
+        self.suffix = suffix.into();
+        self
+    }
+
+    /// Whether codes start with a three character AIM symbology identifier, such as `]E0`
+    pub fn with_symbology_identifier(mut self, enabled: bool) -> Self {
+        self.symbology_identifier = enabled;
+        self
+    }
+
+    pub fn get_prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn get_suffix(&self) -> &str {
+        &self.suffix
+    }
+
+    pub fn has_symbology_identifier(&self) -> bool {
+        self.symbology_identifier
+    }
+
+    /// Code framed by `raw`, with the prefix and the symbology identifier removed
+    fn decode(&self, raw: &str) -> Option<ScannedCode> {
+        let raw = raw.trim();
+        let raw = raw.strip_prefix(self.prefix.as_str()).unwrap_or(raw);
+
+        let mut symbology = None;
+        let mut code = raw;
+        if self.symbology_identifier {
+            let mut chars = raw.chars();
+            if let (Some(']'), Some(aim), Some(_modifier)) =
+                (chars.next(), chars.next(), chars.next())
+            {
+                symbology = Some(Symbology::from_aim(aim));
+                code = chars.as_str();
+            }
+        }
+
+        if code.is_empty() {
+            None
+        } else {
+            Some(ScannedCode {
+                code: code.to_string(),
+                symbology,
+            })
+        }
+    }
+}
+
+/// Reader of the codes sent by a scanner
+pub struct Scanner<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    config: ScannerConfig,
+}
+
+impl Scanner<File> {
+    /// Scanner reading a device file, such as a serial port
+    pub fn open<P: AsRef<Path>>(path: P, config: ScannerConfig) -> Result<Self, ErrorVariant> {
+        let path = path.as_ref();
+        let device = File::open(path).map_err(|e| ErrorVariant::IoError {
+            path: path.display().to_string(),
+            source: e.to_string(),
+        })?;
+        Ok(Scanner::new(device, config))
+    }
+}
+
+impl<R: Read> Scanner<R> {
+    pub fn new(device: R, config: ScannerConfig) -> Self {
+        Scanner {
+            bytes: BufReader::new(device).bytes(),
+            config,
+        }
+    }
+
+    pub fn get_config(&self) -> &ScannerConfig {
+        &self.config
+    }
+
+    /// Wait for the next code, or `None` once the device is closed
+    ///
+    /// Bytes are read as Latin-1, so the device doesn't need to send valid UTF-8
+    pub fn next_code(&mut self) -> Result<Option<ScannedCode>, ErrorVariant> {
+        let mut raw = String::new();
+        loop {
+            let byte = match self.bytes.next() {
+                Some(byte) => byte.map_err(|e| ErrorVariant::IoError {
+                    path: "scanner".to_string(),
+                    source: e.to_string(),
+                })?,
+                None if raw.is_empty() => return Ok(None),
+                None => return Ok(self.config.decode(&raw)),
+            };
+
+            let framed = match char::from(byte) {
+                '\r' | '\n' => raw.len(),
+                c => {
+                    raw.push(c);
+                    match raw.strip_suffix(self.config.suffix.as_str()) {
+                        Some(framed) if !self.config.suffix.is_empty() => framed.len(),
+                        _ => continue,
+                    }
+                }
+            };
+            let code = self.config.decode(&raw[..framed]);
+            raw.clear();
+            if code.is_some() {
+                return Ok(code);
+            }
+        }
+    }
+
+    /// Scan every code into the terminal until the device is closed, returning their count
+    ///
+    /// Every code is scanned as an exact product code; unknown codes are reported to the
+    /// subscribers of the terminal
+    pub fn run(&mut self, terminal: &Terminal) -> Result<usize, ErrorVariant> {
+        let mut count = 0;
+        while let Some(code) = self.next_code()? {
+            terminal.scan([code.get_code()])?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
@@ -0,0 +1,205 @@
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Read, Write};
+
+/// Largest accepted request body, in bytes
+const MAX_BODY_LEN: usize = 1 << 20;
+
+/// Largest accepted request line and headers, in bytes
+const MAX_HEAD_LEN: usize = 16 << 10;
+
+/// Most headers accepted in a request
+const MAX_HEADERS: usize = 100;
+
+/// HTTP request routed by [handle](crate::server::handle)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    pub fn new<M, P, B>(method: M, path: P, body: B) -> Self
+    where
+        M: Into<String>,
+        P: Into<String>,
+        B: Into<String>,
+    {
+        Request {
+            method: method.into(),
+            path: path.into(),
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    pub fn with_header<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+
+    /// Path of the request, without the query string
+    pub fn get_path(&self) -> &str {
+        self.path.split('?').next().unwrap_or_default()
+    }
+
+    /// Value of the first header named `name`, ignoring its case
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_body(&self) -> &str {
+        &self.body
+    }
+
+    /// Read a request, with its body delimited by `Content-Length`
+    ///
+    /// The request line and the headers are capped to [MAX_HEAD_LEN] bytes and [MAX_HEADERS]
+    /// headers, and the body to [MAX_BODY_LEN] bytes
+    pub(crate) fn read<R: BufRead>(reader: &mut R) -> Result<Self, String> {
+        let mut left = MAX_HEAD_LEN;
+        let mut line = String::new();
+        read_head_line(reader, &mut line, &mut left)?;
+        let mut request_line = line.split_whitespace();
+        let (method, path) = match (request_line.next(), request_line.next()) {
+            (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+            _ => return Err(format!("malformed request line `{}`", line.trim())),
+        };
+
+        let mut headers = vec![];
+        loop {
+            line.clear();
+            read_head_line(reader, &mut line, &mut left)?;
+            let header = line.trim();
+            if header.is_empty() {
+                break;
+            }
+            if headers.len() == MAX_HEADERS {
+                return Err(format!("more than {} headers", MAX_HEADERS));
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+
+        let mut request = Request {
+            method,
+            path,
+            headers,
+            body: String::new(),
+        };
+        let len: usize = match request.get_header("content-length") {
+            Some(len) => len
+                .parse()
+                .map_err(|_| format!("invalid content length `{}`", len))?,
+            None => 0,
+        };
+        if len > MAX_BODY_LEN {
+            return Err(format!("body longer than {} bytes", MAX_BODY_LEN));
+        }
+
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+        request.body = String::from_utf8(body).map_err(|e| e.to_string())?;
+
+        Ok(request)
+    }
+}
+
+/// Read a line of the request line or headers, out of the `left` bytes they may still take
+fn read_head_line<R: BufRead>(
+    reader: &mut R,
+    line: &mut String,
+    left: &mut usize,
+) -> Result<(), String> {
+    let read = Read::take(&mut *reader, *left as u64)
+        .read_line(line)
+        .map_err(|e| e.to_string())?;
+    *left -= read;
+    match line.ends_with('\n') {
+        true => Ok(()),
+        false if *left == 0 => Err(format!("request head longer than {} bytes", MAX_HEAD_LEN)),
+        false => Err("connection closed before the end of the request head".to_string()),
+    }
+}
+
+/// JSON response of [handle](crate::server::handle)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    status: u16,
+    body: Value,
+    content_type: &'static str,
+}
+
+impl Response {
+    pub(crate) fn new(status: u16, body: Value) -> Self {
+        Response {
+            status,
+            body,
+            content_type: "application/json",
+        }
+    }
+
+    /// Plain text response, written as is
+    pub(crate) fn text(status: u16, body: String, content_type: &'static str) -> Self {
+        Response {
+            status,
+            body: Value::String(body),
+            content_type,
+        }
+    }
+
+    pub(crate) fn error<S: ToString>(status: u16, message: S) -> Self {
+        Response::new(status, json!({ "error": message.to_string() }))
+    }
+
+    pub fn get_status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn get_body(&self) -> &Value {
+        &self.body
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let body = match &self.body {
+            Value::String(text) if self.content_type != "application/json" => text.clone(),
+            body => body.to_string(),
+        };
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            body.len(),
+            body
+        )?;
+        writer.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
@@ -0,0 +1,179 @@
+use crate::lock;
+use crate::prelude::{Database, ErrorVariant, Terminal, TerminalBuilder, TerminalConfig};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Sessions = Arc<Mutex<HashMap<SocketAddr, Arc<Terminal>>>>;
+
+/// Listener of networked barcode scanners sending one code per line
+///
+/// Every connection gets its own cart, in a terminal identified by the address and port of the
+/// scanner and sharing the database of the listener, so scanners behind the same address don't
+/// share a cart. The cart outlives the connection, for the store software to pick it up with
+/// [ScannerSessions::get_session]
+///
+/// Lines are scanned as exact product codes, ignoring surrounding whitespaces and empty lines. A
+/// line failing to scan, unknown codes included, is answered with the error on a line of its
+/// own, and the following lines are scanned still
+///
+/// # Example
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use std::net::{Shutdown, TcpStream};
+/// use std::thread;
+/// use store_terminal::prelude::*;
+/// use store_terminal::server::ScannerListener;
+///
+/// let database = Database::new();
+/// let terminal = TerminalBuilder::new().with_database(database.clone()).build().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let listener = ScannerListener::bind("127.0.0.1:0", database).unwrap();
+/// let addr = listener.local_addr().unwrap();
+/// let sessions = listener.clone_sessions();
+/// thread::spawn(move || {
+///     while let Err(error) = listener.serve() {
+///         eprintln!("failed to accept a scanner: {}", error);
+///         thread::sleep(std::time::Duration::from_millis(100));
+///     }
+/// });
+///
+/// let mut scanner = TcpStream::connect(addr).unwrap();
+/// scanner.write_all(b"A\r\nB\n\nX\nC\n").unwrap();
+/// scanner.shutdown(Shutdown::Write).unwrap();
+/// let mut errors = String::new();
+/// scanner.read_to_string(&mut errors).unwrap();
+/// assert_eq!(errors.lines().count(), 1);
+/// assert!(errors.contains("X"));
+///
+/// let scanner = scanner.local_addr().unwrap();
+/// let lane = sessions.get_session(&scanner).unwrap();
+/// assert_eq!(lane.get_config().get_terminal_id(), &scanner.to_string());
+/// assert_eq!(lane.get_cart().unwrap().get_total_price(), 15.25);
+/// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 0.0);
+///
+/// assert!(sessions.remove_session(&scanner).is_some());
+/// assert!(sessions.get_addresses().is_empty());
+/// ```
+pub struct ScannerListener {
+    listener: TcpListener,
+    database: Database,
+    sessions: Sessions,
+}
+
+/// Handle to the carts of the scanners of a [ScannerListener], usable while it serves
+#[derive(Clone)]
+pub struct ScannerSessions {
+    sessions: Sessions,
+}
+
+impl ScannerListener {
+    pub fn bind(addr: &str, database: Database) -> Result<Self, ErrorVariant> {
+        let listener = TcpListener::bind(addr).map_err(|e| ErrorVariant::IoError {
+            path: addr.to_string(),
+            source: e.to_string(),
+        })?;
+        Ok(ScannerListener {
+            listener,
+            database,
+            sessions: Arc::default(),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, ErrorVariant> {
+        self.listener
+            .local_addr()
+            .map_err(|e| ErrorVariant::IoError {
+                path: "listener".to_string(),
+                source: e.to_string(),
+            })
+    }
+
+    /// Handle to the carts of the scanners, to keep before calling [ScannerListener::serve]
+    pub fn clone_sessions(&self) -> ScannerSessions {
+        ScannerSessions {
+            sessions: Arc::clone(&self.sessions),
+        }
+    }
+
+    /// Accept scanners until a connection fails to be accepted, returning the error
+    ///
+    /// The carts of the scanners are kept, so serving again picks up where the listener stopped
+    pub fn serve(&self) -> Result<(), ErrorVariant> {
+        for stream in self.listener.incoming() {
+            let stream = stream.map_err(|e| ErrorVariant::IoError {
+                path: "listener".to_string(),
+                source: e.to_string(),
+            })?;
+            let terminal = match stream.peer_addr() {
+                Ok(peer) => self.session(peer)?,
+                Err(_) => continue,
+            };
+            thread::spawn(move || scan_lines(&terminal, stream));
+        }
+        Ok(())
+    }
+
+    fn session(&self, addr: SocketAddr) -> Result<Arc<Terminal>, ErrorVariant> {
+        let mut sessions = lock::lock(&self.sessions);
+        if let Some(terminal) = sessions.get(&addr) {
+            return Ok(Arc::clone(terminal));
+        }
+
+        let terminal = TerminalBuilder::new()
+            .with_database(self.database.clone())
+            .with_config(TerminalConfig::new(addr.to_string()))
+            .build()?;
+        let terminal = Arc::new(terminal);
+        sessions.insert(addr, Arc::clone(&terminal));
+        Ok(terminal)
+    }
+}
+
+impl ScannerSessions {
+    /// Terminal of the connection of the scanner at `addr`, if it has connected
+    pub fn get_session(&self, addr: &SocketAddr) -> Option<Arc<Terminal>> {
+        lock::lock(&self.sessions).get(addr).cloned()
+    }
+
+    /// Forget the terminal of the connection at `addr`, once its cart is dealt with
+    pub fn remove_session(&self, addr: &SocketAddr) -> Option<Arc<Terminal>> {
+        lock::lock(&self.sessions).remove(addr)
+    }
+
+    /// Addresses of the connections of the scanners
+    pub fn get_addresses(&self) -> Vec<SocketAddr> {
+        let mut addresses: Vec<SocketAddr> = lock::lock(&self.sessions).keys().copied().collect();
+        addresses.sort();
+        addresses
+    }
+}
+
+/// Scan every line until the scanner disconnects, answering the failed scans with their error
+fn scan_lines(terminal: &Terminal, stream: TcpStream) {
+    let mut errors = &stream;
+    for line in BufReader::new(&stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let code = line.trim();
+        if code.is_empty() {
+            continue;
+        }
+        let error = match terminal.scan([code]) {
+            Ok(report) if report.get_unknown().is_empty() => continue,
+            Ok(_) => ErrorVariant::ProductNotFound {
+                code: code.to_string(),
+            },
+            Err(e) => e,
+        };
+        // The subscribers of the terminal are told as well, so a scanner that doesn't read its
+        // replies loses nothing
+        let _ = writeln!(errors, "{}", error);
+    }
+}
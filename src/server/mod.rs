@@ -0,0 +1,591 @@
+//! HTTP interface of the terminal, for web-based frontends
+//!
+//! Every request and response body is a JSON document:
+//!
+//! | Route | Description |
+//! |-------|-------------|
+//! | `POST /scan` | Scan `{"codes": "A B"}` or `{"codes": ["A", "B"]}`, as [Terminal::scan] |
+//! | `GET /cart` | Items of the optimized cart and its total |
+//! | `GET /cart/stream` | WebSocket pushing the cart summary on every change of the terminal |
+//! | `POST /checkout` | Sale of the optimized cart, with its items and total, emptying the cart |
+//! | `GET /products`, `GET /promotions` | All the entities, sorted by code |
+//! | `POST /products`, `POST /promotions` | Insert a new entity |
+//! | `GET /products/{code}`, `GET /promotions/{code}` | Fetch an entity |
+//! | `PUT /products/{code}`, `PUT /promotions/{code}` | Insert or replace an entity |
+//! | `DELETE /products/{code}`, `DELETE /promotions/{code}` | Remove an entity |
+//! | `GET /openapi.json` | OpenAPI document of these routes, see [openapi] |
+//!
+//! Failures are reported as `{"error": "<message>"}`, with the status code of the error.
+//! A server given [Metrics] with [Server::with_metrics] also serves them as plain text on
+//! `GET /metrics`
+//!
+//! # Example
+//!
+//! ```
+//! use std::io::{BufRead, BufReader, Read, Write};
+//! use std::net::TcpStream;
+//! use std::sync::Arc;
+//! use std::thread;
+//! use store_terminal::prelude::*;
+//! use store_terminal::server::Server;
+//!
+//! let terminal = Arc::new(Terminal::new().unwrap());
+//! terminal.load_demo_catalog().unwrap();
+//!
+//! let server = Server::bind("127.0.0.1:0", terminal.clone()).unwrap();
+//! let addr = server.local_addr().unwrap();
+//! thread::spawn(move || server.serve());
+//!
+//! let mut stream = TcpStream::connect(addr).unwrap();
+//! stream
+//!     .write_all(b"GET /cart/stream HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+//!         Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n")
+//!     .unwrap();
+//!
+//! let mut stream = BufReader::new(stream);
+//! let mut handshake = String::new();
+//! while !handshake.ends_with("\r\n\r\n") {
+//!     stream.read_line(&mut handshake).unwrap();
+//! }
+//! assert!(handshake.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+//!
+//! let mut next_total = || {
+//!     let mut header = [0; 2];
+//!     stream.read_exact(&mut header).unwrap();
+//!     let mut payload = vec![0; usize::from(header[1])];
+//!     stream.read_exact(&mut payload).unwrap();
+//!     serde_json::from_slice::<serde_json::Value>(&payload).unwrap()["total"].clone()
+//! };
+//! assert_eq!(next_total(), 0.0);
+//!
+//! terminal.scan("A").unwrap();
+//! assert_eq!(next_total(), 2.0);
+//! ```
+
+mod http;
+mod lines;
+mod openapi;
+mod websocket;
+
+pub use self::http::{Request, Response};
+pub use self::lines::{ScannerListener, ScannerSessions};
+pub use self::openapi::openapi;
+
+use crate::database::{Database, DatabaseAppend};
+use crate::metrics::Metrics;
+use crate::prelude::{
+    DatabaseReader, ErrorVariant, Product, Promotion, Terminal, TerminalEntityInterface,
+    TerminalEvent,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{BufReader, BufWriter};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Most connections served at once by default
+const DEFAULT_MAX_CONNECTIONS: usize = 256;
+
+/// Read and write timeout of the connections by default
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// HTTP/1.1 server sharing a terminal between its connections
+///
+/// Every connection is served by its own thread, and closed after its response. The terminal
+/// locks its cart and database internally, so requests run concurrently. Connections beyond
+/// [Server::with_max_connections] are answered with `503 Service Unavailable`, and requests
+/// taking longer than [Server::with_timeout] to arrive with `400 Bad Request`
+///
+/// # Example
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use std::net::TcpStream;
+/// use std::sync::Arc;
+/// use std::thread;
+/// use store_terminal::prelude::*;
+/// use store_terminal::server::Server;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let server = Server::bind("127.0.0.1:0", Arc::new(terminal)).unwrap();
+/// let addr = server.local_addr().unwrap();
+/// thread::spawn(move || server.serve());
+///
+/// let body = r#"{"codes": "A B"}"#;
+/// let mut stream = TcpStream::connect(addr).unwrap();
+/// write!(stream, "POST /scan HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+///
+/// let mut response = String::new();
+/// stream.read_to_string(&mut response).unwrap();
+/// assert!(response.starts_with("HTTP/1.1 200 OK"));
+/// assert!(response.ends_with(r#""accepted":["A","B"],"total":14.0,"unknown":[]}"#));
+/// ```
+pub struct Server {
+    listener: TcpListener,
+    terminal: Arc<Terminal>,
+    metrics: Option<Arc<Metrics>>,
+    max_connections: usize,
+    timeout: Duration,
+}
+
+impl Server {
+    pub fn bind(addr: &str, terminal: Arc<Terminal>) -> Result<Self, ErrorVariant> {
+        let listener = TcpListener::bind(addr).map_err(|e| ErrorVariant::IoError {
+            path: addr.to_string(),
+            source: e.to_string(),
+        })?;
+        Ok(Server {
+            listener,
+            terminal,
+            metrics: None,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Serve `metrics` on `GET /metrics`, for Prometheus to scrape
+    ///
+    /// The metrics are only rendered here; see [Metrics::attach] to record those of the terminal
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpStream;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use store_terminal::metrics::Metrics;
+    /// use store_terminal::prelude::*;
+    /// use store_terminal::server::Server;
+    ///
+    /// let terminal = Arc::new(Terminal::new().unwrap());
+    /// terminal.load_demo_catalog().unwrap();
+    /// let metrics = Arc::new(Metrics::new());
+    /// Metrics::attach(&metrics, &terminal).unwrap();
+    ///
+    /// let server = Server::bind("127.0.0.1:0", terminal.clone())
+    ///     .unwrap()
+    ///     .with_metrics(metrics);
+    /// let addr = server.local_addr().unwrap();
+    /// thread::spawn(move || server.serve());
+    ///
+    /// terminal.scan("A").unwrap();
+    /// let mut stream = TcpStream::connect(addr).unwrap();
+    /// stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+    ///
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// assert!(response.contains("Content-Type: text/plain; version=0.0.4\r\n"));
+    /// assert!(response.contains("store_terminal_scans_total{terminal=\"terminal-1\"} 1\n"));
+    /// ```
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Serve at most `max_connections` connections at once, 256 by default
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpStream;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use store_terminal::prelude::*;
+    /// use store_terminal::server::Server;
+    ///
+    /// let server = Server::bind("127.0.0.1:0", Arc::new(Terminal::new().unwrap()))
+    ///     .unwrap()
+    ///     .with_max_connections(1)
+    ///     .with_timeout(Duration::from_millis(200));
+    /// let addr = server.local_addr().unwrap();
+    /// thread::spawn(move || server.serve());
+    ///
+    /// // The first connection holds the only worker until it times out
+    /// let mut idle = TcpStream::connect(addr).unwrap();
+    /// let mut response = String::new();
+    /// TcpStream::connect(addr).unwrap().read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 503 Service Unavailable"));
+    ///
+    /// let mut response = String::new();
+    /// idle.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 400 Bad Request"));
+    ///
+    /// let headers = "X-Header: 1\r\n".repeat(101);
+    /// let mut stream = TcpStream::connect(addr).unwrap();
+    /// write!(stream, "GET /cart HTTP/1.1\r\n{}\r\n", headers).unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    /// assert!(response.contains("more than 100 headers"));
+    /// ```
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections.max(1);
+        self
+    }
+
+    /// Give up on connections idle for `timeout`, while reading a request or writing a
+    /// response, 30 seconds by default
+    ///
+    /// WebSocket streams wait for the changes of the terminal without a read timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, ErrorVariant> {
+        self.listener
+            .local_addr()
+            .map_err(|e| ErrorVariant::IoError {
+                path: "listener".to_string(),
+                source: e.to_string(),
+            })
+    }
+
+    pub fn get_terminal(&self) -> &Arc<Terminal> {
+        &self.terminal
+    }
+
+    /// Accept connections, emitting [TerminalEvent::AcceptFailed] on the terminal for the
+    /// connections failing to be accepted
+    pub fn serve(self) -> Result<(), ErrorVariant> {
+        let active = Arc::new(AtomicUsize::new(0));
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    self.terminal.emit(TerminalEvent::AcceptFailed {
+                        error: e.to_string(),
+                    })?;
+                    // Give the listener a moment in case it ran out of file descriptors
+                    thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+            };
+            // A client that can't be given a timeout can't be served safely
+            if stream.set_read_timeout(Some(self.timeout)).is_err()
+                || stream.set_write_timeout(Some(self.timeout)).is_err()
+            {
+                continue;
+            }
+            let slot = match Slot::acquire(&active, self.max_connections) {
+                Some(slot) => slot,
+                None => {
+                    let busy = Response::error(503, "too many connections");
+                    let _ = busy.write(&mut BufWriter::new(&stream));
+                    continue;
+                }
+            };
+            let terminal = Arc::clone(&self.terminal);
+            let metrics = self.metrics.clone();
+            thread::spawn(move || {
+                connection(&terminal, metrics.as_deref(), stream);
+                drop(slot);
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Connection counted among the ones served at once, until it is dropped
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn acquire(active: &Arc<AtomicUsize>, max: usize) -> Option<Slot> {
+        active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                if n < max {
+                    Some(n + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| Slot(Arc::clone(active)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn connection(terminal: &Terminal, metrics: Option<&Metrics>, stream: TcpStream) {
+    let response = match Request::read(&mut BufReader::new(&stream)) {
+        Ok(request) => match websocket_key(&request) {
+            Some(key) => {
+                let key = key.to_string();
+                // The display only reads the pushed changes, and may stay quiet indefinitely
+                if stream.set_read_timeout(None).is_ok() {
+                    // As below, a failure means the display is gone
+                    let _ = websocket::stream_cart(terminal, stream, &key);
+                }
+                return;
+            }
+            None => match metrics {
+                Some(metrics) if is_metrics(&request) => {
+                    Response::text(200, metrics.render(), "text/plain; version=0.0.4")
+                }
+                _ => handle(terminal, &request),
+            },
+        },
+        Err(message) => Response::error(400, message),
+    };
+    // The client is gone if the response can't be written, so there is nobody to report to
+    let _ = response.write(&mut BufWriter::new(&stream));
+}
+
+fn is_metrics(request: &Request) -> bool {
+    request.get_method() == "GET" && request.get_path().trim_end_matches('/') == "/metrics"
+}
+
+/// Handshake key of a WebSocket upgrade of `GET /cart/stream`
+fn websocket_key(request: &Request) -> Option<&str> {
+    let upgrade = request.get_header("upgrade")?;
+    if request.get_method() == "GET"
+        && request.get_path().trim_end_matches('/') == "/cart/stream"
+        && upgrade.eq_ignore_ascii_case("websocket")
+    {
+        request.get_header("sec-websocket-key")
+    } else {
+        None
+    }
+}
+
+/// Route a request to the terminal
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+/// use store_terminal::server::{handle, Request};
+///
+/// let terminal = Terminal::new().unwrap();
+///
+/// let product = r#"{"code": "A", "price": 2.0}"#;
+/// let response = handle(&terminal, &Request::new("POST", "/products", product));
+/// assert_eq!(response.get_status(), 201);
+/// assert_eq!(handle(&terminal, &Request::new("POST", "/products", product)).get_status(), 409);
+///
+/// let product = r#"{"code": "A", "price": 2.5}"#;
+/// let response = handle(&terminal, &Request::new("PUT", "/products/A", product));
+/// assert_eq!(response.get_status(), 200);
+///
+/// let response = handle(&terminal, &Request::new("GET", "/products/A", ""));
+/// assert_eq!(response.get_body()["price"], 2.5);
+///
+/// let response = handle(&terminal, &Request::new("POST", "/scan", r#"{"codes": ["A", "A"]}"#));
+/// assert_eq!(response.get_body()["total"], 5.0);
+///
+/// let response = handle(&terminal, &Request::new("POST", "/checkout", ""));
+/// assert_eq!(response.get_body()["id"], 1);
+/// assert_eq!(response.get_body()["items"][0]["amount"], 2.0);
+/// assert_eq!(handle(&terminal, &Request::new("GET", "/cart", "")).get_body()["total"], 0.0);
+///
+/// assert_eq!(handle(&terminal, &Request::new("DELETE", "/products/A", "")).get_status(), 200);
+/// assert_eq!(handle(&terminal, &Request::new("GET", "/products/A", "")).get_status(), 404);
+/// assert_eq!(handle(&terminal, &Request::new("GET", "/nowhere", "")).get_status(), 404);
+/// ```
+pub fn handle(terminal: &Terminal, request: &Request) -> Response {
+    let segments: Vec<String> = request
+        .get_path()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    let response = match (request.get_method(), segments.as_slice()) {
+        ("POST", ["scan"]) => scan(terminal, request.get_body()),
+        ("GET", ["cart"]) => terminal.get_cart().map(|cart| ok(cart.summary_json())),
+        ("GET", ["cart", "stream"]) => Ok(Response::error(400, "expected a WebSocket upgrade")),
+        ("POST", ["checkout"]) => terminal.checkout().map(|sale| ok(sale.summary_json())),
+        ("GET", ["openapi.json"]) => Ok(ok(openapi())),
+        (method, ["products", rest @ ..]) => entity(
+            Entities::<Product> {
+                list: DatabaseReader::products,
+                fetch: DatabaseReader::fetch_product,
+                remove: Database::remove_product,
+            },
+            terminal,
+            method,
+            rest,
+            request.get_body(),
+        ),
+        (method, ["promotions", rest @ ..]) => entity(
+            Entities::<Promotion> {
+                list: DatabaseReader::promotions,
+                fetch: DatabaseReader::fetch_promotion,
+                remove: Database::remove_promotion,
+            },
+            terminal,
+            method,
+            rest,
+            request.get_body(),
+        ),
+        _ => Ok(Response::error(
+            404,
+            format!("no route for `{}`", request.get_path()),
+        )),
+    };
+
+    response.unwrap_or_else(|e| Response::error(status(&e), e))
+}
+
+fn scan(terminal: &Terminal, body: &str) -> Result<Response, ErrorVariant> {
+    let body: Value =
+        serde_json::from_str(body).map_err(|e| ErrorVariant::from_json_error(e, "scan"))?;
+    let report = match &body["codes"] {
+        Value::String(codes) => terminal.scan(codes)?,
+        Value::Array(codes) => match codes.iter().map(Value::as_str).collect::<Option<Vec<_>>>() {
+            Some(codes) => terminal.scan(&codes)?,
+            None => return Ok(Response::error(400, "codes must be strings")),
+        },
+        _ => return Ok(Response::error(400, "missing `codes`")),
+    };
+    Ok(ok(report.summary_json()))
+}
+
+/// Database operations on the products or the promotions
+struct Entities<T> {
+    list: fn(&DatabaseReader) -> Result<Vec<T>, ErrorVariant>,
+    fetch: fn(&DatabaseReader, &str) -> Result<T, ErrorVariant>,
+    remove: fn(&Database, &str) -> Result<T, ErrorVariant>,
+}
+
+fn entity<T>(
+    entities: Entities<T>,
+    terminal: &Terminal,
+    method: &str,
+    path: &[&str],
+    body: &str,
+) -> Result<Response, ErrorVariant>
+where
+    T: TerminalEntityInterface + WithCode + Serialize,
+    Database: DatabaseAppend<T>,
+{
+    let database = terminal.get_db()?;
+    match (method, path) {
+        ("GET", []) => (entities.list)(database)
+            .and_then(|list| to_value(&list))
+            .map(ok),
+        ("POST", []) => {
+            let entity = T::from_json(body.to_string())?;
+            let value = to_value(&entity)?;
+            terminal.change_catalog(|database| database.insert_new(entity))?;
+            Ok(Response::new(201, value))
+        }
+        ("GET", [code]) => (entities.fetch)(database, code)
+            .and_then(|entity| to_value(&entity))
+            .map(ok),
+        ("PUT", [code]) => {
+            let entity = T::from_json(body.to_string())?;
+            if entity.code() != *code {
+                return Ok(Response::error(
+                    400,
+                    format!("code `{}` doesn't match the path", entity.code()),
+                ));
+            }
+            let value = to_value(&entity)?;
+            terminal.change_catalog(|database| database.upsert(entity))?;
+            Ok(ok(value))
+        }
+        ("DELETE", [code]) => terminal
+            .change_catalog(|database| (entities.remove)(database, code))
+            .and_then(|entity| to_value(&entity))
+            .map(ok),
+        ("GET", _) | ("POST", _) | ("PUT", _) | ("DELETE", _) => {
+            Ok(Response::error(404, "no such entity route"))
+        }
+        (method, _) => Ok(Response::error(
+            405,
+            format!("method `{}` not allowed", method),
+        )),
+    }
+}
+
+/// Code of an entity, to check it against the path of a `PUT`
+trait WithCode {
+    fn code(&self) -> &str;
+}
+
+impl WithCode for Product {
+    fn code(&self) -> &str {
+        self.get_code()
+    }
+}
+
+impl WithCode for Promotion {
+    fn code(&self) -> &str {
+        self.get_code()
+    }
+}
+
+fn ok(body: Value) -> Response {
+    Response::new(200, body)
+}
+
+fn to_value<T: Serialize>(entity: &T) -> Result<Value, ErrorVariant> {
+    serde_json::to_value(entity).map_err(|e| ErrorVariant::from_json_error(e, "response"))
+}
+
+fn status(e: &ErrorVariant) -> u16 {
+    match e {
+        ErrorVariant::ProductNotFound { .. }
+        | ErrorVariant::PromotionNotFound { .. }
+        | ErrorVariant::EntityNotFound { .. } => 404,
+        ErrorVariant::ProductAlreadyExists { .. }
+        | ErrorVariant::PromotionAlreadyExists { .. }
+        | ErrorVariant::SessionAlreadyExists { .. }
+        | ErrorVariant::ShiftUnavailable { .. } => 409,
+        ErrorVariant::JsonParseError { .. }
+        | ErrorVariant::InvalidQuantity { .. }
+        | ErrorVariant::InvalidBarcode { .. }
+        | ErrorVariant::InvalidPaymentDetails { .. }
+        | ErrorVariant::InvalidUrl { .. }
+        | ErrorVariant::InvalidJournal { .. }
+        | ErrorVariant::InvalidWeight { .. }
+        | ErrorVariant::InvalidCustomerId { .. }
+        | ErrorVariant::NotEnoughItems { .. }
+        | ErrorVariant::InsufficientPayment { .. }
+        | ErrorVariant::LoyaltyUnavailable { .. } => 400,
+        ErrorVariant::InvalidCredentials { .. } => 401,
+        ErrorVariant::PermissionDenied { .. } | ErrorVariant::ApprovalRequired { .. } => 403,
+        ErrorVariant::ArcUnlockError
+        | ErrorVariant::IoError { .. }
+        | ErrorVariant::PeripheralUnavailable { .. } => 500,
+    }
+}
+
+/// Decode the `%XX` escapes of a path segment, keeping malformed escapes as they are
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(hex) if bytes[i] == b'%' => std::str::from_utf8(hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
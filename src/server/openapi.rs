@@ -0,0 +1,275 @@
+use serde_json::{json, Value};
+
+/// OpenAPI 3.0 document of the routes of [handle](crate::server::handle), served on
+/// `GET /openapi.json` for client SDK generators
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+/// use store_terminal::server::{handle, openapi, Request};
+///
+/// let document = openapi();
+/// assert_eq!(document["openapi"], "3.0.3");
+/// assert!(document["paths"]["/products/{code}"]["put"].is_object());
+/// assert_eq!(
+///     document["components"]["schemas"]["Product"]["required"],
+///     serde_json::json!(["code", "price"])
+/// );
+///
+/// let terminal = Terminal::new().unwrap();
+/// let response = handle(&terminal, &Request::new("GET", "/openapi.json", ""));
+/// assert_eq!(response.get_body(), &document);
+/// ```
+pub fn openapi() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Store terminal",
+            "description": "Scanning, promotions and catalog of a store terminal",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": {
+            "/scan": {
+                "post": operation(
+                    "scan",
+                    "Scan codes into the cart",
+                    Some("ScanRequest"),
+                    "ScanReport",
+                    &["400"],
+                ),
+            },
+            "/cart": {
+                "get": operation("getCart", "Items of the optimized cart", None, "Cart", &[]),
+            },
+            "/cart/stream": {
+                "get": {
+                    "operationId": "streamCart",
+                    "summary": "WebSocket pushing a Cart on every change of the terminal",
+                    "responses": {
+                        "101": { "description": "Switching to the WebSocket protocol" },
+                        "400": error_response(),
+                    },
+                },
+            },
+            "/checkout": {
+                "post": operation(
+                    "checkout",
+                    "Close the sale, returning it and emptying the cart",
+                    None,
+                    "Sale",
+                    &[],
+                ),
+            },
+            "/products": entities("Product", "Products"),
+            "/products/{code}": entity("Product"),
+            "/promotions": entities("Promotion", "Promotions"),
+            "/promotions/{code}": entity("Promotion"),
+        },
+        "components": {
+            "schemas": {
+                "Product": {
+                    "type": "object",
+                    "required": ["code", "price"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "code": { "type": "string", "minLength": 1 },
+                        "price": { "type": "number", "minimum": 0 },
+                        "tax_class": { "type": "string" },
+                        "restriction": { "type": "string", "enum": ["age_restricted", "high_value"] },
+                        "name": { "type": "string" },
+                        "description": { "type": "string" },
+                        "category": { "type": "string" },
+                        "aliases": { "type": "array", "items": { "type": "string", "minLength": 1 } },
+                    },
+                },
+                "ProductAmount": {
+                    "type": "object",
+                    "required": ["product", "amount"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "product": reference("Product"),
+                        "amount": { "type": "number", "exclusiveMinimum": 0 },
+                    },
+                },
+                "Promotion": {
+                    "type": "object",
+                    "required": ["code", "products", "price"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "code": { "type": "string", "minLength": 1 },
+                        "products": { "type": "array", "items": reference("ProductAmount"), "minItems": 1 },
+                        "price": { "type": "number", "minimum": 0 },
+                    },
+                },
+                "CartItem": {
+                    "type": "object",
+                    "required": ["code", "description", "amount", "total"],
+                    "properties": {
+                        "code": { "type": "string" },
+                        "description": { "type": "string" },
+                        "amount": { "type": "number" },
+                        "total": { "type": "number" },
+                    },
+                },
+                "Cart": {
+                    "type": "object",
+                    "required": ["items", "total"],
+                    "properties": {
+                        "items": { "type": "array", "items": reference("CartItem") },
+                        "total": { "type": "number" },
+                    },
+                },
+                "Tender": {
+                    "type": "object",
+                    "required": ["tender", "amount"],
+                    "properties": {
+                        "tender": { "type": "string", "enum": ["cash", "card", "voucher", "gift_card", "other"] },
+                        "amount": { "type": "number" },
+                    },
+                },
+                "Sale": {
+                    "type": "object",
+                    "required": ["id", "timestamp", "items", "tenders", "total", "change"],
+                    "properties": {
+                        "id": { "type": "integer" },
+                        "timestamp": { "type": "integer", "description": "Seconds since the Unix epoch" },
+                        "items": { "type": "array", "items": reference("CartItem") },
+                        "tenders": { "type": "array", "items": reference("Tender") },
+                        "total": { "type": "number" },
+                        "change": { "type": "number", "description": "Cash given back" },
+                    },
+                },
+                "Totals": {
+                    "type": "object",
+                    "description": "Totals of a receipt",
+                    "required": ["regular_price", "savings", "total"],
+                    "properties": {
+                        "regular_price": { "type": "number" },
+                        "savings": { "type": "number" },
+                        "total": { "type": "number" },
+                    },
+                },
+                "ScanRequest": {
+                    "type": "object",
+                    "required": ["codes"],
+                    "properties": {
+                        "codes": {
+                            "oneOf": [
+                                { "type": "string" },
+                                { "type": "array", "items": { "type": "string" } },
+                            ],
+                        },
+                    },
+                },
+                "ScanReport": {
+                    "type": "object",
+                    "required": ["accepted", "unknown", "total"],
+                    "properties": {
+                        "accepted": { "type": "array", "items": { "type": "string" } },
+                        "unknown": { "type": "array", "items": { "type": "string" } },
+                        "total": { "type": "number" },
+                    },
+                },
+                "Error": {
+                    "type": "object",
+                    "required": ["error"],
+                    "properties": { "error": { "type": "string" } },
+                },
+            },
+        },
+    })
+}
+
+/// Routes of the collection of `schema`
+fn entities(schema: &str, plural: &str) -> Value {
+    let mut insert = operation(
+        &format!("insert{}", schema),
+        &format!("Insert a new {}", schema.to_lowercase()),
+        Some(schema),
+        schema,
+        &["400", "409"],
+    );
+    let created = insert["responses"]
+        .as_object_mut()
+        .and_then(|responses| responses.remove("200"));
+    insert["responses"]["201"] = created.unwrap_or_default();
+
+    json!({
+        "get": {
+            "operationId": format!("list{}", plural),
+            "summary": format!("All the {}, sorted by code", plural.to_lowercase()),
+            "responses": {
+                "200": content(json!({ "type": "array", "items": reference(schema) })),
+                "500": error_response(),
+            },
+        },
+        "post": insert,
+    })
+}
+
+/// Routes of an element of the collection of `schema`
+fn entity(schema: &str) -> Value {
+    json!({
+        "parameters": [{
+            "name": "code",
+            "in": "path",
+            "required": true,
+            "schema": { "type": "string" },
+        }],
+        "get": operation(&format!("get{}", schema), "Fetch by code", None, schema, &["404"]),
+        "put": operation(
+            &format!("put{}", schema),
+            "Insert or replace",
+            Some(schema),
+            schema,
+            &["400"],
+        ),
+        "delete": operation(&format!("delete{}", schema), "Remove by code", None, schema, &["404"]),
+    })
+}
+
+fn operation(
+    id: &str,
+    summary: &str,
+    request: Option<&str>,
+    response: &str,
+    errors: &[&str],
+) -> Value {
+    let mut operation = json!({
+        "operationId": id,
+        "summary": summary,
+        "responses": {
+            "200": content(reference(response)),
+            "500": error_response(),
+        },
+    });
+    if let Some(request) = request {
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": { "application/json": { "schema": reference(request) } },
+        });
+    }
+    for error in errors {
+        operation["responses"][*error] = error_response();
+    }
+    operation
+}
+
+fn content(schema: Value) -> Value {
+    json!({
+        "description": "Success",
+        "content": { "application/json": { "schema": schema } },
+    })
+}
+
+fn error_response() -> Value {
+    json!({
+        "description": "Failure",
+        "content": { "application/json": { "schema": reference("Error") } },
+    })
+}
+
+fn reference(schema: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", schema) })
+}
@@ -0,0 +1,205 @@
+//! Minimal WebSocket (RFC 6455) support to push the cart to customer-facing displays
+
+use crate::prelude::{ErrorVariant, Terminal};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::thread;
+
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+
+enum Signal {
+    Changed,
+    Closed,
+}
+
+/// Complete the handshake, then send the cart summary on connection and after every event of
+/// the terminal, until the client closes the connection
+///
+/// Events are coalesced, so a batch scan sends a single snapshot once the display catches up
+pub(crate) fn stream_cart(terminal: &Terminal, stream: TcpStream, key: &str) -> io::Result<()> {
+    let mut writer = &stream;
+    write!(
+        writer,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )?;
+
+    let (tx, rx) = mpsc::channel();
+    let closed = tx.clone();
+    let mut reader = stream.try_clone()?;
+    thread::spawn(move || {
+        // Client frames are only read to notice the end of the connection
+        while let Ok(opcode) = read_frame(&mut reader) {
+            if opcode == OPCODE_CLOSE {
+                break;
+            }
+        }
+        let _ = closed.send(Signal::Closed);
+    });
+    let id = terminal
+        .subscribe(move |_| {
+            let _ = tx.send(Signal::Changed);
+        })
+        .map_err(to_io)?;
+
+    let result = push_snapshots(terminal, writer, &rx);
+    terminal.unsubscribe(id).map_err(to_io)?;
+    result
+}
+
+fn push_snapshots<W: Write>(
+    terminal: &Terminal,
+    mut writer: W,
+    rx: &mpsc::Receiver<Signal>,
+) -> io::Result<()> {
+    loop {
+        let cart = terminal.get_cart().map_err(to_io)?;
+        write_frame(
+            &mut writer,
+            OPCODE_TEXT,
+            cart.summary_json().to_string().as_bytes(),
+        )?;
+
+        match rx.recv() {
+            Ok(Signal::Changed) => (),
+            Ok(Signal::Closed) | Err(_) => break,
+        }
+        if rx.try_iter().any(|signal| matches!(signal, Signal::Closed)) {
+            break;
+        }
+    }
+    write_frame(&mut writer, OPCODE_CLOSE, &[])
+}
+
+fn to_io(e: ErrorVariant) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// Unmasked frame, as sent by servers
+fn write_frame<W: Write>(writer: &mut W, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+/// Read a frame, discarding its payload, and return its opcode
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+    let len = match header[1] & 0x7f {
+        126 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0; 8];
+            reader.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+    let mask = if header[1] & 0x80 != 0 { 4 } else { 0 };
+    io::copy(&mut reader.take(mask + len), &mut io::sink())?;
+    Ok(header[0] & 0x0f)
+}
+
+/// `Sec-WebSocket-Accept` of a handshake key
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key.trim(), ACCEPT_GUID).as_bytes()))
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(h.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,336 @@
+//! Random baskets driven through a terminal, for load testing the optimizer and validating
+//! promotions before launch, with the `simulation` feature
+//!
+//! A [BasketGenerator] draws baskets of the catalog of the terminal, picking whole promotions
+//! at the configured hit rate so they are applied as often as expected from shoppers. A
+//! [Simulation] scans every basket, prices it and checks it out, and summarizes the runs in a
+//! [SimulationReport]. Sales are stored as any other, so simulations are meant for terminals of
+//! their own
+//!
+//! # Example
+//!
+//! ```
+//! use store_terminal::prelude::*;
+//! use store_terminal::simulation::{BasketGenerator, BasketSize, Simulation};
+//! use store_terminal::simulation::{SeedableRng, StdRng};
+//!
+//! let terminal = Terminal::new().unwrap();
+//! terminal.load_demo_catalog().unwrap();
+//!
+//! let generator = BasketGenerator::new()
+//!     .with_size(BasketSize::Uniform { min: 1, max: 6 })
+//!     .with_promotion_hit_rate(0.5);
+//! let report = Simulation::new(generator)
+//!     .with_baskets(20)
+//!     .run(&mut StdRng::seed_from_u64(7), &terminal)
+//!     .unwrap();
+//!
+//! assert_eq!(report.get_baskets(), 20);
+//! assert_eq!(terminal.get_sales().unwrap().len(), 20);
+//! assert!(report.get_total() <= report.get_regular_price());
+//! assert!(report.get_promotion_hit_rate() > 0.0);
+//! assert!(report.get_optimizer_max() >= report.get_optimizer_mean());
+//! ```
+
+use crate::prelude::{Code, DatabaseReader, ErrorVariant, Promotion, Terminal};
+use crate::receipt::round_cents;
+pub use rand::rngs::StdRng;
+pub use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Distribution of the number of picks of a basket, each a product or a whole promotion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BasketSize {
+    /// Always `n` picks, at least 1
+    Fixed(usize),
+    /// From `min` to `max` picks, equally likely
+    Uniform { min: usize, max: usize },
+    /// Mostly small baskets and a long tail of large ones, averaging `mean` picks
+    Geometric { mean: f64 },
+}
+
+impl BasketSize {
+    fn sample<R: Rng + ?Sized>(self, rng: &mut R) -> usize {
+        match self {
+            BasketSize::Fixed(n) => n.max(1),
+            BasketSize::Uniform { min, max } => {
+                let min = min.max(1);
+                rng.gen_range(min, max.max(min) + 1)
+            }
+            BasketSize::Geometric { mean } => {
+                let p = 1.0 / mean.max(1.0);
+                let mut picks = 1;
+                while !rng.gen_bool(p) {
+                    picks += 1;
+                }
+                picks
+            }
+        }
+    }
+}
+
+/// Builder of random baskets of the products of a database
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasketGenerator {
+    size: BasketSize,
+    promotion_hit_rate: f64,
+    max_amount: u32,
+}
+
+impl BasketGenerator {
+    /// Generator of baskets of about 8 picks of up to 3 units, 1 in 5 being a promotion
+    pub fn new() -> Self {
+        BasketGenerator {
+            size: BasketSize::Geometric { mean: 8.0 },
+            promotion_hit_rate: 0.2,
+            max_amount: 3,
+        }
+    }
+
+    pub fn with_size(mut self, size: BasketSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Probability of a pick being every product of a random promotion, from 0 to 1
+    pub fn with_promotion_hit_rate(mut self, rate: f64) -> Self {
+        self.promotion_hit_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Largest amount of a product picked alone, at least 1
+    pub fn with_max_amount(mut self, max_amount: u32) -> Self {
+        self.max_amount = max_amount.max(1);
+        self
+    }
+
+    pub fn get_size(&self) -> BasketSize {
+        self.size
+    }
+
+    pub fn get_promotion_hit_rate(&self) -> f64 {
+        self.promotion_hit_rate
+    }
+
+    pub fn get_max_amount(&self) -> u32 {
+        self.max_amount
+    }
+
+    /// Codes of a basket of the products of `database`, in the order they are scanned
+    ///
+    /// Empty for catalogs without products
+    pub fn generate<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        database: &DatabaseReader,
+    ) -> Result<Vec<Code>, ErrorVariant> {
+        let catalog = database.get_catalog()?;
+        let products = catalog.get_products();
+        let promotions = catalog.get_promotions();
+        if products.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut codes = vec![];
+        for _ in 0..self.size.sample(rng) {
+            if !promotions.is_empty() && rng.gen_bool(self.promotion_hit_rate) {
+                let promotion = &promotions[rng.gen_range(0, promotions.len())];
+                codes.extend(promotion_codes(promotion));
+            } else {
+                let product = &products[rng.gen_range(0, products.len())];
+                let amount = rng.gen_range(1, self.max_amount + 1);
+                codes.extend((0..amount).map(|_| product.get_code().clone()));
+            }
+        }
+        Ok(codes)
+    }
+}
+
+impl Default for BasketGenerator {
+    fn default() -> Self {
+        BasketGenerator::new()
+    }
+}
+
+/// Codes scanned to buy every product of `promotion`, a unit per scan
+fn promotion_codes(promotion: &Promotion) -> Vec<Code> {
+    promotion
+        .get_products()
+        .into_iter()
+        .flat_map(|product| {
+            let units = product.get_amount().ceil().max(1.0) as usize;
+            vec![product.get_code().clone(); units]
+        })
+        .collect()
+}
+
+/// Run of generated baskets through a terminal
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    generator: BasketGenerator,
+    baskets: usize,
+}
+
+impl Simulation {
+    /// Simulation of 100 baskets of `generator`
+    pub fn new(generator: BasketGenerator) -> Self {
+        Simulation {
+            generator,
+            baskets: 100,
+        }
+    }
+
+    pub fn with_baskets(mut self, baskets: usize) -> Self {
+        self.baskets = baskets;
+        self
+    }
+
+    pub fn get_generator(&self) -> &BasketGenerator {
+        &self.generator
+    }
+
+    pub fn get_baskets(&self) -> usize {
+        self.baskets
+    }
+
+    /// Scan, price and check out every basket in `terminal`, starting from an empty cart
+    ///
+    /// The time taken by [Terminal::get_cart] is the time taken by the optimizer to choose the
+    /// promotions of the basket. The first error, such as a checkout refused by the
+    /// configuration of the terminal, stops the run
+    pub fn run<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        terminal: &Terminal,
+    ) -> Result<SimulationReport, ErrorVariant> {
+        let mut report = SimulationReport::default();
+        for promotion in terminal.get_db()?.promotions()? {
+            report.promotions.insert(promotion.get_code().clone(), 0.0);
+        }
+
+        terminal.reset_cart()?;
+        for _ in 0..self.baskets {
+            let codes = self.generator.generate(rng, terminal.get_db()?)?;
+            terminal.scan(&codes)?;
+
+            let started = Instant::now();
+            terminal.get_cart()?;
+            report.optimizer.push(started.elapsed());
+
+            let started = Instant::now();
+            let sale = terminal.checkout()?;
+            report.checkout.push(started.elapsed());
+
+            report.baskets += 1;
+            report.units += codes.len();
+            report.regular_price += sale.get_totals().get_regular_price();
+            report.total += sale.get_totals().get_total();
+            if !sale.get_promotions().is_empty() {
+                report.baskets_with_promotions += 1;
+            }
+            for promotion in sale.get_promotions() {
+                *report
+                    .promotions
+                    .entry(promotion.get_code().clone())
+                    .or_insert(0.0) += promotion.get_applications();
+            }
+        }
+
+        report.regular_price = round_cents(report.regular_price);
+        report.total = round_cents(report.total);
+        Ok(report)
+    }
+}
+
+/// Summary of a [Simulation]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SimulationReport {
+    baskets: usize,
+    baskets_with_promotions: usize,
+    units: usize,
+    regular_price: f64,
+    total: f64,
+    promotions: BTreeMap<Code, f64>,
+    optimizer: Vec<Duration>,
+    checkout: Vec<Duration>,
+}
+
+impl SimulationReport {
+    pub fn get_baskets(&self) -> usize {
+        self.baskets
+    }
+
+    /// Units scanned over every basket
+    pub fn get_units(&self) -> usize {
+        self.units
+    }
+
+    /// Price of the baskets without any promotion
+    pub fn get_regular_price(&self) -> f64 {
+        self.regular_price
+    }
+
+    /// Amount due of the baskets, taxes included
+    pub fn get_total(&self) -> f64 {
+        self.total
+    }
+
+    /// Share of the baskets with at least one promotion applied, from 0 to 1
+    pub fn get_promotion_hit_rate(&self) -> f64 {
+        if self.baskets == 0 {
+            return 0.0;
+        }
+        self.baskets_with_promotions as f64 / self.baskets as f64
+    }
+
+    /// Times each promotion of the catalog was applied, including the ones never applied
+    pub fn get_promotion_applications(&self) -> &BTreeMap<Code, f64> {
+        &self.promotions
+    }
+
+    /// Promotions of the catalog never applied, either unreachable or never worth it
+    pub fn get_unapplied_promotions(&self) -> Vec<&Code> {
+        self.promotions
+            .iter()
+            .filter(|(_, applications)| **applications == 0.0)
+            .map(|(code, _)| code)
+            .collect()
+    }
+
+    /// Time taken to optimize each basket, in the order they were run
+    pub fn get_optimizer_durations(&self) -> &Vec<Duration> {
+        &self.optimizer
+    }
+
+    /// Time taken to check out each basket, in the order they were run
+    pub fn get_checkout_durations(&self) -> &Vec<Duration> {
+        &self.checkout
+    }
+
+    pub fn get_optimizer_mean(&self) -> Duration {
+        mean(&self.optimizer)
+    }
+
+    pub fn get_optimizer_max(&self) -> Duration {
+        self.optimizer.iter().max().copied().unwrap_or_default()
+    }
+
+    /// Time within which `percentile` percent of the baskets were optimized, such as 95
+    pub fn get_optimizer_percentile(&self, percentile: f64) -> Duration {
+        let mut durations = self.optimizer.clone();
+        durations.sort();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * durations.len() as f64).ceil();
+        durations
+            .get((rank as usize).max(1) - 1)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+fn mean(durations: &[Duration]) -> Duration {
+    if durations.is_empty() {
+        return Duration::default();
+    }
+    durations.iter().sum::<Duration>() / durations.len() as u32
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone)]
+pub struct Table {
+    columns: Vec<(String, Alignment)>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Plain text table with aligned columns
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let mut table = Table::new(vec![("Code", Alignment::Left), ("Price", Alignment::Right)]);
+    /// table.push_row(vec!["A".to_string(), "2.00".to_string()]);
+    /// table.push_row(vec!["PROMO".to_string(), "12.50".to_string()]);
+    ///
+    /// assert_eq!(table.render_header(), "Code   Price");
+    /// assert_eq!(table.render_rows(), vec!["A       2.00", "PROMO  12.50"]);
+    /// ```
+    pub fn new(columns: Vec<(&str, Alignment)>) -> Self {
+        let columns = columns
+            .into_iter()
+            .map(|(title, alignment)| (title.to_string(), alignment))
+            .collect();
+        let rows = vec![];
+
+        Table { columns, rows }
+    }
+
+    pub fn push_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    pub fn get_rows(&self) -> &Vec<Vec<String>> {
+        &self.rows
+    }
+
+    pub fn render_header(&self) -> String {
+        let header: Vec<&str> = self.columns.iter().map(|(t, _)| t.as_str()).collect();
+        self.render_line(&header)
+    }
+
+    pub fn render_rows(&self) -> Vec<String> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let row: Vec<&str> = row.iter().map(|c| c.as_str()).collect();
+                self.render_line(&row)
+            })
+            .collect()
+    }
+
+    fn get_widths(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, (title, _))| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| cell.chars().count())
+                    .fold(title.chars().count(), usize::max)
+            })
+            .collect()
+    }
+
+    fn render_line(&self, cells: &[&str]) -> String {
+        let widths = self.get_widths();
+        let line: Vec<String> = self
+            .columns
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(i, ((_, alignment), width))| {
+                let cell = cells.get(i).cloned().unwrap_or("");
+                match alignment {
+                    Alignment::Left => format!("{:<width$}", cell, width = width),
+                    Alignment::Right => format!("{:>width$}", cell, width = width),
+                }
+            })
+            .collect();
+
+        line.join("  ").trim_end().to_string()
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render_header())?;
+        for row in self.render_rows() {
+            write!(f, "\n{}", row)?;
+        }
+        Ok(())
+    }
+}
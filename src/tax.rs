@@ -0,0 +1,138 @@
+//! Tax classes of the products, and the rates their sales are taxed at
+//!
+//! Prices include the tax. The [TaxTable] given to
+//! [TerminalConfig::with_tax_table](crate::prelude::TerminalConfig::with_tax_table) maps the
+//! class set by [Product::with_tax_class](crate::prelude::Product::with_tax_class) to its rate,
+//! products without a class, or of a class missing from the table, being taxed at the
+//! standard rate. Sales break their total down per class, for their receipts, Z-reports and
+//! [TaxReport](crate::prelude::TaxReport)s
+
+use crate::gift_card::GIFT_CARD_CODE;
+use crate::prelude::{SoldProduct, TaxLine};
+
+/// Named rate of a tax, `0.2` for 20%
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaxRate {
+    name: String,
+    rate: f64,
+}
+
+impl TaxRate {
+    pub fn new(name: &str, rate: f64) -> Self {
+        TaxRate {
+            name: name.to_string(),
+            rate,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+/// Rates of the tax classes of the products
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let table = TaxTable::new("VAT", 0.2)
+///     .with_class("food", "VAT reduced", 0.05)
+///     .with_class("books", "VAT zero", 0.0);
+/// assert_eq!(table.get_rate(Some("food")).get_rate(), 0.05);
+/// assert_eq!(table.get_rate(Some("toys")), table.get_standard_rate());
+/// assert_eq!(table.get_rate(None).get_name(), "VAT");
+///
+/// let config = TerminalConfig::default().with_tax_table(table);
+/// let terminal = TerminalBuilder::new().with_config(config).build().unwrap();
+/// terminal
+///     .load_catalog(
+///         vec![Product::new("BREAD", 2.1).with_tax_class("food"), Product::new("PEN", 1.2)],
+///         vec![],
+///     )
+///     .unwrap();
+/// terminal.scan("BREAD PEN").unwrap();
+///
+/// let sale = terminal.checkout().unwrap();
+/// let taxes = sale.get_totals().get_taxes();
+/// assert_eq!((taxes[0].get_class(), taxes[0].get_name()), (None, "VAT"));
+/// assert_eq!((taxes[0].get_net(), taxes[0].get_tax()), (1.0, 0.2));
+/// assert_eq!((taxes[1].get_class(), taxes[1].get_name()), (Some("food"), "VAT reduced"));
+/// assert_eq!((taxes[1].get_net(), taxes[1].get_tax()), (2.0, 0.1));
+/// assert_eq!(sale.get_totals().get_total_tax(), 0.3);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaxTable {
+    standard: TaxRate,
+    classes: Vec<(String, TaxRate)>,
+}
+
+impl TaxTable {
+    /// Table taxing every product at the standard rate `rate`, named `name`
+    pub fn new(name: &str, rate: f64) -> Self {
+        TaxTable {
+            standard: TaxRate::new(name, rate),
+            classes: vec![],
+        }
+    }
+
+    /// Rate of the products of `class`, replacing any previous rate of the class
+    pub fn with_class(mut self, class: &str, name: &str, rate: f64) -> Self {
+        self.classes.retain(|(c, _)| c != class);
+        self.classes
+            .push((class.to_string(), TaxRate::new(name, rate)));
+        self
+    }
+
+    pub fn get_standard_rate(&self) -> &TaxRate {
+        &self.standard
+    }
+
+    /// Rate of the products of `class`, the standard rate if the class is not in the table
+    pub fn get_rate(&self, class: Option<&str>) -> &TaxRate {
+        self.resolve(class).1
+    }
+
+    /// Class of the table and rate of the products of `class`
+    fn resolve(&self, class: Option<&str>) -> (Option<&str>, &TaxRate) {
+        self.classes
+            .iter()
+            .find(|(c, _)| Some(c.as_str()) == class)
+            .map_or((None, &self.standard), |(c, rate)| (Some(c), rate))
+    }
+
+    /// Taxes of the products of a sale
+    pub(crate) fn tax_lines(&self, products: &[SoldProduct]) -> Vec<TaxLine> {
+        tax_lines(products, |class| {
+            let (class, rate) = self.resolve(class);
+            Some((class.map(str::to_string), rate.clone()))
+        })
+    }
+}
+
+/// Taxes of `products` grouped by class, each product being taxed at the rate its class
+/// resolves to with `rate_of`, if any; gift cards sold are not taxed
+pub(crate) fn tax_lines<F>(products: &[SoldProduct], rate_of: F) -> Vec<TaxLine>
+where
+    F: Fn(Option<&str>) -> Option<(Option<String>, TaxRate)>,
+{
+    let mut groups: Vec<(Option<String>, TaxRate, f64)> = vec![];
+    for product in products.iter().filter(|p| p.get_code() != GIFT_CARD_CODE) {
+        if let Some((class, rate)) = rate_of(product.get_tax_class()) {
+            match groups.iter_mut().find(|(c, _, _)| *c == class) {
+                Some(group) => group.2 += product.get_total(),
+                None => groups.push((class, rate, product.get_total())),
+            }
+        }
+    }
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+    groups
+        .into_iter()
+        .map(|(class, rate, gross)| TaxLine::new(class, &rate.name, rate.rate, gross))
+        .collect()
+}
@@ -0,0 +1,290 @@
+use super::{check_amount, keep_optimized, undo_scans, JournalEntry};
+use crate::lock::{self, WakingReadGuard, WakingRwLock, WakingWriteGuard};
+use crate::prelude::{Cart, ErrorVariant, Sale, ScanCodes, ScanReport, Terminal, Undone};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Handle to a shared [Terminal] with `async` scanning and cart operations
+///
+/// The operations never block the executor thread on the cart lock: a task finding it
+/// contended yields to the executor, and is woken once the guard holding it is dropped. Any
+/// executor can drive them, and the returned futures are `Send`, so they can be spawned by
+/// multi-threaded runtimes such as tokio. Other operations are available through
+/// [AsyncTerminal::get_terminal]
+///
+/// # Example
+///
+/// ```
+/// # use std::future::Future;
+/// # use std::sync::Arc;
+/// # use std::task::{Context, Poll, Wake, Waker};
+/// # struct Noop;
+/// # impl Wake for Noop {
+/// #     fn wake(self: Arc<Self>) {}
+/// # }
+/// # fn block_on<F: Future>(future: F) -> F::Output {
+/// #     let waker = Waker::from(Arc::new(Noop));
+/// #     let mut cx = Context::from_waker(&waker);
+/// #     let mut future = Box::pin(future);
+/// #     loop {
+/// #         if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+/// #             return output;
+/// #         }
+/// #     }
+/// # }
+/// use store_terminal::prelude::*;
+///
+/// fn assert_send<T: Send>(_: &T) {}
+///
+/// let terminal = AsyncTerminal::from(Terminal::new().unwrap());
+/// terminal.get_terminal().load_demo_catalog().unwrap();
+///
+/// block_on(async {
+///     terminal.scan("ABCD").await.unwrap();
+///     terminal.scan_with_quantity("C", 2).await.unwrap();
+///     assert_eq!(terminal.get_cart().await.unwrap().get_total_price(), 17.9);
+///
+///     terminal.undo().await.unwrap();
+///     terminal.reset_cart().await.unwrap();
+///     assert_eq!(terminal.get_cart().await.unwrap().get_total_price(), 0.0);
+///
+///     terminal.get_terminal().queue_scan("B");
+///     let sale = terminal.checkout().await.unwrap();
+///     assert_eq!(sale.get_totals().get_total(), 12.0);
+///     assert_eq!(terminal.get_terminal().get_queued_scans(), 0);
+/// });
+///
+/// assert_send(&terminal.get_cart());
+/// ```
+#[derive(Clone)]
+pub struct AsyncTerminal {
+    terminal: Arc<Terminal>,
+}
+
+impl From<Terminal> for AsyncTerminal {
+    fn from(terminal: Terminal) -> Self {
+        AsyncTerminal::new(Arc::new(terminal))
+    }
+}
+
+impl AsyncTerminal {
+    pub fn new(terminal: Arc<Terminal>) -> Self {
+        AsyncTerminal { terminal }
+    }
+
+    pub fn get_terminal(&self) -> &Arc<Terminal> {
+        &self.terminal
+    }
+
+    /// See [Terminal::scan]
+    pub async fn scan<C: ScanCodes>(&self, codes: C) -> Result<ScanReport, ErrorVariant> {
+        let terminal = &self.terminal;
+        let mut tokens = vec![];
+        codes.try_for_each_code(
+            |code| match terminal.lookup_code(code) {
+                Ok(code) => terminal.database.contains_product(&code).unwrap_or(false),
+                Err(_) => true,
+            },
+            |code| {
+                tokens.push(code.to_string());
+                Ok(())
+            },
+        )?;
+
+        let mut report = ScanReport::default();
+        let mut result = Ok(());
+        for code in &tokens {
+            result = self.scan_code(code, &mut report).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        self.terminal.scan_finished(report, result)
+    }
+
+    async fn scan_code(&self, code: &str, report: &mut ScanReport) -> Result<(), ErrorVariant> {
+        let pushed = match self
+            .terminal
+            .lookup_code(code)
+            .and_then(|found| Ok((self.terminal.scanned_amount(code, &found)?, found)))
+        {
+            Ok((amount, found)) => self
+                .terminal
+                .push_in_stock(&mut *self.write_cart().await?, &found, amount)
+                .map(|short| (found, amount, short)),
+            Err(e) => Err(e),
+        };
+        self.terminal.code_scanned(code, pushed, report)
+    }
+
+    /// See [Terminal::scan_with_quantity]
+    pub async fn scan_with_quantity(&self, code: &str, quantity: u32) -> Result<(), ErrorVariant> {
+        self.scan_amount(code, f64::from(quantity)).await
+    }
+
+    /// See [Terminal::scan_with_weight]
+    pub async fn scan_with_weight(&self, code: &str, weight: f64) -> Result<(), ErrorVariant> {
+        self.scan_amount(code, weight).await
+    }
+
+    async fn scan_amount(&self, code: &str, amount: f64) -> Result<(), ErrorVariant> {
+        let code = match check_amount(code, amount).and_then(|()| self.terminal.lookup_code(code)) {
+            Ok(code) => code,
+            Err(e) => return self.terminal.amount_scanned(code, amount, Err(e)),
+        };
+        let pushed = self
+            .terminal
+            .push_in_stock(&mut *self.write_cart().await?, &code, amount);
+        self.terminal.amount_scanned(&code, amount, pushed)
+    }
+
+    /// See [Terminal::flush_scans]
+    pub async fn flush_scans(&self) -> Result<ScanReport, ErrorVariant> {
+        let mut report = ScanReport::default();
+        for code in self.terminal.scans.take() {
+            match self.scan([code.as_str()]).await {
+                Ok(scanned) => report.append(scanned),
+                Err(ErrorVariant::NotEnoughItems { .. }) => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(report)
+    }
+
+    /// See [Terminal::get_cart]
+    pub async fn get_cart(&self) -> Result<Cart, ErrorVariant> {
+        self.flush_scans().await?;
+        let (generation, mut cart) = {
+            let cart = self.read_cart().await?;
+            if cart.is_optimized() {
+                return Ok(cart.clone());
+            }
+            (cart.get_generation(), cart.clone())
+        };
+
+        let previous = super::event::promotion_amounts(&cart);
+        cart.optimize_promotions()?;
+        keep_optimized(&mut *self.write_cart().await?, generation, &cart);
+        self.terminal.promotions_applied(&previous, &cart)?;
+
+        Ok(cart)
+    }
+
+    /// See [Terminal::checkout]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::future::Future;
+    /// # use std::sync::Arc;
+    /// # use std::task::{Context, Poll, Wake, Waker};
+    /// # struct Noop;
+    /// # impl Wake for Noop {
+    /// #     fn wake(self: Arc<Self>) {}
+    /// # }
+    /// # fn block_on<F: Future>(future: F) -> F::Output {
+    /// #     let waker = Waker::from(Arc::new(Noop));
+    /// #     let mut cx = Context::from_waker(&waker);
+    /// #     let mut future = Box::pin(future);
+    /// #     loop {
+    /// #         if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+    /// #             return output;
+    /// #         }
+    /// #     }
+    /// # }
+    /// use store_terminal::prelude::*;
+    ///
+    /// let config = TerminalConfig::default().with_kiosk_mode(true);
+    /// let terminal = TerminalBuilder::new().with_config(config).build().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal
+    ///     .change_catalog(|db| db.upsert(Product::new("WINE", 9.0).with_restriction(Restriction::AgeRestricted)))
+    ///     .unwrap();
+    /// let terminal = AsyncTerminal::from(terminal);
+    ///
+    /// block_on(async {
+    ///     terminal.scan("A WINE").await.unwrap();
+    ///     assert_eq!(
+    ///         terminal.checkout().await.err(),
+    ///         Some(ErrorVariant::ApprovalRequired { codes: vec!["WINE".to_string()] })
+    ///     );
+    ///     assert_eq!(terminal.get_cart().await.unwrap().get_total_price(), 11.0);
+    /// });
+    /// ```
+    pub async fn checkout(&self) -> Result<Sale, ErrorVariant> {
+        let mut drawer_opened = false;
+        loop {
+            let cart = self.get_cart().await?;
+            let sale = self
+                .terminal
+                .prepare_sale(&cart, |_| Ok(()), &mut drawer_opened)?;
+            let stored = match self.write_cart().await {
+                Ok(mut current) => self.terminal.store_unchanged(&mut current, &cart, &sale),
+                Err(e) => Err(e),
+            };
+            if let Some(sale) = self.terminal.finish_sale(&sale, stored)? {
+                return Ok(sale);
+            }
+        }
+    }
+
+    /// See [Terminal::reset_cart]
+    pub async fn reset_cart(&self) -> Result<(), ErrorVariant> {
+        self.flush_scans().await?;
+        let mut cart = self.write_cart().await?;
+        self.terminal.void_cart(&mut cart)?;
+        drop(cart);
+        self.terminal.cart_reset()
+    }
+
+    /// See [Terminal::undo]
+    pub async fn undo(&self) -> Result<Option<Undone>, ErrorVariant> {
+        self.flush_scans().await?;
+        let entry = lock::lock(&self.terminal.journal).pop();
+
+        match entry {
+            None => Ok(None),
+            Some(JournalEntry::Scan(count)) => {
+                let undone = undo_scans(&mut *self.write_cart().await?, count)?;
+                self.terminal.scans_undone(undone).map(Some)
+            }
+            Some(JournalEntry::PriceOverride(restore, event)) => {
+                self.terminal.restore_pricing(restore, event).map(Some)
+            }
+        }
+    }
+
+    fn read_cart(&self) -> LockCart<'_, WakingReadGuard<'_, Cart>> {
+        LockCart {
+            terminal: &self.terminal,
+            poll_lock: WakingRwLock::poll_read,
+        }
+    }
+
+    fn write_cart(&self) -> LockCart<'_, WakingWriteGuard<'_, Cart>> {
+        LockCart {
+            terminal: &self.terminal,
+            poll_lock: WakingRwLock::poll_write,
+        }
+    }
+}
+
+/// Future of a guard of the cart of a terminal, waiting for the release of the guard holding it
+struct LockCart<'a, G> {
+    terminal: &'a Terminal,
+    poll_lock: fn(&'a WakingRwLock<Cart>, &mut Context) -> Poll<G>,
+}
+
+impl<'a, G> Future for LockCart<'a, G> {
+    type Output = Result<G, ErrorVariant>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if let Err(e) = self.terminal.recover_cart() {
+            return Poll::Ready(Err(e));
+        }
+
+        (self.poll_lock)(&self.terminal.cart, cx).map(Ok)
+    }
+}
@@ -0,0 +1,186 @@
+use crate::audit::AuditLog;
+use crate::clock::{Clock, IdGenerator, SystemClock};
+use crate::peripheral::{CashDrawer, CustomerReader, Peripherals, Scale};
+#[cfg(feature = "json")]
+use crate::prelude::CartItemKinds;
+use crate::prelude::{
+    Cart, Database, ErrorVariant, OptimizerStrategy, SaleQueue, SaleSequence, SalesHistory,
+    Terminal, TerminalConfig,
+};
+use std::sync::Arc;
+
+/// Custom construction of a [Terminal](crate::prelude::Terminal)
+#[derive(Default)]
+pub struct TerminalBuilder {
+    database: Option<Database>,
+    cart: Option<Cart>,
+    strategy: Option<Arc<dyn OptimizerStrategy>>,
+    config: TerminalConfig,
+    peripherals: Peripherals,
+    records: Records,
+    clock: Option<Arc<dyn Clock>>,
+    ids: Option<Arc<dyn IdGenerator>>,
+    #[cfg(feature = "json")]
+    kinds: Option<Arc<CartItemKinds>>,
+}
+
+/// Where a terminal keeps, numbers and forwards its sales and audited actions
+#[derive(Default)]
+pub(crate) struct Records {
+    pub sales: SalesHistory,
+    pub sequence: SaleSequence,
+    pub audit: AuditLog,
+    pub queue: SaleQueue,
+}
+
+impl TerminalBuilder {
+    /// Builder of a terminal with an empty database and cart, unless provided otherwise
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// database.append(Product::new("A".to_string(), 2.0)).unwrap();
+    ///
+    /// let mut cart = Cart::new(database.clone());
+    /// cart.push_product("A", 2.0).unwrap();
+    ///
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_database(database)
+    ///     .with_cart(cart)
+    ///     .with_config(TerminalConfig::new("lane-3".to_string()))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// terminal.scan("A").unwrap();
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 6.0);
+    /// assert_eq!(terminal.get_config().get_terminal_id(), "lane-3");
+    /// ```
+    pub fn new() -> Self {
+        TerminalBuilder::default()
+    }
+
+    /// Database of the terminal; an initial cart is rebound to it
+    pub fn with_database(mut self, database: Database) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    pub fn with_cart(mut self, cart: Cart) -> Self {
+        self.cart = Some(cart);
+        self
+    }
+
+    /// Strategy used by the cart to choose the promotions
+    pub fn with_optimizer_strategy<S: OptimizerStrategy + 'static>(mut self, strategy: S) -> Self {
+        self.strategy = Some(Arc::new(strategy));
+        self
+    }
+
+    pub fn with_config(mut self, config: TerminalConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Drawer opened by cash checkouts and [Terminal::open_drawer]
+    pub fn with_cash_drawer<D: CashDrawer + 'static>(mut self, drawer: D) -> Self {
+        self.peripherals.drawer = Some(Arc::new(drawer));
+        self
+    }
+
+    /// Scale weighing the items of [Terminal::scan_weighed]
+    pub fn with_scale<S: Scale + 'static>(mut self, scale: S) -> Self {
+        self.peripherals.scale = Some(Arc::new(scale));
+        self
+    }
+
+    /// Loyalty card or phone number input of [Terminal::identify_customer]
+    pub fn with_customer_reader<R: CustomerReader + 'static>(mut self, reader: R) -> Self {
+        self.peripherals.customer_reader = Some(Arc::new(reader));
+        self
+    }
+
+    /// History the sales are stored in, such as one opened on a file
+    pub fn with_sales_history(mut self, sales: SalesHistory) -> Self {
+        self.records.sales = sales;
+        self
+    }
+
+    /// Numbering of the sales, such as one stored in a file so it carries on after a restart
+    pub fn with_sale_sequence(mut self, sequence: SaleSequence) -> Self {
+        self.records.sequence = sequence;
+        self
+    }
+
+    /// Log the audited actions are appended to, such as one opened on a file
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.records.audit = audit;
+        self
+    }
+
+    /// Queue forwarding the sales to a head office backend, such as one opened on a file so
+    /// the sales of an outage survive a restart
+    pub fn with_sale_queue(mut self, queue: SaleQueue) -> Self {
+        self.records.queue = queue;
+        self
+    }
+
+    /// Clock stamping the sales, refunds, shifts and audit entries, such as a
+    /// [ManualClock](crate::clock::ManualClock) for deterministic tests
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Generator of the identifiers of the cart items
+    pub fn with_id_generator<G: IdGenerator + 'static>(mut self, ids: G) -> Self {
+        self.ids = Some(Arc::new(ids));
+        self
+    }
+
+    /// Kinds of the custom items of the cart, read back by [Terminal::restore_state]
+    ///
+    /// See [CustomCartItem](crate::prelude::CustomCartItem) for an example
+    #[cfg(feature = "json")]
+    pub fn with_cart_item_kinds(mut self, kinds: CartItemKinds) -> Self {
+        self.kinds = Some(Arc::new(kinds));
+        self
+    }
+
+    pub fn build(self) -> Result<Terminal, ErrorVariant> {
+        let (database, mut cart) = match (self.database, self.cart) {
+            (Some(database), Some(mut cart)) => {
+                cart.set_database(database.clone());
+                (database, cart)
+            }
+            (Some(database), None) => (database.clone(), Cart::new(database)),
+            (None, Some(cart)) => (cart.get_database().get_database().clone(), cart),
+            (None, None) => {
+                let database = Database::new();
+                (database.clone(), Cart::new(database))
+            }
+        };
+
+        if let Some(strategy) = self.strategy {
+            cart.set_optimizer_strategy(strategy);
+        }
+        if let Some(ids) = self.ids {
+            cart.set_id_generator(ids);
+        }
+        #[cfg(feature = "json")]
+        if let Some(kinds) = self.kinds {
+            cart.set_item_kinds(kinds);
+        }
+
+        Ok(Terminal::from_parts(
+            database,
+            cart,
+            self.config,
+            self.peripherals,
+            self.records,
+            self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+        ))
+    }
+}
@@ -0,0 +1,395 @@
+use crate::barcode::EmbeddedLayout;
+use crate::prelude::{LoyaltyPolicy, StockPolicy, TaxTable};
+
+/// Settings of a [Terminal](crate::prelude::Terminal)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerminalConfig {
+    terminal_id: String,
+    barcode_validation: bool,
+    embedded_barcodes: Option<EmbeddedLayout>,
+    stock_policy: StockPolicy,
+    stock_reservation: bool,
+    loyalty_policy: Option<LoyaltyPolicy>,
+    operator_login: bool,
+    tax_table: Option<TaxTable>,
+    kiosk_mode: bool,
+}
+
+impl TerminalConfig {
+    pub fn new(terminal_id: String) -> Self {
+        TerminalConfig {
+            terminal_id,
+            barcode_validation: false,
+            embedded_barcodes: None,
+            stock_policy: StockPolicy::default(),
+            stock_reservation: false,
+            loyalty_policy: None,
+            operator_login: false,
+            tax_table: None,
+            kiosk_mode: false,
+        }
+    }
+
+    /// Validate the check digit of scanned barcodes, and look up UPC-A codes as EAN-13
+    ///
+    /// See [barcode](crate::barcode) for the codes taken as barcodes
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_config(TerminalConfig::default().with_barcode_validation(true))
+    ///     .build()
+    ///     .unwrap();
+    /// terminal
+    ///     .load_catalog(vec![Product::new("0036000291452", 1.5)], vec![])
+    ///     .unwrap();
+    ///
+    /// let report = terminal.scan("036000291452 036000291453").unwrap();
+    /// assert_eq!(report.get_accepted(), &vec![Code::from("0036000291452")]);
+    /// assert_eq!(report.get_unknown(), &vec!["036000291453".to_string()]);
+    ///
+    /// assert_eq!(
+    ///     terminal.scan_with_quantity("036000291453", 2).unwrap_err(),
+    ///     ErrorVariant::InvalidBarcode { code: "036000291453".to_string() }
+    /// );
+    /// ```
+    pub fn with_barcode_validation(mut self, enabled: bool) -> Self {
+        self.barcode_validation = enabled;
+        self
+    }
+
+    /// Read the price or weight embedded in the barcodes of `layout`, such as deli scale labels
+    ///
+    /// Their item is looked up in the catalog, and scanned for the embedded weight, or for the
+    /// amount the embedded price pays at the price of the item
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::barcode::EmbeddedLayout;
+    /// use store_terminal::prelude::*;
+    ///
+    /// let config = TerminalConfig::default().with_embedded_barcodes(EmbeddedLayout::default());
+    /// let terminal = TerminalBuilder::new().with_config(config).build().unwrap();
+    /// terminal
+    ///     .load_catalog(
+    ///         vec![Product::new("2112345", 11.5), Product::new("2501234", 4.0)],
+    ///         vec![],
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // 28.75 of the item 12345, priced 11.5 per kilogram, and 1.5 kilograms of the item 01234
+    /// let report = terminal.scan("2112345928753 2501234315000").unwrap();
+    /// assert_eq!(report.get_lines()[0].get_quantity(), 2.5);
+    /// assert_eq!(report.get_lines()[1].get_quantity(), 1.5);
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 34.75);
+    ///
+    /// assert_eq!(terminal.scan("2112345128757").unwrap().get_unknown().len(), 1);
+    /// ```
+    pub fn with_embedded_barcodes(mut self, layout: EmbeddedLayout) -> Self {
+        self.embedded_barcodes = Some(layout);
+        self
+    }
+
+    /// Refuse scans taking more of a product than its stock on hand with [StockPolicy::Block],
+    /// or accept them with a warning with [StockPolicy::Warn], the default
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let config = TerminalConfig::default().with_stock_policy(StockPolicy::Block);
+    /// let terminal = TerminalBuilder::new().with_config(config).build().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.get_db().unwrap().get_inventory().set_stock("B", 1.0).unwrap();
+    ///
+    /// terminal.scan("AB").unwrap();
+    /// assert_eq!(
+    ///     terminal.scan("B").unwrap_err(),
+    ///     ErrorVariant::NotEnoughItems { code: "B".to_string(), requested: 1.0, available: 0.0 }
+    /// );
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 14.0);
+    /// ```
+    pub fn with_stock_policy(mut self, policy: StockPolicy) -> Self {
+        self.stock_policy = policy;
+        self
+    }
+
+    /// Reserve the stock of the scanned products until the cart is checked out, reset or the
+    /// scan undone, so lanes sharing the [Database](crate::prelude::Database) can't sell the
+    /// same units
+    ///
+    /// The [StockPolicy] applies to the stock left unreserved by the carts of all the lanes
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let lane = |id: &str| {
+    ///     let config = TerminalConfig::new(id.to_string())
+    ///         .with_stock_policy(StockPolicy::Block)
+    ///         .with_stock_reservation(true);
+    ///     TerminalBuilder::new()
+    ///         .with_database(database.clone())
+    ///         .with_config(config)
+    ///         .build()
+    ///         .unwrap()
+    /// };
+    /// let (lane_1, lane_2) = (lane("lane-1"), lane("lane-2"));
+    /// lane_1.load_demo_catalog().unwrap();
+    /// database.get_inventory().set_stock("B", 1.0).unwrap();
+    ///
+    /// lane_1.scan("B").unwrap();
+    /// assert!(lane_2.scan("B").is_err());
+    ///
+    /// lane_1.undo().unwrap();
+    /// lane_2.scan("B").unwrap();
+    /// assert!(lane_1.scan("B").is_err());
+    ///
+    /// lane_2.checkout().unwrap();
+    /// assert_eq!(database.stock("B").unwrap(), Some(0.0));
+    /// assert_eq!(database.get_inventory().get_levels()[0].get_reserved(), 0.0);
+    /// ```
+    pub fn with_stock_reservation(mut self, enabled: bool) -> Self {
+        self.stock_reservation = enabled;
+        self
+    }
+
+    /// Earn loyalty points on the checkouts of identified customers, redeemed with
+    /// [Terminal::redeem_points](crate::prelude::Terminal::redeem_points)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "json")]
+    /// # fn main() {
+    /// use store_terminal::customer::Identification;
+    /// use store_terminal::prelude::*;
+    ///
+    /// let config = TerminalConfig::default().with_loyalty_policy(LoyaltyPolicy::new(10.0, 0.01));
+    /// let terminal = TerminalBuilder::new().with_config(config).build().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// let customer = Customer::new("M-1042", Identification::Phone);
+    ///
+    /// terminal.set_customer(customer.clone()).unwrap();
+    /// terminal.scan("AAAAB").unwrap();
+    /// let sale = terminal.checkout().unwrap();
+    /// assert_eq!(sale.get_loyalty().unwrap().get_earned(), 190);
+    /// assert_eq!(terminal.fetch_loyalty_points("M-1042").unwrap(), 190);
+    ///
+    /// terminal.set_customer(customer).unwrap();
+    /// terminal.scan("AB").unwrap();
+    /// assert_eq!(terminal.redeem_points(150).unwrap(), 1.5);
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 12.5);
+    ///
+    /// let sale = terminal.checkout().unwrap();
+    /// assert_eq!(sale.get_totals().get_savings(), 1.5);
+    /// assert_eq!(sale.get_loyalty().unwrap().get_redeemed(), 150);
+    /// assert_eq!(sale.get_loyalty().unwrap().get_earned(), 125);
+    /// assert_eq!(terminal.fetch_loyalty_points("M-1042").unwrap(), 165);
+    ///
+    /// // Refunds give back the redeemed points and take back the earned ones
+    /// let refund = terminal.refund(sale.get_id(), &[("B", 1.0)]).unwrap();
+    /// assert_eq!(refund.get_loyalty().unwrap().get_redeemed(), 129);
+    /// assert_eq!(refund.get_loyalty().unwrap().get_earned(), 107);
+    /// terminal.refund(sale.get_id(), &[("A", 1.0)]).unwrap();
+    /// assert_eq!(terminal.fetch_loyalty_points("M-1042").unwrap(), 190);
+    /// # }
+    /// # #[cfg(not(feature = "json"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_loyalty_policy(mut self, policy: LoyaltyPolicy) -> Self {
+        self.loyalty_policy = Some(policy);
+        self
+    }
+
+    /// Require an operator logged in with [Terminal::login](crate::prelude::Terminal::login),
+    /// and of a role with the [Permission](crate::operator::Permission), for price overrides,
+    /// voids, refunds, catalog changes and resets of the database
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "json")]
+    /// # fn main() {
+    /// use store_terminal::operator::{Operator, Role, OPERATOR_STORE};
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let operators = database.store::<Operator>(OPERATOR_STORE);
+    /// operators.upsert("op-1", &Operator::new("op-1", "Alex", Role::Cashier, "1111")).unwrap();
+    /// operators.upsert("op-7", &Operator::new("op-7", "Sam", Role::Supervisor, "4821")).unwrap();
+    ///
+    /// let config = TerminalConfig::default().with_operator_login(true);
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_database(database)
+    ///     .with_config(config)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(terminal.load_demo_catalog().is_err());
+    /// terminal.login("op-7", "4821").unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// assert!(terminal.login("op-1", "2222").is_err());
+    /// terminal.login("op-1", "1111").unwrap();
+    /// terminal.scan("AB").unwrap();
+    /// assert_eq!(
+    ///     terminal.reset_cart().unwrap_err(),
+    ///     ErrorVariant::PermissionDenied {
+    ///         operation: "void".to_string(),
+    ///         role: "supervisor".to_string(),
+    ///     }
+    /// );
+    /// let sale = terminal.checkout().unwrap();
+    /// assert_eq!(sale.get_operator(), Some("op-1"));
+    /// assert!(terminal.refund(sale.get_id(), &[("B", 1.0)]).is_err());
+    ///
+    /// terminal.login("op-7", "4821").unwrap();
+    /// let refund = terminal.refund(sale.get_id(), &[("B", 1.0)]).unwrap();
+    /// assert_eq!(refund.get_operator(), Some("op-7"));
+    /// assert!(terminal.reset().is_err());
+    ///
+    /// terminal.logout().unwrap();
+    /// let product = terminal.get_db().unwrap().fetch_product("A").unwrap();
+    /// assert!(terminal.set_pricing(product, 1.0).is_err());
+    /// assert_eq!(
+    ///     terminal.change_catalog(|db| db.upsert(Product::new("A", 0.01))).err(),
+    ///     Some(ErrorVariant::PermissionDenied {
+    ///         operation: "catalog change".to_string(),
+    ///         role: "supervisor".to_string(),
+    ///     })
+    /// );
+    /// # }
+    /// # #[cfg(not(feature = "json"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_operator_login(mut self, enabled: bool) -> Self {
+        self.operator_login = enabled;
+        self
+    }
+
+    /// Rates the sales are taxed at, broken down per tax class of their products
+    ///
+    /// See [TaxTable] for an example
+    pub fn with_tax_table(mut self, table: TaxTable) -> Self {
+        self.tax_table = Some(table);
+        self
+    }
+
+    /// Run the terminal as a self-checkout kiosk, where scanning a product with a
+    /// [Restriction](crate::prelude::Restriction) emits
+    /// [TerminalEvent::ApprovalRequired](crate::prelude::TerminalEvent::ApprovalRequired) and
+    /// blocks the checkout with [ErrorVariant::ApprovalRequired](crate::ErrorVariant::ApprovalRequired)
+    /// until a supervisor approves it with
+    /// [Terminal::approve_holds](crate::prelude::Terminal::approve_holds)
+    ///
+    /// Approved products may be scanned again in the same sale without another approval
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "json")]
+    /// # fn main() {
+    /// use std::sync::{Arc, Mutex};
+    /// use store_terminal::operator::{Operator, Role, OPERATOR_STORE};
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let config = TerminalConfig::default().with_kiosk_mode(true);
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_database(database.clone())
+    ///     .with_config(config)
+    ///     .build()
+    ///     .unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal
+    ///     .change_catalog(|db| db.upsert(Product::new("WINE", 9.0).with_restriction(Restriction::AgeRestricted)))
+    ///     .unwrap();
+    /// let operators = database.store::<Operator>(OPERATOR_STORE);
+    /// operators.upsert("op-1", &Operator::new("op-1", "Alex", Role::Cashier, "1111")).unwrap();
+    /// operators.upsert("op-7", &Operator::new("op-7", "Sam", Role::Supervisor, "4821")).unwrap();
+    ///
+    /// let events = Arc::new(Mutex::new(vec![]));
+    /// let log = events.clone();
+    /// terminal.subscribe(move |event| log.lock().unwrap().push(event.clone())).unwrap();
+    ///
+    /// terminal.scan("A WINE").unwrap();
+    /// assert!(events.lock().unwrap().contains(&TerminalEvent::ApprovalRequired {
+    ///     code: Code::from("WINE"),
+    ///     restriction: Restriction::AgeRestricted,
+    /// }));
+    /// assert_eq!(
+    ///     terminal.checkout().unwrap_err(),
+    ///     ErrorVariant::ApprovalRequired { codes: vec!["WINE".to_string()] }
+    /// );
+    ///
+    /// assert!(terminal.approve_holds("op-1", "1111").is_err());
+    /// let approved = terminal.approve_holds("op-7", "4821").unwrap();
+    /// assert_eq!(approved[0].get_code(), "WINE");
+    /// assert!(terminal.get_holds().unwrap().is_empty());
+    ///
+    /// terminal.scan("WINE").unwrap();
+    /// assert_eq!(terminal.checkout().unwrap().get_totals().get_total(), 20.0);
+    /// # }
+    /// # #[cfg(not(feature = "json"))]
+    /// # fn main() {}
+    /// ```
+    pub fn with_kiosk_mode(mut self, enabled: bool) -> Self {
+        self.kiosk_mode = enabled;
+        self
+    }
+
+    pub(crate) fn with_terminal_id(mut self, terminal_id: String) -> Self {
+        self.terminal_id = terminal_id;
+        self
+    }
+
+    /// Identification of the terminal inside the store
+    pub fn get_terminal_id(&self) -> &String {
+        &self.terminal_id
+    }
+
+    pub fn has_barcode_validation(&self) -> bool {
+        self.barcode_validation
+    }
+
+    pub fn get_embedded_barcodes(&self) -> Option<&EmbeddedLayout> {
+        self.embedded_barcodes.as_ref()
+    }
+
+    pub fn get_stock_policy(&self) -> StockPolicy {
+        self.stock_policy
+    }
+
+    pub fn has_stock_reservation(&self) -> bool {
+        self.stock_reservation
+    }
+
+    pub fn get_loyalty_policy(&self) -> Option<&LoyaltyPolicy> {
+        self.loyalty_policy.as_ref()
+    }
+
+    pub fn has_operator_login(&self) -> bool {
+        self.operator_login
+    }
+
+    pub fn get_tax_table(&self) -> Option<&TaxTable> {
+        self.tax_table.as_ref()
+    }
+
+    pub fn has_kiosk_mode(&self) -> bool {
+        self.kiosk_mode
+    }
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        TerminalConfig::new("terminal-1".to_string())
+    }
+}
@@ -0,0 +1,146 @@
+use crate::prelude::{Cart, Code, ErrorVariant, Restriction};
+use std::sync::Arc;
+
+/// Identifier of a listener registered with [Terminal::subscribe](crate::prelude::Terminal::subscribe)
+pub type SubscriptionId = u64;
+
+type Listener = Arc<dyn Fn(&TerminalEvent) + Send + Sync>;
+
+/// Change of state of a [Terminal](crate::prelude::Terminal), delivered to its subscribers
+#[derive(Debug, Clone, PartialEq)]
+pub enum TerminalEvent {
+    /// An amount of a product was added to the cart
+    ScanAccepted { code: Code, amount: f64 },
+    /// A scan took the cart over the stock on hand of the product, accepted by
+    /// [StockPolicy::Warn](crate::prelude::StockPolicy::Warn)
+    OutOfStock { code: Code, stock: f64 },
+    /// A scan was refused, and the cart is unchanged
+    ScanRejected { code: String, error: ErrorVariant },
+    /// An amount of a product scanned earlier was removed by [Terminal::undo](crate::prelude::Terminal::undo)
+    ScanUndone { code: Code, amount: f64 },
+    /// The optimizer applied an amount of a promotion to the cart
+    PromotionApplied { code: Code, amount: f64 },
+    /// The price of a product or promotion was overridden or restored
+    PriceChanged {
+        code: Code,
+        previous: f64,
+        price: f64,
+    },
+    /// A product or promotion was inserted in the catalog, repriced, or removed from it, by
+    /// [Terminal::change_catalog](crate::prelude::Terminal::change_catalog); `None` when it
+    /// is inserted or removed. The products changed are reported first, then the promotions,
+    /// each in order of code
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let changes = Arc::new(Mutex::new(vec![]));
+    /// let listener = changes.clone();
+    /// terminal
+    ///     .subscribe(move |event| {
+    ///         if let TerminalEvent::CatalogChanged { code, previous, price } = event {
+    ///             listener.lock().unwrap().push((code.clone(), *previous, *price));
+    ///         }
+    ///     })
+    ///     .unwrap();
+    ///
+    /// terminal
+    ///     .change_catalog(|database| {
+    ///         database.append(Product::new("E", 3.0))?;
+    ///         let promotion = database.fetch_promotion("PA")?;
+    ///         database.update(promotion.with_new_pricing(6.5)?)?;
+    ///         database.remove_product("D")
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     *changes.lock().unwrap(),
+    ///     vec![
+    ///         (Code::from("D"), Some(0.15), None),
+    ///         (Code::from("E"), None, Some(3.0)),
+    ///         (Code::from("PA"), Some(7.0), Some(6.5)),
+    ///     ]
+    /// );
+    /// ```
+    CatalogChanged {
+        code: Code,
+        previous: Option<f64>,
+        price: Option<f64>,
+    },
+    /// The customer of the sale was identified, by its identifier
+    CustomerIdentified { id: String },
+    /// All the items of the cart were removed
+    CartReset,
+    /// The sale numbered `sale` was closed by
+    /// [Terminal::checkout](crate::prelude::Terminal::checkout) for `total`
+    CheckedOut { sale: u64, total: f64 },
+    /// A checkout took the stock of a product below its reorder threshold
+    LowStock {
+        code: Code,
+        stock: f64,
+        threshold: f64,
+    },
+    /// The sale numbered `sale` was refunded by [Terminal::refund](crate::prelude::Terminal::refund)
+    /// for `total`, a negative amount
+    SaleRefunded { sale: u64, total: f64 },
+    /// The cash drawer was opened by a cash checkout, or manually for `reason`
+    DrawerOpened { reason: Option<String> },
+    /// A thread panicked while changing the cart, and the cart was recovered as it was left
+    CartRecovered,
+    /// A kiosk scanned a restricted product, holding the checkout until a supervisor approves
+    /// it
+    ApprovalRequired {
+        code: Code,
+        restriction: Restriction,
+    },
+    /// The restricted products held at a kiosk were approved by the operator `approver`
+    HoldsApproved { approver: String, codes: Vec<Code> },
+    /// The HTTP server of the terminal failed to accept a connection, and keeps serving
+    AcceptFailed { error: String },
+}
+
+/// Registry of the listeners of a terminal
+#[derive(Default)]
+pub(crate) struct EventBus {
+    next_id: SubscriptionId,
+    listeners: Vec<(SubscriptionId, Listener)>,
+}
+
+impl EventBus {
+    pub fn subscribe(&mut self, listener: Listener) -> SubscriptionId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.listeners.push((id, listener));
+        id
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let count = self.listeners.len();
+        self.listeners.retain(|(listener_id, _)| *listener_id != id);
+        self.listeners.len() != count
+    }
+
+    /// Snapshot of the listeners, so they can be called without holding the bus lock
+    pub fn get_listeners(&self) -> Vec<Listener> {
+        self.listeners
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect()
+    }
+}
+
+/// Amount applied per promotion code, to compare a cart before and after an optimization
+pub(crate) fn promotion_amounts(cart: &Cart) -> Vec<(Code, f64)> {
+    let mut amounts: Vec<(Code, f64)> = vec![];
+    for item in cart.get_items().iter().filter(|item| item.is_promotion()) {
+        match amounts.iter_mut().find(|(code, _)| code == item.get_code()) {
+            Some((_, amount)) => *amount += item.get_amount(),
+            None => amounts.push((item.get_code().clone(), item.get_amount())),
+        }
+    }
+    amounts
+}
@@ -0,0 +1,2014 @@
+use crate::audit::{AuditAction, AuditLog};
+use crate::barcode::{self, EmbeddedCode, EmbeddedValue};
+use crate::clock::Clock;
+#[cfg(feature = "json")]
+use crate::customer::CUSTOMER_STORE;
+#[cfg(feature = "json")]
+use crate::gift_card::{self, GiftCard, GIFT_CARD_CODE, GIFT_CARD_STORE};
+use crate::lock::{self, WakingReadGuard, WakingRwLock, WakingWriteGuard};
+#[cfg(feature = "json")]
+use crate::loyalty::{self, LOYALTY_STORE};
+#[cfg(feature = "json")]
+use crate::operator::OPERATOR_STORE;
+use crate::operator::{Operator, Permission};
+use crate::peripheral::Peripherals;
+use crate::prelude::{
+    Cart, CartItem, Catalog, Code, CustomCartItem, Customer, Database, DatabaseAppend,
+    DatabaseReader, ErrorVariant, Hold, ItemId, Product, ProductAmount, Promotion, Sale, SaleQueue,
+    SaleSequence, SalesHistory, ScanCodes, ScanReport, Shift, ShiftReport, StockPolicy,
+    SubscriptionId, SyncReport, Tender, TenderAmount, TerminalBuilder, TerminalConfig,
+    TerminalEvent, WithNewPricing,
+};
+#[cfg(feature = "json")]
+use crate::prelude::{DatabaseStore, LoyaltyAccount, LoyaltyActivity, LoyaltyRedemption};
+use builder::Records;
+use event::EventBus;
+use scan::ScanQueue;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+pub mod asynchronous;
+pub mod builder;
+pub mod config;
+pub mod event;
+pub mod scan;
+pub mod sessions;
+#[cfg(feature = "json")]
+mod state;
+
+/// Action reverted by [Terminal::undo]
+#[derive(Debug, Clone)]
+pub enum Undone {
+    Scan(Vec<ProductAmount>),
+    PriceOverride,
+}
+
+type RestorePricing = Box<dyn FnOnce(&Database) -> Result<(), ErrorVariant> + Send>;
+
+enum JournalEntry {
+    Scan(usize),
+    PriceOverride(RestorePricing, TerminalEvent),
+}
+
+/// Point of sale over a [Database] and a [Cart]
+///
+/// The terminal is `Send + Sync`, and can be shared between threads through an `Arc`. The cart
+/// is behind a `RwLock`: scans, resets and undo take the write lock, while [Terminal::get_cart]
+/// takes the read lock to copy the cart, and the write lock only to keep a new optimization.
+/// See [Database] for the locking of the catalog, and [CartItem] for the bound on the items of
+/// the cart
+///
+/// User callbacks, the [OptimizerStrategy] of the cart and the listeners of [Terminal::subscribe],
+/// run without holding any lock of the terminal, so a panicking callback leaves the terminal
+/// usable. Should a thread panic while holding the cart anyway, the cart is recovered as it was
+/// left, optimized again, and [TerminalEvent::CartRecovered] is emitted
+///
+/// # Example
+///
+/// ```
+/// use std::panic::{self, AssertUnwindSafe};
+/// use store_terminal::prelude::*;
+///
+/// struct Fragile;
+///
+/// impl OptimizerStrategy for Fragile {
+///     fn get_optimal_products_promotions(
+///         &self,
+///         products: Vec<ProductAmount>,
+///         database: &DatabaseReader,
+///     ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+///         if products.iter().any(|p| p.get_product().get_code() == "D") {
+///             panic!("display thread crashed");
+///         }
+///         DefaultOptimizerStrategy.get_optimal_products_promotions(products, database)
+///     }
+/// }
+///
+/// let terminal = TerminalBuilder::new()
+///     .with_optimizer_strategy(Fragile)
+///     .build()
+///     .unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// terminal.scan("A").unwrap();
+/// terminal.scan("D").unwrap();
+/// assert!(panic::catch_unwind(AssertUnwindSafe(|| terminal.get_cart())).is_err());
+///
+/// terminal.undo().unwrap();
+/// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 2.0);
+/// ```
+pub struct Terminal {
+    database: Database,
+    reader: DatabaseReader,
+    cart: Arc<WakingRwLock<Cart>>,
+    journal: Mutex<Vec<JournalEntry>>,
+    events: Mutex<EventBus>,
+    sales: SalesHistory,
+    sequence: SaleSequence,
+    audit: AuditLog,
+    queue: SaleQueue,
+    clock: Arc<dyn Clock>,
+    config: TerminalConfig,
+    peripherals: Peripherals,
+    operator: Mutex<Option<Operator>>,
+    scans: ScanQueue,
+}
+
+impl Terminal {
+    /// Empty terminal with default settings; see [TerminalBuilder] for custom construction
+    pub fn new() -> Result<Self, ErrorVariant> {
+        TerminalBuilder::new().build()
+    }
+
+    pub(crate) fn from_parts(
+        database: Database,
+        cart: Cart,
+        config: TerminalConfig,
+        peripherals: Peripherals,
+        records: Records,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let cart = Arc::new(WakingRwLock::new(cart));
+        let journal = Mutex::new(vec![]);
+        let events = Mutex::new(EventBus::default());
+
+        Terminal {
+            reader: database.reader().with_clock(Arc::clone(&clock)),
+            database,
+            cart,
+            journal,
+            events,
+            sales: records.sales,
+            sequence: records.sequence,
+            audit: records.audit,
+            queue: records.queue,
+            clock,
+            config,
+            peripherals,
+            operator: Mutex::new(None),
+            scans: ScanQueue::new(),
+        }
+    }
+
+    /// Scanner interface
+    ///
+    /// Accepts a string of codes separated by whitespaces or commas, or any collection of
+    /// codes; see [ScanCodes] and [Codes]. Unknown codes don't abort the batch, and are listed
+    /// in the returned [ScanReport]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// terminal.scan("ABCDABAA").unwrap();
+    /// terminal.scan(["C", "C", "C"]).unwrap();
+    /// terminal.scan(vec!["C".to_string(); 4]).unwrap();
+    ///
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 39.65);
+    ///
+    /// terminal.reset_cart().unwrap();
+    /// terminal
+    ///     .load_catalog(vec![Product::new("7891234567895".to_string(), 8.5)], vec![])
+    ///     .unwrap();
+    ///
+    /// terminal.scan("7891234567895, A 7891234567895").unwrap();
+    ///
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 19.0);
+    /// ```
+    pub fn scan<C: ScanCodes>(&self, codes: C) -> Result<ScanReport, ErrorVariant> {
+        let mut report = ScanReport::default();
+        let result = codes.try_for_each_code(
+            |code| match self.lookup_code(code) {
+                Ok(code) => self.database.contains_product(&code).unwrap_or(false),
+                // Rejected as a whole rather than scanned one character per code
+                Err(_) => true,
+            },
+            |code| {
+                let pushed = self.lookup_code(code).and_then(|found| {
+                    let amount = self.scanned_amount(code, &found)?;
+                    let short = self.push_in_stock(&mut *self.write_cart()?, &found, amount)?;
+                    Ok((found, amount, short))
+                });
+                self.code_scanned(code, pushed, &mut report)
+            },
+        );
+        self.scan_finished(report, result)
+    }
+
+    /// Hand a code over to be scanned later, without waiting for the cart
+    ///
+    /// Conveyor scanners and other bursty inputs queue their codes instead of taking the cart
+    /// lock, so their latency stays flat while the cart is read or optimized. The codes are
+    /// scanned in order, each as a [Terminal::scan] of its own, by [Terminal::flush_scans],
+    /// which [Terminal::get_cart], the checkouts, [Terminal::undo] and [Terminal::reset_cart]
+    /// call first
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Arc::new(Terminal::new().unwrap());
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let conveyors: Vec<_> = (0..4)
+    ///     .map(|_| {
+    ///         let terminal = terminal.clone();
+    ///         thread::spawn(move || {
+    ///             for _ in 0..100 {
+    ///                 terminal.queue_scan("C");
+    ///             }
+    ///         })
+    ///     })
+    ///     .collect();
+    /// conveyors.into_iter().for_each(|c| c.join().unwrap());
+    /// terminal.queue_scan("X");
+    /// assert_eq!(terminal.get_queued_scans(), 401);
+    ///
+    /// let report = terminal.flush_scans().unwrap();
+    /// assert_eq!((report.get_accepted().len(), report.get_unknown().len()), (400, 1));
+    /// assert_eq!(terminal.get_queued_scans(), 0);
+    ///
+    /// terminal.queue_scan("A");
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 403.0);
+    /// ```
+    pub fn queue_scan(&self, code: &str) {
+        self.scans.push(code);
+    }
+
+    /// Number of codes queued by [Terminal::queue_scan] and not yet scanned
+    pub fn get_queued_scans(&self) -> usize {
+        self.scans.count()
+    }
+
+    /// Scan the codes queued by [Terminal::queue_scan], reporting all of them
+    ///
+    /// A code refused for its stock is reported by [TerminalEvent::ScanRejected] and doesn't
+    /// stop the codes queued after it
+    pub fn flush_scans(&self) -> Result<ScanReport, ErrorVariant> {
+        let mut report = ScanReport::default();
+        for code in self.scans.take() {
+            match self.scan([code.as_str()]) {
+                Ok(scanned) => report.append(scanned),
+                Err(ErrorVariant::NotEnoughItems { .. }) => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Code of the catalog to look up for a scanned code, validating barcodes if configured and
+    /// resolving aliases
+    fn lookup_code<'a>(&self, code: &'a str) -> Result<Cow<'a, str>, ErrorVariant> {
+        let code = if let Some(embedded) = self.embedded_code(code)? {
+            Cow::Owned(embedded.get_item().to_string())
+        } else if self.config.has_barcode_validation() {
+            Cow::Owned(barcode::normalize(code)?)
+        } else {
+            Cow::Borrowed(code)
+        };
+
+        // Aliases are scanned as the code of their product
+        match self.database.with_product(&code, |p| p.get_code().clone()) {
+            Ok(found) if found.as_str() != code => Ok(Cow::Owned(found.into())),
+            _ => Ok(code),
+        }
+    }
+
+    fn embedded_code(&self, code: &str) -> Result<Option<EmbeddedCode>, ErrorVariant> {
+        match self.config.get_embedded_barcodes() {
+            Some(layout) => layout.parse(code),
+            None => Ok(None),
+        }
+    }
+
+    /// Amount of the product `found` scanned by `code`: a unit, unless a value is embedded
+    fn scanned_amount(&self, code: &str, found: &str) -> Result<f64, ErrorVariant> {
+        let amount = match self
+            .embedded_code(code)?
+            .map(|embedded| embedded.get_value())
+        {
+            None => return Ok(1.0),
+            Some(EmbeddedValue::Weight(weight)) => weight,
+            Some(EmbeddedValue::Price(price)) => {
+                price / self.database.with_product(found, |p| *p.get_price())?
+            }
+        };
+        check_amount(found, amount).map(|()| amount)
+    }
+
+    /// Push `amount` of the product `code` into the cart, unless it takes more than the stock
+    /// on hand and the terminal blocks it
+    ///
+    /// Returns the stock on hand when the cart takes more than it and the terminal warns
+    fn push_in_stock(
+        &self,
+        cart: &mut Cart,
+        code: &str,
+        amount: f64,
+    ) -> Result<Option<f64>, ErrorVariant> {
+        let inventory = self.database.get_inventory();
+        let block = self.config.get_stock_policy() == StockPolicy::Block;
+        if self.config.has_stock_reservation() {
+            let short = inventory.reserve(code, amount, block)?;
+            return match cart.push_product(code, amount) {
+                Ok(()) => Ok(short),
+                Err(e) => {
+                    inventory.release(code, amount);
+                    Err(e)
+                }
+            };
+        }
+
+        let short = match inventory.get_stock(code) {
+            Some(stock) if cart.get_scanned_amount(code) + amount > stock => Some(stock),
+            _ => None,
+        };
+        if let (Some(stock), true) = (short, block) {
+            return Err(ErrorVariant::NotEnoughItems {
+                code: code.to_string(),
+                requested: amount,
+                available: (stock - cart.get_scanned_amount(code)).max(0.0),
+            });
+        }
+        cart.push_product(code, amount)?;
+        Ok(short)
+    }
+
+    /// Release the stock reserved for products removed from the cart
+    fn release_stock(&self, products: &[ProductAmount]) {
+        if self.config.has_stock_reservation() {
+            let inventory = self.database.get_inventory();
+            for p in products {
+                inventory.release(p.get_code(), *p.get_amount());
+            }
+        }
+    }
+
+    /// Empty the cart, releasing the stock reserved for it
+    fn empty_cart(&self, cart: &mut Cart) -> Result<(), ErrorVariant> {
+        self.release_stock(&cart.get_flat_quantities());
+        cart.reset()
+    }
+
+    /// Report and emit the outcome of the scan of a single code of a batch
+    ///
+    /// A code refused for its stock aborts the batch, as the codes following it may depend on it
+    fn code_scanned(
+        &self,
+        code: &str,
+        pushed: Result<(Cow<str>, f64, Option<f64>), ErrorVariant>,
+        report: &mut ScanReport,
+    ) -> Result<(), ErrorVariant> {
+        match pushed {
+            Ok((found, amount, short)) => {
+                let code = self.database.with_product(&found, |p| {
+                    report.push_accepted(p, amount);
+                    p.get_code().clone()
+                })?;
+                self.emit(TerminalEvent::ScanAccepted {
+                    code: code.clone(),
+                    amount,
+                })?;
+                self.out_of_stock(code.clone(), short)?;
+                self.hold_placed(&code)
+            }
+            Err(error @ ErrorVariant::ProductNotFound { .. })
+            | Err(error @ ErrorVariant::InvalidBarcode { .. }) => {
+                report.push_unknown(code);
+                self.emit(TerminalEvent::ScanRejected {
+                    code: code.to_string(),
+                    error,
+                })
+            }
+            Err(error @ ErrorVariant::NotEnoughItems { .. }) => {
+                self.emit(TerminalEvent::ScanRejected {
+                    code: code.to_string(),
+                    error: error.clone(),
+                })?;
+                Err(error)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Emit [TerminalEvent::OutOfStock] for the stock on hand of a scan exceeding it
+    fn out_of_stock(&self, code: Code, short: Option<f64>) -> Result<(), ErrorVariant> {
+        match short {
+            Some(stock) => self.emit(TerminalEvent::OutOfStock { code, stock }),
+            None => Ok(()),
+        }
+    }
+
+    /// Emit [TerminalEvent::ApprovalRequired] for a scan of a restricted product not approved
+    /// yet, at kiosks
+    fn hold_placed(&self, code: &str) -> Result<(), ErrorVariant> {
+        if !self.config.has_kiosk_mode() {
+            return Ok(());
+        }
+        let restriction = self.database.with_product(code, |p| p.get_restriction())?;
+        match restriction {
+            Some(restriction) if !self.read_cart()?.is_approved(code) => {
+                self.emit(TerminalEvent::ApprovalRequired {
+                    code: Code::from(code),
+                    restriction,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Record the accepted codes of a batch as a single undo step
+    fn scan_finished(
+        &self,
+        report: ScanReport,
+        result: Result<(), ErrorVariant>,
+    ) -> Result<ScanReport, ErrorVariant> {
+        let scanned = report.get_accepted().len();
+        if scanned > 0 {
+            self.record(JournalEntry::Scan(scanned))?;
+        }
+        result.map(|_| report)
+    }
+
+    /// Scan a given number of units of a single product code
+    ///
+    /// The units are added as a single cart line, and reverted by a single [Terminal::undo]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// terminal.scan_with_quantity("A", 4).unwrap();
+    /// terminal.scan_with_quantity("C", 7).unwrap();
+    ///
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 14.25);
+    /// assert!(terminal.scan_with_quantity("B", 0).is_err());
+    /// ```
+    pub fn scan_with_quantity(&self, code: &str, quantity: u32) -> Result<(), ErrorVariant> {
+        self.scan_amount(code, f64::from(quantity))
+    }
+
+    /// Scan a weighed item, with the product price taken as the price per unit of weight
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal
+    ///     .load_catalog(vec![Product::new("BANANA".to_string(), 3.0)], vec![])
+    ///     .unwrap();
+    ///
+    /// terminal.scan_with_weight("BANANA", 1.5).unwrap();
+    ///
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 4.5);
+    /// assert_eq!(
+    ///     terminal.scan_with_weight("BANANA", -1.0).unwrap_err(),
+    ///     ErrorVariant::InvalidQuantity { code: "BANANA".to_string(), quantity: -1.0 }
+    /// );
+    /// ```
+    pub fn scan_with_weight(&self, code: &str, weight: f64) -> Result<(), ErrorVariant> {
+        self.scan_amount(code, weight)
+    }
+
+    /// Scan a weighed item, with its weight read from the scale of the terminal
+    ///
+    /// The product price is sent to price computing scales as the price per unit of weight.
+    /// Returns the weight, scanned as [Terminal::scan_with_weight]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::peripheral::Scale;
+    /// use store_terminal::prelude::*;
+    ///
+    /// struct Platter(f64);
+    ///
+    /// impl Scale for Platter {
+    ///     fn weigh(&self, _unit_price: f64) -> Result<f64, ErrorVariant> {
+    ///         Ok(self.0)
+    ///     }
+    /// }
+    ///
+    /// let terminal = TerminalBuilder::new().with_scale(Platter(1.5)).build().unwrap();
+    /// terminal
+    ///     .load_catalog(vec![Product::new("BANANA".to_string(), 3.0)], vec![])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(terminal.scan_weighed("BANANA").unwrap(), 1.5);
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 4.5);
+    /// assert!(Terminal::new().unwrap().scan_weighed("BANANA").is_err());
+    /// ```
+    pub fn scan_weighed(&self, code: &str) -> Result<f64, ErrorVariant> {
+        let scale = self.peripherals.get_scale()?;
+        let unit_price = *self
+            .database
+            .fetch_product(&self.lookup_code(code)?)?
+            .get_price();
+        let weight = scale.weigh(unit_price)?;
+        self.scan_with_weight(code, weight)?;
+        Ok(weight)
+    }
+
+    fn scan_amount(&self, code: &str, amount: f64) -> Result<(), ErrorVariant> {
+        let code = match check_amount(code, amount).and_then(|()| self.lookup_code(code)) {
+            Ok(code) => code,
+            Err(e) => return self.amount_scanned(code, amount, Err(e)),
+        };
+        let pushed = self.push_in_stock(&mut *self.write_cart()?, &code, amount);
+        self.amount_scanned(&code, amount, pushed)
+    }
+
+    /// Record and emit the outcome of the scan of an amount of a product
+    fn amount_scanned(
+        &self,
+        code: &str,
+        amount: f64,
+        pushed: Result<Option<f64>, ErrorVariant>,
+    ) -> Result<(), ErrorVariant> {
+        match pushed {
+            Ok(short) => {
+                self.record(JournalEntry::Scan(1))?;
+                self.emit(TerminalEvent::ScanAccepted {
+                    code: Code::from(code),
+                    amount,
+                })?;
+                self.out_of_stock(Code::from(code), short)?;
+                self.hold_placed(code)
+            }
+            Err(error) => {
+                self.emit(TerminalEvent::ScanRejected {
+                    code: code.to_string(),
+                    error: error.clone(),
+                })?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Reset the terminal and load the demo catalog
+    #[deprecated(note = "use `Terminal::reset` and, for demos, `Terminal::load_demo_catalog`")]
+    pub fn init(&self) -> Result<(), ErrorVariant> {
+        self.reset()?;
+        self.load_demo_catalog()
+    }
+
+    /// Clear the database, the cart and the undo history
+    pub fn reset(&self) -> Result<(), ErrorVariant> {
+        self.authorize(Permission::DatabaseReset)?;
+        self.database.reset()?;
+        self.write_cart()?.reset()?;
+        lock::lock(&self.journal).clear();
+        self.audit(AuditAction::DatabaseReset)?;
+        self.emit(TerminalEvent::CartReset)
+    }
+
+    /// Append the given products and promotions into the database
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    ///
+    /// let product = Product::new("X".to_string(), 3.0);
+    /// let promotion = Promotion::new("PX".to_string(), vec![product.generate_amount(2.0)], 5.0).unwrap();
+    /// terminal.load_catalog(vec![product], vec![promotion]).unwrap();
+    ///
+    /// terminal.scan("XXX").unwrap();
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 8.0);
+    /// assert!(terminal.get_db().unwrap().fetch_product("A").is_err());
+    /// ```
+    pub fn load_catalog(
+        &self,
+        products: Vec<Product>,
+        promotions: Vec<Promotion>,
+    ) -> Result<(), ErrorVariant> {
+        self.change_catalog(|database| Catalog::new(products, promotions).append_into(database))
+    }
+
+    /// Append the demo products `A`, `B`, `C` and `D`, and the promotions `PA` and `PC`
+    pub fn load_demo_catalog(&self) -> Result<(), ErrorVariant> {
+        self.change_catalog(|database| {
+            database.append(Product::new("A", 2.0))?;
+            database.append(Product::new("B", 12.0))?;
+            database.append(Product::new("C", 1.25))?;
+            database.append(Product::new("D", 0.15))?;
+
+            let products = vec![database.code_to_product_amount("A", 4.0)?];
+            database.append(Promotion::new("PA", products, 7.0)?)?;
+
+            let products = vec![database.code_to_product_amount("C", 6.0)?];
+            database.append(Promotion::new("PC", products, 6.0)?)
+        })
+    }
+
+    /// Apply `change` to the catalog of the database, recording the prices it added, changed
+    /// or removed in the audit log
+    ///
+    /// With [TerminalConfig::with_operator_login], the operator logged in must be allowed to
+    /// [Permission::CatalogChange](crate::operator::Permission::CatalogChange)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let removed = terminal
+    ///     .change_catalog(|database| {
+    ///         database.append(Product::new("A", 2.5))?;
+    ///         database.remove_product("D")
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(removed.get_code(), "D");
+    ///
+    /// let entries = terminal.audit_log().get_entries();
+    /// let actions: Vec<_> = entries.iter().skip(6).map(|e| e.get_action().clone()).collect();
+    /// assert_eq!(
+    ///     actions,
+    ///     vec![
+    ///         AuditAction::CatalogChange {
+    ///             code: "A".to_string(),
+    ///             previous: Some(2.0),
+    ///             price: Some(2.5),
+    ///         },
+    ///         AuditAction::CatalogChange { code: "D".to_string(), previous: Some(0.15), price: None },
+    ///     ]
+    /// );
+    ///
+    /// let events = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    /// let listener = events.clone();
+    /// terminal.subscribe(move |event| listener.lock().unwrap().push(event.clone())).unwrap();
+    /// terminal.change_catalog(|database| database.append(Product::new("E", 3.0))).unwrap();
+    /// assert_eq!(
+    ///     *events.lock().unwrap(),
+    ///     vec![TerminalEvent::CatalogChanged { code: "E".into(), previous: None, price: Some(3.0) }]
+    /// );
+    /// ```
+    pub fn change_catalog<F, R>(&self, change: F) -> Result<R, ErrorVariant>
+    where
+        F: FnOnce(&Database) -> Result<R, ErrorVariant>,
+    {
+        self.authorize(Permission::CatalogChange)?;
+        self.audit_catalog(change)
+    }
+
+    /// Apply `change` to the catalog without authorization, auditing it
+    fn audit_catalog<F, R>(&self, change: F) -> Result<R, ErrorVariant>
+    where
+        F: FnOnce(&Database) -> Result<R, ErrorVariant>,
+    {
+        let before = catalog_prices(&self.database)?;
+        let changed = change(&self.database)?;
+        let after = catalog_prices(&self.database)?;
+        for action in catalog_changes(&before, &after) {
+            if let AuditAction::CatalogChange {
+                code,
+                previous,
+                price,
+            } = &action
+            {
+                self.emit(TerminalEvent::CatalogChanged {
+                    code: Code::from(code.as_str()),
+                    previous: *previous,
+                    price: *price,
+                })?;
+            }
+            self.audit(action)?;
+        }
+        Ok(changed)
+    }
+
+    /// Override the price of a product or promotion; the entity as provided is restored by
+    /// [Terminal::undo]
+    pub fn set_pricing<T>(&self, entity: T, price: f64) -> Result<(), ErrorVariant>
+    where
+        T: WithNewPricing + Send + 'static,
+        Database: DatabaseAppend<T>,
+    {
+        self.authorize(Permission::PriceOverride)?;
+        let code = entity.get_code().clone();
+        let previous = *entity.get_price();
+        let updated = entity.with_new_pricing(price)?;
+        self.database.append(updated)?;
+        self.audit(AuditAction::PriceOverride {
+            code: code.to_string(),
+            previous,
+            price,
+        })?;
+        self.record(JournalEntry::PriceOverride(
+            Box::new(move |database: &Database| database.append(entity)),
+            TerminalEvent::PriceChanged {
+                code: code.clone(),
+                previous: price,
+                price: previous,
+            },
+        ))?;
+        self.emit(TerminalEvent::PriceChanged {
+            code,
+            previous,
+            price,
+        })
+    }
+
+    /// Revert the last scan or price override
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// terminal.scan("AB").unwrap();
+    /// let product = terminal.get_db().unwrap().fetch_product("C").unwrap();
+    /// terminal.set_pricing(product, 10.0).unwrap();
+    /// terminal.scan("C").unwrap();
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 24.0);
+    ///
+    /// terminal.undo().unwrap();
+    /// terminal.undo().unwrap();
+    /// terminal.scan("C").unwrap();
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 15.25);
+    ///
+    /// terminal.undo().unwrap();
+    /// terminal.undo().unwrap();
+    /// assert!(terminal.undo().unwrap().is_none());
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 0.0);
+    /// ```
+    pub fn undo(&self) -> Result<Option<Undone>, ErrorVariant> {
+        self.flush_scans()?;
+        let entry = lock::lock(&self.journal).pop();
+
+        match entry {
+            None => Ok(None),
+            Some(JournalEntry::Scan(count)) => {
+                let undone = undo_scans(&mut *self.write_cart()?, count)?;
+                self.scans_undone(undone).map(Some)
+            }
+            Some(JournalEntry::PriceOverride(restore, event)) => {
+                self.restore_pricing(restore, event).map(Some)
+            }
+        }
+    }
+
+    /// Emit the products removed from the cart by an undo
+    fn scans_undone(&self, undone: Undone) -> Result<Undone, ErrorVariant> {
+        if let Undone::Scan(products) = &undone {
+            self.release_stock(products);
+            for p in products {
+                self.audit(AuditAction::LineVoid {
+                    code: p.get_code().to_string(),
+                    amount: *p.get_amount(),
+                })?;
+                self.emit(TerminalEvent::ScanUndone {
+                    code: p.get_code().clone(),
+                    amount: *p.get_amount(),
+                })?;
+            }
+        }
+        Ok(undone)
+    }
+
+    fn restore_pricing(
+        &self,
+        restore: RestorePricing,
+        event: TerminalEvent,
+    ) -> Result<Undone, ErrorVariant> {
+        restore(&self.database)?;
+        if let TerminalEvent::PriceChanged {
+            code,
+            previous,
+            price,
+        } = &event
+        {
+            self.audit(AuditAction::PriceOverride {
+                code: code.to_string(),
+                previous: *previous,
+                price: *price,
+            })?;
+        }
+        self.emit(event)?;
+        Ok(Undone::PriceOverride)
+    }
+
+    /// Recover the cart poisoned by a thread that panicked while changing it
+    ///
+    /// The cart is kept as it was left, its optimization is discarded and
+    /// [TerminalEvent::CartRecovered] is emitted
+    fn recover_cart(&self) -> Result<(), ErrorVariant> {
+        if !self.cart.is_poisoned() {
+            return Ok(());
+        }
+
+        let recovered = {
+            let mut cart = self.cart.write();
+            let recovered = self.cart.clear_poison();
+            if recovered {
+                cart.invalidate_optimization();
+            }
+            recovered
+        };
+        if recovered {
+            self.emit(TerminalEvent::CartRecovered)?;
+        }
+        Ok(())
+    }
+
+    fn read_cart(&self) -> Result<WakingReadGuard<'_, Cart>, ErrorVariant> {
+        self.recover_cart()?;
+        Ok(self.cart.read())
+    }
+
+    fn write_cart(&self) -> Result<WakingWriteGuard<'_, Cart>, ErrorVariant> {
+        self.recover_cart()?;
+        Ok(self.cart.write())
+    }
+
+    fn record(&self, entry: JournalEntry) -> Result<(), ErrorVariant> {
+        lock::lock(&self.journal).push(entry);
+        Ok(())
+    }
+
+    /// Snapshot of the cart, with the promotions optimized
+    ///
+    /// The optimization runs on a copy, without holding the cart lock, so scans from other
+    /// threads are not blocked by it. The result is kept by the terminal only if the cart didn't
+    /// change meanwhile
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use store_terminal::prelude::*;
+    ///
+    /// fn assert_send_sync<T: Send + Sync>() {}
+    /// assert_send_sync::<Terminal>();
+    ///
+    /// let terminal = Arc::new(Terminal::new().unwrap());
+    /// let products = vec![Product::new("A", 2.0), Product::new("B", 0.5)];
+    /// terminal.load_catalog(products, vec![]).unwrap();
+    ///
+    /// let scanner = {
+    ///     let terminal = terminal.clone();
+    ///     thread::spawn(move || {
+    ///         for _ in 0..100 {
+    ///             terminal.scan("AB").unwrap();
+    ///         }
+    ///     })
+    /// };
+    /// let display = {
+    ///     let terminal = terminal.clone();
+    ///     thread::spawn(move || {
+    ///         for _ in 0..100 {
+    ///             let total = terminal.get_cart().unwrap().get_total_price();
+    ///             assert!(total >= 0.0);
+    ///         }
+    ///     })
+    /// };
+    ///
+    /// scanner.join().unwrap();
+    /// display.join().unwrap();
+    ///
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 250.0);
+    /// ```
+    pub fn get_cart(&self) -> Result<Cart, ErrorVariant> {
+        self.flush_scans()?;
+        let (generation, mut cart) = {
+            let cart = self.read_cart()?;
+            if cart.is_optimized() {
+                return Ok(cart.clone());
+            }
+            (cart.get_generation(), cart.clone())
+        };
+
+        let previous = event::promotion_amounts(&cart);
+        cart.optimize_promotions()?;
+        keep_optimized(&mut *self.write_cart()?, generation, &cart);
+        self.promotions_applied(&previous, &cart)?;
+
+        Ok(cart)
+    }
+
+    /// Emit the promotions applied to `cart` since the `previous` promotion amounts
+    fn promotions_applied(
+        &self,
+        previous: &[(Code, f64)],
+        cart: &Cart,
+    ) -> Result<(), ErrorVariant> {
+        for (code, amount) in event::promotion_amounts(cart) {
+            let applied = previous
+                .iter()
+                .find(|(c, _)| c == &code)
+                .map(|(_, a)| amount - a)
+                .unwrap_or(amount);
+            if applied > 0.0 {
+                self.emit(TerminalEvent::PromotionApplied {
+                    code,
+                    amount: applied,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the sale, finalizing the optimized cart into a stored [Sale] and emptying it
+    ///
+    /// [TerminalEvent::CheckedOut] is emitted, followed by [TerminalEvent::CartReset]. Items
+    /// scanned while the cart is optimized are never lost: they are part of the returned sale
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AB").unwrap();
+    ///
+    /// let events = Arc::new(Mutex::new(vec![]));
+    /// let log = events.clone();
+    /// terminal.subscribe(move |event| log.lock().unwrap().push(event.clone())).unwrap();
+    ///
+    /// assert_eq!(terminal.checkout().unwrap().get_totals().get_total(), 14.0);
+    /// assert!(terminal.get_cart().unwrap().get_items().is_empty());
+    /// assert_eq!(
+    ///     *events.lock().unwrap(),
+    ///     vec![TerminalEvent::CheckedOut { sale: 1, total: 14.0 }, TerminalEvent::CartReset]
+    /// );
+    /// ```
+    pub fn checkout(&self) -> Result<Sale, ErrorVariant> {
+        self.close_sale(|_| Ok(()))
+    }
+
+    /// Close the sale paid in full with `tender`, opening the cash drawer first for cash
+    ///
+    /// A drawer failing to open is reported before the sale is closed, so it can be paid
+    /// otherwise. Lanes without a drawer check out as [Terminal::checkout]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::{Arc, Mutex};
+    /// use store_terminal::peripheral::CashDrawer;
+    /// use store_terminal::prelude::*;
+    ///
+    /// #[derive(Default)]
+    /// struct Drawer(AtomicUsize);
+    ///
+    /// impl CashDrawer for Drawer {
+    ///     fn open(&self) -> Result<(), ErrorVariant> {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let drawer = Arc::new(Drawer::default());
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_cash_drawer(drawer.clone())
+    ///     .build()
+    ///     .unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let events = Arc::new(Mutex::new(vec![]));
+    /// let log = events.clone();
+    /// terminal.subscribe(move |event| log.lock().unwrap().push(event.clone())).unwrap();
+    ///
+    /// terminal.scan("A").unwrap();
+    /// terminal.checkout_with_tender(Tender::Card).unwrap();
+    /// terminal.scan("B").unwrap();
+    /// terminal.checkout_with_tender(Tender::Cash).unwrap();
+    /// terminal.open_drawer("change for a 50").unwrap();
+    /// assert_eq!(drawer.0.load(Ordering::SeqCst), 2);
+    ///
+    /// let openings: Vec<_> = events
+    ///     .lock()
+    ///     .unwrap()
+    ///     .iter()
+    ///     .filter_map(|event| match event {
+    ///         TerminalEvent::DrawerOpened { reason } => Some(reason.clone()),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    /// assert_eq!(openings, vec![None, Some("change for a 50".to_string())]);
+    /// ```
+    pub fn checkout_with_tender(&self, tender: Tender) -> Result<Sale, ErrorVariant> {
+        self.close_sale(|sale| match sale.get_amount_due() {
+            due if due > 0.0 => sale.add_tender(tender, due),
+            _ => Ok(()),
+        })
+    }
+
+    /// Close the sale paid with `tenders`, which must cover its total
+    ///
+    /// The sale is left open when the tenders fall short, so more can be given. Cash over the
+    /// amount due is returned as the change of the sale, and opens the cash drawer first as
+    /// [Terminal::checkout_with_tender]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AB").unwrap();
+    ///
+    /// let short = [TenderAmount::new(Tender::Voucher, 5.0)];
+    /// assert_eq!(
+    ///     terminal.checkout_with_tenders(&short).unwrap_err(),
+    ///     ErrorVariant::InsufficientPayment { total: 14.0, paid: 5.0 }
+    /// );
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 14.0);
+    ///
+    /// let tenders = [
+    ///     TenderAmount::new(Tender::Voucher, 5.0),
+    ///     TenderAmount::new(Tender::Cash, 10.0),
+    /// ];
+    /// let sale = terminal.checkout_with_tenders(&tenders).unwrap();
+    /// assert_eq!(sale.get_change_due(), 1.0);
+    /// assert!(terminal.get_cart().unwrap().get_items().is_empty());
+    /// ```
+    pub fn checkout_with_tenders(&self, tenders: &[TenderAmount]) -> Result<Sale, ErrorVariant> {
+        self.close_sale(|sale| {
+            for tender in tenders {
+                sale.add_tender(tender.get_tender(), tender.get_amount())?;
+            }
+            sale.check_paid()
+        })
+    }
+
+    /// Sell a gift card loading `amount` onto the card `number`, as a cart line reverted by
+    /// [Terminal::undo]
+    ///
+    /// The card is issued, or its balance topped up, when the sale is checked out
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// terminal.sell_gift_card("6035-0001", 50.0).unwrap();
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 50.0);
+    /// assert!(terminal.fetch_gift_card("6035-0001").is_err());
+    ///
+    /// terminal.checkout_with_tender(Tender::Card).unwrap();
+    /// assert_eq!(terminal.fetch_gift_card("6035-0001").unwrap().get_balance(), 50.0);
+    ///
+    /// terminal.sell_gift_card("6035-0002", 20.0).unwrap();
+    /// terminal.undo().unwrap();
+    /// terminal.checkout().unwrap();
+    /// assert!(terminal.fetch_gift_card("6035-0002").is_err());
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn sell_gift_card(&self, number: &str, amount: f64) -> Result<(), ErrorVariant> {
+        if number.trim().is_empty() {
+            return Err(ErrorVariant::InvalidPaymentDetails {
+                field: "gift card number".to_string(),
+                value: number.to_string(),
+            });
+        }
+        if let Err(e) = check_amount(GIFT_CARD_CODE, amount) {
+            return self.amount_scanned(GIFT_CARD_CODE, amount, Err(e));
+        }
+        self.write_cart()?.push_gift_card(number, amount);
+        self.amount_scanned(GIFT_CARD_CODE, amount, Ok(None))
+    }
+
+    /// Close the sale paid with the balance of the gift card `number`, up to the total, and
+    /// with `tenders` for the rest
+    ///
+    /// The balance is drawn before the sale is closed, and given back if it can't be closed,
+    /// so lanes sharing the database can't spend it twice
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.sell_gift_card("6035-0001", 10.0).unwrap();
+    /// terminal.checkout_with_tender(Tender::Cash).unwrap();
+    ///
+    /// terminal.scan("AB").unwrap();
+    /// assert!(terminal.checkout_with_gift_card("6035-0001", &[]).is_err());
+    /// assert_eq!(terminal.fetch_gift_card("6035-0001").unwrap().get_balance(), 10.0);
+    ///
+    /// let cash = [TenderAmount::new(Tender::Cash, 5.0)];
+    /// let sale = terminal.checkout_with_gift_card("6035-0001", &cash).unwrap();
+    /// assert_eq!(sale.get_tenders()[0], TenderAmount::new(Tender::GiftCard, 10.0));
+    /// assert_eq!(sale.get_change_due(), 1.0);
+    /// assert_eq!(terminal.fetch_gift_card("6035-0001").unwrap().get_balance(), 0.0);
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn checkout_with_gift_card(
+        &self,
+        number: &str,
+        tenders: &[TenderAmount],
+    ) -> Result<Sale, ErrorVariant> {
+        let cards = self.database.store::<GiftCard>(GIFT_CARD_STORE);
+        let due = Sale::new(&self.get_cart()?).get_amount_due();
+        let drawn = gift_card::draw(&cards, number, due)?;
+
+        let sale = self.close_sale(|sale| {
+            if drawn > 0.0 {
+                sale.add_tender(Tender::GiftCard, drawn)?;
+            }
+            for tender in tenders {
+                sale.add_tender(tender.get_tender(), tender.get_amount())?;
+            }
+            sale.check_paid()
+        });
+        if sale.is_err() && drawn > 0.0 {
+            gift_card::credit(&cards, number, drawn)?;
+        }
+        sale
+    }
+
+    /// Gift card `number`, with its balance
+    #[cfg(feature = "json")]
+    pub fn fetch_gift_card(&self, number: &str) -> Result<GiftCard, ErrorVariant> {
+        self.database
+            .store::<GiftCard>(GIFT_CARD_STORE)
+            .fetch(number)
+    }
+
+    /// Sales closed by the terminal, oldest first
+    pub fn get_sales(&self) -> Result<Vec<Sale>, ErrorVariant> {
+        Ok(self.sales.get_sales())
+    }
+
+    pub fn fetch_sale(&self, id: u64) -> Result<Sale, ErrorVariant> {
+        self.sales.fetch(id)
+    }
+
+    /// Sales and refunds waiting for the head office backend
+    pub fn get_sale_queue(&self) -> &SaleQueue {
+        &self.queue
+    }
+
+    /// Upload the sales queued for the head office backend, as after an outage; see
+    /// [SaleQueue]
+    pub fn sync_sales(&self) -> Result<SyncReport, ErrorVariant> {
+        self.queue.sync()
+    }
+
+    /// Sales and refunds of the terminal, with its Z-reports
+    pub fn get_sales_history(&self) -> &SalesHistory {
+        &self.sales
+    }
+
+    /// Refund `lines` of the sale `sale_id`, as codes and amounts of its products
+    ///
+    /// Products of a promotion are refunded at their share of the promotion price, paid back in
+    /// the tenders of the sale in proportion to what each of them paid. The loyalty points of
+    /// the customer are reversed in the same proportion: the redeemed points are given back
+    /// and the earned ones taken back. Returns the stored refund, a sale of negative amounts
+    /// linked to the original, and emits [TerminalEvent::SaleRefunded]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AAAAB").unwrap();
+    /// let sale = terminal.checkout().unwrap();
+    ///
+    /// // One of the four A of the promotion PA, 4 for 7.0, and the B
+    /// let refund = terminal.refund(sale.get_id(), &[("A", 1.0), ("B", 1.0)]).unwrap();
+    /// assert_eq!(refund.get_refunded_sale(), Some(1));
+    /// assert_eq!(refund.get_lines()[0].get_total(), -1.75);
+    /// assert_eq!(refund.get_totals().get_total(), -13.75);
+    /// assert_eq!(refund.get_totals().get_savings(), -0.25);
+    ///
+    /// assert_eq!(
+    ///     terminal.refund(1, &[("A", 4.0)]).unwrap_err(),
+    ///     ErrorVariant::NotEnoughItems { code: "A".to_string(), requested: 4.0, available: 3.0 }
+    /// );
+    /// assert!(terminal.refund(1, &[("C", 1.0)]).is_err());
+    /// assert!(terminal.refund(9, &[("A", 1.0)]).is_err());
+    /// ```
+    pub fn refund(&self, sale_id: u64, lines: &[(&str, f64)]) -> Result<Sale, ErrorVariant> {
+        self.authorize(Permission::Refund)?;
+        let refund = self.sales.refund(
+            sale_id,
+            lines,
+            self.get_operator_id(),
+            self.config.get_terminal_id(),
+            self.clock.now(),
+            |next| self.number(next),
+        )?;
+        #[cfg(feature = "json")]
+        if let Some(activity) = refund.get_loyalty() {
+            loyalty::reverse(&self.loyalty_accounts(), activity)?;
+        }
+        self.forward(&refund)?;
+        self.emit(TerminalEvent::SaleRefunded {
+            sale: sale_id,
+            total: refund.get_totals().get_total(),
+        })?;
+        Ok(refund)
+    }
+
+    /// Open a shift of the terminal with `float` in the drawer; its sales are reconciled by
+    /// [Terminal::close_shift]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let shift = terminal.open_shift(100.0).unwrap();
+    /// assert_eq!(shift.get_number(), 1);
+    /// assert!(terminal.open_shift(50.0).is_err());
+    ///
+    /// terminal.scan("AB").unwrap();
+    /// let tenders = [TenderAmount::new(Tender::Cash, 20.0)];
+    /// terminal.checkout_with_tenders(&tenders).unwrap();
+    /// terminal.scan("B").unwrap();
+    /// terminal.checkout_with_tender(Tender::Card).unwrap();
+    /// terminal.refund(1, &[("A", 1.0)]).unwrap();
+    /// let refund = terminal.refund(2, &[("B", 1.0)]).unwrap();
+    /// assert_eq!(refund.get_tenders(), &vec![TenderAmount::new(Tender::Card, -12.0)]);
+    ///
+    /// let report = terminal.close_shift(111.5).unwrap();
+    /// assert_eq!((report.get_sales(), report.get_refunds()), (2, 2));
+    /// assert_eq!(
+    ///     report.get_tenders(),
+    ///     &vec![TenderAmount::new(Tender::Cash, 14.0), TenderAmount::new(Tender::Card, 12.0)]
+    /// );
+    /// assert_eq!(report.get_cash_refunds(), 2.0);
+    /// assert_eq!(report.get_expected(), 112.0);
+    /// assert_eq!(report.get_difference(), -0.5);
+    /// assert_eq!(report.render_text(24).last().unwrap(), "SHORT               0.50");
+    ///
+    /// assert_eq!(
+    ///     terminal.close_shift(0.0).unwrap_err(),
+    ///     ErrorVariant::ShiftUnavailable { reason: "no shift is open".to_string() }
+    /// );
+    /// assert_eq!(terminal.open_shift(100.0).unwrap().get_number(), 2);
+    /// assert_eq!(terminal.get_sales_history().get_shift_reports(), vec![report]);
+    /// ```
+    pub fn open_shift(&self, float: f64) -> Result<Shift, ErrorVariant> {
+        self.sales.open_shift(
+            float,
+            self.get_operator_id(),
+            self.config.get_terminal_id(),
+            self.clock.now(),
+        )
+    }
+
+    /// Shift open on the terminal, if any
+    pub fn get_shift(&self) -> Option<Shift> {
+        self.sales.get_shift(self.config.get_terminal_id())
+    }
+
+    /// Close the shift open, returning the over/short report of the `counted_cash` in the
+    /// drawer against the float and the cash sales and refunds of the shift
+    pub fn close_shift(&self, counted_cash: f64) -> Result<ShiftReport, ErrorVariant> {
+        self.sales.close_shift(
+            counted_cash,
+            self.config.get_terminal_id(),
+            self.clock.now(),
+        )
+    }
+
+    /// Unpaid sale of `cart`, taxed at the rates of the configuration
+    pub(crate) fn new_sale(&self, cart: &Cart) -> Sale {
+        let sale = Sale::new(cart).with_timestamp(self.clock.now());
+        match self.config.get_tax_table() {
+            Some(table) => sale.with_taxes(table),
+            None => sale,
+        }
+    }
+
+    /// Close the sale of the cart once `pay` succeeds, opening the drawer for cash tenders
+    fn close_sale<F>(&self, pay: F) -> Result<Sale, ErrorVariant>
+    where
+        F: Fn(&mut Sale) -> Result<(), ErrorVariant>,
+    {
+        let mut drawer_opened = false;
+        loop {
+            let cart = self.get_cart()?;
+            let sale = self.prepare_sale(&cart, &pay, &mut drawer_opened)?;
+            let stored = self
+                .write_cart()
+                .and_then(|mut current| self.store_unchanged(&mut current, &cart, &sale));
+            if let Some(sale) = self.finish_sale(&sale, stored)? {
+                return Ok(sale);
+            }
+        }
+    }
+
+    /// Sale of the optimized `cart` paid by `pay`, with the points its customer redeems taken
+    /// out of their balance
+    ///
+    /// Kiosk sales with restricted products waiting for approval are refused. The drawer is
+    /// opened for cash tenders unless `drawer_opened` already
+    fn prepare_sale<F>(
+        &self,
+        cart: &Cart,
+        pay: F,
+        drawer_opened: &mut bool,
+    ) -> Result<Sale, ErrorVariant>
+    where
+        F: Fn(&mut Sale) -> Result<(), ErrorVariant>,
+    {
+        self.check_holds(cart)?;
+        let mut sale = self.new_sale(cart);
+        pay(&mut sale)?;
+
+        let cash = sale
+            .get_tenders()
+            .iter()
+            .any(|t| t.get_tender() == Tender::Cash);
+        if let (true, false, Some(drawer)) = (cash, *drawer_opened, &self.peripherals.drawer) {
+            drawer.open()?;
+            *drawer_opened = true;
+            self.emit(TerminalEvent::DrawerOpened { reason: None })?;
+        }
+
+        #[cfg(feature = "json")]
+        let sale = self.debit_points(cart, sale)?;
+        Ok(sale)
+    }
+
+    /// Store the prepared `sale` in the history and reset the `current` cart, unless it
+    /// changed since `cart` was taken
+    ///
+    /// The cart is kept if the sale fails to be stored, so a checkout is either stored or left
+    /// to be tried again
+    pub(super) fn store_unchanged(
+        &self,
+        current: &mut Cart,
+        cart: &Cart,
+        sale: &Sale,
+    ) -> Result<Option<Sale>, ErrorVariant> {
+        if current.get_generation() != cart.get_generation() {
+            return Ok(None);
+        }
+        let sale = self.sales.push(
+            sale.clone()
+                .with_operator(self.get_operator_id())
+                .with_terminal(self.config.get_terminal_id()),
+            |next| self.number(next),
+        )?;
+        current.reset()?;
+        Ok(Some(sale))
+    }
+
+    /// Complete the checkout of the prepared `sale` once `stored`, or give its points back and
+    /// return `None` for the sale to be prepared again from the current cart
+    #[cfg_attr(not(feature = "json"), allow(unused_variables))]
+    fn finish_sale(
+        &self,
+        sale: &Sale,
+        stored: Result<Option<Sale>, ErrorVariant>,
+    ) -> Result<Option<Sale>, ErrorVariant> {
+        #[cfg(feature = "json")]
+        if !matches!(stored, Ok(Some(_))) {
+            self.return_points(sale)?;
+        }
+        match stored? {
+            Some(sale) => self.checked_out(sale).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Redeem `points` of the identified customer as a discount of the cart, replacing any
+    /// previous redemption, or give them up with `0`, returning the discount
+    ///
+    /// The points are worth [LoyaltyPolicy::get_points_value](crate::prelude::LoyaltyPolicy::get_points_value)
+    /// and are taken out of the balance of the customer when the sale is checked out. See
+    /// [TerminalConfig::with_loyalty_policy] for an example
+    #[cfg(feature = "json")]
+    pub fn redeem_points(&self, points: u64) -> Result<f64, ErrorVariant> {
+        let unavailable = |reason: &str| ErrorVariant::LoyaltyUnavailable {
+            reason: reason.to_string(),
+        };
+        let policy = self
+            .config
+            .get_loyalty_policy()
+            .ok_or_else(|| unavailable("no loyalty policy configured"))?;
+        let cart = self.get_cart()?;
+        let customer = cart
+            .get_customer()
+            .ok_or_else(|| unavailable("no customer identified"))?
+            .get_id();
+
+        if points == 0 {
+            self.write_cart()?.set_loyalty_redemption(None);
+            return Ok(0.0);
+        }
+        let value = policy.get_points_value(points);
+        let price = cart.get_total_price() + cart.get_loyalty_discount();
+        if points < policy.get_minimum_redemption() || value > price {
+            return Err(ErrorVariant::InvalidQuantity {
+                code: customer.to_string(),
+                quantity: points as f64,
+            });
+        }
+        let available = self.fetch_loyalty_points(customer)?;
+        if points > available {
+            return Err(ErrorVariant::NotEnoughItems {
+                code: customer.to_string(),
+                requested: points as f64,
+                available: available as f64,
+            });
+        }
+
+        self.write_cart()?
+            .set_loyalty_redemption(Some(LoyaltyRedemption::new(points, value)));
+        Ok(value)
+    }
+
+    /// Loyalty points of the customer identified by `customer`, 0 without an account
+    #[cfg(feature = "json")]
+    pub fn fetch_loyalty_points(&self, customer: &str) -> Result<u64, ErrorVariant> {
+        match self.loyalty_accounts().fetch(customer) {
+            Ok(account) => Ok(account.get_points()),
+            Err(ErrorVariant::EntityNotFound { .. }) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg(feature = "json")]
+    fn loyalty_accounts(&self) -> DatabaseStore<LoyaltyAccount> {
+        self.database.store(LOYALTY_STORE)
+    }
+
+    /// Sale of `cart` with the points its customer earns and redeems, taking the redeemed
+    /// points out of their balance
+    #[cfg(feature = "json")]
+    fn debit_points(&self, cart: &Cart, sale: Sale) -> Result<Sale, ErrorVariant> {
+        let (policy, customer) = match (self.config.get_loyalty_policy(), cart.get_customer()) {
+            (Some(policy), Some(customer)) => (policy, customer),
+            _ => return Ok(sale),
+        };
+        let id = customer.get_id();
+
+        let redeemed = cart.get_loyalty_redemption().map_or(0, |r| r.get_points());
+        if redeemed > 0 {
+            loyalty::debit(&self.loyalty_accounts(), id, redeemed)?;
+        }
+        let earned = policy.get_customer_points_earned(customer, sale.get_net_total());
+        Ok(sale.with_loyalty(Some(LoyaltyActivity::new(id, earned, redeemed))))
+    }
+
+    /// Give back the points redeemed by a sale that wasn't closed
+    #[cfg(feature = "json")]
+    fn return_points(&self, sale: &Sale) -> Result<(), ErrorVariant> {
+        match sale.get_loyalty() {
+            Some(activity) if activity.get_redeemed() > 0 => loyalty::credit(
+                &self.loyalty_accounts(),
+                activity.get_customer(),
+                activity.get_redeemed(),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Open the cash drawer outside of a sale, recording `reason` for the audit of the lane
+    ///
+    /// [TerminalEvent::DrawerOpened] is emitted with the reason, so subscribers can log it
+    pub fn open_drawer(&self, reason: &str) -> Result<(), ErrorVariant> {
+        self.peripherals.get_drawer()?.open()?;
+        self.emit(TerminalEvent::DrawerOpened {
+            reason: Some(reason.to_string()),
+        })
+    }
+
+    /// Identify the customer of the sale in progress, until the cart is reset or checked out
+    ///
+    /// The cart is optimized again for the customer, and [TerminalEvent::CustomerIdentified]
+    /// is emitted
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::customer::Identification;
+    /// use store_terminal::prelude::*;
+    ///
+    /// /// Promotions reserved to the members of the loyalty program
+    /// struct MembersOnly;
+    ///
+    /// impl OptimizerStrategy for MembersOnly {
+    ///     fn get_optimal_products_promotions(
+    ///         &self,
+    ///         products: Vec<ProductAmount>,
+    ///         _database: &DatabaseReader,
+    ///     ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+    ///         Ok((products, vec![]))
+    ///     }
+    ///
+    ///     fn get_optimal_customer_promotions(
+    ///         &self,
+    ///         products: Vec<ProductAmount>,
+    ///         database: &DatabaseReader,
+    ///         customer: Option<&Customer>,
+    ///     ) -> Result<(Vec<ProductAmount>, Vec<Promotion>), ErrorVariant> {
+    ///         match customer {
+    ///             Some(_) => DefaultOptimizerStrategy.get_optimal_products_promotions(products, database),
+    ///             None => self.get_optimal_products_promotions(products, database),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_optimizer_strategy(MembersOnly)
+    ///     .build()
+    ///     .unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("AAAA").unwrap();
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 8.0);
+    ///
+    /// terminal
+    ///     .set_customer(Customer::new("M-1042", Identification::Phone))
+    ///     .unwrap();
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 7.0);
+    /// assert_eq!(terminal.get_customer().unwrap().unwrap().get_id(), "M-1042");
+    ///
+    /// terminal.checkout().unwrap();
+    /// assert_eq!(terminal.get_customer().unwrap(), None);
+    /// ```
+    pub fn set_customer(&self, customer: Customer) -> Result<(), ErrorVariant> {
+        let id = customer.get_id().to_string();
+        self.write_cart()?.set_customer(Some(customer));
+        self.emit(TerminalEvent::CustomerIdentified { id })
+    }
+
+    /// Read the customer from the reader of the terminal, identifying it for the sale
+    ///
+    /// Returns `None`, leaving the sale unchanged, when nothing was presented to the reader
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use store_terminal::peripheral::MagstripeReader;
+    /// use store_terminal::prelude::*;
+    ///
+    /// let terminal = TerminalBuilder::new()
+    ///     .with_customer_reader(MagstripeReader::new(Cursor::new(";6012345678901234=2512?\n")))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let customer = terminal.identify_customer().unwrap().unwrap();
+    /// assert_eq!(customer.get_id(), "6012345678901234");
+    /// assert_eq!(terminal.get_customer().unwrap(), Some(customer));
+    /// assert_eq!(terminal.identify_customer().unwrap(), None);
+    /// assert!(Terminal::new().unwrap().identify_customer().is_err());
+    /// ```
+    pub fn identify_customer(&self) -> Result<Option<Customer>, ErrorVariant> {
+        let customer = self.peripherals.get_customer_reader()?.read()?;
+        #[cfg(feature = "json")]
+        let customer = match customer {
+            Some(customer) => Some(self.with_account(customer)?),
+            None => None,
+        };
+        if let Some(customer) = &customer {
+            self.set_customer(customer.clone())?;
+        }
+        Ok(customer)
+    }
+
+    /// Identify the customer of the sale in progress by the stored account `id`, with its
+    /// name, tier and tax exemption
+    ///
+    /// Customers read by [Terminal::identify_customer] get their stored account as well
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::customer::{Identification, CUSTOMER_STORE};
+    /// use store_terminal::prelude::*;
+    ///
+    /// let database = Database::new();
+    /// let terminal = TerminalBuilder::new().with_database(database.clone()).build().unwrap();
+    /// let account = Customer::new("M-1042", Identification::Phone)
+    ///     .with_name("Jane Doe")
+    ///     .with_tier("gold");
+    /// let customers = database.store::<Customer>(CUSTOMER_STORE);
+    /// customers.upsert("M-1042", &account).unwrap();
+    ///
+    /// assert_eq!(terminal.attach_customer("M-1042").unwrap(), account);
+    /// assert_eq!(terminal.get_customer().unwrap().unwrap().get_tier(), Some("gold"));
+    /// assert!(terminal.attach_customer("M-2048").is_err());
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn attach_customer(&self, id: &str) -> Result<Customer, ErrorVariant> {
+        let customer = self.database.store::<Customer>(CUSTOMER_STORE).fetch(id)?;
+        self.set_customer(customer.clone())?;
+        Ok(customer)
+    }
+
+    /// Customer with its stored account, if it has one
+    #[cfg(feature = "json")]
+    fn with_account(&self, customer: Customer) -> Result<Customer, ErrorVariant> {
+        match self
+            .database
+            .store::<Customer>(CUSTOMER_STORE)
+            .fetch(customer.get_id())
+        {
+            Ok(account) => Ok(customer.with_account(&account)),
+            Err(ErrorVariant::EntityNotFound { .. }) => Ok(customer),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Customer of the sale in progress, if identified
+    pub fn get_customer(&self) -> Result<Option<Customer>, ErrorVariant> {
+        Ok(self.read_cart()?.get_customer().cloned())
+    }
+
+    /// Number of the next sale of the terminal, not below the `next` of its history
+    fn number(&self, next: u64) -> Result<u64, ErrorVariant> {
+        self.sequence.next(self.config.get_terminal_id(), next)
+    }
+
+    /// Queue `sale` for the head office backend, if any, and sync the queue; the sales stay
+    /// queued while the backend can't be reached
+    fn forward(&self, sale: &Sale) -> Result<(), ErrorVariant> {
+        self.queue.push(self.config.get_terminal_id(), sale)?;
+        self.queue.sync().map(|_| ())
+    }
+
+    /// Credit the gift cards and points, forward, take out of the stock and announce the sale
+    /// stored from a cart just reset
+    fn checked_out(&self, sale: Sale) -> Result<Sale, ErrorVariant> {
+        #[cfg(feature = "json")]
+        for load in sale.get_gift_card_loads() {
+            let cards = self.database.store::<GiftCard>(GIFT_CARD_STORE);
+            gift_card::credit(&cards, load.get_number(), load.get_amount())?;
+        }
+        #[cfg(feature = "json")]
+        if let Some(activity) = sale.get_loyalty() {
+            loyalty::credit(
+                &self.loyalty_accounts(),
+                activity.get_customer(),
+                activity.get_earned(),
+            )?;
+        }
+        self.forward(&sale)?;
+        let low = self
+            .database
+            .get_inventory()
+            .sell(sale.get_products(), self.config.has_stock_reservation());
+
+        self.emit(TerminalEvent::CheckedOut {
+            sale: sale.get_id(),
+            total: sale.get_totals().get_total(),
+        })?;
+        for level in low {
+            if let Some(threshold) = level.get_reorder_threshold() {
+                self.emit(TerminalEvent::LowStock {
+                    code: level.get_code().clone(),
+                    stock: level.get_quantity(),
+                    threshold,
+                })?;
+            }
+        }
+        self.cart_reset()?;
+        Ok(sale)
+    }
+
+    /// Add an item of a kind defined outside the crate, such as a fee or a warranty
+    ///
+    /// See [CustomCartItem] for an example
+    pub fn push_item<I: CustomCartItem + 'static>(&self, item: I) -> Result<(), ErrorVariant> {
+        self.write_cart()?.push_custom_item(item);
+        Ok(())
+    }
+
+    /// Remove the custom item `id` from the cart, returning it
+    pub fn remove_item(&self, id: &ItemId) -> Result<Option<Box<dyn CartItem>>, ErrorVariant> {
+        Ok(self.write_cart()?.remove_custom_item(id))
+    }
+
+    /// Void the sale in progress, emptying the cart
+    pub fn reset_cart(&self) -> Result<(), ErrorVariant> {
+        self.flush_scans()?;
+        let mut cart = self.write_cart()?;
+        self.void_cart(&mut cart)?;
+        drop(cart);
+        self.cart_reset()
+    }
+
+    /// Empty the cart, authorizing and auditing the void of its items
+    pub(crate) fn void_cart(&self, cart: &mut Cart) -> Result<(), ErrorVariant> {
+        if cart.get_items().is_empty() {
+            return self.empty_cart(cart);
+        }
+        self.authorize(Permission::Void)?;
+        let units = cart
+            .get_flat_quantities()
+            .iter()
+            .map(|p| *p.get_amount())
+            .sum();
+        let total = cart.get_total_price();
+        self.empty_cart(cart)?;
+        self.audit(AuditAction::CartReset { units, total })
+    }
+
+    /// Log the operator `id` in with its `pin`, replacing the operator logged in
+    ///
+    /// See [TerminalConfig::with_operator_login] for an example
+    #[cfg(feature = "json")]
+    pub fn login(&self, id: &str, pin: &str) -> Result<Operator, ErrorVariant> {
+        let operator = self.authenticate(id, pin)?;
+        *lock::lock(&self.operator) = Some(operator.clone());
+        Ok(operator)
+    }
+
+    /// Stored operator `id`, if `pin` is its PIN
+    #[cfg(feature = "json")]
+    fn authenticate(&self, id: &str, pin: &str) -> Result<Operator, ErrorVariant> {
+        self.database
+            .store::<Operator>(OPERATOR_STORE)
+            .fetch(id)
+            .ok()
+            .filter(|operator| operator.check_pin(pin))
+            .ok_or_else(|| ErrorVariant::InvalidCredentials {
+                operator: id.to_string(),
+            })
+    }
+
+    /// Restricted products of the cart waiting for approval, always empty unless the terminal
+    /// is a kiosk
+    ///
+    /// See [TerminalConfig::with_kiosk_mode] for an example
+    pub fn get_holds(&self) -> Result<Vec<Hold>, ErrorVariant> {
+        if !self.config.has_kiosk_mode() {
+            return Ok(vec![]);
+        }
+        self.flush_scans()?;
+        Ok(self.read_cart()?.get_holds())
+    }
+
+    /// Approve the restricted products held in the cart with the credentials of the operator
+    /// `id`, who must be allowed to [Permission::ApproveHold], returning the approved holds
+    ///
+    /// The operator approves at the kiosk without logging in. The approval is audited and
+    /// emits [TerminalEvent::HoldsApproved]; see [TerminalConfig::with_kiosk_mode] for an
+    /// example
+    #[cfg(feature = "json")]
+    pub fn approve_holds(&self, id: &str, pin: &str) -> Result<Vec<Hold>, ErrorVariant> {
+        let operator = self.authenticate(id, pin)?;
+        let permission = Permission::ApproveHold;
+        if !operator.can(permission) {
+            return Err(ErrorVariant::PermissionDenied {
+                operation: permission.to_string(),
+                role: permission.get_role().to_string(),
+            });
+        }
+
+        self.flush_scans()?;
+        let holds = self.write_cart()?.approve_holds();
+        if holds.is_empty() {
+            return Ok(holds);
+        }
+        let codes: Vec<Code> = holds.iter().map(|h| h.get_code().clone()).collect();
+        self.audit(AuditAction::HoldApproval {
+            approver: operator.get_id().to_string(),
+            codes: codes.iter().map(|c| c.to_string()).collect(),
+        })?;
+        self.emit(TerminalEvent::HoldsApproved {
+            approver: operator.get_id().to_string(),
+            codes,
+        })?;
+        Ok(holds)
+    }
+
+    /// Refuse to close a sale of a kiosk with restricted products waiting for approval
+    fn check_holds(&self, cart: &Cart) -> Result<(), ErrorVariant> {
+        if !self.config.has_kiosk_mode() {
+            return Ok(());
+        }
+        match cart.get_holds() {
+            holds if holds.is_empty() => Ok(()),
+            holds => Err(ErrorVariant::ApprovalRequired {
+                codes: holds.iter().map(|h| h.get_code().to_string()).collect(),
+            }),
+        }
+    }
+
+    /// Log the operator out, returning it
+    pub fn logout(&self) -> Result<Option<Operator>, ErrorVariant> {
+        Ok(lock::lock(&self.operator).take())
+    }
+
+    /// Operator logged in, recorded on the sales and refunds of the terminal
+    pub fn get_operator(&self) -> Result<Option<Operator>, ErrorVariant> {
+        Ok(lock::lock(&self.operator).clone())
+    }
+
+    fn get_operator_id(&self) -> Option<String> {
+        lock::lock(&self.operator)
+            .as_ref()
+            .map(|operator| operator.get_id().to_string())
+    }
+
+    /// Check the operator logged in may perform `permission`, if the terminal requires a login
+    pub(crate) fn authorize(&self, permission: Permission) -> Result<(), ErrorVariant> {
+        if !self.config.has_operator_login() {
+            return Ok(());
+        }
+        match &*lock::lock(&self.operator) {
+            Some(operator) if operator.can(permission) => Ok(()),
+            _ => Err(ErrorVariant::PermissionDenied {
+                operation: permission.to_string(),
+                role: permission.get_role().to_string(),
+            }),
+        }
+    }
+
+    /// Trail of the price overrides, voids, resets and catalog changes of the terminal
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit
+    }
+
+    fn audit(&self, action: AuditAction) -> Result<(), ErrorVariant> {
+        self.audit.append(
+            self.config.get_terminal_id(),
+            self.get_operator_id(),
+            self.clock.now(),
+            action,
+        )
+    }
+
+    /// Drop the undo steps of the scans of a reset cart
+    fn cart_reset(&self) -> Result<(), ErrorVariant> {
+        lock::lock(&self.journal).retain(|entry| !matches!(entry, JournalEntry::Scan(_)));
+        self.emit(TerminalEvent::CartReset)
+    }
+
+    /// Register a listener called with every [TerminalEvent], on the thread that caused it
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    ///
+    /// let events = Arc::new(Mutex::new(vec![]));
+    /// let log = events.clone();
+    /// let id = terminal
+    ///     .subscribe(move |event| log.lock().unwrap().push(event.clone()))
+    ///     .unwrap();
+    ///
+    /// terminal.scan("A X").unwrap();
+    /// terminal.reset_cart().unwrap();
+    /// assert!(terminal.unsubscribe(id).unwrap());
+    /// terminal.scan("A").unwrap();
+    ///
+    /// assert_eq!(
+    ///     *events.lock().unwrap(),
+    ///     vec![
+    ///         TerminalEvent::ScanAccepted { code: Code::from("A"), amount: 1.0 },
+    ///         TerminalEvent::ScanRejected {
+    ///             code: "X".to_string(),
+    ///             error: ErrorVariant::ProductNotFound { code: "X".to_string() },
+    ///         },
+    ///         TerminalEvent::CartReset,
+    ///     ]
+    /// );
+    /// ```
+    pub fn subscribe<F>(&self, listener: F) -> Result<SubscriptionId, ErrorVariant>
+    where
+        F: Fn(&TerminalEvent) + Send + Sync + 'static,
+    {
+        Ok(lock::lock(&self.events).subscribe(Arc::new(listener)))
+    }
+
+    /// Remove a listener, returning `false` if it was not registered
+    pub fn unsubscribe(&self, id: SubscriptionId) -> Result<bool, ErrorVariant> {
+        Ok(lock::lock(&self.events).unsubscribe(id))
+    }
+
+    pub(crate) fn emit(&self, event: TerminalEvent) -> Result<(), ErrorVariant> {
+        let listeners = lock::lock(&self.events).get_listeners();
+        listeners.iter().for_each(|listener| listener(&event));
+        Ok(())
+    }
+
+    /// Read-only view of the database; the catalog is changed with [Terminal::change_catalog]
+    pub fn get_db(&self) -> Result<&DatabaseReader, ErrorVariant> {
+        Ok(&self.reader)
+    }
+
+    pub fn get_config(&self) -> &TerminalConfig {
+        &self.config
+    }
+
+    /// Clock stamping the sales, refunds, shifts and audit entries of the terminal
+    pub fn get_clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+}
+
+/// Lanes shutting down release the stock reserved for their cart
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if let Ok(cart) = self.read_cart() {
+            self.release_stock(&cart.get_flat_quantities());
+        }
+    }
+}
+
+/// Prices of the products and of the promotions of the database, by code
+fn catalog_prices(database: &Database) -> Result<[BTreeMap<String, f64>; 2], ErrorVariant> {
+    let products = database
+        .products()?
+        .iter()
+        .map(|p| (p.get_code().to_string(), *p.get_price()))
+        .collect();
+    let promotions = database
+        .promotions()?
+        .iter()
+        .map(|p| (p.get_code().to_string(), *p.get_price()))
+        .collect();
+    Ok([products, promotions])
+}
+
+/// Prices added, changed or removed between two snapshots of [catalog_prices]
+fn catalog_changes(
+    before: &[BTreeMap<String, f64>; 2],
+    after: &[BTreeMap<String, f64>; 2],
+) -> Vec<AuditAction> {
+    let mut changes = vec![];
+    for (before, after) in before.iter().zip(after) {
+        let codes: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+        for code in codes {
+            let (previous, price) = (before.get(code).copied(), after.get(code).copied());
+            if previous != price {
+                changes.push(AuditAction::CatalogChange {
+                    code: code.clone(),
+                    previous,
+                    price,
+                });
+            }
+        }
+    }
+    changes
+}
+
+fn check_amount(code: &str, amount: f64) -> Result<(), ErrorVariant> {
+    if amount.is_finite() && amount > 0.0 {
+        Ok(())
+    } else {
+        Err(ErrorVariant::InvalidQuantity {
+            code: code.to_string(),
+            quantity: amount,
+        })
+    }
+}
+
+fn undo_scans(cart: &mut Cart, count: usize) -> Result<Undone, ErrorVariant> {
+    let mut undone = vec![];
+    for _ in 0..count {
+        if let Some(p) = cart.undo()? {
+            undone.push(p);
+        }
+    }
+    Ok(Undone::Scan(undone))
+}
+
+/// Keep the optimized copy of the cart, unless the cart changed since the copy was taken
+fn keep_optimized(current: &mut Cart, generation: u64, cart: &Cart) {
+    if current.get_generation() == generation {
+        *current = cart.clone();
+    }
+}
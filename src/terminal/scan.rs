@@ -0,0 +1,349 @@
+use crate::lock;
+use crate::prelude::{Code, ErrorVariant, Product};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+/// Aggregated quantity and regular price of a scanned code
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanLine {
+    code: Code,
+    quantity: f64,
+    total: f64,
+}
+
+impl ScanLine {
+    pub fn get_code(&self) -> &Code {
+        &self.code
+    }
+
+    pub fn get_quantity(&self) -> f64 {
+        self.quantity
+    }
+
+    /// Regular price of the scanned quantity, before promotions
+    pub fn get_total(&self) -> f64 {
+        self.total
+    }
+}
+
+/// Outcome of a [Terminal::scan](crate::prelude::Terminal::scan)
+///
+/// Unknown codes are reported instead of aborting the batch, so the valid codes are kept in
+/// the cart
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let report = terminal.scan(["A", "X", "B", "A"]).unwrap();
+///
+/// assert_eq!(report.get_accepted(), &vec!["A", "B", "A"]);
+/// assert_eq!(report.get_unknown(), &vec!["X"]);
+/// assert_eq!(report.get_lines()[0].get_quantity(), 2.0);
+/// assert_eq!(report.get_lines()[0].get_total(), 4.0);
+/// assert_eq!(report.get_total(), 16.0);
+/// assert!(!report.is_complete());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanReport {
+    accepted: Vec<Code>,
+    unknown: Vec<String>,
+    lines: Vec<ScanLine>,
+}
+
+impl ScanReport {
+    /// Accepted codes, in scan order
+    pub fn get_accepted(&self) -> &Vec<Code> {
+        &self.accepted
+    }
+
+    /// Codes not found in the database, in scan order
+    pub fn get_unknown(&self) -> &Vec<String> {
+        &self.unknown
+    }
+
+    /// Accepted codes aggregated per code, in order of first scan
+    pub fn get_lines(&self) -> &Vec<ScanLine> {
+        &self.lines
+    }
+
+    /// Regular price of all accepted codes, before promotions
+    pub fn get_total(&self) -> f64 {
+        self.lines
+            .iter()
+            .fold(0.0, |total, line| total + line.total)
+    }
+
+    /// Accepted and unknown codes, and the total of the accepted ones
+    #[cfg(any(feature = "ffi", feature = "server"))]
+    pub(crate) fn summary_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "accepted": self.accepted,
+            "unknown": self.unknown,
+            "total": self.get_total(),
+        })
+    }
+
+    /// Check if every code of the batch was accepted
+    pub fn is_complete(&self) -> bool {
+        self.unknown.is_empty()
+    }
+
+    pub(crate) fn push_accepted(&mut self, product: &Product, amount: f64) {
+        let code = product.get_code();
+        let total = product.get_price() * amount;
+        match self.lines.iter_mut().find(|line| &line.code == code) {
+            Some(line) => {
+                line.quantity += amount;
+                line.total += total;
+            }
+            None => self.lines.push(ScanLine {
+                code: code.clone(),
+                quantity: amount,
+                total,
+            }),
+        }
+        self.accepted.push(code.clone());
+    }
+
+    pub(crate) fn push_unknown(&mut self, code: &str) {
+        self.unknown.push(code.to_string());
+    }
+
+    /// Add the codes and lines of the batch scanned after this one
+    pub(crate) fn append(&mut self, report: ScanReport) {
+        for line in report.lines {
+            match self.lines.iter_mut().find(|l| l.code == line.code) {
+                Some(l) => {
+                    l.quantity += line.quantity;
+                    l.total += line.total;
+                }
+                None => self.lines.push(line),
+            }
+        }
+        self.accepted.extend(report.accepted);
+        self.unknown.extend(report.unknown);
+    }
+}
+
+/// Codes handed over by [Terminal::queue_scan](crate::prelude::Terminal::queue_scan), waiting
+/// to be scanned into the cart
+///
+/// Pushing only sends over a channel, so scanners never wait for the cart nor for each other
+pub(crate) struct ScanQueue {
+    sender: Sender<String>,
+    receiver: Mutex<Receiver<String>>,
+    queued: AtomicUsize,
+}
+
+impl ScanQueue {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        ScanQueue {
+            sender,
+            receiver: Mutex::new(receiver),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn push(&self, code: &str) {
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        // The receiver lives as long as the sender, so the code is never refused
+        let _ = self.sender.send(code.to_string());
+    }
+
+    /// Number of codes pushed and not yet taken
+    pub(crate) fn count(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// Codes pushed so far, in order, without locking the receiver if there are none
+    pub(crate) fn take(&self) -> Vec<String> {
+        if self.count() == 0 {
+            return vec![];
+        }
+        let codes: Vec<String> = lock::lock(&self.receiver).try_iter().collect();
+        self.queued.fetch_sub(codes.len(), Ordering::SeqCst);
+        codes
+    }
+}
+
+/// Input accepted by [Terminal::scan](crate::prelude::Terminal::scan)
+///
+/// A string is split into tokens by whitespaces and commas, while collections and [Codes] yield
+/// one code per element. No intermediate buffer is allocated
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let catalog = ["7891234567895", "SKU-10", "A", "B"];
+/// let mut codes = vec![];
+/// "7891234567895,SKU-10  AB\tA, , X 7890000000000 AX"
+///     .try_for_each_code(
+///         |code| catalog.contains(&code),
+///         |code| {
+///             codes.push(code.to_string());
+///             Ok(())
+///         },
+///     )
+///     .unwrap();
+///
+/// assert_eq!(
+///     codes,
+///     ["7891234567895", "SKU-10", "A", "B", "A", "X", "7890000000000", "AX"]
+/// );
+///
+/// let mut codes = vec![];
+/// let result = "A SKU-99 B".try_for_each_code(
+///     |code| catalog.contains(&code),
+///     |code| {
+///         codes.push(code.to_string());
+///         if catalog.contains(&code) {
+///             Ok(())
+///         } else {
+///             Err(ErrorVariant::ProductNotFound {
+///                 code: code.to_string(),
+///             })
+///         }
+///     },
+/// );
+///
+/// assert!(result.is_err());
+/// assert_eq!(codes, ["A", "SKU-99"]);
+/// ```
+pub trait ScanCodes {
+    /// Call `scan` for every code of the input, stopping at the first error
+    ///
+    /// `is_code` checks if a code is registered, for inputs that need to disambiguate tokens
+    fn try_for_each_code<P, F>(self, is_code: P, scan: F) -> Result<(), ErrorVariant>
+    where
+        P: Fn(&str) -> bool,
+        F: FnMut(&str) -> Result<(), ErrorVariant>;
+}
+
+/// Wrapper to scan any iterator of codes
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let driver_output = "A;B;C";
+/// terminal.scan(Codes(driver_output.split(';'))).unwrap();
+///
+/// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 15.25);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Codes<I>(pub I);
+
+impl<I> ScanCodes for Codes<I>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    fn try_for_each_code<P, F>(self, _is_code: P, mut scan: F) -> Result<(), ErrorVariant>
+    where
+        P: Fn(&str) -> bool,
+        F: FnMut(&str) -> Result<(), ErrorVariant>,
+    {
+        self.0.into_iter().try_for_each(|c| scan(c.as_ref()))
+    }
+}
+
+impl ScanCodes for &str {
+    /// Every token is first scanned as an exact product code, so barcodes and SKUs work as
+    /// they are. A token that is not a known code is scanned one character per code only if
+    /// every character is a known code, keeping the `ABCD` shorthand of single character
+    /// catalogs; otherwise it is scanned as a whole, and reported unknown
+    fn try_for_each_code<P, F>(self, is_code: P, mut scan: F) -> Result<(), ErrorVariant>
+    where
+        P: Fn(&str) -> bool,
+        F: FnMut(&str) -> Result<(), ErrorVariant>,
+    {
+        let mut buffer = [0; 4];
+        self.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+            .try_for_each(|token| {
+                let shorthand = !is_code(token)
+                    && token.chars().nth(1).is_some()
+                    && token.chars().all(|c| is_code(c.encode_utf8(&mut buffer)));
+                if shorthand {
+                    token
+                        .chars()
+                        .try_for_each(|c| scan(c.encode_utf8(&mut buffer)))
+                } else {
+                    scan(token)
+                }
+            })
+    }
+}
+
+impl ScanCodes for &String {
+    fn try_for_each_code<P, F>(self, is_code: P, scan: F) -> Result<(), ErrorVariant>
+    where
+        P: Fn(&str) -> bool,
+        F: FnMut(&str) -> Result<(), ErrorVariant>,
+    {
+        self.as_str().try_for_each_code(is_code, scan)
+    }
+}
+
+impl ScanCodes for String {
+    fn try_for_each_code<P, F>(self, is_code: P, scan: F) -> Result<(), ErrorVariant>
+    where
+        P: Fn(&str) -> bool,
+        F: FnMut(&str) -> Result<(), ErrorVariant>,
+    {
+        self.as_str().try_for_each_code(is_code, scan)
+    }
+}
+
+impl<T: AsRef<str>> ScanCodes for &[T] {
+    fn try_for_each_code<P, F>(self, is_code: P, scan: F) -> Result<(), ErrorVariant>
+    where
+        P: Fn(&str) -> bool,
+        F: FnMut(&str) -> Result<(), ErrorVariant>,
+    {
+        Codes(self).try_for_each_code(is_code, scan)
+    }
+}
+
+impl<T: AsRef<str>, const N: usize> ScanCodes for [T; N] {
+    fn try_for_each_code<P, F>(self, is_code: P, scan: F) -> Result<(), ErrorVariant>
+    where
+        P: Fn(&str) -> bool,
+        F: FnMut(&str) -> Result<(), ErrorVariant>,
+    {
+        Codes(self).try_for_each_code(is_code, scan)
+    }
+}
+
+impl<T: AsRef<str>> ScanCodes for &Vec<T> {
+    fn try_for_each_code<P, F>(self, is_code: P, scan: F) -> Result<(), ErrorVariant>
+    where
+        P: Fn(&str) -> bool,
+        F: FnMut(&str) -> Result<(), ErrorVariant>,
+    {
+        Codes(self).try_for_each_code(is_code, scan)
+    }
+}
+
+impl<T: AsRef<str>> ScanCodes for Vec<T> {
+    fn try_for_each_code<P, F>(self, is_code: P, scan: F) -> Result<(), ErrorVariant>
+    where
+        P: Fn(&str) -> bool,
+        F: FnMut(&str) -> Result<(), ErrorVariant>,
+    {
+        Codes(self).try_for_each_code(is_code, scan)
+    }
+}
@@ -0,0 +1,203 @@
+use crate::lock;
+use crate::prelude::{
+    Database, ErrorVariant, SaleSequence, SalesHistory, Terminal, TerminalBuilder, TerminalConfig,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Name of the sessions in [ErrorVariant::EntityNotFound]
+const SESSION_STORE: &str = "sessions";
+
+/// Independent cart sessions over one shared [Database], so a single process serves every lane
+/// and self-checkout kiosk of a store
+///
+/// Every session is a [Terminal] of its own, identified by its session id, so scanning and
+/// checking out in a session only takes the locks of its cart. The table of sessions is locked
+/// just long enough to create, look up or close one. Catalog and stock changes are seen by
+/// every session at once
+///
+/// Sessions share the [SalesHistory] and the [SaleSequence] of the server, so a lane closed
+/// and opened again numbers its sales on from where it stopped. Sales record the session id of
+/// their lane, and every lane opens and closes its own shift, reconciling the cash of its own
+/// drawer
+///
+/// ```
+/// use store_terminal::prelude::*;
+///
+/// let server = TerminalServer::new(Database::new());
+/// server.get_database().append(Product::new("A", 2.0)).unwrap();
+/// let lane_1 = server.create_session("lane-1").unwrap();
+/// let lane_2 = server.create_session("lane-2").unwrap();
+///
+/// lane_1.open_shift(50.0).unwrap();
+/// lane_2.open_shift(100.0).unwrap();
+/// assert!(lane_2.open_shift(100.0).is_err());
+///
+/// lane_1.scan("A").unwrap();
+/// let sale = lane_1.checkout_with_tender(Tender::Cash).unwrap();
+/// assert_eq!(sale.get_terminal(), Some("lane-1"));
+/// lane_2.scan("A A").unwrap();
+/// lane_2.checkout_with_tender(Tender::Cash).unwrap();
+/// lane_2.refund(sale.get_id(), &[("A", 1.0)]).unwrap();
+///
+/// let report = lane_1.close_shift(52.0).unwrap();
+/// assert_eq!((report.get_sales(), report.get_refunds()), (1, 0));
+/// assert!(report.is_balanced());
+/// assert!(lane_1.get_shift().is_none());
+///
+/// let report = lane_2.close_shift(102.0).unwrap();
+/// assert_eq!((report.get_sales(), report.get_refunds()), (1, 1));
+/// assert_eq!(report.get_shift().get_terminal(), Some("lane-2"));
+/// assert!(report.is_balanced());
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use std::thread;
+/// use store_terminal::prelude::*;
+///
+/// let server = TerminalServer::new(Database::new())
+///     .with_config(TerminalConfig::default().with_kiosk_mode(true));
+/// server.get_database().append(Product::new("A".to_string(), 2.0)).unwrap();
+///
+/// let lanes: Vec<_> = (1..=3)
+///     .map(|lane| server.create_session(&format!("lane-{}", lane)).unwrap())
+///     .collect();
+/// let scanners: Vec<_> = lanes
+///     .iter()
+///     .enumerate()
+///     .map(|(i, lane)| {
+///         let lane = lane.clone();
+///         thread::spawn(move || lane.scan(vec!["A"; i + 1]).unwrap())
+///     })
+///     .collect();
+/// for scanner in scanners {
+///     scanner.join().unwrap();
+/// }
+///
+/// let kiosk = server.get_session("lane-3").unwrap();
+/// assert_eq!(kiosk.get_config().get_terminal_id(), "lane-3");
+/// assert!(kiosk.get_config().has_kiosk_mode());
+/// assert_eq!(kiosk.get_cart().unwrap().get_total_price(), 6.0);
+/// assert_eq!(lanes[0].get_cart().unwrap().get_total_price(), 2.0);
+///
+/// assert_eq!(
+///     server.create_session("lane-1").err(),
+///     Some(ErrorVariant::SessionAlreadyExists { id: "lane-1".to_string() })
+/// );
+///
+/// let closed = server.close_session("lane-2").unwrap();
+/// let sale = closed.checkout().unwrap();
+/// assert_eq!(sale.get_totals().get_total(), 4.0);
+/// assert_eq!(server.get_session_ids(), vec!["lane-1", "lane-3"]);
+/// assert!(server.get_session("lane-2").is_err());
+///
+/// let reopened = server.create_session("lane-2").unwrap();
+/// reopened.scan("A").unwrap();
+/// assert!(reopened.checkout().unwrap().get_id() > sale.get_id());
+/// assert_eq!(server.get_sales_history().get_sales().len(), 2);
+/// ```
+pub struct TerminalServer {
+    database: Database,
+    config: TerminalConfig,
+    sales: SalesHistory,
+    sequence: SaleSequence,
+    sessions: Mutex<HashMap<String, Arc<Terminal>>>,
+}
+
+impl TerminalServer {
+    /// Server without sessions over `database`
+    pub fn new(database: Database) -> Self {
+        TerminalServer {
+            database,
+            config: TerminalConfig::default(),
+            sales: SalesHistory::new(),
+            sequence: SaleSequence::new(),
+            sessions: Mutex::default(),
+        }
+    }
+
+    /// Settings of the sessions created from now on, each identified by its session id
+    pub fn with_config(mut self, config: TerminalConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// History storing the sales of every session, such as one opened on a file
+    pub fn with_sales_history(mut self, sales: SalesHistory) -> Self {
+        self.sales = sales;
+        self
+    }
+
+    /// Numbering of the sales of every session, such as one stored in a file so it carries on
+    /// after a restart
+    pub fn with_sale_sequence(mut self, sequence: SaleSequence) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    pub fn get_database(&self) -> &Database {
+        &self.database
+    }
+
+    pub fn get_config(&self) -> &TerminalConfig {
+        &self.config
+    }
+
+    pub fn get_sales_history(&self) -> &SalesHistory {
+        &self.sales
+    }
+
+    pub fn get_sale_sequence(&self) -> &SaleSequence {
+        &self.sequence
+    }
+
+    /// Open a session with an empty cart, such as for a lane starting its day
+    pub fn create_session(&self, id: &str) -> Result<Arc<Terminal>, ErrorVariant> {
+        let mut sessions = lock::lock(&self.sessions);
+        if sessions.contains_key(id) {
+            return Err(ErrorVariant::SessionAlreadyExists { id: id.to_string() });
+        }
+
+        let terminal = TerminalBuilder::new()
+            .with_database(self.database.clone())
+            .with_config(self.config.clone().with_terminal_id(id.to_string()))
+            .with_sales_history(self.sales.clone())
+            .with_sale_sequence(self.sequence.clone())
+            .build()?;
+        let terminal = Arc::new(terminal);
+        sessions.insert(id.to_string(), Arc::clone(&terminal));
+        Ok(terminal)
+    }
+
+    pub fn get_session(&self, id: &str) -> Result<Arc<Terminal>, ErrorVariant> {
+        lock::lock(&self.sessions)
+            .get(id)
+            .cloned()
+            .ok_or_else(|| not_found(id))
+    }
+
+    /// Remove a session, returning its terminal so a cart left behind can still be checked out
+    ///
+    /// Requests holding the terminal of the session complete as usual
+    pub fn close_session(&self, id: &str) -> Result<Arc<Terminal>, ErrorVariant> {
+        lock::lock(&self.sessions)
+            .remove(id)
+            .ok_or_else(|| not_found(id))
+    }
+
+    /// Identifiers of the open sessions, sorted
+    pub fn get_session_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = lock::lock(&self.sessions).keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+}
+
+fn not_found(id: &str) -> ErrorVariant {
+    ErrorVariant::EntityNotFound {
+        store: SESSION_STORE.to_string(),
+        code: id.to_string(),
+    }
+}
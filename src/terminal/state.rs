@@ -0,0 +1,115 @@
+use crate::cart::CartState;
+use crate::lock;
+use crate::prelude::{Catalog, ErrorVariant, Terminal};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+/// What a terminal loses to a crash: its catalog, the scans of its cart and the number of its
+/// last sale
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TerminalState {
+    catalog: Catalog,
+    cart: CartState,
+    last_sale: u64,
+}
+
+impl Terminal {
+    /// Write the catalog, the cart and the sale counter of the terminal to the file at `path`,
+    /// for [Terminal::restore_state] after a crash or a power loss
+    ///
+    /// The state is written to a temporary file renamed over the previous one, so a crash
+    /// while saving leaves either the previous state or the new one. Saving after every scan
+    /// keeps the basket being served
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    ///
+    /// let path = std::env::temp_dir().join(format!("state-{}.json", std::process::id()));
+    /// let lane = || {
+    ///     TerminalBuilder::new()
+    ///         .with_config(TerminalConfig::new("lane-4".to_string()))
+    ///         .build()
+    ///         .unwrap()
+    /// };
+    ///
+    /// let terminal = lane();
+    /// terminal.load_demo_catalog().unwrap();
+    /// terminal.scan("A").unwrap();
+    /// terminal.checkout().unwrap();
+    /// terminal.scan("AAAAB").unwrap();
+    /// terminal.save_state(&path).unwrap();
+    /// drop(terminal);
+    ///
+    /// let terminal = lane();
+    /// assert!(terminal.restore_state(&path).unwrap());
+    /// assert_eq!(terminal.get_cart().unwrap().get_total_price(), 19.0);
+    /// assert_eq!(terminal.checkout().unwrap().get_id(), 2);
+    ///
+    /// std::fs::remove_file(&path).unwrap();
+    /// assert!(!lane().restore_state(&path).unwrap());
+    /// ```
+    pub fn save_state(&self, path: &Path) -> Result<(), ErrorVariant> {
+        self.flush_scans()?;
+        let state = {
+            let cart = self.read_cart()?;
+            TerminalState {
+                catalog: self.database.get_catalog()?,
+                cart: cart.get_state()?,
+                last_sale: self.sequence.get_last(self.config.get_terminal_id()),
+            }
+        };
+        let content = serde_json::to_string(&state)
+            .map_err(|e| ErrorVariant::from_json_error(e, "terminal state"))?;
+
+        let io_error = |e: std::io::Error| ErrorVariant::IoError {
+            path: path.display().to_string(),
+            source: e.to_string(),
+        };
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        File::create(&temp)
+            .and_then(|mut file| {
+                file.write_all(content.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|()| fs::rename(&temp, path))
+            .map_err(io_error)
+    }
+
+    /// Replace the catalog and the cart of the terminal with the ones saved by
+    /// [Terminal::save_state] at `path`, numbering the next sales after the saved ones
+    ///
+    /// Returns whether there was a state to restore. The scans are restored at the prices
+    /// they were scanned at, and the catalog changes are audited as any other. Custom items are
+    /// read back by the [CartItemKinds](crate::prelude::CartItemKinds) of the terminal
+    pub fn restore_state(&self, path: &Path) -> Result<bool, ErrorVariant> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => {
+                return Err(ErrorVariant::IoError {
+                    path: path.display().to_string(),
+                    source: e.to_string(),
+                })
+            }
+        };
+        let TerminalState {
+            catalog,
+            cart,
+            last_sale,
+        } = crate::from_json_str(&content, "terminal state")?;
+
+        // Custom items of unknown kinds fail before anything is restored
+        self.write_cart()?.set_state(cart)?;
+        self.audit_catalog(|database| database.replace(catalog))?;
+        self.sequence
+            .restore(self.config.get_terminal_id(), last_sale)?;
+        lock::lock(&self.journal).clear();
+        Ok(true)
+    }
+}
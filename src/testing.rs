@@ -0,0 +1,232 @@
+//! Random products, promotions, catalogs and carts for property tests, with the `testing`
+//! feature
+//!
+//! Generators draw from any [Rng]; seeding a [StdRng] with [SeedableRng::seed_from_u64] makes
+//! a failing case reproducible. Amounts are whole units, so [brute_force_price] can price every
+//! combination of promotions of the generated carts
+//!
+//! # Example
+//!
+//! ```
+//! use store_terminal::prelude::*;
+//! use store_terminal::testing::{brute_force_price, CartGenerator, CatalogGenerator};
+//! use store_terminal::testing::{SeedableRng, StdRng};
+//!
+//! for seed in 0..50 {
+//!     let mut rng = StdRng::seed_from_u64(seed);
+//!     let catalog = CatalogGenerator::new().generate(&mut rng);
+//!     let database = Database::new();
+//!     catalog.clone().append_into(&database).unwrap();
+//!
+//!     let mut cart = CartGenerator::new().generate(&mut rng, &database).unwrap();
+//!     let products = cart.get_flat_quantities();
+//!     let regular: f64 = products.iter().map(|p| p.get_total_price()).sum();
+//!     let optimal = brute_force_price(&products, catalog.get_promotions());
+//!
+//!     let total = cart.optimize_promotions().unwrap().get_total_price();
+//!     assert!(total >= optimal - 0.005, "seed {}: {} below {}", seed, total, optimal);
+//!     assert!(total <= regular + 0.005, "seed {}: {} above {}", seed, total, regular);
+//! }
+//! ```
+
+use crate::prelude::{Cart, Catalog, Database, ErrorVariant, Product, ProductAmount, Promotion};
+use crate::receipt::round_cents;
+pub use rand::rngs::StdRng;
+pub use rand::{Rng, SeedableRng};
+
+/// Value drawn at random, for property tests
+pub trait Arbitrary: Sized {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self;
+}
+
+/// Product coded `P0` to `P99`, priced from 0.01 to 50.00
+impl Arbitrary for Product {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let code = format!("P{}", rng.gen_range(0, 100));
+        Product::new(code, price(rng, 5000))
+    }
+}
+
+/// Arbitrary product, in 1 to 5 units
+impl Arbitrary for ProductAmount {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let amount = f64::from(rng.gen_range(1, 6));
+        ProductAmount::new(Product::arbitrary(rng), amount)
+    }
+}
+
+/// Promotion coded `X0` to `X99` of 1 to 3 arbitrary product amounts, priced below them
+impl Arbitrary for Promotion {
+    fn arbitrary<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let products = (0..rng.gen_range(1, 4))
+            .map(|_| ProductAmount::arbitrary(rng))
+            .collect();
+        let code = format!("X{}", rng.gen_range(0, 100));
+        promotion(rng, code, products)
+    }
+}
+
+/// Builder of random catalogs whose promotions are made of their products
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogGenerator {
+    products: usize,
+    promotions: usize,
+    max_amount: u32,
+}
+
+impl CatalogGenerator {
+    /// Generator of 6 products and 4 promotions of up to 3 units of a product
+    pub fn new() -> Self {
+        CatalogGenerator {
+            products: 6,
+            promotions: 4,
+            max_amount: 3,
+        }
+    }
+
+    /// Products coded `P0` to `P<products - 1>`, at least 1
+    pub fn with_products(mut self, products: usize) -> Self {
+        self.products = products.max(1);
+        self
+    }
+
+    /// Promotions coded `X0` to `X<promotions - 1>`
+    pub fn with_promotions(mut self, promotions: usize) -> Self {
+        self.promotions = promotions;
+        self
+    }
+
+    /// Largest amount of a product in a promotion, at least 1
+    pub fn with_max_amount(mut self, max_amount: u32) -> Self {
+        self.max_amount = max_amount.max(1);
+        self
+    }
+
+    pub fn generate<R: Rng + ?Sized>(&self, rng: &mut R) -> Catalog {
+        let products: Vec<Product> = (0..self.products)
+            .map(|i| Product::new(format!("P{}", i), price(rng, 2000)))
+            .collect();
+
+        let promotions = (0..self.promotions)
+            .map(|i| {
+                let lines = rng.gen_range(1, products.len().min(3) + 1);
+                let amounts = (0..lines)
+                    .map(|_| {
+                        let product = &products[rng.gen_range(0, products.len())];
+                        let amount = f64::from(rng.gen_range(1, self.max_amount + 1));
+                        product.generate_amount(amount)
+                    })
+                    .collect();
+                promotion(rng, format!("X{}", i), amounts)
+            })
+            .collect();
+
+        Catalog::new(products, promotions)
+    }
+}
+
+impl Default for CatalogGenerator {
+    fn default() -> Self {
+        CatalogGenerator::new()
+    }
+}
+
+/// Builder of random carts of the products of a database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartGenerator {
+    max_lines: usize,
+    max_amount: u32,
+}
+
+impl CartGenerator {
+    /// Generator of up to 8 scans of up to 4 units
+    pub fn new() -> Self {
+        CartGenerator {
+            max_lines: 8,
+            max_amount: 4,
+        }
+    }
+
+    /// Largest number of scans, at least 1
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines.max(1);
+        self
+    }
+
+    /// Largest amount of a scan, at least 1
+    pub fn with_max_amount(mut self, max_amount: u32) -> Self {
+        self.max_amount = max_amount.max(1);
+        self
+    }
+
+    /// Cart of 1 to the maximum scans of random products of `database`, not optimized
+    pub fn generate<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        database: &Database,
+    ) -> Result<Cart, ErrorVariant> {
+        let codes: Vec<String> = database
+            .get_catalog()?
+            .get_products()
+            .iter()
+            .map(|p| p.get_code().to_string())
+            .collect();
+        let mut cart = Cart::new(database.clone());
+        if codes.is_empty() {
+            return Ok(cart);
+        }
+
+        for _ in 0..rng.gen_range(1, self.max_lines + 1) {
+            let code = &codes[rng.gen_range(0, codes.len())];
+            let amount = f64::from(rng.gen_range(1, self.max_amount + 1));
+            cart.push_product(code, amount)?;
+        }
+        Ok(cart)
+    }
+}
+
+impl Default for CartGenerator {
+    fn default() -> Self {
+        CartGenerator::new()
+    }
+}
+
+/// Lowest price of `products` over every combination of `promotions`, each applied any number
+/// of times, for checking the optimizer on small carts
+///
+/// The search is exponential in the number of promotions and of their applications
+pub fn brute_force_price(products: &[ProductAmount], promotions: &[Promotion]) -> f64 {
+    round_cents(lowest_price(products.to_vec(), promotions))
+}
+
+fn lowest_price(products: Vec<ProductAmount>, promotions: &[Promotion]) -> f64 {
+    let (promotion, rest) = match promotions.split_first() {
+        Some(split) => split,
+        None => return products.iter().map(|p| p.get_total_price()).sum(),
+    };
+
+    let without = lowest_price(products.clone(), rest);
+    if !promotion.is_contained_by(&products.iter().collect()) {
+        return without;
+    }
+    match promotion.consume_items(products) {
+        Ok(remaining) => without.min(promotion.get_price() + lowest_price(remaining, promotions)),
+        Err(_) => without,
+    }
+}
+
+/// Price from 0.01 to `max_cents` hundredths
+fn price<R: Rng + ?Sized>(rng: &mut R, max_cents: u32) -> f64 {
+    f64::from(rng.gen_range(1, max_cents + 1)) / 100.0
+}
+
+/// Promotion of `products` at 50% to 99% of their regular price
+fn promotion<R: Rng + ?Sized>(
+    rng: &mut R,
+    code: String,
+    products: Vec<ProductAmount>,
+) -> Promotion {
+    let regular: f64 = products.iter().map(|p| p.get_total_price()).sum();
+    let price = round_cents(regular * f64::from(rng.gen_range(50, 100)) / 100.0);
+    Promotion::grouped(code.into(), products, price)
+}
@@ -0,0 +1,391 @@
+//! Notification of checkouts to external systems, such as loyalty programs and ERPs
+//!
+//! Every sale closed by a terminal the [Webhooks] are attached to is posted as a JSON document
+//! to the configured URLs:
+//!
+//! ```json
+//! {"terminal": "terminal-1", "id": 1, "timestamp": 1760000000,
+//!  "items": [{"code", "description", "amount", "total"}],
+//!  "tenders": [], "total": 14.0, "change": 0.0}
+//! ```
+//!
+//! When a webhook has a secret, the request carries the header
+//! `X-Store-Terminal-Signature: sha256=<hex>`, the HMAC-SHA256 of the body keyed by the secret,
+//! so the receiver can check the sale comes from the store. Only `http://` URLs are supported;
+//! receivers outside the store network are reached through a TLS terminating proxy
+
+use crate::digest;
+use crate::lock;
+use crate::prelude::{ErrorVariant, Sale, SubscriptionId, Terminal, TerminalEvent};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Header carrying the signature of the body
+pub const SIGNATURE_HEADER: &str = "X-Store-Terminal-Signature";
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Receiver of the sales, at an `http://host[:port]/path` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Webhook {
+    url: String,
+    host: String,
+    port: u16,
+    path: String,
+    secret: Option<String>,
+}
+
+impl Webhook {
+    /// Receiver at `url`, rejecting anything but `http://` URLs with
+    /// [ErrorVariant::InvalidUrl], `https://` included
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use store_terminal::prelude::*;
+    /// use store_terminal::webhook::Webhook;
+    ///
+    /// assert!(Webhook::new("http://erp.local:8080/sales").is_ok());
+    /// assert_eq!(
+    ///     Webhook::new("https://erp.example.com/sales").unwrap_err(),
+    ///     ErrorVariant::InvalidUrl { url: "https://erp.example.com/sales".to_string() }
+    /// );
+    /// ```
+    pub fn new(url: &str) -> Result<Self, ErrorVariant> {
+        let invalid = || ErrorVariant::InvalidUrl {
+            url: url.to_string(),
+        };
+
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Webhook {
+            url: url.to_string(),
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            secret: None,
+        })
+    }
+
+    /// Sign the posted sales with `secret`
+    pub fn with_secret(mut self, secret: &str) -> Self {
+        self.secret = Some(secret.to_string());
+        self
+    }
+
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    /// Post `body` once, succeeding on a 2xx response
+    pub fn post(&self, body: &str) -> Result<(), ErrorVariant> {
+        let io_error = |e: std::io::Error| ErrorVariant::IoError {
+            path: self.url.clone(),
+            source: e.to_string(),
+        };
+
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).map_err(io_error)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(io_error)?;
+        stream.set_write_timeout(Some(TIMEOUT)).map_err(io_error)?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n",
+            self.path,
+            self.host,
+            body.len()
+        );
+        if let Some(secret) = &self.secret {
+            request.push_str(&format!(
+                "{}: {}\r\n",
+                SIGNATURE_HEADER,
+                signature(secret, body.as_bytes())
+            ));
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+        stream.write_all(request.as_bytes()).map_err(io_error)?;
+
+        let mut status_line = String::new();
+        BufReader::new(stream)
+            .read_line(&mut status_line)
+            .map_err(io_error)?;
+        match status_line.split_whitespace().nth(1) {
+            Some(status) if status.starts_with('2') && status.len() == 3 => Ok(()),
+            status => Err(ErrorVariant::IoError {
+                path: self.url.clone(),
+                source: format!("unexpected response status {}", status.unwrap_or("none")),
+            }),
+        }
+    }
+}
+
+/// Delivery of a sale to a webhook that failed every retry
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedDelivery {
+    webhook: Webhook,
+    sale: Value,
+    error: ErrorVariant,
+}
+
+impl FailedDelivery {
+    pub fn get_webhook(&self) -> &Webhook {
+        &self.webhook
+    }
+
+    /// Document of the sale, to post again with [Webhook::post]
+    pub fn get_sale(&self) -> &Value {
+        &self.sale
+    }
+
+    /// Error of the last attempt
+    pub fn get_error(&self) -> &ErrorVariant {
+        &self.error
+    }
+}
+
+/// Webhooks notified of every checkout, with retries of failed deliveries
+///
+/// Clones share the deliveries that failed every retry, kept until
+/// [Webhooks::take_failed_deliveries]
+///
+/// # Example
+///
+/// ```
+/// use std::io::{BufRead, BufReader, Read, Write};
+/// use std::net::TcpListener;
+/// use std::thread;
+/// use store_terminal::prelude::*;
+/// use store_terminal::webhook::{self, Webhook, Webhooks};
+///
+/// // Receiver failing the first delivery, then returning the signature and body of the retry
+/// let receiver = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let url = format!("http://{}/sales", receiver.local_addr().unwrap());
+/// let received = thread::spawn(move || {
+///     let (mut client, _) = receiver.accept().unwrap();
+///     client.write_all(b"HTTP/1.1 503 Service Unavailable\r\n\r\n").unwrap();
+///
+///     let (client, _) = receiver.accept().unwrap();
+///     let mut reader = BufReader::new(client);
+///     let (mut signature, mut len) = (String::new(), 0);
+///     reader.read_line(&mut String::new()).unwrap();
+///     loop {
+///         let mut line = String::new();
+///         reader.read_line(&mut line).unwrap();
+///         match line.trim_end().split_once(": ") {
+///             Some(("X-Store-Terminal-Signature", value)) => signature = value.to_string(),
+///             Some(("Content-Length", value)) => len = value.parse().unwrap(),
+///             None => break,
+///             _ => (),
+///         }
+///     }
+///     let mut body = vec![0; len];
+///     reader.read_exact(&mut body).unwrap();
+///     reader.get_mut().write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+///     (signature, body)
+/// });
+///
+/// let terminal = Terminal::new().unwrap();
+/// terminal.load_demo_catalog().unwrap();
+///
+/// let webhooks = Webhooks::new()
+///     .with_webhook(Webhook::new(&url).unwrap().with_secret("s3cret"))
+///     .with_retries(2);
+/// webhooks.attach(&terminal).unwrap();
+/// terminal.scan("AB").unwrap();
+/// terminal.checkout_with_tender(Tender::Card).unwrap();
+///
+/// let (signature, body) = received.join().unwrap();
+/// assert_eq!(signature, webhook::signature("s3cret", &body));
+/// let sale: serde_json::Value = serde_json::from_slice(&body).unwrap();
+/// assert_eq!(sale["terminal"], "terminal-1");
+/// assert_eq!(sale["id"], 1);
+/// assert_eq!(sale["total"], 14.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Webhooks {
+    webhooks: Vec<Webhook>,
+    retries: u32,
+    backoff: Duration,
+    failed: Arc<Mutex<Vec<FailedDelivery>>>,
+}
+
+impl Webhooks {
+    pub fn new() -> Self {
+        Webhooks {
+            webhooks: vec![],
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            failed: Arc::default(),
+        }
+    }
+
+    pub fn with_webhook(mut self, webhook: Webhook) -> Self {
+        self.webhooks.push(webhook);
+        self
+    }
+
+    /// Deliveries attempted again after a failure, 3 by default
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Wait before the first retry, doubled for every following one
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn get_webhooks(&self) -> &Vec<Webhook> {
+        &self.webhooks
+    }
+
+    /// Post the sales closed by `terminal` in the background, until the returned subscription
+    /// is removed
+    ///
+    /// Every checkout of the terminal is posted, whichever method closed it. The lane never
+    /// waits for the receivers; deliveries that fail every retry are kept for
+    /// [Webhooks::take_failed_deliveries]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::net::TcpListener;
+    /// use std::time::{Duration, Instant};
+    /// use store_terminal::prelude::*;
+    /// use store_terminal::webhook::{Webhook, Webhooks};
+    ///
+    /// // Nobody accepts the connections of a listener that is dropped
+    /// let url = format!("http://{}/sales", TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap());
+    ///
+    /// let terminal = Terminal::new().unwrap();
+    /// terminal.load_demo_catalog().unwrap();
+    /// let webhooks = Webhooks::new()
+    ///     .with_webhook(Webhook::new(&url).unwrap())
+    ///     .with_retries(1)
+    ///     .with_backoff(Duration::from_millis(10));
+    /// webhooks.attach(&terminal).unwrap();
+    ///
+    /// terminal.scan("A").unwrap();
+    /// terminal.checkout_with_tenders(&[TenderAmount::new(Tender::Cash, 5.0)]).unwrap();
+    ///
+    /// let started = Instant::now();
+    /// let failed = loop {
+    ///     let failed = webhooks.take_failed_deliveries();
+    ///     if !failed.is_empty() || started.elapsed() > Duration::from_secs(10) {
+    ///         break failed;
+    ///     }
+    ///     std::thread::sleep(Duration::from_millis(10));
+    /// };
+    /// assert_eq!(failed.len(), 1);
+    /// assert_eq!(failed[0].get_webhook().get_url(), url);
+    /// assert_eq!(failed[0].get_sale()["total"], 2.0);
+    /// assert!(webhooks.take_failed_deliveries().is_empty());
+    /// ```
+    pub fn attach(&self, terminal: &Terminal) -> Result<SubscriptionId, ErrorVariant> {
+        let webhooks = self.clone();
+        let sales = terminal.get_sales_history().clone();
+        let terminal_id = terminal.get_config().get_terminal_id().clone();
+        terminal.subscribe(move |event| {
+            if let TerminalEvent::CheckedOut { sale, .. } = event {
+                // The sale is stored before the event is emitted
+                if let Ok(sale) = sales.fetch(*sale) {
+                    let document = sale_json(&terminal_id, &sale);
+                    let webhooks = webhooks.clone();
+                    thread::spawn(move || webhooks.deliver_or_keep(document));
+                }
+            }
+        })
+    }
+
+    /// Deliveries that failed every retry since the last call, oldest first
+    pub fn take_failed_deliveries(&self) -> Vec<FailedDelivery> {
+        std::mem::take(&mut *lock::lock(&self.failed))
+    }
+
+    /// Post `sale` to every webhook, keeping the deliveries failing every retry
+    fn deliver_or_keep(&self, sale: Value) {
+        let results = self.deliver(&sale);
+        let failed = self
+            .webhooks
+            .iter()
+            .zip(results)
+            .filter_map(|(webhook, result)| result.err().map(|error| (webhook, error)));
+        for (webhook, error) in failed {
+            lock::lock(&self.failed).push(FailedDelivery {
+                webhook: webhook.clone(),
+                sale: sale.clone(),
+                error,
+            });
+        }
+    }
+
+    /// Post `sale` to every webhook, retrying failures, returning the outcome of each one
+    pub fn deliver(&self, sale: &Value) -> Vec<Result<(), ErrorVariant>> {
+        let body = sale.to_string();
+        self.webhooks
+            .iter()
+            .map(|webhook| {
+                let mut backoff = self.backoff;
+                let mut result = webhook.post(&body);
+                for _ in 0..self.retries {
+                    if result.is_ok() {
+                        break;
+                    }
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                    result = webhook.post(&body);
+                }
+                result
+            })
+            .collect()
+    }
+}
+
+impl Default for Webhooks {
+    fn default() -> Self {
+        Webhooks::new()
+    }
+}
+
+/// Document of a finalized sale, as posted to the webhooks
+pub fn sale_json(terminal_id: &str, sale: &Sale) -> Value {
+    let mut document = sale.summary_json();
+    document["terminal"] = Value::from(terminal_id);
+    document
+}
+
+/// Value of the [SIGNATURE_HEADER] of `body`, for receivers to check the deliveries
+///
+/// # Example
+///
+/// ```
+/// use store_terminal::webhook;
+///
+/// assert_eq!(
+///     webhook::signature("key", b"The quick brown fox jumps over the lazy dog"),
+///     "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+/// );
+/// ```
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    format!(
+        "sha256={}",
+        digest::hex(&digest::hmac_sha256(secret.as_bytes(), body))
+    )
+}